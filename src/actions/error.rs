@@ -10,4 +10,10 @@ pub enum ActionError {
 
     #[error("Nothing found for {:?}", _0)]
     NothingFound(Vec<String>),
+
+    #[error("More than one project matches: {}", _0.join(", "))]
+    Ambiguous(Vec<String>),
+
+    #[error("{} projects could not be migrated", _0)]
+    MigrationFailed(usize),
 }
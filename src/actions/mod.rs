@@ -1,7 +1,7 @@
 //! General actions
 
 use anyhow::Error;
-use bill::Currency;
+use bill::{Currency, Tax};
 use chrono::prelude::*;
 use icalendar::Calendar;
 #[cfg(feature = "meta")]
@@ -10,16 +10,38 @@ use toml;
 #[cfg(feature = "meta")]
 use std::fs;
 
-use std::{collections::HashMap, fmt::Write, path::PathBuf, process::Command};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::PathBuf,
+    process::Command,
+    sync::Arc,
+};
 
+#[cfg(feature = "git_statuses")]
+use crate::storage::repo::{self, Commit, Pulled, Status};
 use crate::{
-    project::{spec::*, Project},
-    storage::{self, Storable, StorageDir},
-    util,
+    project::{
+        catalogue::{self, Catalogue, Price, PriceChange},
+        client_book::{self, ClientBook, ClientBookError, ClientEntry},
+        migration::{Migrated, CURRENT_FORMAT},
+        product::known_tax_rates,
+        search::{self, Found},
+        spec::*,
+        Project,
+    },
+    storage::{self, journal::Entry, Storable, StorageDir},
+    util::{self, currency::Sums},
 };
 
 pub mod error;
-use self::error::*;
+pub mod report;
+pub mod wages;
+use self::{
+    error::*,
+    report::{GroupBy, Report},
+    wages::{wages_of, EmployeeWages, Month},
+};
 
 /// Helper method that passes projects matching the `search_terms` to the passt closure `f`
 pub fn with_projects<F>(dir: StorageDir, search_terms: &[&str], f: F) -> Result<(), Error>
@@ -39,83 +61,210 @@ where
     Ok(())
 }
 
+/// Command CSV
+///
+/// Projects are grouped by currency, amounts of different currencies never end up in one block.
 pub fn csv(year: i32) -> Result<String, Error> {
     let mut projects = storage::setup::<Project>()?.open_projects(StorageDir::Year(year))?;
-    projects.sort_by(|pa, pb| {
-        pa.index()
-            .unwrap_or_else(|| "zzzz".to_owned())
-            .cmp(&pb.index().unwrap_or_else(|| "zzzz".to_owned()))
+    projects.sort_by_cached_key(|project| {
+        (
+            currency_code(project),
+            project.index().unwrap_or_else(|| "zzzz".to_owned()),
+        )
     });
     projects_to_csv(&projects)
 }
 
+/// ISO 4217 code of the currency of `project`, or its symbol if that is unknown.
+pub(crate) fn currency_code(project: &Project) -> String {
+    let symbol = project.currency_symbol();
+    match symbol.and_then(util::currency::by_symbol) {
+        Some(info) => info.code.to_owned(),
+        None => symbol.map(String::from).unwrap_or_default(),
+    }
+}
+
 /// Produces a csv string from a list of `Project`s
 pub fn projects_to_csv(projects: &[Project]) -> Result<String, Error> {
+    let lines = projects.iter().flat_map(csv_lines).collect::<Vec<_>>();
+    lines_to_csv(&lines)
+}
+
+/// One line of the csv, before the tax rates of all other lines are known.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct CsvLine {
+    pub cells: Vec<String>,
+    /// rate, amount before tax and tax in cents, `None` if the project has no valid bill
+    pub by_tax_rate: Option<Vec<(f64, i64, i64)>>,
+}
+
+/// The line of `project`, followed by one that takes it back if the invoice was canceled.
+pub fn csv_lines(project: &Project) -> Vec<CsvLine> {
+    let mut lines = vec![CsvLine {
+        cells: vec![
+            project.field("InvoiceNumber").unwrap_or_else(|| String::from(r#""""#)),
+            project.field("Name").unwrap_or_else(|| String::from(r#""""#)),
+            project
+                .field("event/dates/0/begin")
+                .unwrap_or_else(|| String::from(r#""""#)),
+            project.field("invoice/date").unwrap_or_else(|| String::from(r#""""#)),
+            project.field("Employees").unwrap_or_else(|| String::from(r#""""#)),
+            project.field("Responsible").unwrap_or_else(|| String::from(r#""""#)),
+            project
+                .field("invoice/payed_date")
+                .unwrap_or_else(|| String::from(r#""""#)),
+            project
+                .sum_sold()
+                .map(|c| c.value().to_string())
+                .unwrap_or_else(|_| String::from(r#""""#)),
+            currency_code(project),
+            String::from(if project.canceled() { "canceled" } else { "" }),
+        ],
+        by_tax_rate: sums_by_tax_rate(project, 1),
+    }];
+
+    if project.invoice_canceled() {
+        lines.push(cancellation_line(project));
+    }
+    lines
+}
+
+/// Puts together the csv, with two columns for every tax rate that occurs.
+pub fn lines_to_csv(lines: &[CsvLine]) -> Result<String, Error> {
     let mut string = String::new();
     let splitter = ";";
+    let rates = tax_rates_of(lines);
 
-    writeln!(
-        &mut string,
-        "{}",
-        [
-            lformat!("INum"),        // Rnum
-            lformat!("Designation"), //Bezeichnung
-            lformat!("Date"),        // Datum
-            lformat!("InvoiceDate"), // Rechnungsdatum
-            lformat!("Caterer"),     // Betreuer
-            lformat!("Responsible"), //Verantwortlich
-            lformat!("Payed on"),    // Bezahlt am
-            lformat!("Amount"),      // Betrag
-            lformat!("Canceled")     //Canceled
-        ]
-        .join(splitter)
-    )?;
+    let mut header = vec![
+        lformat!("INum"),        // Rnum
+        lformat!("Designation"), //Bezeichnung
+        lformat!("Date"),        // Datum
+        lformat!("InvoiceDate"), // Rechnungsdatum
+        lformat!("Caterer"),     // Betreuer
+        lformat!("Responsible"), //Verantwortlich
+        lformat!("Payed on"),    // Bezahlt am
+        lformat!("Amount"),      // Betrag
+        lformat!("Currency"),    // Währung
+        lformat!("Canceled"),    //Canceled
+    ];
+    for rate in &rates {
+        let percent = (rate.value() * 1000.0).round() / 10.0;
+        header.push(lformat!("Amount {}%", percent));
+        header.push(lformat!("Tax {}%", percent));
+    }
+    writeln!(&mut string, "{}", header.join(splitter))?;
 
-    for project in projects {
-        writeln!(
-            &mut string,
-            "{}",
-            [
-                project.field("InvoiceNumber").unwrap_or_else(|| String::from(r#""""#)),
-                project.field("Name").unwrap_or_else(|| String::from(r#""""#)),
-                project
-                    .field("event/dates/0/begin")
-                    .unwrap_or_else(|| String::from(r#""""#)),
-                project.field("invoice/date").unwrap_or_else(|| String::from(r#""""#)),
-                project.field("Employees").unwrap_or_else(|| String::from(r#""""#)),
-                project.field("Responsible").unwrap_or_else(|| String::from(r#""""#)),
-                project
-                    .field("invoice/payed_date")
-                    .unwrap_or_else(|| String::from(r#""""#)),
-                project
-                    .sum_sold()
-                    .map(|c| c.value().to_string())
-                    .unwrap_or_else(|_| String::from(r#""""#)),
-                String::from(if project.canceled() { "canceled" } else { "" })
-            ]
-            .join(splitter)
-        )?;
+    for line in lines {
+        let mut row = line.cells.clone();
+        for rate in &rates {
+            let sums = match line.by_tax_rate {
+                Some(ref sums) => sums
+                    .iter()
+                    .find(|(r, ..)| Tax::new(*r) == *rate)
+                    .map_or([String::from("0"), String::from("0")], |(_, amount, tax)| {
+                        [amount.to_string(), tax.to_string()]
+                    }),
+                None => [String::from(r#""""#), String::from(r#""""#)],
+            };
+            row.extend(sums);
+        }
+        writeln!(&mut string, "{}", row.join(splitter))?;
     }
     Ok(string)
 }
 
-fn open_payments(projects: &[Project]) -> Currency {
+/// A canceled invoice gets a second line that takes back the amounts of the first.
+fn cancellation_line(project: &Project) -> CsvLine {
+    let empty = || String::from(r#""""#);
+    CsvLine {
+        cells: vec![
+            project.cancellation().number_str().unwrap_or_else(empty),
+            project.field("Name").unwrap_or_else(empty),
+            project.field("event/dates/0/begin").unwrap_or_else(empty),
+            project.field("cancellation/date").unwrap_or_else(empty),
+            project.field("Employees").unwrap_or_else(empty),
+            project.field("Responsible").unwrap_or_else(empty),
+            empty(),
+            project
+                .sum_sold()
+                .map(|c| (-c.value()).to_string())
+                .unwrap_or_else(|_| empty()),
+            currency_code(project),
+            String::from("canceled"),
+        ],
+        by_tax_rate: sums_by_tax_rate(project, -1),
+    }
+}
+
+/// Configured tax rates plus every other rate that occurs in `lines`, sorted.
+fn tax_rates_of(lines: &[CsvLine]) -> Vec<Tax> {
+    let mut rates = known_tax_rates().to_vec();
+    for sums in lines.iter().filter_map(|line| line.by_tax_rate.as_ref()) {
+        rates.extend(sums.iter().map(|(rate, ..)| Tax::new(*rate)));
+    }
+    rates.sort();
+    rates.dedup();
+    rates
+}
+
+/// Amount before tax and tax of the invoice per rate, in cents.
+///
+/// `sign` is `-1` for amounts that are taken back.
+fn sums_by_tax_rate(project: &Project, sign: i64) -> Option<Vec<(f64, i64, i64)>> {
+    let (_, invoice) = project.bills().ok()?;
+    Some(
+        invoice
+            .iter()
+            .map(|(rate, list)| {
+                (
+                    rate.value(),
+                    sign * list.gross_sum().value(),
+                    sign * list.tax_sum().value(),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn open_invoices(projects: &[Project]) -> Vec<&Project> {
     projects
         .iter()
-        .filter(|&p| !p.canceled() && !p.is_payed() && p.age().unwrap_or(0) > 0)
-        .filter_map(|p| p.sum_sold().ok())
-        .fold(Currency::default(), |acc, x| acc + x)
+        .filter(|&p| !p.canceled() && !p.invoice_canceled() && !p.is_payed() && p.age().unwrap_or(0) > 0)
+        .collect()
 }
 
-fn open_wages(projects: &[Project]) -> Currency {
+fn open_payments(projects: &[Project]) -> Sums {
+    open_invoices(projects)
+        .into_iter()
+        .filter_map(|p| p.outstanding().ok())
+        .collect()
+}
+
+fn partial_payments(projects: &[Project]) -> Sums {
+    open_invoices(projects)
+        .into_iter()
+        .map(Redeemable::payed_sum)
+        .filter(|payed| payed.value != 0)
+        .collect()
+}
+
+fn outstanding_invoices(projects: &[Project]) -> Vec<(String, Currency)> {
+    open_invoices(projects)
+        .into_iter()
+        .filter_map(|p| p.outstanding().ok().map(|o| (p.short_desc(), o)))
+        .collect()
+}
+
+fn open_wages(projects: &[Project]) -> Sums {
     projects
         .iter()
         .filter(|p| !p.canceled() && p.age().unwrap_or(0) > 0)
         .filter_map(|p| p.hours().net_wages())
-        .fold(Currency::default(), |acc, x| acc + x)
+        .collect()
 }
 
-fn unpayed_employees(projects: &[Project]) -> HashMap<String, Currency> {
+fn unpayed_employees(projects: &[Project]) -> HashMap<String, Sums> {
     let mut buckets = HashMap::new();
     let employees = projects
         .iter()
@@ -124,33 +273,268 @@ fn unpayed_employees(projects: &[Project]) -> HashMap<String, Currency> {
         .flat_map(IntoIterator::into_iter);
 
     for employee in employees {
-        let bucket = buckets.entry(employee.name.clone()).or_insert_with(Currency::new);
-        *bucket = *bucket + employee.salary;
+        buckets
+            .entry(employee.name.clone())
+            .or_insert_with(Sums::new)
+            .add(employee.salary);
     }
     buckets
 }
 
+/// Amounts of projects in different currencies are summed up separately.
 #[derive(Debug)]
 pub struct Dues {
-    pub acc_sum_sold: Currency,
-    pub acc_wages: Currency,
-    pub unpayed_employees: HashMap<String, Currency>,
+    /// still owed by customers, after subtracting partial payments
+    pub acc_sum_sold: Sums,
+    /// partial payments already received for open invoices
+    pub acc_payed: Sums,
+    pub acc_wages: Sums,
+    pub unpayed_employees: HashMap<String, Sums>,
+    /// outstanding balance per open invoice
+    pub open_invoices: Vec<(String, Currency)>,
 }
 
 /// Command DUES
 pub fn dues() -> Result<Dues, Error> {
     let projects = storage::setup::<Project>()?.open_projects(StorageDir::Working)?;
-    let acc_sum_sold: Currency = open_payments(&projects);
+    let acc_sum_sold = open_payments(&projects);
+    let acc_payed = partial_payments(&projects);
     let acc_wages = open_wages(&projects);
     let unpayed_employees = unpayed_employees(&projects);
+    let open_invoices = outstanding_invoices(&projects);
 
     Ok(Dues {
         acc_sum_sold,
+        acc_payed,
         acc_wages,
         unpayed_employees,
+        open_invoices,
     })
 }
 
+/// Command REPORT
+///
+/// Covers the working directory and the archive, or only the projects of `year`.
+pub fn report(year: Option<i32>, group_by: Option<GroupBy>) -> Result<Report, Error> {
+    let projects = storage::setup::<Project>()?.open_all_projects()?;
+    Report::from_projects(
+        projects
+            .iter()
+            .filter(|project| year.is_none() || project.year() == year),
+        year,
+        group_by,
+    )
+}
+
+/// Command WAGES
+///
+/// Covers the working directory and the archive.
+pub fn wages(month: Option<Month>, employee: Option<&str>) -> Result<Vec<EmployeeWages>, Error> {
+    let projects = storage::setup::<Project>()?.open_all_projects()?;
+    Ok(wages_of(projects.iter(), month, employee))
+}
+
+/// Command PAY
+///
+/// Records a payment for exactly one project.
+pub fn pay(dir: StorageDir, search_terms: &[&str], payment: &Payment) -> Result<Currency, Error> {
+    let project = one_project(dir, search_terms)?;
+    project.add_payment(payment)?;
+    Project::open(&project.file())?.outstanding()
+}
+
+/// Command HOURS
+///
+/// Records a shift in exactly one project and returns it as it is now.
+pub fn add_shift(
+    dir: StorageDir,
+    search_terms: &[&str],
+    name: &str,
+    date: Option<Date<Utc>>,
+    begin: NaiveTime,
+    end: NaiveTime,
+    pause: i64,
+) -> Result<Project, Error> {
+    let project = one_project(dir, search_terms)?;
+    project.add_shift(name, date, begin, end, pause)?;
+    Project::open(&project.file())
+}
+
+fn one_project(dir: StorageDir, search_terms: &[&str]) -> Result<Project, Error> {
+    let mut projects = storage::setup::<Project>()?.search_projects_any(dir, search_terms)?;
+    match projects.len() {
+        1 => Ok(projects.remove(0)),
+        0 => anyhow::bail!(ActionError::NothingFound(
+            search_terms.iter().map(ToString::to_string).collect()
+        )),
+        _ => anyhow::bail!(ActionError::Ambiguous(
+            projects.iter().map(Storable::short_desc).collect()
+        )),
+    }
+}
+
+/// Changes to the storage, oldest first, see [`storage::journal`].
+pub fn history() -> Result<Vec<Entry>, Error> {
+    storage::setup::<Project>()?.history()
+}
+
+/// Reverses the last `count` changes to the storage, newest first.
+pub fn undo(count: usize) -> Result<Vec<Entry>, Error> {
+    storage::setup_with_git::<Project>()?.undo(count)
+}
+
+/// Branch, upstream and changes of the storage repository.
+#[cfg(feature = "git_statuses")]
+pub fn git_status() -> Result<Status, Error> {
+    Ok(storage::setup_with_git::<Project>()?.get_repository()?.status()?)
+}
+
+/// Commits every change in the storage, without a `message` it names the changed projects.
+#[cfg(feature = "git_statuses")]
+pub fn commit_all(message: Option<&str>) -> Result<Commit, Error> {
+    let storage = storage::setup_with_git::<Project>()?;
+    let repo = storage.get_repository()?;
+    repo.add_all()?;
+    let message = match message {
+        Some(message) => message.to_owned(),
+        None => repo::message_for(&repo.status()?.staged()),
+    };
+    Ok(repo.commit(&message)?)
+}
+
+/// Brings in the changes from the remote, see [`repo::Repository::pull`].
+#[cfg(feature = "git_statuses")]
+pub fn pull(rebase: bool) -> Result<Pulled, Error> {
+    Ok(storage::setup_with_git::<Project>()?.get_repository()?.pull(rebase)?)
+}
+
+/// Uploads local commits to the remote.
+#[cfg(feature = "git_statuses")]
+pub fn push() -> Result<(), Error> {
+    Ok(storage::setup_with_git::<Project>()?.get_repository()?.push()?)
+}
+
+/// Projects in `dir` that contain every one of `terms`, best first, see [`crate::project::search`].
+pub fn search(dir: StorageDir, terms: &[&str]) -> Result<Vec<Found<Project>>, Error> {
+    let projects = storage::setup::<Project>()?.open_projects(dir)?;
+    Ok(search::search(projects, |project| project, terms))
+}
+
+/// Invoice numbers by year of the invoice, with the projects that carry them.
+pub type InvoiceNumbers = BTreeMap<i32, BTreeMap<i64, Vec<String>>>;
+
+/// Collects the invoice numbers of `projects`, projects without an invoice date count for the year of the event.
+pub fn invoice_numbers<'a, I>(projects: I) -> InvoiceNumbers
+where
+    I: IntoIterator<Item = &'a Project>,
+{
+    let mut numbers = InvoiceNumbers::new();
+    for project in projects {
+        let year = project
+            .invoice()
+            .date()
+            .ok()
+            .map(|d| d.year())
+            .or_else(|| project.year());
+        if let (Ok(number), Some(year)) = (project.invoice().number(), year) {
+            numbers
+                .entry(year)
+                .or_default()
+                .entry(number)
+                .or_default()
+                .push(project.short_desc());
+        }
+    }
+    numbers
+}
+
+/// Next free invoice number of `year` among `numbers`.
+pub fn next_invoice_number_of(numbers: &InvoiceNumbers, year: i32) -> i64 {
+    1 + numbers
+        .get(&year)
+        .and_then(|numbers| numbers.keys().next_back())
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Next free invoice number of `year`, working directory and archive alike.
+pub fn next_invoice_number(year: i32) -> Result<i64, Error> {
+    let projects = storage::setup::<Project>()?.open_all_projects()?;
+    Ok(next_invoice_number_of(&invoice_numbers(projects.iter()), year))
+}
+
+/// Highest cancellation number of `year` among `projects`.
+///
+/// Cancellations count for the year they were made in, or that of the project if they have no date.
+pub fn last_cancellation_number<'a, I>(projects: I, year: i32) -> Option<i64>
+where
+    I: IntoIterator<Item = &'a Project>,
+{
+    projects
+        .into_iter()
+        .filter(|project| {
+            let date = project.cancellation().date();
+            date.ok().map(|d| d.year()).or_else(|| project.year()) == Some(year)
+        })
+        .filter_map(|project| project.cancellation().number().ok())
+        .max()
+}
+
+/// Next free cancellation number of `year`, working directory and archive alike.
+pub fn next_cancellation_number(year: i32) -> Result<i64, Error> {
+    let projects = storage::setup::<Project>()?.open_all_projects()?;
+    Ok(1 + last_cancellation_number(projects.iter(), year).unwrap_or(0))
+}
+
+/// What is wrong with the invoice numbers of one year.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvoiceNumberCheck {
+    pub year: i32,
+    /// numbers used by more than one project
+    pub duplicates: Vec<(i64, Vec<String>)>,
+    /// numbers between 1 and the highest one that nobody uses
+    pub missing: Vec<i64>,
+}
+
+/// Finds duplicates and gaps, only years with problems are returned.
+pub fn check_invoice_numbers_of(numbers: &InvoiceNumbers) -> Vec<InvoiceNumberCheck> {
+    numbers
+        .iter()
+        .map(|(&year, numbers)| InvoiceNumberCheck {
+            year,
+            duplicates: numbers
+                .iter()
+                .filter(|(_, projects)| projects.len() > 1)
+                .map(|(&number, projects)| (number, projects.clone()))
+                .collect(),
+            missing: (1..=numbers.keys().next_back().copied().unwrap_or(0))
+                .filter(|number| !numbers.contains_key(number))
+                .collect(),
+        })
+        .filter(|check| !check.duplicates.is_empty() || !check.missing.is_empty())
+        .collect()
+}
+
+/// Command INVOICE --check
+pub fn check_invoice_numbers() -> Result<Vec<InvoiceNumberCheck>, Error> {
+    let projects = storage::setup::<Project>()?.open_all_projects()?;
+    Ok(check_invoice_numbers_of(&invoice_numbers(projects.iter())))
+}
+
+/// Command SPEC --schema
+///
+/// The JSON Schema of the project format, pretty printed.
+#[cfg(all(feature = "serialization", feature = "deserialization"))]
+pub fn spec_schema() -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(crate::project::schema::schema())?)
+}
+
+/// Command SPEC --schema (feature deactivated)
+#[cfg(not(all(feature = "serialization", feature = "deserialization")))]
+pub fn spec_schema() -> Result<String, Error> {
+    anyhow::bail!(crate::project::error::ProjectError::FeatureDeactivated)
+}
+
 /// Testing only, tries to run complete spec on all projects.
 /// TODO: make this not panic :D
 /// TODO: move this to `spec::all_the_things`
@@ -187,6 +571,237 @@ pub fn spec() -> Result<(), Error> {
     Ok(())
 }
 
+/// What [`migrate`] did.
+#[derive(Debug, Default)]
+pub struct Migration {
+    pub migrated: Vec<(Project, Migrated)>,
+    /// projects that could not be migrated or written, these are left as they were
+    pub failed: Vec<(Project, Error)>,
+}
+
+/// Command MIGRATE
+///
+/// Upgrades the selected projects, all of `dir` if there are no `search_terms`, and commits them if there is a repository.
+/// Every migration is worked out before anything is written, one project that fails does not stop the others.
+pub fn migrate(dir: StorageDir, search_terms: &[&str], dry_run: bool) -> Result<Migration, Error> {
+    let storage = storage::setup_with_git::<Project>()?;
+    let projects = if search_terms.is_empty() {
+        storage.open_projects(dir)?
+    } else {
+        let projects = storage.search_projects_any(dir, search_terms)?;
+        if projects.is_empty() {
+            anyhow::bail!(ActionError::NothingFound(
+                search_terms.iter().map(ToString::to_string).collect()
+            ));
+        }
+        projects
+    };
+
+    let mut migration = Migration::default();
+    for project in projects {
+        match project.migration() {
+            Ok(Some(migrated)) => migration.migrated.push((project, migrated)),
+            Ok(None) => {},
+            Err(error) => migration.failed.push((project, error)),
+        }
+    }
+    if dry_run {
+        return Ok(migration);
+    }
+
+    let (mut written, mut failed) = (Vec::new(), Vec::new());
+    for (project, migrated) in migration.migrated {
+        match project.apply_migration(&migrated) {
+            Ok(()) => written.push((project, migrated)),
+            Err(error) => failed.push((project, error)),
+        }
+    }
+    migration.migrated = written;
+    migration.failed.append(&mut failed);
+
+    if let (Some(repo), false) = (storage.repository(), migration.migrated.is_empty()) {
+        let paths = migration
+            .migrated
+            .iter()
+            .map(|(project, _)| project.file())
+            .collect::<Vec<_>>();
+        let message = format!("migrated {} projects to format {}", paths.len(), CURRENT_FORMAT);
+        repo.commit_paths(&paths, &message)?;
+    }
+    Ok(migration)
+}
+
+/// Opens the client book of the storage, fresh from disk.
+pub fn client_book() -> Result<ClientBook, Error> {
+    ClientBook::open_default()
+}
+
+/// Writes a file to `extras` and adds it to git, `what` describes the change in the journal.
+fn write_extra_file(file_name: &str, content: &str, what: &str) -> Result<PathBuf, Error> {
+    let storage = storage::setup_with_git::<Project>()?;
+    let path = storage.write_extra_file(file_name, content, what)?;
+    if let Some(repo) = storage.repository() {
+        repo.add(std::slice::from_ref(&path))?;
+    }
+    Ok(path)
+}
+
+/// Writes the client book back to `extras/clients.yml` and adds it to git.
+pub fn write_client_book(book: &ClientBook, what: &str) -> Result<PathBuf, Error> {
+    write_extra_file(client_book::FILE_NAME, book.content(), what)
+}
+
+/// Command CLIENT ADD
+///
+/// Returns the id of the new client.
+pub fn add_client(entry: &ClientEntry) -> Result<String, Error> {
+    let mut book = client_book()?;
+    let id = book.add(entry)?;
+    write_client_book(&book, &format!("added {}", id))?;
+    Ok(id)
+}
+
+/// Command CLIENT ADD --from
+///
+/// Copies the client of exactly one project into the book,
+/// with `link` the project refers to the new entry instead of keeping its own copy.
+pub fn add_client_from_project(dir: StorageDir, search_terms: &[&str], link: bool) -> Result<String, Error> {
+    let projects = storage::setup::<Project>()?.search_projects_any(dir, search_terms)?;
+    let project = match projects.as_slice() {
+        [project] => project,
+        [] => anyhow::bail!(ActionError::NothingFound(
+            search_terms.iter().map(ToString::to_string).collect()
+        )),
+        _ => anyhow::bail!(ActionError::Ambiguous(
+            projects.iter().map(Storable::short_desc).collect()
+        )),
+    };
+    let id = add_client(&ClientEntry::from_client(&project.client()))?;
+    if link {
+        project.link_client(&id)?;
+    }
+    Ok(id)
+}
+
+/// Command CLIENT EDIT <ID> <FIELD> <VALUE>
+pub fn set_client_field(id: &str, field: &str, value: &str) -> Result<(), Error> {
+    let mut book = client_book()?;
+    book.set(id, field, value)?;
+    write_client_book(&book, &format!("set {}.{}", id, field))?;
+    Ok(())
+}
+
+/// Everything we did for one client.
+pub struct ClientHistory {
+    pub entry: ClientEntry,
+    pub projects: Vec<Project>,
+    /// sum of all invoices that were not canceled, per currency
+    pub revenue: Sums,
+    /// what is not yet payed of these invoices, per currency
+    pub outstanding: Sums,
+}
+
+/// Command CLIENT SHOW
+///
+/// Collects the projects of a client from the working directory and the archive,
+/// those that refer to it by `client.ref` and those with the same email address.
+pub fn client_history(id: &str) -> Result<ClientHistory, Error> {
+    let book = Arc::new(client_book()?);
+    let entry = book.get(id).ok_or_else(|| ClientBookError::UnknownClient(id.into()))?;
+
+    let all_projects = storage::setup::<Project>()?.open_all_projects()?;
+    let projects = all_projects
+        .iter()
+        .map(|project| project.clone().with_client_book(Arc::clone(&book)))
+        .filter(|project| {
+            project.yaml()["client"]["ref"].as_str() == Some(id)
+                || (entry.email.is_some() && project.client().email().ok() == entry.email.as_deref())
+        })
+        .collect::<Vec<_>>();
+
+    let mut revenue = Sums::new();
+    let mut outstanding = Sums::new();
+    for project in projects
+        .iter()
+        .filter(|p| p.invoice().number().is_ok() && !p.invoice_canceled())
+    {
+        revenue.add(project.sum_sold()?);
+        outstanding.add(project.outstanding()?);
+    }
+
+    Ok(ClientHistory {
+        entry,
+        projects,
+        revenue,
+        outstanding,
+    })
+}
+
+/// Opens the product catalogue of the storage, fresh from disk.
+pub fn catalogue() -> Result<Catalogue, Error> {
+    Catalogue::open_default()
+}
+
+/// Writes the catalogue back to `extras/catalogue.yml` and adds it to git.
+pub fn write_catalogue(catalogue: &Catalogue, what: &str) -> Result<PathBuf, Error> {
+    write_extra_file(catalogue::FILE_NAME, catalogue.content(), what)
+}
+
+/// Command CATALOGUE ADD
+pub fn add_catalogue_product(
+    id: &str,
+    name: &str,
+    unit: Option<&str>,
+    tax: Option<f64>,
+    price: Price,
+) -> Result<(), Error> {
+    let mut catalogue = catalogue()?;
+    catalogue.add(id, name, unit, tax, price)?;
+    write_catalogue(&catalogue, &format!("added {}", id))?;
+    Ok(())
+}
+
+/// How to bump prices.
+#[derive(Debug, Clone, Copy)]
+pub enum Bump {
+    /// to a new price
+    To(f64),
+    /// by a percentage of the current price
+    By(f64),
+}
+
+/// Command CATALOGUE BUMP
+///
+/// Gives the products of `ids`, or all if there are none, a new price from `since` on.
+/// Returns the changes.
+pub fn bump_prices(ids: &[&str], bump: Bump, since: Date<Utc>) -> Result<Vec<PriceChange>, Error> {
+    let mut catalogue = catalogue()?;
+    let ids = if ids.is_empty() {
+        catalogue.ids().into_iter().map(ToOwned::to_owned).collect::<Vec<_>>()
+    } else {
+        ids.iter().map(ToString::to_string).collect()
+    };
+
+    let mut changes = Vec::new();
+    for id in &ids {
+        let before = catalogue.price_at(id, since).map(|price| price.price);
+        let after = match (bump, &before) {
+            (Bump::To(price), _) => price,
+            (Bump::By(percent), Ok(before)) => before * (1.0 + percent / 100.0),
+            (Bump::By(_), Err(error)) => anyhow::bail!(error.clone()),
+        };
+        catalogue.set_price(id, Price { since, price: after })?;
+        changes.push(PriceChange {
+            id: id.clone(),
+            name: catalogue.name(id).into(),
+            before: before.ok(),
+            after: Some(catalogue::round_cents(after)),
+        });
+    }
+    write_catalogue(&catalogue, &format!("bumped {}", ids.join(", ")))?;
+    Ok(changes)
+}
+
 pub fn delete_project_confirmation(dir: StorageDir, search_terms: &[&str]) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
     for project in storage.search_projects_any(dir, search_terms)? {
@@ -270,20 +885,65 @@ pub fn clone_remote(url: &str, to: &str) -> Result<(), Error> {
 /// Shared extra information stored in the repo
 #[cfg_attr(feature = "meta", derive(Serialize))]
 #[cfg_attr(feature = "meta", derive(Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MetaStore {
     pub api: ApiKeys,
 }
 
 #[cfg_attr(feature = "meta", derive(Serialize))]
 #[cfg_attr(feature = "meta", derive(Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 /// ApiKeys store
+///
+/// `keys` lists every valid key, `users` maps a key to the name of its owner.
 pub struct ApiKeys {
     pub keys: Vec<String>,
     pub users: HashMap<String, String>,
 }
 
+impl ApiKeys {
+    /// Returns the owner of `key`, if the key is valid.
+    ///
+    /// Keys without a known owner are valid, but anonymous.
+    pub fn authenticate(&self, key: &str) -> Option<&str> {
+        if self.keys.iter().any(|k| k == key) {
+            Some(self.users.get(key).map_or("anonymous", String::as_str))
+        } else {
+            None
+        }
+    }
+
+    /// Adds a new random key for `user` and returns it.
+    #[cfg(feature = "meta")]
+    pub fn generate(&mut self, user: &str) -> String {
+        use rand::{distributions::Alphanumeric, Rng};
+        let key: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        self.keys.push(key.clone());
+        self.users.insert(key.clone(), user.to_owned());
+        key
+    }
+
+    /// Removes `key_or_user`, which is either a key itself or the owner of keys.
+    ///
+    /// Returns the removed keys.
+    pub fn revoke(&mut self, key_or_user: &str) -> Vec<String> {
+        let users = &self.users;
+        let (revoked, kept) = self
+            .keys
+            .drain(..)
+            .partition::<Vec<_>, _>(|key| key == key_or_user || users.get(key).is_some_and(|u| u == key_or_user));
+        self.keys = kept;
+        for key in &revoked {
+            self.users.remove(key);
+        }
+        revoked
+    }
+}
+
 /// Parses meta store
 #[cfg(feature = "meta")]
 pub fn parse_meta() -> Result<MetaStore, Error> {
@@ -300,13 +960,154 @@ pub fn get_api_keys() -> Result<ApiKeys, Error> {
     Ok(parse_meta()?.api)
 }
 
+/// Writes meta store back to `extras/meta.toml`
+#[cfg(feature = "meta")]
+pub fn write_meta(store: &MetaStore) -> Result<(), Error> {
+    let storage = storage::setup::<Project>()?;
+    if !storage.extras_dir().exists() {
+        fs::create_dir(storage.extras_dir())?;
+    }
+    let path = storage.get_extra_file("meta.toml")?;
+    fs::write(path, toml::to_string(store)?)?;
+    Ok(())
+}
+
+/// Command META GEN-KEY
+///
+/// Creates a new api key for `user`, creates the meta store if necessary.
+#[cfg(feature = "meta")]
+pub fn generate_api_key(user: &str) -> Result<String, Error> {
+    let path = storage::setup::<Project>()?.get_extra_file("meta.toml")?;
+    // a meta.toml that does not parse is not replaced, that would lose every key in it
+    let mut store = if path.exists() {
+        parse_meta()?
+    } else {
+        log::warn!("{}", lformat!("starting a new meta store"));
+        MetaStore::default()
+    };
+    let key = store.api.generate(user);
+    write_meta(&store)?;
+    Ok(key)
+}
+
+/// Command META REVOKE-KEY
+///
+/// Removes a key or all keys of a user.
+#[cfg(feature = "meta")]
+pub fn revoke_api_key(key_or_user: &str) -> Result<Vec<String>, Error> {
+    let mut store = parse_meta()?;
+    let revoked = store.api.revoke(key_or_user);
+    if revoked.is_empty() {
+        anyhow::bail!(ActionError::NothingFound(vec![key_or_user.to_owned()]));
+    }
+    write_meta(&store)?;
+    Ok(revoked)
+}
+
 pub fn store_meta() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
     let repo = storage.get_repository()?;
     let path = storage.get_extra_file("meta.toml")?;
-    if repo.add(&[path]).success() {
-        Ok(())
-    } else {
-        Err(ActionError::AddingFailed.into())
+    repo.add(&[path])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoice_number_gaps_and_duplicates() {
+        let project = |name: &str, number: i64, date: &str| {
+            Project::from_file_content(&format!(
+                "event: {{ name: {} }}\ninvoice: {{ number: {}, date: {} }}",
+                name, number, date
+            ))
+            .unwrap()
+        };
+        let projects = vec![
+            project("Eins", 1, "01.02.2019"),
+            project("Zwei", 2, "01.03.2019"),
+            project("Auch Zwei", 2, "01.04.2019"),
+            project("Vier", 4, "01.05.2019"),
+            project("Neu", 1, "01.01.2020"),
+        ];
+
+        let numbers = invoice_numbers(&projects);
+        assert_eq!(numbers[&2019].keys().copied().collect::<Vec<_>>(), vec![1, 2, 4]);
+        assert_eq!(
+            check_invoice_numbers_of(&numbers),
+            vec![InvoiceNumberCheck {
+                year: 2019,
+                duplicates: vec![(2, vec!["Zwei".into(), "Auch Zwei".into()])],
+                missing: vec![3],
+            }]
+        );
+    }
+
+    #[test]
+    fn invoice_number_of_a_prior_year_project() {
+        let dir = tempdir::TempDir::new("asciii_invoice").unwrap();
+        let file = dir.path().join("old.yml");
+        std::fs::write(&file, "event: { name: Alt, dates: [ { begin: 03.11.2019 } ] }\n").unwrap();
+        let today = Utc.ymd(2021, 1, 15);
+
+        let project = Project::open(&file).unwrap();
+        let number = next_invoice_number_of(&invoice_numbers(&[]), today.year());
+        project.set_invoice_number(number, today).unwrap();
+
+        let projects = vec![Project::open(&file).unwrap()];
+        assert_eq!(projects[0].invoice().date().unwrap(), today);
+        let numbers = invoice_numbers(&projects);
+        assert!(!numbers.contains_key(&2019));
+        assert_eq!(next_invoice_number_of(&numbers, today.year()), number + 1);
+    }
+
+    #[test]
+    fn cancellation_numbers_per_year() {
+        let project = |number: i64, date: &str| {
+            Project::from_file_content(&format!(
+                "event: {{ name: Storno }}\ncancellation: {{ number: {}, date: {} }}",
+                number, date
+            ))
+            .unwrap()
+        };
+        let projects = vec![
+            project(1, "01.02.2019"),
+            project(3, "01.03.2019"),
+            project(1, "01.01.2020"),
+        ];
+        assert_eq!(last_cancellation_number(&projects, 2019), Some(3));
+        assert_eq!(last_cancellation_number(&projects, 2020), Some(1));
+        assert_eq!(last_cancellation_number(&projects, 2021), None);
+    }
+
+    fn api_keys() -> ApiKeys {
+        ApiKeys {
+            keys: vec!["secret".into(), "other".into(), "orphan".into()],
+            users: maplit::hashmap! {
+                "secret".to_owned() => "hendrik".to_owned(),
+                "other".to_owned() => "hendrik".to_owned(),
+            },
+        }
+    }
+
+    #[test]
+    fn authenticate_api_keys() {
+        let keys = api_keys();
+        assert_eq!(keys.authenticate("secret"), Some("hendrik"));
+        assert_eq!(keys.authenticate("orphan"), Some("anonymous"));
+        assert_eq!(keys.authenticate("hendrik"), None);
+        assert_eq!(keys.authenticate(""), None);
+    }
+
+    #[test]
+    fn revoke_api_keys() {
+        let mut keys = api_keys();
+        assert_eq!(keys.revoke("orphan"), vec!["orphan"]);
+        assert_eq!(keys.revoke("hendrik"), vec!["secret", "other"]);
+        assert!(keys.keys.is_empty());
+        assert!(keys.users.is_empty());
+        assert!(keys.revoke("hendrik").is_empty());
     }
 }
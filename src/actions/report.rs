@@ -0,0 +1,449 @@
+//! Revenue, wages and delays of many projects, summed up by month, client, manager or product.
+//!
+//! Amounts of different currencies are never added up,
+//! a group with projects in two currencies becomes two rows.
+
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+use anyhow::Error;
+use bill::Currency;
+use chrono::prelude::*;
+
+use crate::{
+    project::{
+        spec::{HasEmployees, Invoicable, IsClient, IsProject, Redeemable},
+        Project,
+    },
+    storage::Storable,
+    util::{currency, currency_to_string},
+};
+
+/// What the rows of a [`Report`] stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// month of the event, `2024-03`
+    Month,
+    /// `client.ref`, otherwise the name of the client
+    Client,
+    Manager,
+    /// the items of the invoices, wages are split by their share of the revenue
+    Product,
+}
+
+impl GroupBy {
+    pub const VARIANTS: &'static [&'static str] = &["month", "client", "manager", "product"];
+}
+
+impl FromStr for GroupBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "month" => Ok(GroupBy::Month),
+            "client" => Ok(GroupBy::Client),
+            "manager" => Ok(GroupBy::Manager),
+            "product" => Ok(GroupBy::Product),
+            _ => anyhow::bail!(lformat!(
+                "can't group by {:?}, only by {}",
+                s,
+                GroupBy::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// One group of projects in one currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    pub key: String,
+    pub events: usize,
+    /// invoiced amount without tax
+    pub revenue: Currency,
+    pub wages: Currency,
+    /// days between event and invoice, on average
+    pub invoice_delay: Option<f64>,
+    /// days between invoice and payment, on average
+    pub payment_delay: Option<f64>,
+}
+
+impl ReportRow {
+    /// What is left of the revenue after paying the wages.
+    pub fn margin(&self) -> Currency {
+        Currency {
+            symbol: self.revenue.symbol,
+            value: self.revenue.value - self.wages.value,
+        }
+    }
+
+    /// The row as it is printed in a table.
+    pub fn cells(&self) -> Vec<String> {
+        let days = |days: Option<f64>| days.map(|days| lformat!("{:.1} days", days)).unwrap_or_default();
+        vec![
+            self.key.clone(),
+            self.events.to_string(),
+            currency_to_string(&self.revenue),
+            currency_to_string(&self.wages),
+            currency_to_string(&self.margin()),
+            days(self.invoice_delay),
+            days(self.payment_delay),
+        ]
+    }
+
+    /// ISO 4217 code of the currency, or its symbol if that is unknown.
+    pub fn currency_code(&self) -> String {
+        match currency::code_of(&self.revenue) {
+            Some(code) => code.to_owned(),
+            None => self.revenue.symbol.map(String::from).unwrap_or_default(),
+        }
+    }
+}
+
+/// What one project adds to a row.
+struct Entry {
+    key: String,
+    revenue: Currency,
+    wages: Currency,
+    invoice_delay: Option<i64>,
+    payment_delay: Option<i64>,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    events: usize,
+    revenue: i64,
+    wages: i64,
+    invoice_delays: Vec<i64>,
+    payment_delays: Vec<i64>,
+}
+
+fn average(days: &[i64]) -> Option<f64> {
+    if days.is_empty() {
+        None
+    } else {
+        Some(days.iter().sum::<i64>() as f64 / days.len() as f64)
+    }
+}
+
+/// Revenue, wages and delays of projects, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub year: Option<i32>,
+    pub group_by: Option<GroupBy>,
+    /// empty unless grouped
+    pub rows: Vec<ReportRow>,
+    /// all projects, one row per currency
+    pub totals: Vec<ReportRow>,
+}
+
+impl Report {
+    /// Sums up all `projects` that were not canceled, those whose bills can't be calculated are left out.
+    ///
+    /// Totals count every project once, even if it has several products.
+    pub fn from_projects<'a, I>(projects: I, year: Option<i32>, group_by: Option<GroupBy>) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a Project>,
+    {
+        let mut rows = BTreeMap::new();
+        let mut totals = BTreeMap::new();
+        for project in projects.into_iter().filter(|p| !p.canceled() && !p.invoice_canceled()) {
+            let grouped = match group_by {
+                Some(_) => entries(project, group_by),
+                None => Ok(Vec::new()),
+            };
+            match entries(project, None).and_then(|total| Ok((total, grouped?))) {
+                Ok((total, entries)) => {
+                    total.iter().for_each(|entry| add(&mut totals, entry));
+                    entries.iter().for_each(|entry| add(&mut rows, entry));
+                },
+                Err(error) => log::warn!("{}: {}", project.short_desc(), error),
+            }
+        }
+        Ok(Report {
+            year,
+            group_by,
+            rows: finish(rows),
+            totals: finish(totals),
+        })
+    }
+
+    /// Semicolon separated like the `csv` command, amounts in cents.
+    pub fn to_csv(&self) -> Result<String, Error> {
+        let splitter = ";";
+        let mut string = String::new();
+        let header = [
+            lformat!("Group"),
+            lformat!("Currency"),
+            lformat!("Events"),
+            lformat!("Revenue"),
+            lformat!("Wages"),
+            lformat!("Margin"),
+            lformat!("Invoice delay"),
+            lformat!("Payment delay"),
+        ];
+        writeln!(&mut string, "{}", header.join(splitter))?;
+        for row in self.rows.iter().chain(&self.totals) {
+            let days = |days: Option<f64>| days.map(|days| format!("{:.1}", days)).unwrap_or_default();
+            let fields = [
+                format!("{:?}", row.key),
+                row.currency_code(),
+                row.events.to_string(),
+                row.revenue.value.to_string(),
+                row.wages.value.to_string(),
+                row.margin().value.to_string(),
+                days(row.invoice_delay),
+                days(row.payment_delay),
+            ];
+            writeln!(&mut string, "{}", fields.join(splitter))?;
+        }
+        Ok(string)
+    }
+
+    /// Amounts in cents, like [`to_csv`](Self::to_csv).
+    #[cfg(feature = "serialization")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        use serde_json::json;
+        let row = |row: &ReportRow| {
+            json!({
+                "key": row.key,
+                "currency": row.currency_code(),
+                "events": row.events,
+                "revenue": row.revenue.value,
+                "wages": row.wages.value,
+                "margin": row.margin().value,
+                "invoice_delay": row.invoice_delay,
+                "payment_delay": row.payment_delay,
+            })
+        };
+        let report = json!({
+            "year": self.year,
+            "group_by": self.group_by.map(|by| GroupBy::VARIANTS[by as usize]),
+            "rows": self.rows.iter().map(row).collect::<Vec<_>>(),
+            "totals": self.totals.iter().map(row).collect::<Vec<_>>(),
+        });
+        Ok(serde_json::to_string(&report)?)
+    }
+
+    /// (feature deactivated) Amounts in cents
+    #[cfg(not(feature = "serialization"))]
+    pub fn to_json(&self) -> Result<String, Error> {
+        anyhow::bail!(crate::project::error::ProjectError::FeatureDeactivated)
+    }
+}
+
+fn add(rows: &mut BTreeMap<(String, Option<char>), Accumulator>, entry: &Entry) {
+    let row = rows.entry((entry.key.clone(), entry.revenue.symbol)).or_default();
+    row.events += 1;
+    row.revenue += entry.revenue.value;
+    row.invoice_delays.extend(entry.invoice_delay);
+    row.payment_delays.extend(entry.payment_delay);
+    // wages are not necessarily paid in the currency of the revenue
+    rows.entry((entry.key.clone(), entry.wages.symbol)).or_default().wages += entry.wages.value;
+}
+
+fn finish(rows: BTreeMap<(String, Option<char>), Accumulator>) -> Vec<ReportRow> {
+    rows.into_iter()
+        .map(|((key, symbol), row)| ReportRow {
+            key,
+            events: row.events,
+            revenue: Currency {
+                symbol,
+                value: row.revenue,
+            },
+            wages: Currency {
+                symbol,
+                value: row.wages,
+            },
+            invoice_delay: average(&row.invoice_delays),
+            payment_delay: average(&row.payment_delays),
+        })
+        .collect()
+}
+
+fn days_between(from: Option<Date<Utc>>, to: Option<Date<Utc>>) -> Option<i64> {
+    Some(to?.signed_duration_since(from?).num_days())
+}
+
+/// Splits a project into the groups it belongs to.
+///
+/// Unlike [`Project::our_bad`] and [`Project::their_bad`] delays only count once they are over,
+/// a project that is not payed yet has no payment delay.
+fn entries(project: &Project, group_by: Option<GroupBy>) -> Result<Vec<Entry>, Error> {
+    let (_, invoice) = project.bills()?;
+    let revenue = invoice.gross_total();
+    let wages = project.hours().wages().unwrap_or(Currency {
+        symbol: revenue.symbol,
+        value: 0,
+    });
+    let event_date = project.event_date().ok();
+    let invoice_date = project.invoice().date().ok();
+    let entry = |key: String, share: f64| Entry {
+        key,
+        revenue: revenue * share,
+        wages: wages * share,
+        invoice_delay: days_between(event_date, invoice_date),
+        payment_delay: days_between(invoice_date, project.payed_date().ok()),
+    };
+    let unknown = || String::from("-");
+
+    let keys = match group_by {
+        None => vec![(lformat!("Total"), 1.0)],
+        Some(GroupBy::Month) => vec![(
+            event_date
+                .map(|date| date.format("%Y-%m").to_string())
+                .unwrap_or_else(unknown),
+            1.0,
+        )],
+        Some(GroupBy::Client) => vec![(
+            project.yaml()["client"]["ref"]
+                .as_str()
+                .map(ToOwned::to_owned)
+                .or_else(|| project.client().full_name())
+                .unwrap_or_else(unknown),
+            1.0,
+        )],
+        Some(GroupBy::Manager) => vec![(
+            project
+                .responsible()
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|_| unknown()),
+            1.0,
+        )],
+        Some(GroupBy::Product) => {
+            let mut products = BTreeMap::<String, i64>::new();
+            for item in invoice.as_items() {
+                *products.entry(item.product.name.to_owned()).or_default() += item.gross().value;
+            }
+            products
+                .into_iter()
+                .map(|(name, value)| {
+                    let share = if revenue.value == 0 {
+                        0.0
+                    } else {
+                        value as f64 / revenue.value as f64
+                    };
+                    (name, share)
+                })
+                .collect()
+        },
+    };
+    Ok(keys.into_iter().map(|(key, share)| entry(key, share)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(content: &str) -> Project {
+        Project::from_file_content(content).unwrap()
+    }
+
+    fn projects() -> Vec<Project> {
+        vec![
+            project(
+                "event: { name: Eins, dates: [ { begin: 03.03.2024 } ] }\nmanager: Anna\nclient: { ref: uni }\ntax: 0.19\nhours: { salary: 10, caterers: { Bob: 2 } }\nproducts:\n  Kaffee: { amount: 10, price: 2.5 }\n  Tee: { amount: 10, price: 1.5 }\ninvoice: { number: 1, date: 13.03.2024, payed_date: 23.03.2024 }\n",
+            ),
+            project(
+                "event: { name: Zwei, dates: [ { begin: 20.03.2024 } ] }\nmanager: Anna\ntax: 0.19\nhours: { salary: 8 }\nproducts:\n  Kaffee: { amount: 10, price: 2.5 }\ninvoice: { number: 2, date: 22.03.2024 }\n",
+            ),
+            project(
+                "event: { name: Drei, dates: [ { begin: 04.04.2024 } ] }\nmanager: Bert\ncurrency: USD\ntax: 0.19\nhours: { salary: 8 }\nproducts:\n  Kaffee: { amount: 4, price: 3 }\n",
+            ),
+            project(
+                "event: { name: Abgesagt, dates: [ { begin: 04.04.2024 } ] }\ncanceled: true\ntax: 0.19\nhours: { salary: 8 }\nproducts:\n  Kaffee: { amount: 4, price: 3 }\n",
+            ),
+        ]
+    }
+
+    fn summary(rows: &[ReportRow]) -> Vec<(&str, String, usize, i64, i64)> {
+        rows.iter()
+            .map(|row| {
+                (
+                    row.key.as_str(),
+                    row.currency_code(),
+                    row.events,
+                    row.revenue.value,
+                    row.margin().value,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn by_month() {
+        let report = Report::from_projects(&projects(), Some(2024), Some(GroupBy::Month)).unwrap();
+        assert_eq!(
+            summary(&report.rows),
+            vec![
+                ("2024-03", "EUR".to_owned(), 2, 8500, 6500),
+                ("2024-04", "USD".to_owned(), 1, 1200, 1200)
+            ]
+        );
+        assert_eq!(report.rows[0].invoice_delay, Some(6.0));
+        assert_eq!(report.rows[0].payment_delay, Some(10.0));
+        assert_eq!(report.rows[1].invoice_delay, None);
+        assert_eq!(report.totals.len(), 2);
+        assert_eq!(report.totals[1].events, 2);
+    }
+
+    #[test]
+    fn by_client_manager_and_product() {
+        let projects = projects();
+        let report = |group_by| Report::from_projects(&projects, None, Some(group_by)).unwrap();
+        let by = |group_by| report(group_by).rows;
+        assert_eq!(
+            by(GroupBy::Client)
+                .iter()
+                .map(|row| row.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["-", "-", "uni"]
+        );
+        assert_eq!(
+            summary(&by(GroupBy::Manager)),
+            vec![
+                ("Anna", "EUR".to_owned(), 2, 8500, 6500),
+                ("Bert", "USD".to_owned(), 1, 1200, 1200)
+            ]
+        );
+        // the 20€ wages of "Eins" are split by revenue, 25€ to 20€ to 15€
+        assert_eq!(
+            summary(&by(GroupBy::Product)),
+            vec![
+                ("Kaffee", "USD".to_owned(), 1, 1200, 1200),
+                ("Kaffee", "EUR".to_owned(), 2, 5000, 4167),
+                ("Service", "EUR".to_owned(), 1, 2000, 1333),
+                ("Tee", "EUR".to_owned(), 1, 1500, 1000)
+            ]
+        );
+        assert_eq!(
+            summary(&report(GroupBy::Product).totals),
+            vec![
+                ("Total", "USD".to_owned(), 1, 1200, 1200),
+                ("Total", "EUR".to_owned(), 2, 8500, 6500)
+            ]
+        );
+    }
+
+    #[test]
+    fn wages_stay_in_the_configured_currency() {
+        let usd = project(
+            "event: { name: Drei, dates: [ { begin: 04.04.2024 } ] }\ncurrency: USD\ntax: 0.19\nhours: { salary: 8, caterers: { Bob: 1 } }\nproducts:\n  Kaffee: { amount: 4, price: 3 }\n",
+        );
+        let report = Report::from_projects(&[usd], None, None).unwrap();
+        assert_eq!(
+            summary(&report.totals),
+            vec![
+                ("Total", "USD".to_owned(), 1, 2000, 2000),
+                ("Total", "EUR".to_owned(), 0, 0, -800)
+            ]
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let report = Report::from_projects(&projects()[1..2], None, None).unwrap();
+        assert_eq!(
+            report.to_csv().unwrap(),
+            "Group;Currency;Events;Revenue;Wages;Margin;Invoice delay;Payment delay\n\"Total\";EUR;1;2500;0;2500;2.0;\n"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Hours and wages per employee, across projects.
+//!
+//! A project counts as payed out once it has an `hours.wages_date`.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::Error;
+use bill::Currency;
+use chrono::prelude::*;
+
+use crate::{
+    project::{
+        spec::{HasEmployees, IsProject},
+        Project,
+    },
+    util::{currency::Sums, currency_to_string},
+};
+
+/// A month of a year, `03.2024`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Month {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl Month {
+    pub fn contains(&self, date: Date<Utc>) -> bool {
+        date.year() == self.year && date.month() == self.month
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}.{}", self.month, self.year)
+    }
+}
+
+/// Reads `03.2024`, `2024-03` or just `3` for this year.
+impl FromStr for Month {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.trim().split(['.', '-', '/']).collect::<Vec<_>>();
+        let numbers = parts
+            .iter()
+            .map(|part| part.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>();
+        let (year, month) = match numbers.as_deref() {
+            Ok([month]) => (Local::now().year(), *month),
+            Ok([year, month]) if parts[0].len() == 4 => (*year, *month),
+            Ok([month, year]) => (*year, *month),
+            _ => anyhow::bail!(lformat!("{:?} is not a month, try 03.2024", s)),
+        };
+        if !(1..=12).contains(&month) {
+            anyhow::bail!(lformat!("{:?} is not a month, try 03.2024", s));
+        }
+        Ok(Month {
+            year,
+            month: month as u32,
+        })
+    }
+}
+
+/// What one employee did in one project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shift {
+    pub project: String,
+    pub date: Option<Date<Utc>>,
+    pub time: f64,
+    pub salary: Currency,
+    pub wage: Currency,
+    /// `hours.wages_date` of the project
+    pub payed_on: Option<Date<Utc>>,
+}
+
+/// Everything one employee worked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmployeeWages {
+    pub name: String,
+    /// ordered by date
+    pub shifts: Vec<Shift>,
+}
+
+impl EmployeeWages {
+    pub fn total_time(&self) -> f64 {
+        self.shifts.iter().map(|shift| shift.time).sum()
+    }
+
+    pub fn total(&self) -> Sums {
+        self.shifts.iter().map(|shift| shift.wage).collect()
+    }
+
+    /// Wages of projects that don't have a `wages_date` yet.
+    pub fn unpayed(&self) -> Sums {
+        self.shifts
+            .iter()
+            .filter(|shift| shift.payed_on.is_none())
+            .map(|shift| shift.wage)
+            .collect()
+    }
+
+    /// The shifts written out for a payslip template.
+    pub fn payslip(&self, period: Option<Month>) -> Payslip {
+        let dmy = |date: Option<Date<Utc>>| date.map(|d| d.format("%d.%m.%Y").to_string());
+        Payslip {
+            employee: self.name.clone(),
+            period: period.map(|month| month.to_string()),
+            date: Utc::today().format("%d.%m.%Y").to_string(),
+            shifts: self
+                .shifts
+                .iter()
+                .map(|shift| PayslipShift {
+                    project: shift.project.clone(),
+                    date: dmy(shift.date),
+                    time: shift.time.to_string(),
+                    salary: currency_to_string(&shift.salary),
+                    wage: currency_to_string(&shift.wage),
+                    payed_on: dmy(shift.payed_on),
+                })
+                .collect(),
+            total_time: self.total_time().to_string(),
+            total: self.total().to_string(),
+            unpayed: self.unpayed().to_string(),
+        }
+    }
+}
+
+/// What a payslip template gets to see as `document`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct Payslip {
+    pub employee: String,
+    pub period: Option<String>,
+    /// day the payslip was made
+    pub date: String,
+    pub shifts: Vec<PayslipShift>,
+    pub total_time: String,
+    pub total: String,
+    pub unpayed: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct PayslipShift {
+    pub project: String,
+    pub date: Option<String>,
+    pub time: String,
+    pub salary: String,
+    pub wage: String,
+    pub payed_on: Option<String>,
+}
+
+/// Collects the shifts of every employee, optionally only those of one `month` or of one `employee`.
+///
+/// Canceled projects are left out, names are compared case insensitively, the first spelling is kept.
+pub fn wages_of<'a, I>(projects: I, month: Option<Month>, employee: Option<&str>) -> Vec<EmployeeWages>
+where
+    I: IntoIterator<Item = &'a Project>,
+{
+    let mut wages: Vec<EmployeeWages> = Vec::new();
+    let wanted = employee.map(str::to_lowercase);
+
+    for project in projects.into_iter().filter(|p| !p.canceled()) {
+        let date = project.event_date().ok();
+        if let Some(month) = month {
+            if !date.is_some_and(|date| month.contains(date)) {
+                continue;
+            }
+        }
+        let hours = project.hours();
+        let payed_on = hours.wages_date().ok();
+        for employee in hours.employees().unwrap_or_default() {
+            let key = employee.name.to_lowercase();
+            if wanted.as_ref().is_some_and(|wanted| *wanted != key) {
+                continue;
+            }
+            let shift = Shift {
+                project: project.name().unwrap_or_default().to_owned(),
+                date,
+                time: employee.time,
+                salary: employee.salary,
+                wage: employee.wage,
+                payed_on,
+            };
+            match wages.iter_mut().find(|wages| wages.name.to_lowercase() == key) {
+                Some(wages) => wages.shifts.push(shift),
+                None => wages.push(EmployeeWages {
+                    name: employee.name,
+                    shifts: vec![shift],
+                }),
+            }
+        }
+    }
+
+    wages.sort_by(|a, b| a.name.cmp(&b.name));
+    for employee in &mut wages {
+        employee.shifts.sort_by_key(|shift| shift.date);
+    }
+    wages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(content: &str) -> Project {
+        Project::from_file_content(content).unwrap()
+    }
+
+    fn projects() -> Vec<Project> {
+        vec![
+            project(
+                "event: { name: Eins, dates: [ { begin: 20.03.2024 } ] }\nhours: { salary: 10, caterers: { Bob: 2, Anna: 3 }, wages_date: 01.04.2024 }\n",
+            ),
+            project("event: { name: Zwei, dates: [ { begin: 03.03.2024 } ] }\nhours: { salary: 12, caterers: { bob: 1.5 } }\n"),
+            project("event: { name: Drei, dates: [ { begin: 04.04.2024 } ] }\nhours: { salary: 10, caterers: { Bob: 4 } }\n"),
+            project(
+                "event: { name: Abgesagt, dates: [ { begin: 04.04.2024 } ] }\ncanceled: true\nhours: { salary: 10, caterers: { Bob: 4 } }\n",
+            ),
+        ]
+    }
+
+    #[test]
+    fn months() {
+        assert_eq!("03.2024".parse::<Month>().unwrap(), Month { year: 2024, month: 3 });
+        assert_eq!("2024-03".parse::<Month>().unwrap(), "3.2024".parse::<Month>().unwrap());
+        assert_eq!("12".parse::<Month>().unwrap().year, Local::now().year());
+        assert!("13.2024".parse::<Month>().is_err());
+        assert!("März".parse::<Month>().is_err());
+    }
+
+    #[test]
+    fn per_employee() {
+        let projects = projects();
+        let wages = wages_of(&projects, None, None);
+        assert_eq!(
+            wages.iter().map(|wages| wages.name.as_str()).collect::<Vec<_>>(),
+            vec!["Anna", "Bob"]
+        );
+
+        let bob = &wages[1];
+        assert_eq!(
+            bob.shifts
+                .iter()
+                .map(|shift| shift.project.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Zwei", "Eins", "Drei"]
+        );
+        assert_eq!(bob.total_time(), 7.5);
+        assert_eq!(bob.total().to_string(), "78,00€");
+        assert_eq!(bob.unpayed().to_string(), "58,00€");
+    }
+
+    #[test]
+    fn one_month_of_one_employee() {
+        let projects = projects();
+        let march = "03.2024".parse().ok();
+        let bob = wages_of(&projects, march, Some("BOB"));
+        assert_eq!(
+            bob.iter()
+                .map(|wages| (wages.name.as_str(), wages.total_time()))
+                .collect::<Vec<_>>(),
+            vec![("Bob", 3.5)]
+        );
+
+        let payslip = bob[0].payslip(march);
+        assert_eq!(payslip.period.as_deref(), Some("03.2024"));
+        assert_eq!(payslip.shifts[0].payed_on, None);
+        assert_eq!(payslip.shifts[1].payed_on.as_deref(), Some("01.04.2024"));
+        assert_eq!(payslip.total, "38,00€");
+        assert_eq!(payslip.unpayed, "18,00€");
+    }
+}
@@ -2,19 +2,34 @@ use env_logger::{self, Env};
 
 use actix_web::{middleware, web, App, HttpResponse, HttpServer};
 
-use asciii::server::ProjectLoader;
+use asciii::{
+    actions::ApiKeys,
+    server::{watcher::ProjectChange, ProjectLoader},
+};
 use icalendar::Calendar;
+use tokio::sync::broadcast;
 
 use std::{
+    collections::BTreeSet,
     env,
-    sync::{mpsc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Mutex, RwLock,
+    },
     thread,
+    time::Duration,
 };
 
 // TODO: replace by actor
 lazy_static::lazy_static! {
     pub static ref PROJECTS: Mutex<ProjectLoader> = Mutex::new(ProjectLoader::new());
 
+    /// `None` if no keys could be loaded, every request is rejected then
+    pub static ref API_KEYS: RwLock<Option<ApiKeys>> = RwLock::new(load_api_keys());
+
+    /// Every project the watcher reloaded, see `/api/events`
+    pub static ref CHANGES: broadcast::Sender<ProjectChange> = broadcast::channel(64).0;
+
     pub static ref CHANNEL: mpsc::SyncSender<()> = {
         let (tx, rx) = mpsc::sync_channel::<()>(1);
 
@@ -25,8 +40,11 @@ lazy_static::lazy_static! {
                 rx.recv().unwrap();
                 count += 1;
                 if count % 6 == 0 {
-                    log::debug!("updating projects");
-                    PROJECTS.lock().unwrap().update();
+                    if !WATCHING.load(Ordering::Relaxed) {
+                        log::debug!("updating projects");
+                        PROJECTS.lock().unwrap().update();
+                    }
+                    *API_KEYS.write().unwrap() = load_api_keys();
                 }
                 log::debug!("call-count: {}", count);
             }
@@ -39,17 +57,182 @@ const LOG_VAR: &str = "ASCIII_LOG";
 const BIND_VAR: &str = "ASCIII_BIND";
 const BIND_HOST: &str = "127.0.0.1";
 const BIND_PORT: &str = "8000";
+const NO_AUTH_VAR: &str = "ASCIII_NO_AUTH";
+
+/// Editors tend to touch a file several times per save
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Set while the watcher keeps `PROJECTS` current, the periodic full update is skipped then
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Reloads changed projects as they are written, moved or deleted and broadcasts them on `CHANGES`.
+fn watch_projects() -> Option<notify::RecommendedWatcher> {
+    let (tx, rx) = mpsc::channel();
+    let watcher = asciii::server::watcher::watch(&PROJECTS.lock().unwrap().storage, tx)
+        .map_err(|error| log::warn!("not watching projects, falling back to periodic updates: {}", error))
+        .ok()?;
+
+    thread::spawn(move || {
+        while let Ok(first) = rx.recv() {
+            let mut paths = BTreeSet::from([first]);
+            while let Ok(path) = rx.recv_timeout(DEBOUNCE) {
+                paths.insert(path);
+            }
+
+            let mut loader = PROJECTS.lock().unwrap();
+            for path in paths {
+                for change in loader.refresh_path(&path) {
+                    log::info!("{:?}", change);
+                    // nobody might be listening
+                    CHANGES.send(change).ok();
+                }
+            }
+        }
+    });
+
+    WATCHING.store(true, Ordering::Relaxed);
+    Some(watcher)
+}
+
+/// `1`, `true`, `yes` or `on`, anything else counts as not set.
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn load_api_keys() -> Option<ApiKeys> {
+    asciii::actions::get_api_keys()
+        .map_err(|error| log::error!("cannot load api keys from meta.toml: {}", error))
+        .ok()
+}
+
+pub mod auth {
+    use actix_web::{
+        dev::{Service, ServiceRequest, ServiceResponse},
+        http::header,
+        web, Error, HttpMessage, HttpResponse,
+    };
+    use serde::Deserialize;
+    use std::{future::Future, pin::Pin};
+
+    use super::{api::ErrorResponse, API_KEYS};
+
+    /// Name of the owner of the api key a request was authenticated with.
+    ///
+    /// Available to handlers via `req.extensions()`.
+    #[derive(Debug, Clone)]
+    pub struct User(pub String);
+
+    #[derive(Deserialize)]
+    struct KeyQuery {
+        key: String,
+    }
+
+    /// The only route that takes the key as `?key=`, `EventSource` cannot send headers
+    const KEY_IN_QUERY: &str = "/api/events";
+
+    fn bearer_token(req: &ServiceRequest) -> Option<String> {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_owned())
+            .or_else(|| {
+                web::Query::<KeyQuery>::from_query(req.query_string())
+                    .ok()
+                    .filter(|_| req.path() == KEY_IN_QUERY)
+                    .map(|query| query.into_inner().key)
+            })
+    }
+
+    /// The first line of the request with the value of `?key=` left out, for the access log.
+    pub fn redacted_request_line(req: &ServiceRequest) -> String {
+        let query = req
+            .query_string()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some(("key", _)) => "key=redacted",
+                _ => pair,
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let separator = if query.is_empty() { "" } else { "?" };
+        format!(
+            "{} {}{}{} {:?}",
+            req.method(),
+            req.path(),
+            separator,
+            query,
+            req.version()
+        )
+    }
+
+    /// Requires a valid `Authorization: Bearer <key>` header, or `?key=<key>` for `/api/events`, see `asciii meta gen-key`.
+    ///
+    /// Passes every request through unchecked if `disabled`.
+    pub fn require_api_key<S>(
+        req: ServiceRequest,
+        service: &S,
+        disabled: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<ServiceResponse, Error>>>>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+        S::Future: 'static,
+    {
+        if disabled {
+            return Box::pin(service.call(req));
+        }
+
+        let user = bearer_token(&req).and_then(|token| {
+            API_KEYS
+                .read()
+                .unwrap()
+                .as_ref()
+                .and_then(|keys| keys.authenticate(&token).map(ToOwned::to_owned))
+        });
+
+        let response = match user {
+            Some(user) => {
+                log::debug!("authenticated as {:?}", user);
+                req.extensions_mut().insert(User(user));
+                Ok(service.call(req))
+            },
+            None => {
+                log::warn!("rejecting unauthenticated request to {}", req.path());
+                let body = ErrorResponse {
+                    error: String::from("missing or invalid api key"),
+                };
+                Err(req.into_response(HttpResponse::Unauthorized().json(body)))
+            },
+        };
+
+        Box::pin(async move {
+            match response {
+                Ok(future) => future.await,
+                Err(rejected) => Ok(rejected),
+            }
+        })
+    }
+}
 
 pub mod api {
 
-    use actix_web::{get, http::header, web, HttpRequest, HttpResponse};
+    use actix_web::{get, http::header, post, route, web, HttpRequest, HttpResponse};
 
+    #[cfg(feature = "git_statuses")]
+    use asciii::storage::repo::RepoError;
     use asciii::{
-        project::export::{Complete, ExportTarget},
-        storage::Storable,
+        project::{
+            export::{Complete, ExportTarget},
+            query::Query,
+            search::{search as search_projects, terms, Hit},
+            Project,
+        },
+        server::error::ServerError,
+        storage::{Storable, StorageError},
     };
     use linked_hash_map::LinkedHashMap;
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
 
     use super::*;
 
@@ -63,6 +246,113 @@ pub mod api {
         name: String,
     }
 
+    #[derive(Deserialize, Debug)]
+    pub struct CreateRequest {
+        name: String,
+        template: Option<String>,
+        #[serde(default)]
+        fill: LinkedHashMap<String, String>,
+    }
+
+    #[derive(Deserialize, Debug, Default)]
+    pub struct ArchiveRequest {
+        year: Option<i32>,
+    }
+
+    /// `?where=Final > 500 and not payed&sort=Date desc`, see [`asciii::project::query`]
+    #[derive(Deserialize, Debug, Default)]
+    pub struct QueryRequest {
+        #[serde(rename = "where")]
+        filter: Option<String>,
+        sort: Option<String>,
+    }
+
+    impl QueryRequest {
+        /// The projects that match, in the requested order
+        fn select<'a, I>(&self, projects: I) -> Result<Vec<(&'a String, &'a Project)>, anyhow::Error>
+        where
+            I: IntoIterator<Item = (&'a String, &'a Project)>,
+        {
+            let mut query = self.filter.as_deref().unwrap_or_default().parse::<Query>()?;
+            if let Some(ref sort) = self.sort {
+                query = query.order_by(sort)?;
+            }
+            let mut selected = projects
+                .into_iter()
+                .filter(|(_, project)| query.matches(project))
+                .collect::<Vec<_>>();
+            query.sort_by_project(&mut selected, |(_, project)| project);
+            Ok(selected)
+        }
+    }
+
+    /// `?q=kaffee "Max Mustermann"&year=2024`, see [`asciii::project::search`]
+    #[derive(Deserialize, Debug)]
+    pub struct SearchRequest {
+        q: String,
+        year: Option<i32>,
+        #[serde(default)]
+        working: bool,
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct SearchResult<'a> {
+        name: &'a str,
+        score: u32,
+        hits: Vec<Hit>,
+    }
+
+    #[derive(Serialize, Debug)]
+    pub struct ErrorResponse {
+        pub error: String,
+    }
+
+    /// Maps errors from the library onto http responses
+    pub fn error_response(error: &anyhow::Error) -> HttpResponse {
+        log::error!("{}", error);
+        let body = ErrorResponse {
+            error: error.to_string(),
+        };
+        #[cfg(feature = "git_statuses")]
+        if let Some(RepoError::Conflicts(..) | RepoError::RebaseConflicts(..) | RepoError::Rejected(..)) =
+            error.downcast_ref::<RepoError>()
+        {
+            return HttpResponse::Conflict().json(body);
+        }
+        match (
+            error.downcast_ref::<ServerError>(),
+            error.downcast_ref::<StorageError>(),
+        ) {
+            (Some(ServerError::ProjectNotFound(_)), _) | (_, Some(StorageError::TemplateNotFound)) => {
+                HttpResponse::NotFound().json(body)
+            },
+            (_, Some(StorageError::ProjectDirExists | StorageError::ProjectFileExists)) => {
+                HttpResponse::Conflict().json(body)
+            },
+            _ => HttpResponse::BadRequest().json(body),
+        }
+    }
+
+    /// Responds with the exported project, just like the `GET` routes do
+    fn project_response(result: Result<asciii::project::Project, anyhow::Error>) -> HttpResponse {
+        match result {
+            Ok(project) => {
+                let exported: Complete = project.export();
+                HttpResponse::Ok().json(exported)
+            },
+            Err(error) => error_response(&error),
+        }
+    }
+
+    /// Runs `f` on the thread pool, for file system and git2 work and anything holding [`PROJECTS`] meanwhile.
+    async fn blocking<T, F>(f: F) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce() -> Result<T, anyhow::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        web::block(f).await?
+    }
+
     #[get("/version")]
     pub async fn version() -> HttpResponse {
         let version: &str = asciii::VERSION_JSON.as_ref();
@@ -72,6 +362,58 @@ pub mod api {
             .body(version)
     }
 
+    /// Streams a `ProjectChange` as server-sent event whenever a project file changes.
+    #[get("/events")]
+    pub async fn events() -> HttpResponse {
+        use tokio::sync::broadcast::error::RecvError;
+        log::info!("events");
+
+        let changes = futures_util::stream::unfold(CHANGES.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) => {
+                        let event = format!("data: {}\n\n", serde_json::to_string(&change).unwrap());
+                        return Some((Ok::<_, actix_web::Error>(web::Bytes::from(event)), receiver));
+                    },
+                    Err(RecvError::Lagged(missed)) => log::warn!("client missed {} changes", missed),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .streaming(changes)
+    }
+
+    /// Projects that contain every term of `q`, best first, with the lines they were found in.
+    #[get("/search")]
+    pub async fn search(query: web::Query<SearchRequest>) -> HttpResponse {
+        log::info!("search({:?})", query.q);
+        self::CHANNEL.send(()).unwrap();
+        let loader = self::PROJECTS.lock().unwrap();
+
+        let projects = if query.working {
+            &loader.state.working
+        } else {
+            &loader.state.mapped
+        };
+        let in_scope = projects
+            .iter()
+            .filter(|(_, project)| query.year.is_none() || Storable::year(*project) == query.year);
+
+        let found = search_projects(in_scope, |(_, project)| project, &terms(&query.q))
+            .into_iter()
+            .map(|found| SearchResult {
+                name: found.item.0,
+                score: found.score,
+                hits: found.hits,
+            })
+            .collect::<Vec<_>>();
+        HttpResponse::Ok().json(found)
+    }
+
     pub mod calendar {
         use super::*;
         use asciii::project::spec::HasEvents;
@@ -112,25 +454,24 @@ pub mod api {
         }
 
         #[get("/year/{year}")]
-        pub async fn by_year(param: web::Path<YearRequest>) -> HttpResponse {
+        pub async fn by_year(param: web::Path<YearRequest>, query: web::Query<QueryRequest>) -> HttpResponse {
             log::info!("by_year");
             self::CHANNEL.send(()).unwrap();
             let loader = self::PROJECTS.lock().unwrap();
-            let exported = loader
-                .state
-                .mapped
-                .iter()
-                .filter(|&(_, p)| {
-                    if let Some(y) = Storable::year(p) {
-                        y == param.year
-                    } else {
-                        false
-                    }
-                })
-                .map(|(ident, _p)| ident.as_str())
-                .collect::<Vec<&str>>();
-
-            HttpResponse::Ok().json(exported)
+            let of_year = loader.state.mapped.iter().filter(|&(_, p)| {
+                if let Some(y) = Storable::year(p) {
+                    y == param.year
+                } else {
+                    false
+                }
+            });
+            match query.select(of_year) {
+                Ok(selected) => {
+                    let exported = selected.iter().map(|(ident, _p)| ident.as_str()).collect::<Vec<&str>>();
+                    HttpResponse::Ok().json(exported)
+                },
+                Err(error) => error_response(&error),
+            }
         }
 
         #[get("/{name}")]
@@ -153,75 +494,202 @@ pub mod api {
         }
 
         #[get("/workingdir")]
-        pub async fn working_dir() -> HttpResponse {
+        pub async fn working_dir(query: web::Query<QueryRequest>) -> HttpResponse {
             log::info!("projects/workingdir");
             let loader = self::PROJECTS.lock().unwrap();
-            let list = loader.state.working.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+            let selected = query.select(loader.state.working.iter());
 
             self::CHANNEL.send(()).unwrap();
 
-            HttpResponse::Ok().json(&list)
+            match selected {
+                Ok(selected) => HttpResponse::Ok().json(selected.iter().map(|(ident, _)| ident).collect::<Vec<_>>()),
+                Err(error) => error_response(&error),
+            }
         }
 
-        pub async fn all_names() -> HttpResponse {
+        pub async fn all_names(query: web::Query<QueryRequest>) -> HttpResponse {
             let loader = self::PROJECTS.lock().unwrap();
-            let list = loader.state.mapped.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+            let selected = query.select(loader.state.mapped.iter());
 
             self::CHANNEL.send(()).unwrap();
 
-            HttpResponse::Ok().json(&list)
+            match selected {
+                Ok(selected) => HttpResponse::Ok().json(selected.iter().map(|(ident, _)| ident).collect::<Vec<_>>()),
+                Err(error) => error_response(&error),
+            }
+        }
+
+        pub async fn create(request: web::Json<CreateRequest>) -> HttpResponse {
+            log::info!("create({:?})", request.name);
+            let request = request.into_inner();
+            project_response(
+                blocking(move || {
+                    let template = request
+                        .template
+                        .as_deref()
+                        .unwrap_or_else(|| asciii::CONFIG.get_str("template"));
+                    let fill = request
+                        .fill
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value.clone()))
+                        .collect();
+                    self::PROJECTS
+                        .lock()
+                        .unwrap()
+                        .create_project(&request.name, template, &fill)
+                })
+                .await,
+            )
+        }
+
+        #[route("/{name}", method = "PUT", method = "PATCH")]
+        pub async fn set_fields(
+            param: web::Path<NameRequest>,
+            fields: web::Json<LinkedHashMap<String, String>>,
+        ) -> HttpResponse {
+            log::info!("set_fields({:?}, {:?})", param.name, fields);
+            let (name, fields) = (param.into_inner().name, fields.into_inner());
+            project_response(blocking(move || self::PROJECTS.lock().unwrap().set_fields(&name, &fields)).await)
+        }
+
+        #[post("/{name}/archive")]
+        pub async fn archive(param: web::Path<NameRequest>, body: Option<web::Json<ArchiveRequest>>) -> HttpResponse {
+            log::info!("archive({:?})", param.name);
+            let (name, year) = (param.into_inner().name, body.and_then(|b| b.year));
+            project_response(blocking(move || self::PROJECTS.lock().unwrap().archive_project(&name, year)).await)
+        }
+
+        #[post("/{name}/unarchive")]
+        pub async fn unarchive(param: web::Path<NameRequest>) -> HttpResponse {
+            log::info!("unarchive({:?})", param.name);
+            let name = param.into_inner().name;
+            project_response(blocking(move || self::PROJECTS.lock().unwrap().unarchive_project(&name)).await)
+        }
+    }
+
+    #[cfg(feature = "git_statuses")]
+    pub mod git {
+        use asciii::actions;
+
+        use super::*;
+
+        #[derive(Deserialize, Debug, Default)]
+        pub struct CommitRequest {
+            message: Option<String>,
+        }
+
+        #[derive(Deserialize, Debug, Default)]
+        pub struct PullRequest {
+            #[serde(default)]
+            rebase: bool,
+        }
+
+        #[get("")]
+        pub async fn status() -> HttpResponse {
+            match blocking(actions::git_status).await {
+                Ok(status) => HttpResponse::Ok().json(status),
+                Err(error) => error_response(&error),
+            }
+        }
+
+        #[post("/commit")]
+        pub async fn commit(body: Option<web::Json<CommitRequest>>) -> HttpResponse {
+            let message = body.and_then(|body| body.into_inner().message);
+            log::info!("commit({:?})", message);
+            match blocking(move || actions::commit_all(message.as_deref())).await {
+                Ok(commit) => HttpResponse::Ok().json(commit),
+                Err(error) => error_response(&error),
+            }
+        }
+
+        #[post("/pull")]
+        pub async fn pull(body: Option<web::Json<PullRequest>>) -> HttpResponse {
+            let rebase = body.is_some_and(|body| body.rebase);
+            log::info!("pull(rebase: {})", rebase);
+            let pulled = blocking(move || {
+                let pulled = actions::pull(rebase)?;
+                self::PROJECTS.lock().unwrap().update();
+                Ok(pulled)
+            });
+            match pulled.await {
+                Ok(pulled) => HttpResponse::Ok().json(pulled),
+                Err(error) => error_response(&error),
+            }
+        }
+
+        #[post("/push")]
+        pub async fn push() -> HttpResponse {
+            log::info!("push()");
+            match blocking(actions::push).await {
+                Ok(()) => HttpResponse::NoContent().finish(),
+                Err(error) => error_response(&error),
+            }
         }
     }
 
     pub mod full_projects {
         use super::*;
 
-        #[get("/year/{year}")]
-        pub async fn by_year(param: web::Path<YearRequest>) -> HttpResponse {
-            let loader = self::PROJECTS.lock().unwrap();
-            let exported = loader
-                .state
-                .mapped
-                .iter()
-                .filter(|&(_, p)| {
-                    if let Some(y) = Storable::year(p) {
-                        y == param.year
-                    } else {
-                        false
-                    }
-                })
+        fn exported(selected: Vec<(&String, &Project)>) -> LinkedHashMap<String, Complete> {
+            selected
+                .into_iter()
                 .map(|(ident, p)| {
                     let exported: Complete = p.export();
                     (ident.clone(), exported)
                 })
-                .collect::<LinkedHashMap<String, Complete>>();
+                .collect()
+        }
+
+        #[get("/year/{year}")]
+        pub async fn by_year(param: web::Path<YearRequest>, query: web::Query<QueryRequest>) -> HttpResponse {
+            let loader = self::PROJECTS.lock().unwrap();
+            let of_year = loader.state.mapped.iter().filter(|&(_, p)| {
+                if let Some(y) = Storable::year(p) {
+                    y == param.year
+                } else {
+                    false
+                }
+            });
+            let selected = query.select(of_year);
 
             self::CHANNEL.send(()).unwrap();
 
-            HttpResponse::Ok().json(exported)
+            match selected {
+                Ok(selected) => HttpResponse::Ok().json(exported(selected)),
+                Err(error) => error_response(&error),
+            }
         }
 
         #[get("/workingdir")]
-        pub async fn working_dir() -> HttpResponse {
+        pub async fn working_dir(query: web::Query<QueryRequest>) -> HttpResponse {
             log::info!("full_projects/workingdir");
             let loader = self::PROJECTS.lock().unwrap();
-            let list = loader
-                .state
-                .working
-                .iter()
-                .map(|(ident, p)| {
-                    let exported: Complete = p.export();
-                    (ident, exported)
-                })
-                .collect::<LinkedHashMap<_, _>>();
+            let selected = query.select(loader.state.working.iter());
 
             self::CHANNEL.send(()).unwrap();
 
-            HttpResponse::Ok().json(&list)
+            match selected {
+                Ok(selected) => HttpResponse::Ok().json(exported(selected)),
+                Err(error) => error_response(&error),
+            }
         }
     }
 }
 
+#[cfg(feature = "git_statuses")]
+fn git_routes(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("git")
+            .service(api::git::status)
+            .service(api::git::commit)
+            .service(api::git::pull)
+            .service(api::git::push),
+    );
+}
+
+#[cfg(not(feature = "git_statuses"))]
+fn git_routes(_: &mut web::ServiceConfig) {}
+
 #[actix_web::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     color_backtrace::install();
@@ -234,20 +702,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let bind_to = env::var(BIND_VAR).unwrap_or_else(|_| format!("{BIND_HOST}:{BIND_PORT}"));
 
     log::info!("running asciii-web");
-    log::warn!("do not host this on a public server, there is no security by design");
+    let no_auth = env::var(NO_AUTH_VAR).is_ok_and(|value| is_truthy(&value));
+    if no_auth {
+        log::warn!("{} is set, the api is open to anyone who can reach it", NO_AUTH_VAR);
+    } else if API_KEYS.read().unwrap().is_none() {
+        log::warn!("no api keys loaded, every request will be rejected, see `asciii meta gen-key`");
+    }
+
+    let _watcher = watch_projects();
 
     let server = HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
+            // like `Logger::default()`, without api keys
+            .wrap(
+                middleware::Logger::new(r#"%a "%{request}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#)
+                    .custom_request_replace("request", auth::redacted_request_line),
+            )
             .service(
                 web::scope("api")
-                    .service(web::resource("projects").route(web::get().to(api::projects::all_names)))
+                    .wrap_fn(move |req, service| auth::require_api_key(req, service, no_auth))
+                    .service(
+                        web::resource("projects")
+                            .route(web::get().to(api::projects::all_names))
+                            .route(web::post().to(api::projects::create)),
+                    )
                     .service(api::version)
+                    .service(api::events)
+                    .service(api::search)
                     .service(
                         web::scope("projects")
                             .service(api::projects::years)
                             .service(api::projects::by_year)
                             .service(api::projects::working_dir)
+                            .service(api::projects::archive)
+                            .service(api::projects::unarchive)
+                            .service(api::projects::set_fields)
                             .service(api::projects::by_name),
                     )
                     .service(
@@ -256,7 +745,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .service(api::full_projects::working_dir)
                             .service(api::projects::years),
                     )
-                    .service(api::calendar::calendar),
+                    .service(api::calendar::calendar)
+                    .configure(git_routes),
             )
             // .service(fs::Files::new("/", "webapp/public").index_file("index.html"))
             .service(web::resource("/").route(
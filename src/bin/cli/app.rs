@@ -136,6 +136,12 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                              .takes_value(true)
                              .multiple(true)
                             )
+                        .arg(Arg::with_name("where")
+                             .help(lformat!("Select by a query, e.g. \"Final > 500 and Year = 2024 and not payed order by Date\"").as_ref())
+                             .short('w')
+                             .long("where")
+                             .takes_value(true)
+                            )
                         .arg(Arg::with_name("errors")
                              .help(lformat!("Show Errors for each project").as_ref())
                              .long("errors")
@@ -170,10 +176,9 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                              .conflicts_with("csv")
                             )
                         .arg(Arg::with_name("sort")
-                             .help(lformat!("Sort by :").as_ref())
+                             .help(lformat!("Sort by date, index, name, manager or any other field, \"Final desc\" sorts descending").as_ref())
                              .long("sort")
                              .short('s')
-                             .possible_values(["date",  "index",  "name",  "manager"])
                              .takes_value(true)
                             )
                         .arg(Arg::with_name("all")
@@ -197,6 +202,10 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                              .short('p')
                             )
 
+                        .arg(Arg::with_name("no-cache")
+                             .help(lformat!("Open every project instead of using the project cache").as_ref())
+                             .long("no-cache")
+                            )
                         .arg(Arg::with_name("broken")
                              .help(lformat!("List broken projects  without project file").as_ref())
                              .long("broken")
@@ -310,6 +319,195 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                         )
                 .subcommand(SubCommand::with_name("store"))
                 .subcommand(SubCommand::with_name("dump"))
+                .subcommand(SubCommand::with_name("gen-key")
+                        .about(lformat!("Generate a new api key for asciii-web").as_ref())
+                        .arg(Arg::with_name("user")
+                             .help(lformat!("Who the key belongs to").as_ref())
+                             .required(true)
+                            )
+                        )
+                .subcommand(SubCommand::with_name("revoke-key")
+                        .about(lformat!("Revoke an api key, or all keys of a user").as_ref())
+                        .arg(Arg::with_name("key")
+                             .help(lformat!("The key or its user").as_ref())
+                             .required(true)
+                            )
+                        )
+                )
+
+            .subcommand(SubCommand::with_name("client")
+                .about(lformat!("Manage the clients shared by projects").as_ref())
+                .settings(&[AppSettings::SubcommandRequiredElseHelp])
+                .subcommand(SubCommand::with_name("list")
+                        .about(lformat!("List all clients").as_ref())
+                        )
+                .subcommand(SubCommand::with_name("add")
+                        .about(lformat!("Add a client").as_ref())
+                        .arg(Arg::with_name("title")
+                             .help(lformat!("Mr, Ms, Herr, Frau, ...").as_ref())
+                             .long("title")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("first_name")
+                             .long("first-name")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("last_name")
+                             .long("last-name")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("email")
+                             .long("email")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("address")
+                             .help(lformat!("Postal address, including the name").as_ref())
+                             .long("address")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("country")
+                             .help(lformat!("Country code, like DE").as_ref())
+                             .long("country")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("from")
+                             .help(lformat!("Copy the client of a project").as_ref())
+                             .long("from")
+                             .takes_value(true)
+                             .conflicts_with_all(&["title", "first_name", "last_name", "email", "address", "country"])
+                            )
+                        .arg(Arg::with_name("link")
+                             .help(lformat!("Let the project refer to the new client instead of keeping a copy").as_ref())
+                             .long("link")
+                             .requires("from")
+                            )
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Pick an archived project").as_ref())
+                             .short('a')
+                             .long("archive")
+                             .min_values(0)
+                             .takes_value(true)
+                             .requires("from")
+                            )
+                        )
+                .subcommand(SubCommand::with_name("edit")
+                        .about(lformat!("Change a client, opens the client book without a field").as_ref())
+                        .arg(Arg::with_name("id")
+                             .help(lformat!("The id of the client").as_ref())
+                             .required(true)
+                            )
+                        .arg(Arg::with_name("field")
+                             .help(lformat!("title, first_name, last_name, email, address or country").as_ref())
+                             .requires("value")
+                            )
+                        .arg(Arg::with_name("value")
+                             .help(lformat!("The new value, empty to clear the field").as_ref())
+                            )
+                        .arg(Arg::with_name("editor")
+                             .help(lformat!("Override the configured editor").as_ref())
+                             .short('e')
+                             .long("editor")
+                             .takes_value(true)
+                            )
+                        )
+                .subcommand(SubCommand::with_name("show")
+                        .about(lformat!("Show a client with all of its projects").as_ref())
+                        .arg(Arg::with_name("id")
+                             .help(lformat!("The id of the client").as_ref())
+                             .required(true)
+                            )
+                        )
+                )
+
+            .subcommand(SubCommand::with_name("catalogue")
+                .about(lformat!("Manage the products shared by projects and their prices").as_ref())
+                .settings(&[AppSettings::SubcommandRequiredElseHelp])
+                .subcommand(SubCommand::with_name("list")
+                        .about(lformat!("List all products with their prices").as_ref())
+                        .arg(Arg::with_name("date")
+                             .help(lformat!("Show the prices of another day").as_ref())
+                             .validator(validators::is_dmy)
+                             .short('d')
+                             .long("date")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("history")
+                             .help(lformat!("Show all prices").as_ref())
+                             .short('H')
+                             .long("history")
+                             .conflicts_with("date")
+                            )
+                        )
+                .subcommand(SubCommand::with_name("add")
+                        .about(lformat!("Add a product").as_ref())
+                        .arg(Arg::with_name("id")
+                             .help(lformat!("The id projects refer to the product by").as_ref())
+                             .required(true)
+                            )
+                        .arg(Arg::with_name("price")
+                             .help(lformat!("Price without tax").as_ref())
+                             .required(true)
+                            )
+                        .arg(Arg::with_name("name")
+                             .help(lformat!("Name on offers and invoices, the id if not set").as_ref())
+                             .long("name")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("unit")
+                             .help(lformat!("e.g. 1l, stk").as_ref())
+                             .long("unit")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("tax")
+                             .help(lformat!("Tax rate, if it differs from that of the project").as_ref())
+                             .long("tax")
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("since")
+                             .help(lformat!("First day of the price, today if not set").as_ref())
+                             .validator(validators::is_dmy)
+                             .long("since")
+                             .takes_value(true)
+                            )
+                        )
+                .subcommand(SubCommand::with_name("bump")
+                        .about(lformat!("Set new prices, the old ones stay valid for older projects").as_ref())
+                        .arg(Arg::with_name("id")
+                             .help(lformat!("Products to bump, all if none are given").as_ref())
+                             .multiple(true)
+                            )
+                        .arg(Arg::with_name("to")
+                             .help(lformat!("The new price").as_ref())
+                             .long("to")
+                             .takes_value(true)
+                             .required_unless_present("by")
+                            )
+                        .arg(Arg::with_name("by")
+                             .help(lformat!("Change the prices by a percentage").as_ref())
+                             .long("by")
+                             .takes_value(true)
+                             .allow_hyphen_values(true)
+                             .conflicts_with("to")
+                            )
+                        .arg(Arg::with_name("since")
+                             .help(lformat!("First day of the new prices, today if not set").as_ref())
+                             .validator(validators::is_dmy)
+                             .long("since")
+                             .takes_value(true)
+                            )
+                        )
+                .subcommand(SubCommand::with_name("diff")
+                        .about(lformat!("Show which prices changed between two days").as_ref())
+                        .arg(Arg::with_name("from")
+                             .help(lformat!("The earlier day").as_ref())
+                             .validator(validators::is_dmy)
+                             .required(true)
+                            )
+                        .arg(Arg::with_name("to")
+                             .help(lformat!("The later day, today if not set").as_ref())
+                             .validator(validators::is_dmy)
+                            )
+                        )
                 )
 
             .subcommand(SubCommand::with_name("archive")
@@ -354,6 +552,92 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                             )
                        )
 
+            .subcommand(SubCommand::with_name("migrate")
+                        .about(lformat!("Upgrade project files to the current format").as_ref())
+                        .arg(Arg::with_name("search_term")
+                             .help(lformat!("Search term, possibly event name, all projects if omitted").as_ref())
+                             .multiple(true)
+                            )
+
+                        .arg(Arg::with_name("dry_run")
+                             .help(lformat!("Only show what would change").as_ref())
+                             .long("dry-run")
+                             .short('n')
+                            )
+
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Migrate archived projects").as_ref())
+                             .short('a')
+                             .long("archive")
+                             .min_values(0)
+                             .takes_value(true)
+                            )
+                       )
+
+            .subcommand(SubCommand::with_name("history")
+                        .about(lformat!("Show what asciii changed in the storage").as_ref())
+                        .arg(Arg::with_name("count")
+                             .help(lformat!("Only the last changes").as_ref())
+                             .long("count")
+                             .short('n')
+                             .validator(|n| n.parse::<usize>().map(|_ok|()).map_err(|e|e.to_string()))
+                             .takes_value(true)
+                            )
+                        .arg(Arg::with_name("no-colors")
+                             .help(lformat!("Don't mark undone changes").as_ref())
+                             .long("no-colors")
+                            )
+                       )
+
+            .subcommand(SubCommand::with_name("undo")
+                        .about(lformat!("Undo the last changes, moves, deletions and edits").as_ref())
+                        .arg(Arg::with_name("count")
+                             .help(lformat!("How many changes to undo").as_ref())
+                             .validator(|n| n.parse::<usize>().map(|_ok|()).map_err(|e|e.to_string()))
+                             .default_value("1")
+                            )
+                       )
+
+            .subcommand(SubCommand::with_name("search")
+                        .aliases(&["find", "grep"])
+                        .about(lformat!("Search names, clients, locations, products and descriptions of all projects").as_ref())
+                        .arg(Arg::with_name("search_term")
+                             .help(lformat!("Every term has to be found, quote phrases").as_ref())
+                             .required(true)
+                             .multiple(true)
+                            )
+
+                        .arg(Arg::with_name("working")
+                             .help(lformat!("Only search the working directory").as_ref())
+                             .long("working")
+                             .short('w')
+                            )
+
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Only search the archive of that year").as_ref())
+                             .long("archive")
+                             .short('a')
+                             .min_values(0)
+                             .takes_value(true)
+                             .conflicts_with("working")
+                            )
+
+                        .arg(Arg::with_name("year")
+                             .help(lformat!("Only search projects of that year, archived or not").as_ref())
+                             .long("year")
+                             .short('y')
+                             .min_values(0)
+                             .takes_value(true)
+                             .conflicts_with_all(&["working", "archive"])
+                            )
+
+                        .arg(Arg::with_name("no-colors")
+                             .help(lformat!("Don't highlight what was found").as_ref())
+                             .long("no-colors")
+                             .short('n')
+                            )
+                       )
+
             .subcommand(SubCommand::with_name("show")
                         .aliases(&["display"])
                         .about(lformat!("Display a specific project").as_ref())
@@ -454,12 +738,20 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                             )
 
                         .arg(Arg::with_name("field name")
-                             .help(lformat!("Which field to set").as_ref())
+                             .help(lformat!("Which field to set, either a template field or a path like invoice.payed_date").as_ref())
                              .required(true)
                             )
 
                         .arg(Arg::with_name("field value")
                              .help(lformat!("What to put in the field").as_ref())
+                             .required_unless_present("delete")
+                            )
+
+                        .arg(Arg::with_name("delete")
+                             .help(lformat!("Remove the path and everything below it").as_ref())
+                             .long("delete")
+                             .short('d')
+                             .conflicts_with("field value")
                             )
 
                         .arg(Arg::with_name("archive")
@@ -475,9 +767,126 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                         .about(lformat!("Assign invoice id to project").as_ref())
                         .arg(Arg::with_name("search_term")
                              .help(lformat!("Search term, possibly event name").as_ref())
+                             .required_unless_present("check")
+                            )
+
+                        .arg(Arg::with_name("check")
+                             .help(lformat!("Report duplicate and missing invoice numbers of every year").as_ref())
+                             .long("check")
+                             .short('c')
+                             .conflicts_with("search_term")
+                            )
+
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Pick an archived project").as_ref())
+                             .short('a')
+                             .long("archive")
+                             .min_values(0)
+                             .takes_value(true)
+                            )
+                        )
+
+            .subcommand(SubCommand::with_name("cancel")
+                        .about(lformat!("Cancel the invoice of a project with a credit note").as_ref())
+                        .arg(Arg::with_name("search_term")
+                             .help(lformat!("Search term, possibly event name").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("reason")
+                             .help(lformat!("Why the invoice is canceled").as_ref())
+                             .long("reason")
+                             .short('r')
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Pick an archived project").as_ref())
+                             .short('a')
+                             .long("archive")
+                             .min_values(0)
+                             .takes_value(true)
+                            )
+                        )
+
+            .subcommand(SubCommand::with_name("pay")
+                        .about(lformat!("Record a payment for a project").as_ref())
+                        .arg(Arg::with_name("search_term")
+                             .help(lformat!("Search term, possibly event name").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("amount")
+                             .help(lformat!("How much was payed").as_ref())
                              .required(true)
                             )
 
+                        .arg(Arg::with_name("date")
+                             .help(lformat!("When the payment arrived (dd.mm.yyyy), defaults to today").as_ref())
+                             .long("date")
+                             .short('d')
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("method")
+                             .help(lformat!("How it was payed, e.g. cash or transfer").as_ref())
+                             .long("method")
+                             .short('m')
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("note")
+                             .help(lformat!("A note to keep with the payment").as_ref())
+                             .long("note")
+                             .short('n')
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("archive")
+                             .help(lformat!("Pick an archived project").as_ref())
+                             .short('a')
+                             .long("archive")
+                             .min_values(0)
+                             .takes_value(true)
+                            )
+                        )
+
+            .subcommand(SubCommand::with_name("hours")
+                        .about(lformat!("Record a shift of an employee").as_ref())
+                        .arg(Arg::with_name("search_term")
+                             .help(lformat!("Search term, possibly event name").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("name")
+                             .help(lformat!("Who worked").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("begin")
+                             .help(lformat!("Check-in (hh:mm)").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("end")
+                             .help(lformat!("Check-out (hh:mm)").as_ref())
+                             .required(true)
+                            )
+
+                        .arg(Arg::with_name("date")
+                             .help(lformat!("Day of the shift (dd.mm.yyyy), defaults to the first day of the event").as_ref())
+                             .long("date")
+                             .short('d')
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("pause")
+                             .help(lformat!("Minutes of breaks").as_ref())
+                             .long("pause")
+                             .short('p')
+                             .takes_value(true)
+                            )
+
                         .arg(Arg::with_name("archive")
                              .help(lformat!("Pick an archived project").as_ref())
                              .short('a')
@@ -593,7 +1002,7 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                         .about(lformat!("Experimental: open dues").as_ref())
 
                         .arg(Arg::with_name("invoices")
-                             .help(lformat!("Show unpayed wages").as_ref())
+                             .help(lformat!("Show outstanding balance per invoice").as_ref())
                              .long("invoices")
                              .short('i')
                             )
@@ -606,6 +1015,100 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                             )
                        )
 
+            .subcommand(SubCommand::with_name("report")
+                        .about(lformat!("Revenue, wages and delays, in total or grouped").as_ref())
+                        .aliases(&["stats"])
+
+                        .arg(Arg::with_name("year")
+                             .help(lformat!("Only projects from that year, archived or not").as_ref())
+                             .short('y')
+                             .long("year")
+                             .validator(|y| y.parse::<i32>().map(|_ok|()).map_err(|e|e.to_string()))
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("by")
+                             .help(lformat!("Group by :").as_ref())
+                             .long("by")
+                             .short('b')
+                             .possible_values(asciii::actions::report::GroupBy::VARIANTS)
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("json")
+                             .help(lformat!("Show as JSON").as_ref())
+                             .long("json")
+                             .short('j')
+                            )
+
+                        .arg(Arg::with_name("csv")
+                             .help(lformat!("Show as csv").as_ref())
+                             .long("csv")
+                             .short('c')
+                             .conflicts_with("json")
+                            )
+                       )
+
+            .subcommand(SubCommand::with_name("wages")
+                        .about(lformat!("Hours and wages per employee, payslips").as_ref())
+                        .aliases(&["payroll"])
+
+                        .arg(Arg::with_name("month")
+                             .help(lformat!("Only that month, e.g. 03.2024").as_ref())
+                             .short('m')
+                             .long("month")
+                             .validator(|m| m.parse::<asciii::actions::wages::Month>().map(|_ok|()).map_err(|e|e.to_string()))
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("employee")
+                             .help(lformat!("Only this employee").as_ref())
+                             .short('e')
+                             .long("employee")
+                             .takes_value(true)
+                            )
+
+                        .arg(Arg::with_name("payslip")
+                             .help(lformat!("Create a payslip for every employee").as_ref())
+                             .short('p')
+                             .long("payslip")
+                            )
+
+                        .arg(Arg::with_name("template")
+                             .help(lformat!("Use a particular template").as_ref())
+                             .short('t')
+                             .long("template")
+                             .takes_value(true)
+                             .requires("payslip")
+                            )
+
+                        .arg(Arg::with_name("output")
+                             .help(lformat!("Manually pass a output folder").as_ref())
+                             .long("output")
+                             .takes_value(true)
+                             .requires("payslip")
+                            )
+
+                        .arg(Arg::with_name("dry-run")
+                             .help(lformat!("Do not create final output file").as_ref())
+                             .short('d')
+                             .long("dry")
+                             .requires("payslip")
+                            )
+
+                        .arg(Arg::with_name("print-only")
+                             .help(lformat!("Only prints to stdout").as_ref())
+                             .long("print")
+                             .requires("payslip")
+                            )
+
+                        .arg(Arg::with_name("open")
+                             .help(lformat!("Open the pdf file afterwards.").as_ref())
+                             .long("open")
+                             .requires("payslip")
+                            )
+                       )
+
             .subcommand(SubCommand::with_name("make")
                         .about(lformat!("Creates documents from projects").as_ref())
                         .aliases(&["mk"])
@@ -664,6 +1167,24 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                              .long("invoice")
                              )
 
+                        .arg(Arg::with_name("cancellation")
+                             .help(lformat!("Produce the cancellation (Storno) of the invoice").as_ref())
+                             .long("cancellation")
+                             .conflicts_with_all(&["offer", "invoice"])
+                             )
+
+                        .arg(Arg::with_name("xrechnung")
+                             .help(lformat!("Produce an XRechnung e-invoice instead of a document, a credit note with --cancellation").as_ref())
+                             .long("xrechnung")
+                             .conflicts_with_all(&["offer", "pdf-only", "template", "open"])
+                             )
+
+                        .arg(Arg::with_name("zugferd")
+                             .help(lformat!("Embed the invoice as ZUGFeRD/Factur-X into the pdf").as_ref())
+                             .long("zugferd")
+                             .conflicts_with_all(&["offer", "xrechnung"])
+                             )
+
                         .arg(Arg::with_name("archive")
                              .help(lformat!("Pick an archived project").as_ref())
                              .short('a')
@@ -835,6 +1356,12 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
             .subcommand(SubCommand::with_name("commit")
                         .aliases(&["cm"])
                         .about(lformat!("Save changes locally").as_ref())
+                        .arg(Arg::with_name("message")
+                             .help(lformat!("Commit message, names the changed projects if left out").as_ref())
+                             .short('m')
+                             .long("message")
+                             .takes_value(true)
+                            )
                        )
 
             .subcommand(SubCommand::with_name("push")
@@ -866,7 +1393,7 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
             .subcommand(SubCommand::with_name("pop").about(lformat!("equals git pop").as_ref()))
 
             .subcommand(SubCommand::with_name("log")
-                        .aliases(&["lg", "hist", "history"])
+                        .aliases(&["lg", "hist"])
                         .about(lformat!("Show commit logs").as_ref())
                         .arg(Arg::with_name("search_term")
                              .help(lformat!("Search term, possibly event name").as_ref())
@@ -918,6 +1445,14 @@ pub fn with_cli<F> (app_handler:F) where F: Fn(Command) {
                     )
             )
 
+            .subcommand(SubCommand::with_name("spec")
+                .about(lformat!("Checks the working projects against the specification").as_ref())
+                .arg(Arg::with_name("schema")
+                        .help(lformat!("Print the JSON Schema of the project file format").as_ref())
+                        .long("schema")
+                    )
+            )
+
             .subcommand(SubCommand::with_name("doc")
                 .about(lformat!("Opens the online documentation, please read it").as_ref())
             )
@@ -957,13 +1492,22 @@ pub fn match_matches(matches: &ArgMatches) {
      Some(("new",       sub_m)) => subcommands::new(sub_m),
      Some(("edit",      sub_m)) => subcommands::edit(sub_m),
      Some(("meta",      sub_m)) => subcommands::meta(sub_m),
+     Some(("client",    sub_m)) => subcommands::client(sub_m),
+     Some(("catalogue", sub_m)) => subcommands::catalogue(sub_m),
      Some(("workspace", sub_m)) => subcommands::workspace(sub_m),
      Some(("set",       sub_m)) => subcommands::set(sub_m),
      Some(("invoice",   sub_m)) => subcommands::invoice(sub_m),
+     Some(("pay",       sub_m)) => subcommands::pay(sub_m),
+     Some(("hours",     sub_m)) => subcommands::hours(sub_m),
+     Some(("cancel",    sub_m)) => subcommands::cancel(sub_m),
      Some(("show",      sub_m)) => subcommands::show(sub_m),
+     Some(("search",    sub_m)) => subcommands::search(sub_m),
+     Some(("history",   sub_m)) => subcommands::history(sub_m),
+     Some(("undo",      sub_m)) => subcommands::undo(sub_m),
      Some(("calendar",  sub_m)) => subcommands::calendar(sub_m),
      Some(("archive",   sub_m)) => subcommands::archive(sub_m),
      Some(("unarchive", sub_m)) => subcommands::unarchive(sub_m),
+     Some(("migrate",   sub_m)) => subcommands::migrate(sub_m),
      Some(("config",    sub_m)) => subcommands::config(sub_m),
      Some(("whoami",    _          )) => subcommands::config_show("user/name"),
      Some(("nocommand", sub_m)) => subcommands::no_command(sub_m),
@@ -982,6 +1526,8 @@ pub fn match_matches(matches: &ArgMatches) {
      Some(("version",   sub_m)) => subcommands::version(sub_m),
 
      Some(("dues",      sub_m)) => subcommands::dues(sub_m),
+     Some(("report",    sub_m)) => subcommands::report(sub_m),
+     Some(("wages",     sub_m)) => subcommands::wages(sub_m),
      Some(("shell",     sub_m)) => subcommands::shell(sub_m),
 
      Some(("remote",    _          )) => subcommands::git_remote(),
@@ -990,7 +1536,7 @@ pub fn match_matches(matches: &ArgMatches) {
      Some(("cleanup",   sub_m)) => subcommands::git_cleanup(sub_m),
      Some(("status",    _          )) => subcommands::git_status(),
      Some(("add",       sub_m)) => subcommands::git_add(sub_m),
-     Some(("commit",    _          )) => subcommands::git_commit(),
+     Some(("commit",    sub_m)) => subcommands::git_commit(sub_m),
      Some(("push",      _          )) => subcommands::git_push(),
      Some(("stash",     _          )) => subcommands::git_stash(),
      Some(("pop",       _          )) => subcommands::git_stash_pop(),
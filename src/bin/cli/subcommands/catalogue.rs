@@ -0,0 +1,148 @@
+use anyhow::{format_err, Error};
+use chrono::prelude::*;
+use clap::ArgMatches;
+
+use asciii::{
+    actions::{self, Bump},
+    project::catalogue::{Price, PriceChange},
+    util::{self, yaml::parse_dmy_date},
+};
+
+/// Command CATALOGUE
+pub fn catalogue(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("list", sub_m)) => catalogue_list(sub_m),
+        Some(("add", sub_m)) => catalogue_add(sub_m),
+        Some(("bump", sub_m)) => catalogue_bump(sub_m),
+        Some(("diff", sub_m)) => catalogue_diff(sub_m),
+        _ => Ok(()),
+    }
+}
+
+fn date_or_today(matches: &ArgMatches, name: &str) -> Date<Utc> {
+    matches
+        .value_of(name)
+        .and_then(parse_dmy_date)
+        .unwrap_or_else(Utc::today)
+}
+
+fn parse_number(number: &str) -> Result<f64, Error> {
+    number
+        .replace(',', ".")
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .map_err(|_| format_err!("{:?} is not a number", number))
+}
+
+fn price_to_string(price: Option<f64>) -> String {
+    price
+        .map(|price| util::currency_to_string(&util::to_currency(price)))
+        .unwrap_or_else(|| String::from("-"))
+}
+
+/// Command CATALOGUE LIST
+fn catalogue_list(matches: &ArgMatches) -> Result<(), Error> {
+    let catalogue = actions::catalogue()?;
+    let date = date_or_today(matches, "date");
+    let ids = catalogue.ids();
+    let width = ids.iter().map(|id| id.len()).max().unwrap_or(0);
+
+    for id in ids {
+        let unit = catalogue.unit(id).unwrap_or_default();
+        if matches.is_present("history") {
+            println!(
+                "{:width$}  {}",
+                id,
+                format!("{} {}", catalogue.name(id), unit).trim_end(),
+                width = width
+            );
+            for price in catalogue.prices(id) {
+                println!(
+                    "{:width$}  {:>10}  {}",
+                    "",
+                    price_to_string(Some(price.price)),
+                    lformat!("since {}", price.since.format("%d.%m.%Y")),
+                    width = width
+                );
+            }
+        } else {
+            let price = catalogue.price_at(id, date).ok();
+            println!(
+                "{:width$}  {:24} {:8} {:>10}  {}",
+                id,
+                catalogue.name(id),
+                unit,
+                price_to_string(price.map(|p| p.price)),
+                price
+                    .map(|p| lformat!("since {}", p.since.format("%d.%m.%Y")))
+                    .unwrap_or_default(),
+                width = width
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Command CATALOGUE ADD
+fn catalogue_add(matches: &ArgMatches) -> Result<(), Error> {
+    let id = matches.value_of("id").unwrap();
+    let price = Price {
+        since: date_or_today(matches, "since"),
+        price: parse_number(matches.value_of("price").unwrap())?,
+    };
+    let tax = matches.value_of("tax").map(parse_number).transpose()?;
+    actions::add_catalogue_product(
+        id,
+        matches.value_of("name").unwrap_or(id),
+        matches.value_of("unit"),
+        tax,
+        price,
+    )?;
+    println!("{}", lformat!("added {:?} to the catalogue", id));
+    Ok(())
+}
+
+fn print_changes(changes: &[PriceChange]) {
+    let width = changes.iter().map(|change| change.id.len()).max().unwrap_or(0);
+    for change in changes {
+        let percent = match (change.before, change.after) {
+            (Some(before), Some(after)) if before != 0.0 => format!("{:+.1}%", (after / before - 1.0) * 100.0),
+            _ => String::new(),
+        };
+        println!(
+            "{:width$}  {:24} {:>10} → {:>10}  {}",
+            change.id,
+            change.name,
+            price_to_string(change.before),
+            price_to_string(change.after),
+            percent,
+            width = width
+        );
+    }
+}
+
+/// Command CATALOGUE BUMP
+fn catalogue_bump(matches: &ArgMatches) -> Result<(), Error> {
+    let ids: Vec<&str> = matches.values_of("id").map(Iterator::collect).unwrap_or_default();
+    let bump = match (matches.value_of("to"), matches.value_of("by")) {
+        (Some(price), _) => Bump::To(parse_number(price)?),
+        (None, Some(percent)) => Bump::By(parse_number(percent)?),
+        (None, None) => unreachable!("clap requires either --to or --by"),
+    };
+    let since = date_or_today(matches, "since");
+    let changes = actions::bump_prices(&ids, bump, since)?;
+    print_changes(&changes);
+    Ok(())
+}
+
+/// Command CATALOGUE DIFF
+fn catalogue_diff(matches: &ArgMatches) -> Result<(), Error> {
+    let from = date_or_today(matches, "from");
+    let to = date_or_today(matches, "to");
+    let changes = actions::catalogue()?.changes(from, to);
+    if changes.is_empty() {
+        println!("{}", lformat!("No prices changed"));
+    }
+    print_changes(&changes);
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use anyhow::Error;
+use clap::ArgMatches;
+use yaml_rust::Yaml;
+
+use asciii::{
+    actions, print,
+    project::client_book::{ClientBookError, ClientEntry, FILE_NAME},
+    storage::*,
+    util, CONFIG,
+};
+
+use super::matches_to_dir;
+
+/// Command CLIENT
+pub fn client(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("list", _)) => client_list(),
+        Some(("add", sub_m)) => client_add(sub_m),
+        Some(("edit", sub_m)) => client_edit(sub_m),
+        Some(("show", sub_m)) => client_show(sub_m),
+        _ => Ok(()),
+    }
+}
+
+/// Command CLIENT LIST
+fn client_list() -> Result<(), Error> {
+    let book = actions::client_book()?;
+    let ids = book.ids();
+    let width = ids.iter().map(|id| id.len()).max().unwrap_or(0);
+    for id in ids {
+        let entry = book.get(id).unwrap_or_default();
+        println!(
+            "{:width$}  {:30}  {}",
+            id,
+            entry.name(),
+            entry.email.unwrap_or_default(),
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// Command CLIENT ADD
+fn client_add(matches: &ArgMatches) -> Result<(), Error> {
+    let id = if let Some(search_term) = matches.value_of("from") {
+        actions::add_client_from_project(matches_to_dir(matches), &[search_term], matches.is_present("link"))?
+    } else {
+        let value = |name| matches.value_of(name).map(ToOwned::to_owned);
+        actions::add_client(&ClientEntry {
+            title: value("title"),
+            first_name: value("first_name"),
+            last_name: value("last_name"),
+            email: value("email"),
+            address: value("address"),
+            country: value("country"),
+        })?
+    };
+    println!("{}", lformat!("added client {:?}", id));
+    Ok(())
+}
+
+/// Command CLIENT EDIT
+fn client_edit(matches: &ArgMatches) -> Result<(), Error> {
+    let id = matches.value_of("id").unwrap();
+    if let (Some(field), Some(value)) = (matches.value_of("field"), matches.value_of("value")) {
+        actions::set_client_field(id, field, value)
+    } else {
+        if !actions::client_book()?.contains(id) {
+            return Err(ClientBookError::UnknownClient(id.into()).into());
+        }
+        let editor = matches
+            .value_of("editor")
+            .or_else(|| CONFIG.get("user/editor").and_then(Yaml::as_str));
+        let path = setup::<asciii::project::Project>()?.get_extra_file(FILE_NAME)?;
+        util::pass_to_command(editor, &[path])?;
+        Ok(())
+    }
+}
+
+/// Command CLIENT SHOW
+fn client_show(matches: &ArgMatches) -> Result<(), Error> {
+    let id = matches.value_of("id").unwrap();
+    let history = actions::client_history(id)?;
+    let entry = &history.entry;
+
+    println!("{}", entry.name());
+    for line in [&entry.title, &entry.email, &entry.address, &entry.country]
+        .iter()
+        .copied()
+        .flatten()
+    {
+        println!("{}", line.trim_end());
+    }
+    println!();
+
+    print::print_projects(print::simple_rows(&history.projects, &print::ListConfig::default()));
+    println!();
+    println!(
+        "{}",
+        lformat!(
+            "{} projects, revenue {}, outstanding {}",
+            history.projects.len(),
+            history.revenue,
+            history.outstanding
+        )
+    );
+    Ok(())
+}
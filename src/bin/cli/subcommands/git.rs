@@ -1,8 +1,11 @@
+use std::io::{stdout, IsTerminal};
+
 use anyhow::{bail, format_err, Error};
 use clap::ArgMatches;
 
 use asciii::project::Project;
-use asciii::{storage, util};
+use asciii::storage::repo::{message_for, Pulled};
+use asciii::{print, storage, util};
 
 use super::matches_to_paths;
 
@@ -10,80 +13,44 @@ use super::matches_to_paths;
 pub fn git_log(matches: &ArgMatches) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
     let paths = matches_to_paths(matches, &storage)?;
-    let repo = storage.repository().unwrap();
-    if !repo.log(&paths).success() {
-        bail!(format_err!("git log did not exit successfully"));
-    } else {
-        Ok(())
-    }
+    let repo = storage.get_repository()?;
+    print::print_commits(&repo.log(&paths)?, stdout().is_terminal());
+    Ok(())
 }
 
 /// Command STATUS
 pub fn git_status() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
-    if !repo.status().success() {
-        bail!(format_err!("git status did not exit successfully"));
-    } else {
-        Ok(())
-    }
+    let repo = storage.get_repository()?;
+    print::print_git_status(&repo.status()?, stdout().is_terminal());
+    Ok(())
 }
 
 /// Command COMMIT
-pub fn git_commit() -> Result<(), Error> {
-    let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
-    if !repo.commit().success() {
-        bail!(format_err!("git commit did not exit successfully"));
-    } else {
-        Ok(())
-    }
-}
-
-/// Get git `user.name`
 ///
-/// `git config --local user.name`
-
-/// Command REMOTE
-/// exact replica of `git remote -v`
-#[cfg(not(feature = "git_statuses"))]
-pub fn git_remote() -> Result<(), Error> {
+/// Commits what was added, without `--message` the message names the changed projects.
+pub fn git_commit(matches: &ArgMatches) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    storage.repository().unwrap().remote();
+    let repo = storage.get_repository()?;
+    let message = match matches.value_of("message") {
+        Some(message) => message.to_owned(),
+        None => message_for(&repo.status()?.staged()),
+    };
+    let commit = repo.commit(&message)?;
+    println!("{} {}", commit.short_id(), commit.summary);
     Ok(())
 }
 
 /// Command REMOTE
 /// exact replica of `git remote -v`
-#[cfg(feature = "git_statuses")]
 pub fn git_remote() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-
-    if let Some(r) = storage.repository() {
-        let repo = &r.repo;
-
-        for remote_name in repo.remotes().unwrap().iter() {
-            if let Some(name) = remote_name {
-                if let Ok(remote) = repo.find_remote(name) {
-                    println!(
-                        "{}",
-                        lformat!(
-                            "{}  {} (fetch)\n{}  {} (push)",
-                            remote.name().unwrap_or("no name"),
-                            remote.url().unwrap_or("no url"),
-                            remote.name().unwrap_or("no name"),
-                            remote.pushurl().or_else(|| remote.url()).unwrap_or(""),
-                        )
-                    );
-                } else {
-                    log::error!("{}", lformat!("no remote"))
-                }
-            } else {
-                log::error!("{}", lformat!("no remote name"))
-            }
-        }
+    for (name, url, push_url) in storage.get_repository()?.remotes()? {
+        println!(
+            "{}",
+            lformat!("{}  {} (fetch)\n{}  {} (push)", name, url, name, push_url)
+        );
     }
-
     Ok(())
 }
 
@@ -91,54 +58,39 @@ pub fn git_remote() -> Result<(), Error> {
 pub fn git_add(matches: &ArgMatches) -> Result<(), Error> {
     log::trace!("git_add {:#?}", matches);
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
+    let repo = storage.get_repository()?;
     let paths = matches_to_paths(matches, &storage)?;
 
     if matches.is_present("all") {
-        if repo.add_all().success() {
-            Ok(())
-        } else {
-            bail!(format_err!("git add did not exit successfully"));
-        }
+        repo.add_all()?;
     } else if matches.is_present("search_term") {
-        if repo.add(&paths).success() {
-            Ok(())
-        } else {
-            bail!(format_err!("git add did not exit successfully"));
-        }
+        repo.add(&paths)?;
     } else {
         bail!(format_err!("Nothing selected"));
     }
+    Ok(())
 }
 
 /// Command DIFF
 pub fn git_diff(matches: &ArgMatches) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
     let paths = matches_to_paths(matches, &storage)?;
-    let repo = storage.repository().unwrap();
-    let flags = if matches.is_present("staged") {
-        vec!["--staged"]
-    } else {
-        Vec::new()
-    };
-    if !repo.diff(&paths, &flags).success() {
-        bail!(format_err!("git diff did not exit successfully"));
-    }
+    let repo = storage.get_repository()?;
+    let diff = repo.diff(&paths, matches.is_present("staged"))?;
+    print::print_diff(&diff, stdout().is_terminal());
     Ok(())
 }
 
 /// Command PULL
 pub fn git_pull(matches: &ArgMatches) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
+    let repo = storage.get_repository()?;
 
-    let success = if matches.is_present("rebase") {
-        repo.pull_rebase().success()
-    } else {
-        repo.pull().success()
-    };
-    if !success {
-        bail!(format_err!("git pull did not exit successfully"));
+    match repo.pull(matches.is_present("rebase"))? {
+        Pulled::UpToDate => println!("{}", lformat!("Already up to date.")),
+        Pulled::FastForward(count) => println!("{}", lformat!("Fast-forwarded by {} commits.", count)),
+        Pulled::Merged => println!("{}", lformat!("Merged the remote changes.")),
+        Pulled::Rebased(count) => println!("{}", lformat!("Rebased {} local commits.", count)),
     }
     Ok(())
 }
@@ -146,20 +98,14 @@ pub fn git_pull(matches: &ArgMatches) -> Result<(), Error> {
 /// Command PUSH
 pub fn git_push() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
-    if !repo.push().success() {
-        bail!(format_err!("git push did not exit successfully"));
-    }
+    storage.get_repository()?.push()?;
     Ok(())
 }
 
 /// Command STASH
 pub fn git_stash() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
-    if !repo.stash().success() {
-        bail!(format_err!("git stash did not exit successfully"));
-    }
+    storage.get_repository()?.stash()?;
     Ok(())
 }
 
@@ -167,14 +113,13 @@ pub fn git_stash() -> Result<(), Error> {
 pub fn git_cleanup(matches: &ArgMatches) -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
     let paths = matches_to_paths(matches, &storage)?;
-    let repo = storage.repository().unwrap();
-    // TODO: implement `.and()` for exit status
+    let repo = storage.get_repository()?;
 
     if util::really(&format!(
         "Do you really want to reset any changes you made to:\n {paths:?}\n",
-    )) && !(repo.checkout(&paths).success() && repo.clean(&paths).success())
-    {
-        bail!(format_err!("clean was not successful"));
+    )) {
+        repo.checkout(&paths)?;
+        repo.clean(&paths)?;
     }
     Ok(())
 }
@@ -182,10 +127,6 @@ pub fn git_cleanup(matches: &ArgMatches) -> Result<(), Error> {
 /// Command STASH POP
 pub fn git_stash_pop() -> Result<(), Error> {
     let storage = storage::setup_with_git::<Project>()?;
-    let repo = storage.repository().unwrap();
-    if !repo.stash_pop().success() {
-        bail!(format_err!("git stash pop did not exit successfully"));
-    } else {
-        Ok(())
-    }
+    storage.get_repository()?.stash_pop()?;
+    Ok(())
 }
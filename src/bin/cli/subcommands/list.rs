@@ -2,9 +2,10 @@ use anyhow::Error;
 use chrono::prelude::*;
 use clap::ArgMatches;
 
-use asciii::print::{self, ListConfig, ListMode};
-use asciii::project::spec::IsProject;
-use asciii::project::{ComputedField, Project};
+use asciii::print::{self, ListConfig, ListMode, Listable};
+#[cfg(feature = "serialization")]
+use asciii::project::cache::{Cache, Summary};
+use asciii::project::{query::Query, ComputedField, Project};
 use asciii::storage::*;
 use asciii::CONFIG;
 
@@ -36,6 +37,7 @@ pub fn list(matches: &ArgMatches) -> Result<(), Error> {
             mode: list_mode,
             details: extra_details.or(config_details),
             filter_by: matches.values_of("filter").map(Iterator::collect),
+            query: matches.value_of("where"),
             show_errors: matches.is_present("errors"),
 
             ..Default::default()
@@ -78,7 +80,8 @@ pub fn list(matches: &ArgMatches) -> Result<(), Error> {
         if matches.is_present("broken") {
             list_broken_projects(dir)?; // XXX Broken
         } else {
-            list_projects(dir, &list_config)?;
+            let use_cache = CONFIG.get_bool("list/cache") && !matches.is_present("no-cache");
+            list_projects(dir, &list_config, use_cache)?;
         }
         Ok(())
     }
@@ -93,7 +96,9 @@ pub fn list(matches: &ArgMatches) -> Result<(), Error> {
 /// * `print::verbose_rows()`
 ///
 /// which it prints with `print::print_projects()`
-fn list_projects(dir: StorageDir, list_config: &ListConfig<'_>) -> Result<(), Error> {
+///
+/// Unless there is something to filter by, this lists the summaries in the project cache instead of opening every project.
+fn list_projects(dir: StorageDir, list_config: &ListConfig<'_>, use_cache: bool) -> Result<(), Error> {
     let storage = if CONFIG.get_bool("list/gitstatus") {
         setup_with_git::<Project>()?
     } else {
@@ -101,6 +106,20 @@ fn list_projects(dir: StorageDir, list_config: &ListConfig<'_>) -> Result<(), Er
     };
     log::debug!("listing projects: {}", storage.working_dir().display());
 
+    #[cfg(feature = "serialization")]
+    if use_cache && cacheable(list_config) {
+        let mut cache = Cache::open(&storage);
+        let mut summaries = cache.summaries(&storage, dir)?;
+        if let Err(error) = cache.save() {
+            log::warn!("{}", lformat!("could not write the project cache: {}", error));
+        }
+        sort_builtin(&mut summaries, list_config.sort_by);
+        print_list(&summaries, list_config);
+        return Ok(());
+    }
+    #[cfg(not(feature = "serialization"))]
+    let _ = use_cache;
+
     let mut projects = storage.open_projects(dir)?;
 
     // filtering, can you read this
@@ -108,40 +127,71 @@ fn list_projects(dir: StorageDir, list_config: &ListConfig<'_>) -> Result<(), Er
         projects.filter_by_all(filters);
     }
 
+    let query = list_config.query.map(str::parse::<Query>).transpose()?;
+    if let Some(ref query) = query {
+        projects.retain(|project| query.matches(project));
+    }
+
     // sorting
-    match list_config.sort_by {
-        "manager" => projects.sort_by(|pa, pb| pa.responsible().cmp(&pb.responsible())),
-        "date" => projects.sort_by_key(|pa| pa.modified_date()),
-        "name" => projects.sort_by_key(|pa| pa.short_desc()),
-        "index" => projects.sort_by(|pa, pb| {
-            pa.index()
-                .unwrap_or_else(|| "zzzz".to_owned())
-                .cmp(&pb.index().unwrap_or_else(|| "zzzz".to_owned()))
-        }), // TODO: rename to ident
-        _ => projects.sort_by(|pa, pb| {
-            pa.index()
-                .unwrap_or_else(|| "zzzz".to_owned())
-                .cmp(&pb.index().unwrap_or_else(|| "zzzz".to_owned()))
+    if !sort_builtin(&mut projects, list_config.sort_by) {
+        sort_builtin(&mut projects, "index");
+        Query::default().order_by(list_config.sort_by)?.sort(&mut projects);
+    }
+
+    // an `order by` of the query comes last, so it wins
+    if let Some(ref query) = query {
+        query.sort(&mut projects);
+    }
+
+    print_list(&projects, list_config);
+    Ok(())
+}
+
+/// Whether the cached summaries know everything `list_config` asks for.
+#[cfg(feature = "serialization")]
+fn cacheable(list_config: &ListConfig<'_>) -> bool {
+    list_config.filter_by.is_none()
+        && list_config.query.is_none()
+        && matches!(list_config.sort_by, "manager" | "date" | "name" | "index")
+        && list_config
+            .details
+            .iter()
+            .flatten()
+            .all(|detail| Summary::has_field(detail))
+}
+
+/// Sorts by manager, date, name or index, `false` if `sort_by` is none of them.
+fn sort_builtin<P: Listable>(projects: &mut [P], sort_by: &str) -> bool {
+    match sort_by {
+        "manager" => projects.sort_by_cached_key(|pa| {
+            let manager = pa.responsible();
+            (manager.is_none(), manager)
         }),
+        "date" => projects.sort_by_key(|pa| pa.date()),
+        "name" => projects.sort_by_cached_key(|pa| pa.short_desc()),
+        "index" => projects.sort_by_cached_key(|pa| pa.index().unwrap_or_else(|| "zzzz".to_owned())), // TODO: rename to ident
+        _ => return false,
     }
+    true
+}
 
+fn print_list<P: Listable>(projects: &[P], list_config: &ListConfig<'_>) {
     // fit screen
     let wide_enough = true;
 
     if !wide_enough && list_config.mode != ListMode::Csv {
         // TODO: room for improvement
-        print::print_projects(print::simple_rows(&projects, list_config));
+        print::print_projects(print::simple_rows(projects, list_config));
     } else {
         log::debug!("list_mode: {:?}", list_config.mode);
         match list_config.mode {
-            ListMode::Csv => print::print_csv(&projects),
-            ListMode::Paths => print::print_projects(print::path_rows(&projects, list_config)),
-            ListMode::Simple => print::print_projects(print::simple_rows(&projects, list_config)),
-            ListMode::Verbose => print::print_projects(print::verbose_rows(&projects, list_config)),
-            ListMode::Nothing => print::print_projects(print::dynamic_rows(&projects, list_config)),
+            ListMode::Csv => print::print_csv(projects),
+            ListMode::Paths => print::print_projects(print::path_rows(projects, list_config)),
+            ListMode::Simple => print::print_projects(print::simple_rows(projects, list_config)),
+            ListMode::Verbose => print::print_projects(print::verbose_rows(projects, list_config)),
+            ListMode::Nothing => print::print_projects(print::dynamic_rows(projects, list_config)),
         }
     }
-    Ok(())
 }
 
 /// Command LIST --broken
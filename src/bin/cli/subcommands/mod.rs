@@ -1,4 +1,5 @@
 use std::{
+    cell::Cell,
     collections::HashMap,
     env,
     ffi::OsStr,
@@ -15,11 +16,15 @@ use yaml_rust::Yaml;
 use asciii::{
     self,
     actions::{self, error::ActionError},
-    config,
-    project::{Exportable, Project},
+    config, print,
+    project::{
+        spec::{HasEmployees, Invoicable, Payment},
+        Exportable, Project,
+    },
     storage::*,
     templater::Templater,
-    util, CONFIG,
+    util::{self, yaml::parse_dmy_date},
+    CONFIG,
 };
 
 #[cfg(feature = "document_export")]
@@ -31,8 +36,20 @@ use asciii::project::BillType;
 // path_rows, dynamic_rows,
 // print_projects,print_csv};
 
+pub mod catalogue;
+pub use self::catalogue::*;
+
+pub mod client;
+pub use self::client::*;
+
+#[cfg(feature = "git_statuses")]
 pub mod git;
+#[cfg(feature = "git_statuses")]
 pub use self::git::*;
+#[cfg(not(feature = "git_statuses"))]
+pub mod no_git;
+#[cfg(not(feature = "git_statuses"))]
+pub use self::no_git::*;
 
 pub mod list;
 pub use self::list::*;
@@ -40,6 +57,9 @@ pub use self::list::*;
 pub mod show;
 pub use self::show::*;
 
+pub mod wages;
+pub use self::wages::*;
+
 #[cfg(feature = "shell")]
 use super::shell;
 
@@ -154,6 +174,59 @@ fn matches_to_search(matches: &ArgMatches) -> (Vec<&str>, StorageDir) {
     (search_terms, dir)
 }
 
+/// Command HISTORY
+pub fn history(matches: &ArgMatches) -> Result<(), Error> {
+    let entries = actions::history()?;
+    let count = matches
+        .value_of("count")
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(entries.len());
+    let use_colors = CONFIG.get_bool("list/colors") && !matches.is_present("no-colors");
+    print::print_history(&entries[entries.len().saturating_sub(count)..], use_colors);
+    Ok(())
+}
+
+/// Command UNDO
+pub fn undo(matches: &ArgMatches) -> Result<(), Error> {
+    let count = matches
+        .value_of("count")
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(1);
+    for entry in actions::undo(count)? {
+        println!("{}", lformat!("undid {}", entry));
+    }
+    Ok(())
+}
+
+/// Command SEARCH
+///
+/// Searches everything unless told `--working`, `--archive` or `--year`.
+pub fn search(matches: &ArgMatches) -> Result<(), Error> {
+    let dir = if matches.is_present("working") {
+        StorageDir::Working
+    } else if matches.is_present("archive") || matches.is_present("year") {
+        matches_to_dir(matches)
+    } else {
+        StorageDir::All
+    };
+    let terms = matches
+        .values_of("search_term")
+        .map(Iterator::collect::<Vec<_>>)
+        .unwrap_or_default();
+
+    let found = actions::search(dir, &terms)?;
+    if found.is_empty() {
+        bail!(actions::error::ActionError::NothingFound(
+            terms.iter().map(ToString::to_string).collect()
+        ));
+    }
+    print::print_found(
+        &found,
+        CONFIG.get_bool("list/colors") && !matches.is_present("no-colors"),
+    );
+    Ok(())
+}
+
 /// Produces a list of paths.
 /// This is more general than `with_projects`, as this includes templates too.
 pub fn matches_to_paths(matches: &ArgMatches, storage: &Storage<Project>) -> Result<Vec<PathBuf>, Error> {
@@ -295,6 +368,21 @@ pub fn meta(matches: &ArgMatches) -> Result<(), Error> {
         let meta = actions::parse_meta();
         println!("{:#?}", meta);
     }
+
+    if let Some(matches) = matches.subcommand_matches("gen-key") {
+        let user = matches.value_of("user").unwrap();
+        log::trace!("--> generating key for {:?}", user);
+        let key = actions::generate_api_key(user)?;
+        println!("{}", key);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("revoke-key") {
+        let key = matches.value_of("key").unwrap();
+        log::trace!("--> revoking {:?}", key);
+        for revoked in actions::revoke_api_key(key)? {
+            println!("{}", lformat!("revoked {}", revoked));
+        }
+    }
     Ok(())
 }
 
@@ -326,6 +414,11 @@ where
 
 /// Command SET
 pub fn set(m: &ArgMatches) -> Result<(), Error> {
+    let name = m.value_of("field name").unwrap();
+    if m.is_present("delete") || name.contains(['.', '/']) {
+        return set_path(m, name);
+    }
+
     let field = m
         .value_of("field name")
         .unwrap()
@@ -353,33 +446,54 @@ pub fn set(m: &ArgMatches) -> Result<(), Error> {
     Ok(())
 }
 
+/// Command SET with a path instead of a template field
+fn set_path(m: &ArgMatches, path: &str) -> Result<(), Error> {
+    let value = m.value_of("field value");
+    let (search_terms, dir) = matches_to_search(m);
+
+    actions::with_projects(dir, &search_terms, |project| {
+        let question = match value {
+            Some(value) => lformat!(
+                "Do you want to set {} to {:?} in {:?}?",
+                path,
+                value,
+                project.short_desc()
+            ),
+            None => lformat!("Do you want to remove {} from {:?}?", path, project.short_desc()),
+        };
+        if !util::really(&question) {
+            return Err(format_err!("Don't want to"));
+        }
+        match value {
+            Some(value) => project.set_value(path, value),
+            None => project.delete_value(path),
+        }
+    })?;
+    Ok(())
+}
+
 /// Command INVOICE
 pub fn invoice(m: &ArgMatches) -> Result<(), Error> {
-    let storage = setup::<Project>()?;
-    let dir = StorageDir::Year(Utc::today().year());
-    let projects = storage.open_projects(dir)?;
-    let invoice_number = 1 + projects
-        .iter()
-        .filter_map(|p| p.field("invoice/number"))
-        .map(|s| s.parse::<u32>())
-        .filter_map(Result::ok)
-        .max()
-        .unwrap_or(0);
-    let value = invoice_number.to_string();
-
+    if m.is_present("check") {
+        return check_invoice_numbers();
+    }
     let (search_terms, dir) = matches_to_search(m);
 
-    let field = "INVOICE-NUMBER";
     actions::with_projects(dir, &search_terms, |project| {
-        if !project.empty_fields().iter().any(|s| s == field) {
+        if project.invoice().number().is_ok() {
             return Err(format_err!("Invoice number already set in {}", project.short_desc()));
         }
+        // the number comes from the series of the invoice date, which is today unless set already,
+        // numbers given to earlier projects of this run are on disk by now
+        let date = project.invoice().date().unwrap_or_else(|_| Utc::today());
+        let number = actions::next_invoice_number(date.year())?;
         if util::really(&lformat!(
             "Do you want to set the invoice number in {:?} to {}?",
             project.short_desc(),
-            value
+            number
         )) {
-            project.replace_field(field, &value)
+            project.set_invoice_number(number, date)?;
+            Ok(())
         } else {
             Err(format_err!("Don't want to"))
         }
@@ -387,6 +501,121 @@ pub fn invoice(m: &ArgMatches) -> Result<(), Error> {
     Ok(())
 }
 
+/// Command CANCEL
+pub fn cancel(m: &ArgMatches) -> Result<(), Error> {
+    // every canceled invoice gets a number of its own
+    let number = Cell::new(actions::next_cancellation_number(Utc::today().year())?);
+    let reason = m.value_of("reason");
+
+    let (search_terms, dir) = matches_to_search(m);
+
+    actions::with_projects(dir, &search_terms, |project| {
+        if project.invoice().number().is_err() {
+            return Err(format_err!("{} has no invoice to cancel", project.short_desc()));
+        }
+        if util::really(&lformat!(
+            "Do you want to cancel invoice {} of {:?} with cancellation number {}?",
+            project.invoice().number_long_str().unwrap_or_default(),
+            project.short_desc(),
+            number.get()
+        )) {
+            project.add_cancellation(number.get(), Utc::today(), reason)?;
+            number.set(number.get() + 1);
+            Ok(())
+        } else {
+            Err(format_err!("Don't want to"))
+        }
+    })?;
+    Ok(())
+}
+
+/// Command PAY
+pub fn pay(m: &ArgMatches) -> Result<(), Error> {
+    let amount = m.value_of("amount").unwrap();
+    let amount = amount
+        .replace(',', ".")
+        .parse::<f64>()
+        .map_err(|_| format_err!("{:?} is not an amount", amount))?;
+    let date = match m.value_of("date") {
+        Some(date) => parse_dmy_date(date).ok_or_else(|| format_err!("{:?} is not a date", date))?,
+        None => Utc::today(),
+    };
+    let payment = Payment {
+        date,
+        amount: util::to_currency(amount),
+        method: m.value_of("method").map(ToOwned::to_owned),
+        note: m.value_of("note").map(ToOwned::to_owned),
+    };
+    let (search_terms, dir) = matches_to_search(m);
+
+    let outstanding = actions::pay(dir, &search_terms, &payment)?;
+    println!(
+        "{}",
+        lformat!("Still outstanding: {}", util::currency_to_string(&outstanding))
+    );
+    Ok(())
+}
+
+/// Command HOURS
+pub fn hours(m: &ArgMatches) -> Result<(), Error> {
+    let name = m.value_of("name").unwrap();
+    let time = |arg| {
+        let time = m.value_of(arg).unwrap();
+        Some(time)
+            .filter(|t| t.chars().all(|c| c.is_ascii_digit() || c == ':' || c == '.'))
+            .and_then(util::naive_time_from_str)
+            .ok_or_else(|| format_err!("{:?} is not a time", time))
+    };
+    let date = match m.value_of("date") {
+        Some(date) => Some(parse_dmy_date(date).ok_or_else(|| format_err!("{:?} is not a date", date))?),
+        None => None,
+    };
+    let pause = match m.value_of("pause") {
+        Some(pause) => pause
+            .parse::<u32>()
+            .map(i64::from)
+            .map_err(|_| format_err!("{:?} is not a number of minutes", pause))?,
+        None => 0,
+    };
+    let (search_terms, dir) = matches_to_search(m);
+
+    let project = actions::add_shift(dir, &search_terms, name, date, time("begin")?, time("end")?, pause)?;
+    if let Some(employee) = project
+        .hours()
+        .employees()?
+        .iter()
+        .find(|employee| employee.name == name)
+    {
+        println!(
+            "{}",
+            lformat!("{} worked {}h in {}", name, employee.time, project.short_desc())
+        );
+    }
+    Ok(())
+}
+
+/// Command INVOICE --check
+fn check_invoice_numbers() -> Result<(), Error> {
+    let checks = actions::check_invoice_numbers()?;
+    for check in &checks {
+        for (number, projects) in &check.duplicates {
+            println!(
+                "{}",
+                lformat!("{}: R{:03} is used by {}", check.year, number, projects.join(", "))
+            );
+        }
+        for number in &check.missing {
+            println!("{}", lformat!("{}: R{:03} is missing", check.year, number));
+        }
+    }
+    if checks.is_empty() {
+        println!("{}", lformat!("Invoice numbers are gapless and unique"));
+        Ok(())
+    } else {
+        bail!(lformat!("Invoice numbers are not gapless"))
+    }
+}
+
 /// Command CALENDAR
 pub fn calendar(matches: &ArgMatches) -> Result<(), Error> {
     let calendar = actions::calendar_with_tasks(matches_to_dir(matches), matches.is_present("tasks"))?;
@@ -397,8 +626,12 @@ pub fn calendar(matches: &ArgMatches) -> Result<(), Error> {
 /// Command SPEC
 /// TODO: make this not panic :D
 /// TODO: move this to `spec::all_the_things`
-pub fn spec(_: &ArgMatches) -> Result<(), Error> {
-    actions::spec()?;
+pub fn spec(matches: &ArgMatches) -> Result<(), Error> {
+    if matches.is_present("schema") {
+        println!("{}", actions::spec_schema()?);
+    } else {
+        actions::spec()?;
+    }
     Ok(())
 }
 
@@ -407,11 +640,16 @@ use self::document_export::ExportConfig;
 
 #[cfg(feature = "document_export")]
 fn infer_bill_type(m: &ArgMatches) -> Option<BillType> {
-    match (m.is_present("offer"), m.is_present("invoice")) {
-        (true, true) => unreachable!("this should have been prevented by clap-rs"),
-        (true, false) => Some(BillType::Offer),
-        (false, true) => Some(BillType::Invoice),
-        (false, false) => None,
+    match (
+        m.is_present("offer"),
+        m.is_present("invoice"),
+        m.is_present("cancellation"),
+    ) {
+        (false, false, false) => None,
+        (true, false, false) => Some(BillType::Offer),
+        (false, true, false) => Some(BillType::Invoice),
+        (false, false, true) => Some(BillType::Cancellation),
+        _ => unreachable!("this should have been prevented by clap-rs"),
     }
 }
 
@@ -433,6 +671,7 @@ fn matches_to_export_config(m: &ArgMatches) -> Option<ExportConfig> {
         force: m.is_present("force"),
         print_only: m.is_present("print-only"),
         open: m.is_present("open"),
+        zugferd: m.is_present("zugferd"),
     };
 
     if m.is_present("search_term") {
@@ -467,6 +706,9 @@ fn matches_to_export_config(m: &ArgMatches) -> Option<ExportConfig> {
 pub fn make(m: &ArgMatches) -> Result<(), Error> {
     log::debug!("{:?}", m);
     if let Some(ref config) = matches_to_export_config(m) {
+        if m.is_present("xrechnung") {
+            return make_xrechnung(config);
+        }
         document_export::projects_to_doc(config)?; // TODO: if-let this TODO should return Result
         Ok(())
     } else {
@@ -474,15 +716,46 @@ pub fn make(m: &ArgMatches) -> Result<(), Error> {
     }
 }
 
+/// Command MAKE --xrechnung
+#[cfg(feature = "document_export")]
+fn make_xrechnung(config: &ExportConfig<'_>) -> Result<(), Error> {
+    let storage = setup::<Project>()?;
+    let bill_type = match config.bill_type {
+        Some(BillType::Cancellation) => BillType::Cancellation,
+        _ => BillType::Invoice,
+    };
+    for project in storage.open_projects(&config.select)? {
+        let xml = if bill_type == BillType::Cancellation {
+            project.cancellation_to_xrechnung()?
+        } else {
+            project.to_xrechnung()?
+        };
+        if config.print_only {
+            println!("{}", xml);
+        } else if config.dry_run {
+            let target = project.full_file_path(bill_type, "xml")?;
+            println!("{}", lformat!("Dry run! Would write {}", target.display()));
+        } else {
+            let target = match config.output {
+                Some(output) if output.is_dir() => {
+                    let file_name = project.full_file_path(bill_type, "xml")?;
+                    output.join(file_name.file_name().unwrap())
+                },
+                Some(output) => output.to_owned(),
+                None => project.full_file_path(bill_type, "xml")?,
+            };
+            fs::write(&target, xml)?;
+            println!("{}", lformat!("Wrote {}", target.display()));
+        }
+    }
+    Ok(())
+}
+
 /// Command DELETE
 pub fn delete(m: &ArgMatches) -> Result<(), Error> {
     let (search_terms, dir) = matches_to_search(m);
-    if m.is_present("template") {
-        unimplemented!();
-    } else {
-        actions::delete_project_confirmation(dir, &search_terms)?;
-        Ok(())
-    }
+    actions::delete_project_confirmation(dir, &search_terms)?;
+    Ok(())
 }
 
 #[cfg(not(feature = "document_export"))]
@@ -519,6 +792,40 @@ pub fn unarchive(matches: &ArgMatches) -> Result<(), Error> {
     Ok(())
 }
 
+/// Command MIGRATE
+pub fn migrate(matches: &ArgMatches) -> Result<(), Error> {
+    let (search_terms, dir) = matches_to_search(matches);
+    let dry_run = matches.is_present("dry_run");
+    let migration = actions::migrate(dir, &search_terms, dry_run)?;
+    let migrated = &migration.migrated;
+
+    for (project, migrated) in migrated {
+        println!(
+            "{} ({} → {})\n{}",
+            project.file().display(),
+            migrated.from,
+            migrated.to,
+            migrated.diff()
+        );
+    }
+
+    if migrated.is_empty() && migration.failed.is_empty() {
+        println!("{}", lformat!("Everything is up to date"));
+    } else if dry_run {
+        println!("{}", lformat!("{} projects would be migrated", migrated.len()));
+    } else {
+        println!("{}", lformat!("{} projects migrated", migrated.len()));
+    }
+
+    for (project, error) in &migration.failed {
+        eprintln!("{}: {:#}", project.file().display(), error);
+    }
+    if !migration.failed.is_empty() {
+        bail!(actions::error::ActionError::MigrationFailed(migration.failed.len()));
+    }
+    Ok(())
+}
+
 pub fn config(matches: &ArgMatches) -> Result<(), Error> {
     let editor = matches
         .value_of("editor")
@@ -641,17 +948,41 @@ pub fn version(matches: &ArgMatches) -> Result<(), Error> {
 pub fn dues(matches: &ArgMatches) -> Result<(), Error> {
     let dues = actions::dues();
     if let Ok(dues) = dues {
-        println!("Open Payments: {}", dues.acc_sum_sold.postfix());
-        println!("Open Wages:    {}", dues.acc_wages.postfix());
+        println!("Open Payments: {}", dues.acc_sum_sold);
+        println!("Already Payed: {}", dues.acc_payed);
+        println!("Open Wages:    {}", dues.acc_wages);
+        if matches.is_present("invoices") {
+            for (project, outstanding) in &dues.open_invoices {
+                println!("{}:    {}", project, util::currency_to_string(outstanding));
+            }
+        }
         if matches.is_present("wages") {
             for (employee, open_wages) in &dues.unpayed_employees {
-                println!("{}:    {}", employee, open_wages.postfix());
+                println!("{}:    {}", employee, open_wages);
             }
         }
     }
     Ok(())
 }
 
+/// Command REPORT
+pub fn report(matches: &ArgMatches) -> Result<(), Error> {
+    let year = matches
+        .value_of("year")
+        .map(|y| y.parse::<i32>().map_err(|_| format_err!("{:?} is not a year", y)))
+        .transpose()?;
+    let group_by = matches.value_of("by").map(str::parse).transpose()?;
+    let report = actions::report(year, group_by)?;
+    if matches.is_present("json") {
+        println!("{}", report.to_json()?);
+    } else if matches.is_present("csv") {
+        print!("{}", report.to_csv()?);
+    } else {
+        print::print_report(&report);
+    }
+    Ok(())
+}
+
 // pub fn open_path(matches:&ArgMatches){path(matches, |path| {open::that(path).unwrap();})}
 pub fn open_path(m: &ArgMatches) -> Result<(), Error> {
     path(m, |path| {
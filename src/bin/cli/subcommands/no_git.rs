@@ -0,0 +1,52 @@
+//! The git commands need the `git_statuses` feature.
+
+use anyhow::{bail, Error};
+use clap::ArgMatches;
+
+fn not_built_in() -> Result<(), Error> {
+    bail!(lformat!("Git functionality not built-in with this release!"))
+}
+
+pub fn git_log(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_status() -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_commit(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_remote() -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_add(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_diff(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_pull(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_push() -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_stash() -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_cleanup(_: &ArgMatches) -> Result<(), Error> {
+    not_built_in()
+}
+
+pub fn git_stash_pop() -> Result<(), Error> {
+    not_built_in()
+}
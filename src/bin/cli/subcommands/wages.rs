@@ -0,0 +1,97 @@
+use anyhow::Error;
+use clap::ArgMatches;
+
+use asciii::{
+    actions::{self, wages::EmployeeWages},
+    util,
+};
+
+/// Command WAGES
+pub fn wages(matches: &ArgMatches) -> Result<(), Error> {
+    let month = matches.value_of("month").map(str::parse).transpose()?;
+    let wages = actions::wages(month, matches.value_of("employee"))?;
+    if wages.is_empty() {
+        println!("{}", lformat!("Nobody worked then"));
+    }
+
+    for employee in &wages {
+        if matches.is_present("payslip") {
+            make_payslip(matches, employee, month)?;
+        } else {
+            print_wages(employee);
+        }
+    }
+    Ok(())
+}
+
+fn print_wages(employee: &EmployeeWages) {
+    println!("{}", employee.name);
+    let width = employee
+        .shifts
+        .iter()
+        .map(|shift| shift.project.chars().count())
+        .max()
+        .unwrap_or(0);
+    for shift in &employee.shifts {
+        println!(
+            "  {:10}  {:width$}  {:>5}h  {:>10}  {}",
+            shift.date.map(|d| d.format("%d.%m.%Y").to_string()).unwrap_or_default(),
+            shift.project,
+            shift.time,
+            util::currency_to_string(&shift.wage),
+            shift
+                .payed_on
+                .map(|d| lformat!("payed on {}", d.format("%d.%m.%Y")))
+                .unwrap_or_else(|| lformat!("unpayed")),
+            width = width
+        );
+    }
+    println!(
+        "  {}",
+        lformat!(
+            "{}h, wages {}, unpayed {}",
+            employee.total_time(),
+            employee.total(),
+            employee.unpayed()
+        )
+    );
+    println!();
+}
+
+/// Command WAGES --payslip
+#[cfg(feature = "document_export")]
+fn make_payslip(
+    matches: &ArgMatches,
+    employee: &EmployeeWages,
+    month: Option<actions::wages::Month>,
+) -> Result<(), Error> {
+    use asciii::{
+        document_export::{self, ExportConfig},
+        CONFIG,
+    };
+    use std::path::Path;
+
+    let config = ExportConfig {
+        template_name: matches
+            .value_of("template")
+            .unwrap_or_else(|| CONFIG.get_str("document_export/payslip_template")),
+        output: matches.value_of("output").map(Path::new),
+        dry_run: matches.is_present("dry-run"),
+        print_only: matches.is_present("print-only"),
+        open: matches.is_present("open"),
+        ..ExportConfig::default()
+    };
+    if let Some(path) = document_export::payslip_to_doc(&employee.payslip(month), &config)? {
+        println!("{}", lformat!("Wrote {}", path.display()));
+        if config.open {
+            open::that(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "document_export"))]
+fn make_payslip(_: &ArgMatches, _: &EmployeeWages, _: Option<actions::wages::Month>) -> Result<(), Error> {
+    log::error!("Make functionality not built-in with this release!");
+    Ok(())
+}
@@ -10,4 +10,13 @@ pub enum ExportError {
 
     #[error("Template not found at {:?}", _0)]
     TemplateNotFoundAt(PathBuf),
+
+    #[error("Invalid project: {}", _0.join(", "))]
+    InvalidProject(Vec<String>),
+
+    #[error("Unknown renderer {:?}, try \"latex\" or \"typst\"", _0)]
+    UnknownRenderer(String),
+
+    #[error("Only invoices and cancellations can be embedded as ZUGFeRD")]
+    NoZugferdForOffer,
 }
@@ -1,6 +1,7 @@
 //! Fills export templates to create tex documents.
 //!
 //! Haven't decided on a templating engine yet, my own will probably not do.
+//! How the filled template becomes a pdf is up to a [`Renderer`](renderer/trait.Renderer.html).
 
 use std::{
     fs,
@@ -10,15 +11,16 @@ use std::{
 
 use anyhow::{bail, Error};
 use serde::ser::Serialize;
-use yaml_rust::Yaml;
+use tempdir::TempDir;
 
 use handlebars::{no_escape, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext};
 
 use crate::{
+    actions::wages::Payslip,
     project::{
         self,
         export::ExportTarget,
-        BillType::{self, Invoice, Offer},
+        BillType::{self, Cancellation, Invoice, Offer},
         Exportable, Project,
     },
     storage::{self, Storable, StorageSelection},
@@ -26,14 +28,20 @@ use crate::{
 };
 
 pub mod error;
+pub mod renderer;
+pub mod zugferd;
 
-use self::error::*;
+use self::{
+    error::*,
+    renderer::{Latex, Renderer},
+};
 
 #[cfg_attr(feature = "serialization", derive(Serialize))]
 struct DocAndStorage<'a, T: Serialize> {
     document: &'a T,
     storage: Option<storage::Paths>,
     is_invoice: bool,
+    is_cancellation: bool,
 }
 
 impl<'a, T: 'a + Serialize> DocAndStorage<'a, T> {
@@ -42,6 +50,17 @@ impl<'a, T: 'a + Serialize> DocAndStorage<'a, T> {
             document,
             storage: storage::setup::<Project>().ok().map(|s| s.paths()),
             is_invoice: bill_type == Invoice,
+            is_cancellation: bill_type == Cancellation,
+        }
+    }
+
+    /// For documents that are neither offer nor invoice.
+    fn without_bill(document: &T) -> DocAndStorage<'_, T> {
+        DocAndStorage {
+            document,
+            storage: storage::setup::<Project>().ok().map(|s| s.paths()),
+            is_invoice: false,
+            is_cancellation: false,
         }
     }
 }
@@ -88,7 +107,7 @@ impl HelperDef for CountHelper {
     }
 }
 
-/// Takes a `T: Serialize` and a template path and does it's thing.
+/// Takes a `T: Serialize` and a latex template path and does it's thing.
 ///
 /// Returns path to created file, potentially in a `tempdir`.
 // pub fn fill_template<E:Serialize>(document:E, template_file:&Path) -> PathBuf{
@@ -96,20 +115,40 @@ pub fn fill_template<E, P>(document: &E, bill_type: BillType, template_path: P)
 where
     E: Serialize,
     P: AsRef<Path>,
+{
+    fill_template_for(&Latex::from_config(), document, bill_type, template_path)
+}
+
+/// Like `fill_template()`, but escapes for whatever `renderer` expects.
+pub fn fill_template_for<E, P>(
+    renderer: &dyn Renderer,
+    document: &E,
+    bill_type: BillType,
+    template_path: P,
+) -> Result<String, Error>
+where
+    E: Serialize,
+    P: AsRef<Path>,
+{
+    fill(renderer, &DocAndStorage::from(document, bill_type), template_path)
+}
+
+fn fill<T, P>(renderer: &dyn Renderer, data: &T, template_path: P) -> Result<String, Error>
+where
+    T: Serialize,
+    P: AsRef<Path>,
 {
     let mut handlebars = Handlebars::new();
 
     handlebars.register_escape_fn(no_escape);
-    handlebars.register_escape_fn(|data| data.replace('\n', r"\newline "));
+    handlebars.register_escape_fn(renderer.escape());
 
     handlebars.register_helper("inc", Box::new(IncHelper));
     // handlebars.register_helper("count", Box::new(count_helper));
 
     handlebars.register_template_file("document", template_path).unwrap();
 
-    Ok(handlebars
-        .render("document", &DocAndStorage::from(document, bill_type))
-        .map(|r| r.replace('<', "{").replace('>', "}"))?)
+    Ok(handlebars.render("document", data).map(|r| renderer.finish(r))?)
 }
 
 fn file_age(path: &Path) -> Result<time::Duration, Error> {
@@ -118,9 +157,8 @@ fn file_age(path: &Path) -> Result<time::Duration, Error> {
     Ok(modified.elapsed()?)
 }
 
-fn output_template_path(template_name: &str) -> Result<PathBuf, Error> {
+fn output_template_path(template_name: &str, template_ext: &str) -> Result<PathBuf, Error> {
     // construct_template_path(&template_name) {
-    let template_ext = crate::CONFIG.get_str("extensions/output_template");
     let mut template_path = PathBuf::new();
     template_path.push(storage::get_storage_path());
     template_path.push(crate::CONFIG.get_str("dirs/templates"));
@@ -151,30 +189,28 @@ fn project_to_doc(project: &Project, config: &ExportConfig<'_>) -> Result<Option
         force,
         pdf_only,
         print_only,
+        zugferd,
         ..
     } = config;
 
     // init_export_config()
-    let output_ext = crate::CONFIG.get_str("extensions/output_file");
+    let renderer = renderer::for_template(template_name)?;
+    let output_ext = renderer.output_extension();
     let convert_ext = crate::CONFIG.get_str("document_export/output_extension");
-    let convert_tool = crate::CONFIG.get_str("document_export/convert_tool");
     let output_folder = util::get_valid_path(crate::CONFIG.get_str("output_path")).unwrap();
-    let trash_exts = crate::CONFIG
-        .get("document_export/trash_extensions")
-        .expect("Faulty default config")
-        .as_vec()
-        .expect("Faulty default config")
-        .iter()
-        .map(Yaml::as_str)
-        .collect::<Vec<_>>();
-
-    let template_path = output_template_path(template_name)?;
-    log::debug!("converting with {:?}", convert_tool);
+
+    let template_path = output_template_path(template_name, renderer.template_extension())?;
+    log::debug!("converting with {:?}", renderer);
     log::debug!("template {:?}", template_path);
 
     // project_readiness(&project) {
+    let invalid = project.validate_taxes().validation_errors;
+    if !invalid.is_empty() {
+        bail!(ExportError::InvalidProject(invalid));
+    }
     let missing_for_offer = project.is_missing_for_offer();
     let missing_for_invoice = project.is_missing_for_invoice();
+    let missing_for_cancellation = project.is_missing_for_cancellation();
     let project_file = project.file();
 
     let default_mode = if missing_for_invoice.is_empty() { Invoice } else { Offer };
@@ -188,6 +224,15 @@ fn project_to_doc(project: &Project, config: &ExportConfig<'_>) -> Result<Option
             (Some(Invoice), Some(project.dir().join(project.invoice_file_name(output_ext)
                                                     .expect("this should have been caught by missing_for_invoice()")))),
 
+        Cancellation if missing_for_cancellation.is_empty() =>
+            (Some(Cancellation), Some(project.dir().join(project.cancellation_file_name(output_ext)
+                                                    .expect("this should have been caught by missing_for_cancellation()")))),
+
+        Cancellation => {
+            log::error!("cannot create a cancellation, check out:{}",missing_for_cancellation.join("|"));
+            (None,None)
+        },
+
         Offer if !missing_for_offer.is_empty() && bill_type.is_some() => {
             log::error!("cannot create an offer, check out:{}",missing_for_offer.join("|"));
             (None,None)
@@ -208,7 +253,13 @@ fn project_to_doc(project: &Project, config: &ExportConfig<'_>) -> Result<Option
 
     if let (Some(tex_file), Some(dyn_bill)) = (outfile_tex, dyn_bill_type) {
         let exported_project: project::export::Complete = project.export();
-        let filled = fill_template(&exported_project, dyn_bill, &template_path)?;
+        let filled = fill_template_for(renderer.as_ref(), &exported_project, dyn_bill, &template_path)?;
+        let e_invoice = match dyn_bill {
+            _ if !zugferd => None,
+            Invoice => Some(project.to_factur_x()?),
+            Cancellation => Some(project.cancellation_to_factur_x()?),
+            Offer => bail!(ExportError::NoZugferdForOffer),
+        };
 
         let pdffile = util::to_local_file(&tex_file, convert_ext);
 
@@ -258,7 +309,7 @@ fn project_to_doc(project: &Project, config: &ExportConfig<'_>) -> Result<Option
         } else {
             // ok, we really have to work
 
-            let mut outfile_path = if pdf_only {
+            let outfile_path = if pdf_only {
                 let (tex_age, project_age) = (file_age(&tex_file)?, file_age(&project_file)?);
                 log::info!("recreating the pdf");
                 log::debug!("{:?} -> {:?}", tex_file, document_file);
@@ -274,31 +325,14 @@ fn project_to_doc(project: &Project, config: &ExportConfig<'_>) -> Result<Option
                 log::debug!("{} vs\n        {}", tex_file.display(), outfile_path.display());
                 outfile_path
             };
-            util::pass_to_command(Some(convert_tool), &[&outfile_path])?;
-
-            // clean up expected log and aux files etc
-            for trash_ext in trash_exts.iter().filter_map(|x| *x) {
-                let trash_file = util::to_local_file(&tex_file, trash_ext);
-                if trash_file.exists() {
-                    fs::remove_file(&trash_file)?;
-                    log::debug!("just deleted: {}", trash_file.display())
-                } else {
-                    log::debug!(
-                        "I expected there to be a {}, but there wasn't any ?",
-                        trash_file.display()
-                    )
-                }
+            let file = renderer.render(&outfile_path)?;
+            if let Some(e_invoice) = e_invoice {
+                zugferd::embed(&file, &e_invoice)?;
             }
 
             // now we move the created pdf
-            outfile_path.set_extension("pdf");
-            if pdffile.exists() || outfile_path.exists() {
-                let file = if pdffile.exists() { pdffile } else { outfile_path };
-                log::debug!("now there is be a {:?} -> {:?}", file, document_file);
-                fs::rename(&file, &document_file)?;
-            } else {
-                bail!(ExportError::NoPdfCreated);
-            }
+            log::debug!("now there is be a {:?} -> {:?}", file, document_file);
+            fs::rename(&file, &document_file)?;
             Ok(Some(document_file))
         }
     } else {
@@ -317,6 +351,8 @@ pub struct ExportConfig<'a> {
     pub force: bool,
     pub print_only: bool,
     pub open: bool,
+    /// embed the invoice as ZUGFeRD/Factur-X into the pdf
+    pub zugferd: bool,
 }
 
 impl<'a> Default for ExportConfig<'a> {
@@ -331,6 +367,7 @@ impl<'a> Default for ExportConfig<'a> {
             force: false,
             print_only: false,
             open: true,
+            zugferd: false,
         }
     }
 }
@@ -348,3 +385,51 @@ pub fn projects_to_doc(config: &ExportConfig<'_>) -> Result<(), Error> {
     }
     Ok(())
 }
+
+/// Creates the payslip of one employee, `config.select` and `config.bill_type` are ignored.
+///
+/// The template is filled in a `tempdir`, only the pdf ends up in the `output_path`.
+#[cfg(feature = "document_export")]
+pub fn payslip_to_doc(payslip: &Payslip, config: &ExportConfig<'_>) -> Result<Option<PathBuf>, Error> {
+    let renderer = renderer::for_template(config.template_name)?;
+    let template_path = output_template_path(config.template_name, renderer.template_extension())?;
+    let filled = fill(renderer.as_ref(), &DocAndStorage::without_bill(payslip), &template_path)?;
+
+    let mut file_stem = format!("{} {}", lformat!("Payslip"), payslip.employee);
+    if let Some(ref period) = payslip.period {
+        file_stem.push(' ');
+        file_stem.push_str(period);
+    }
+    let file_stem = file_stem.replace([' ', '/'], "_");
+    let pdf_name = Path::new(&file_stem).with_extension(crate::CONFIG.get_str("document_export/output_extension"));
+    let document_file = match config.output {
+        Some(output) if output.is_dir() => output.join(&pdf_name),
+        Some(output) => output.to_owned(),
+        None => util::get_valid_path(crate::CONFIG.get_str("output_path"))
+            .ok_or(ExportError::NoPdfCreated)?
+            .join(&pdf_name),
+    };
+
+    if config.print_only {
+        println!("{}", filled);
+        Ok(None)
+    } else if config.dry_run {
+        log::warn!(
+            "Dry run! This does not produce any output:\n * {}",
+            document_file.display()
+        );
+        Ok(None)
+    } else {
+        let temp_dir = TempDir::new("asciii_payslip")?;
+        let source = temp_dir
+            .path()
+            .join(&file_stem)
+            .with_extension(renderer.output_extension());
+        fs::write(&source, filled)?;
+        let file = renderer.render(&source)?;
+        log::debug!("now there is be a {:?} -> {:?}", file, document_file);
+        fs::copy(&file, &document_file)?;
+        fs::remove_file(&file)?;
+        Ok(Some(document_file))
+    }
+}
@@ -0,0 +1,207 @@
+//! Backends that turn a filled template into a pdf.
+//!
+//! Which one is used is configured per template, see `document_export/renderers` in the config.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Error};
+use yaml_rust::Yaml;
+
+use super::error::ExportError;
+use crate::util;
+
+/// A document format that templates can be written in and that can be converted to pdf.
+pub trait Renderer: std::fmt::Debug {
+    /// Extension of the filled template, e.g. `tex`
+    fn output_extension(&self) -> &str;
+
+    /// Extension of the template files, e.g. `tex.hbs`
+    fn template_extension(&self) -> &str;
+
+    /// Escapes values before they are inserted into the template.
+    fn escape(&self) -> fn(&str) -> String;
+
+    /// Applied to the filled template before it is written.
+    fn finish(&self, filled: String) -> String {
+        filled
+    }
+
+    /// Converts the filled template at `source` and returns the path of the created pdf.
+    fn render(&self, source: &Path) -> Result<PathBuf, Error>;
+}
+
+/// The `pdflatex` pipeline, which is the default.
+#[derive(Debug)]
+pub struct Latex {
+    pub convert_tool: String,
+    pub trash_extensions: Vec<String>,
+}
+
+impl Latex {
+    pub fn from_config() -> Self {
+        Latex {
+            convert_tool: crate::CONFIG.get_to_string("document_export/convert_tool"),
+            trash_extensions: crate::CONFIG
+                .get("document_export/trash_extensions")
+                .and_then(Yaml::as_vec)
+                .map(|exts| exts.iter().filter_map(Yaml::as_str).map(ToOwned::to_owned).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Renderer for Latex {
+    fn output_extension(&self) -> &str {
+        crate::CONFIG.get_str("extensions/output_file")
+    }
+
+    fn template_extension(&self) -> &str {
+        crate::CONFIG.get_str("extensions/output_template")
+    }
+
+    fn escape(&self) -> fn(&str) -> String {
+        |data| data.replace('\n', r"\newline ")
+    }
+
+    /// Latex templates write `<` and `>` for braces, handlebars would choke on them otherwise.
+    fn finish(&self, filled: String) -> String {
+        filled.replace('<', "{").replace('>', "}")
+    }
+
+    fn render(&self, source: &Path) -> Result<PathBuf, Error> {
+        log::debug!("converting with {:?}", self.convert_tool);
+        util::pass_to_command(Some(&self.convert_tool), &[source])?;
+
+        // clean up expected log and aux files etc
+        for trash_ext in &self.trash_extensions {
+            let trash_file = util::to_local_file(source, trash_ext);
+            if trash_file.exists() {
+                fs::remove_file(&trash_file)?;
+                log::debug!("just deleted: {}", trash_file.display())
+            } else {
+                log::debug!(
+                    "I expected there to be a {}, but there wasn't any ?",
+                    trash_file.display()
+                )
+            }
+        }
+
+        // pdflatex writes into the current directory
+        let local_pdf = util::to_local_file(source, "pdf");
+        let pdf = source.with_extension("pdf");
+        if local_pdf.exists() {
+            Ok(local_pdf)
+        } else if pdf.exists() {
+            Ok(pdf)
+        } else {
+            bail!(ExportError::NoPdfCreated)
+        }
+    }
+}
+
+/// Renders [typst](https://typst.app) templates, no TeX installation needed.
+#[derive(Debug)]
+pub struct Typst {
+    pub convert_tool: String,
+}
+
+impl Typst {
+    pub fn from_config() -> Self {
+        Typst {
+            convert_tool: crate::CONFIG.get_to_string("document_export/typst_tool"),
+        }
+    }
+}
+
+impl Renderer for Typst {
+    fn output_extension(&self) -> &str {
+        "typ"
+    }
+
+    fn template_extension(&self) -> &str {
+        "typ.hbs"
+    }
+
+    fn escape(&self) -> fn(&str) -> String {
+        escape_typst
+    }
+
+    fn render(&self, source: &Path) -> Result<PathBuf, Error> {
+        let pdf = source.with_extension("pdf");
+        log::debug!("converting with {:?}", self.convert_tool);
+        let status = std::process::Command::new(&self.convert_tool)
+            .arg("compile")
+            .arg(source)
+            .arg(&pdf)
+            .status()?;
+        if status.success() && pdf.exists() {
+            Ok(pdf)
+        } else {
+            bail!(ExportError::NoPdfCreated)
+        }
+    }
+}
+
+/// Escapes markup, values are meant to be inserted as content, not into string literals.
+///
+/// Lists (`-`, `+`) and headings (`=`) are only markup at the start of a line, `/` is escaped everywhere.
+/// Each `\n` becomes a line break, so the next line starts after it.
+fn escape_typst(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    let mut line_start = true;
+    for c in data.chars() {
+        match c {
+            '\\' | '#' | '*' | '_' | '`' | '$' | '@' | '<' | '>' | '[' | ']' | '~' | '/' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '-' | '+' | '=' if line_start => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' => escaped.push_str(" \\\n"),
+            _ => escaped.push(c),
+        }
+        line_start = c == '\n' || (line_start && (c == ' ' || c == '\t'));
+    }
+    escaped
+}
+
+/// Picks the renderer configured for `template_name`, falls back to `document_export/renderer`.
+pub fn for_template(template_name: &str) -> Result<Box<dyn Renderer>, Error> {
+    let name = crate::CONFIG
+        .get(&format!("document_export/renderers/{}", template_name))
+        .and_then(Yaml::as_str)
+        .unwrap_or_else(|| crate::CONFIG.get_str("document_export/renderer"));
+    log::debug!("rendering {:?} with {:?}", template_name, name);
+
+    match name {
+        "latex" => Ok(Box::new(Latex::from_config())),
+        "typst" => Ok(Box::new(Typst::from_config())),
+        unknown => bail!(ExportError::UnknownRenderer(unknown.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typst_escapes_markup() {
+        assert_eq!(escape_typst("Foo & Bar"), "Foo & Bar");
+        assert_eq!(escape_typst("#set *bold* $1"), r"\#set \*bold\* \$1");
+        assert_eq!(escape_typst("Street 1\n12345 City"), "Street 1 \\\n12345 City");
+    }
+
+    #[test]
+    fn typst_escapes_line_starts() {
+        assert_eq!(escape_typst("- no list"), r"\- no list");
+        assert_eq!(escape_typst("= no heading"), r"\= no heading");
+        assert_eq!(escape_typst("/ no: term"), r"\/ no: term");
+        assert_eq!(escape_typst("a\n+ b\n  - c"), "a \\\n\\+ b \\\n  \\- c");
+        assert_eq!(escape_typst("1 - 2 = -1 + x"), "1 - 2 = -1 + x");
+    }
+}
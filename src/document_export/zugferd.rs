@@ -0,0 +1,213 @@
+//! Attaches the CII e-invoice to a rendered pdf, which makes it a ZUGFeRD/Factur-X invoice.
+//!
+//! The xml becomes an embedded file of the document (`/AF` and `/Names/EmbeddedFiles`),
+//! the XMP metadata declares PDF/A-3b and the Factur-X fields readers look for.
+//! Whether the rest of the pdf is valid PDF/A-3 (embedded fonts, output intent) is up to the renderer.
+
+use std::path::Path;
+
+use anyhow::Error;
+use lopdf::{dictionary, Dictionary, Document, Object, Stream};
+
+/// The name Factur-X and ZUGFeRD expect for the EN 16931 profile
+pub const FILE_NAME: &str = "factur-x.xml";
+
+const CONFORMANCE_LEVEL: &str = "EN 16931";
+
+/// Embeds `xml` into the pdf at `pdf`, which is overwritten.
+pub fn embed(pdf: &Path, xml: &str) -> Result<(), Error> {
+    let mut document = Document::load(pdf)?;
+    embed_into(&mut document, xml)?;
+    document.save(pdf)?;
+    Ok(())
+}
+
+fn embed_into(document: &mut Document, xml: &str) -> Result<(), Error> {
+    let modified = chrono::Utc::now().format("D:%Y%m%d%H%M%SZ").to_string();
+    let file = document.add_object(Stream::new(
+        dictionary! {
+            "Type" => "EmbeddedFile",
+            "Subtype" => Object::Name(b"text/xml".to_vec()),
+            "Params" => dictionary! {
+                "Size" => xml.len() as i64,
+                "ModDate" => Object::string_literal(modified),
+            },
+        },
+        xml.as_bytes().to_vec(),
+    ));
+    let file_spec = document.add_object(dictionary! {
+        "Type" => "Filespec",
+        "F" => Object::string_literal(FILE_NAME),
+        "UF" => Object::string_literal(FILE_NAME),
+        "Desc" => Object::string_literal("Factur-X/ZUGFeRD invoice"),
+        "AFRelationship" => "Alternative",
+        "EF" => dictionary! { "F" => file, "UF" => file },
+    });
+    let metadata = document.add_object(Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        xmp_metadata().into_bytes(),
+    ));
+    let embedded_files = dictionary! {
+        "Names" => vec![Object::string_literal(FILE_NAME), file_spec.into()],
+    };
+
+    // hyperref already puts named destinations into the catalog, those have to stay
+    let names = match document.catalog()?.get(b"Names") {
+        Ok(Object::Reference(id)) => Some(*id),
+        _ => None,
+    };
+    match names {
+        Some(id) => document.get_dictionary_mut(id)?.set("EmbeddedFiles", embedded_files),
+        None => {
+            let catalog = document.catalog_mut()?;
+            match catalog.get_mut(b"Names") {
+                Ok(Object::Dictionary(names)) => names.set("EmbeddedFiles", embedded_files),
+                _ => catalog.set("Names", dictionary! { "EmbeddedFiles" => embedded_files }),
+            }
+        },
+    }
+
+    let catalog: &mut Dictionary = document.catalog_mut()?;
+    catalog.set("AF", vec![Object::from(file_spec)]);
+    catalog.set("Metadata", metadata);
+
+    // PDF/A-3 is based on PDF 1.7
+    if document.version.as_str() < "1.7" {
+        document.version = "1.7".into();
+    }
+    Ok(())
+}
+
+fn xmp_metadata() -> String {
+    let property = |name: &str, description: &str| {
+        format!(
+            r#"            <rdf:li rdf:parseType="Resource">
+              <pdfaProperty:name>{}</pdfaProperty:name>
+              <pdfaProperty:valueType>Text</pdfaProperty:valueType>
+              <pdfaProperty:category>external</pdfaProperty:category>
+              <pdfaProperty:description>{}</pdfaProperty:description>
+            </rdf:li>
+"#,
+            name, description
+        )
+    };
+    let properties = [
+        property("DocumentFileName", "name of the embedded XML invoice file"),
+        property("DocumentType", "INVOICE"),
+        property("Version", "the actual version of the Factur-X XML schema"),
+        property("ConformanceLevel", "the conformance level of the embedded Factur-X data"),
+    ]
+    .concat();
+
+    format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description rdf:about="" xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/">
+      <pdfaid:part>3</pdfaid:part>
+      <pdfaid:conformance>B</pdfaid:conformance>
+    </rdf:Description>
+    <rdf:Description rdf:about="" xmlns:fx="urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#">
+      <fx:DocumentType>INVOICE</fx:DocumentType>
+      <fx:DocumentFileName>{file_name}</fx:DocumentFileName>
+      <fx:Version>1.0</fx:Version>
+      <fx:ConformanceLevel>{level}</fx:ConformanceLevel>
+    </rdf:Description>
+    <rdf:Description rdf:about="" xmlns:pdfaExtension="http://www.aiim.org/pdfa/ns/extension/" xmlns:pdfaSchema="http://www.aiim.org/pdfa/ns/schema#" xmlns:pdfaProperty="http://www.aiim.org/pdfa/ns/property#">
+      <pdfaExtension:schemas>
+        <rdf:Bag>
+          <rdf:li rdf:parseType="Resource">
+            <pdfaSchema:schema>Factur-X PDFA Extension Schema</pdfaSchema:schema>
+            <pdfaSchema:namespaceURI>urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#</pdfaSchema:namespaceURI>
+            <pdfaSchema:prefix>fx</pdfaSchema:prefix>
+            <pdfaSchema:property>
+              <rdf:Seq>
+{properties}              </rdf:Seq>
+            </pdfaSchema:property>
+          </rdf:li>
+        </rdf:Bag>
+      </pdfaExtension:schemas>
+    </rdf:Description>
+  </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+        bom = '\u{feff}',
+        file_name = FILE_NAME,
+        level = CONFORMANCE_LEVEL,
+        properties = properties,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attached<'a>(document: &'a Document, name: &[u8]) -> &'a Object {
+        let (_, object) = document
+            .dereference(document.catalog().unwrap().get(name).unwrap())
+            .unwrap();
+        object
+    }
+
+    fn embedded_xml(document: &Document) -> Vec<u8> {
+        let names = attached(document, b"Names").as_dict().unwrap();
+        let files = names.get(b"EmbeddedFiles").unwrap().as_dict().unwrap();
+        let files = files.get(b"Names").unwrap().as_array().unwrap();
+        assert_eq!(files[0].as_str().unwrap(), FILE_NAME.as_bytes());
+        let spec = document.get_dictionary(files[1].as_reference().unwrap()).unwrap();
+        assert_eq!(spec.get(b"AFRelationship").unwrap().as_name().unwrap(), b"Alternative");
+        let file = spec.get(b"EF").unwrap().as_dict().unwrap().get(b"F").unwrap();
+        let (_, file) = document.dereference(file).unwrap();
+        file.as_stream().unwrap().content.clone()
+    }
+
+    fn document_with_catalog(catalog: Dictionary) -> Document {
+        let mut document = Document::with_version("1.5");
+        let pages = document.add_object(dictionary! { "Type" => "Pages", "Kids" => vec![], "Count" => 0 });
+        let mut catalog = catalog;
+        catalog.set("Type", "Catalog");
+        catalog.set("Pages", pages);
+        let root = document.add_object(catalog);
+        document.trailer.set("Root", root);
+        document
+    }
+
+    #[test]
+    fn embeds_the_invoice() {
+        let mut document = document_with_catalog(Dictionary::new());
+        embed_into(&mut document, "<rsm:CrossIndustryInvoice/>").unwrap();
+
+        let mut saved = Vec::new();
+        document.save_to(&mut saved).unwrap();
+        let document = Document::load_mem(&saved).unwrap();
+
+        assert_eq!(document.version, "1.7");
+        assert_eq!(embedded_xml(&document), b"<rsm:CrossIndustryInvoice/>");
+        assert_eq!(attached(&document, b"AF").as_array().unwrap().len(), 1);
+        let metadata = attached(&document, b"Metadata").as_stream().unwrap();
+        let metadata = String::from_utf8(metadata.content.clone()).unwrap();
+        assert!(metadata.contains("<pdfaid:part>3</pdfaid:part>"));
+        assert!(metadata.contains("<fx:DocumentFileName>factur-x.xml</fx:DocumentFileName>"));
+        assert!(metadata.contains("<fx:ConformanceLevel>EN 16931</fx:ConformanceLevel>"));
+    }
+
+    #[test]
+    fn keeps_named_destinations() {
+        let mut document = document_with_catalog(Dictionary::new());
+        let dests = dictionary! { "Dests" => dictionary! { "Names" => vec![] } };
+        let names = document.add_object(dests);
+        document.catalog_mut().unwrap().set("Names", names);
+        embed_into(&mut document, "<xml/>").unwrap();
+
+        let names = attached(&document, b"Names").as_dict().unwrap();
+        assert!(names.has(b"Dests"));
+        assert_eq!(embedded_xml(&document), b"<xml/>");
+
+        let mut document = document_with_catalog(dictionary! {
+            "Names" => dictionary! { "Dests" => dictionary! { "Names" => vec![] } }
+        });
+        embed_into(&mut document, "<xml/>").unwrap();
+        assert!(attached(&document, b"Names").as_dict().unwrap().has(b"Dests"));
+        assert_eq!(embedded_xml(&document), b"<xml/>");
+    }
+}
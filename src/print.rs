@@ -3,16 +3,24 @@
 use chrono::prelude::*;
 use prettytable::{
     cell, color,
-    format::{FormatBuilder, LinePosition, LineSeparator},
+    format::{Alignment, FormatBuilder, LinePosition, LineSeparator},
     row, Attr, Cell, Row, Table,
 };
 
+use std::path::PathBuf;
+
+#[cfg(all(feature = "serialization", not(target_arch = "wasm32")))]
+use crate::project::cache::Summary;
+#[cfg(feature = "git_statuses")]
+use crate::storage::repo::{Commit, Status};
 use crate::{
+    actions::{csv_lines, lines_to_csv, report::Report, CsvLine},
     project::{
+        search::Found,
         spec::{HasEmployees, HasEvents, Invoicable, IsProject, Redeemable},
         BillType, Exportable, Project,
     },
-    storage::Storable,
+    storage::{journal::Entry, repo::GitStatus, Storable},
     util::currency_to_string,
 };
 
@@ -24,6 +32,8 @@ pub struct ListConfig<'a> {
     pub git_status: bool,
     pub sort_by: &'a str,
     pub filter_by: Option<Vec<&'a str>>,
+    /// see [`project::query`](crate::project::query)
+    pub query: Option<&'a str>,
     pub use_colors: bool,
     pub details: Option<Vec<&'a str>>,
 }
@@ -49,18 +59,159 @@ impl<'a> Default for ListConfig<'a> {
             show_errors: false,
             sort_by: crate::CONFIG.get_str("list/sort"),
             filter_by: None,
+            query: None,
             use_colors: crate::CONFIG.get_bool("list/colors"),
             details: None,
         }
     }
 }
 
+/// What the rows of `list` need to know, projects have it and so do their cached summaries.
+pub trait Listable {
+    fn short_desc(&self) -> String;
+    fn canceled(&self) -> bool;
+    fn responsible(&self) -> Option<String>;
+    fn invoice_number(&self) -> Option<String>;
+    fn date(&self) -> Option<Date<Utc>>;
+    fn index(&self) -> Option<String>;
+    fn file(&self) -> PathBuf;
+    fn git_status(&self) -> GitStatus;
+    fn missing_for_offer(&self) -> Vec<String>;
+    fn missing_for_invoice(&self) -> Vec<String>;
+    fn missing_for_archive(&self) -> Vec<String>;
+    fn offer_file_exists(&self) -> bool;
+    fn invoice_file_exists(&self) -> bool;
+    fn is_payed(&self) -> bool;
+    fn employees_payed(&self) -> bool;
+    /// The final sum, or why there is none
+    fn final_sum(&self) -> String;
+    fn field(&self, name: &str) -> Option<String>;
+    fn csv_lines(&self) -> Vec<CsvLine>;
+}
+
+impl Listable for Project {
+    fn short_desc(&self) -> String {
+        Storable::short_desc(self)
+    }
+    fn canceled(&self) -> bool {
+        IsProject::canceled(self)
+    }
+    fn responsible(&self) -> Option<String> {
+        IsProject::responsible(self).ok().map(ToOwned::to_owned)
+    }
+    fn invoice_number(&self) -> Option<String> {
+        self.invoice().number_str()
+    }
+    fn date(&self) -> Option<Date<Utc>> {
+        self.modified_date()
+    }
+    fn index(&self) -> Option<String> {
+        Storable::index(self)
+    }
+    fn file(&self) -> PathBuf {
+        Storable::file(self)
+    }
+    fn git_status(&self) -> GitStatus {
+        self.get_git_status()
+    }
+    fn missing_for_offer(&self) -> Vec<String> {
+        self.is_missing_for_offer()
+    }
+    fn missing_for_invoice(&self) -> Vec<String> {
+        self.is_missing_for_invoice()
+    }
+    fn missing_for_archive(&self) -> Vec<String> {
+        Project::is_ready_for_archive(self)
+    }
+    fn offer_file_exists(&self) -> bool {
+        Exportable::offer_file_exists(self)
+    }
+    fn invoice_file_exists(&self) -> bool {
+        Exportable::invoice_file_exists(self)
+    }
+    fn is_payed(&self) -> bool {
+        Redeemable::is_payed(self)
+    }
+    fn employees_payed(&self) -> bool {
+        self.hours().employees_payed()
+    }
+    fn final_sum(&self) -> String {
+        self.sum_sold()
+            .map(|i| currency_to_string(&i))
+            .unwrap_or_else(|e| format!("{}", e))
+    }
+    fn field(&self, name: &str) -> Option<String> {
+        Project::field(self, name)
+    }
+    fn csv_lines(&self) -> Vec<CsvLine> {
+        csv_lines(self)
+    }
+}
+
+#[cfg(all(feature = "serialization", not(target_arch = "wasm32")))]
+impl Listable for Summary {
+    fn short_desc(&self) -> String {
+        self.short_desc.clone()
+    }
+    fn canceled(&self) -> bool {
+        self.canceled
+    }
+    fn responsible(&self) -> Option<String> {
+        self.responsible.clone()
+    }
+    fn invoice_number(&self) -> Option<String> {
+        self.invoice_number.clone()
+    }
+    fn date(&self) -> Option<Date<Utc>> {
+        Summary::date(self)
+    }
+    fn index(&self) -> Option<String> {
+        self.index.clone()
+    }
+    fn file(&self) -> PathBuf {
+        self.file.clone()
+    }
+    fn git_status(&self) -> GitStatus {
+        self.git_status.clone().unwrap_or(GitStatus::Unknown)
+    }
+    fn missing_for_offer(&self) -> Vec<String> {
+        self.missing_for_offer.clone()
+    }
+    fn missing_for_invoice(&self) -> Vec<String> {
+        self.missing_for_invoice.clone()
+    }
+    fn missing_for_archive(&self) -> Vec<String> {
+        self.missing_for_archive.clone()
+    }
+    fn offer_file_exists(&self) -> bool {
+        self.offer_file.as_ref().is_some_and(|f| f.exists())
+    }
+    fn invoice_file_exists(&self) -> bool {
+        self.invoice_file.as_ref().is_some_and(|f| f.exists())
+    }
+    fn is_payed(&self) -> bool {
+        self.payed
+    }
+    fn employees_payed(&self) -> bool {
+        self.employees_payed
+    }
+    fn final_sum(&self) -> String {
+        self.final_sum.clone().unwrap_or_else(|e| e)
+    }
+    fn field(&self, name: &str) -> Option<String> {
+        Summary::field(self, name)
+    }
+    fn csv_lines(&self) -> Vec<CsvLine> {
+        self.csv.clone()
+    }
+}
+
 // TODO: move `payed_to_cell` into computed_field.rs
 #[rustfmt::skip]
-fn payed_to_cell(project:&Project) -> Cell {
+fn payed_to_cell<P: Listable>(project:&P) -> Cell {
     let sym = crate::CONFIG.get_str("currency");
 
-    match (project.is_payed(), project.hours().employees_payed()) {
+    match (project.is_payed(), project.employees_payed()) {
         (false, false) => Cell::new("✗").with_style(Attr::ForegroundColor(color::RED)),
         (_,     false) |
         (false,  _   ) => Cell::new(sym).with_style(Attr::ForegroundColor(color::YELLOW)),
@@ -81,13 +232,13 @@ fn result_to_cell(res: &[String], bold:bool) -> Cell{
 
 /// create a Style string from the properties of a project
 #[rustfmt::skip]
-fn project_to_style(project:&Project) -> &str{
+fn project_to_style<P: Listable>(project:&P) -> &'static str{
     // can be send as invoice
-    if project.is_missing_for_invoice().is_empty(){
+    if project.missing_for_invoice().is_empty(){
         return "d"
     }
 
-    if let Some(date) = project.modified_date(){
+    if let Some(date) = project.date(){
         let age = (Local::today().signed_duration_since(date)).num_days();
         if project.canceled(){
             return ""
@@ -106,7 +257,7 @@ fn project_to_style(project:&Project) -> &str{
 }
 
 /// produces the rows used in `print_projects()`
-pub fn path_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row> {
+pub fn path_rows<P: Listable>(projects: &[P], list_config: &ListConfig<'_>) -> Vec<Row> {
     projects
         .iter()
         .map(|project| {
@@ -116,7 +267,7 @@ pub fn path_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row>
                 ""
             };
             Row::new(vec![
-                cell!(project.invoice().number_str().unwrap_or_default()),
+                cell!(project.invoice_number().unwrap_or_default()),
                 cell!(project.short_desc()).style_spec(row_style),
                 cell!(project.file().display()),
                 //cell!(project.date().map(|d|d.format("%d.%m.%Y").to_string()).unwrap_or("no_date".into())),
@@ -127,7 +278,7 @@ pub fn path_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row>
 }
 
 /// Triggered by `list --simple`, usually you set this in your config under `list/verbose: false`.
-pub fn simple_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row> {
+pub fn simple_rows<P: Listable>(projects: &[P], list_config: &ListConfig<'_>) -> Vec<Row> {
     projects
         .iter()
         .map(|project| {
@@ -144,9 +295,9 @@ pub fn simple_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Ro
                 })
                 .style_spec(row_style),
                 //cell!(project.manager()),
-                cell!(project.invoice().number_str().unwrap_or_default()),
+                cell!(project.invoice_number().unwrap_or_default()),
                 cell!(project
-                    .modified_date()
+                    .date()
                     .map(|d| d.format("%d.%m.%Y").to_string())
                     .unwrap_or_else(|| "no_date".into())),
                 //cell!(project.file().display()),
@@ -159,7 +310,7 @@ pub fn simple_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Ro
 ///
 /// produces the rows used in `print_projects()`
 #[inline]
-pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row> {
+pub fn verbose_rows<P: Listable>(projects: &[P], list_config: &ListConfig<'_>) -> Vec<Row> {
     log::trace!("verbose_rows {:#?}", list_config);
     projects
         .iter()
@@ -177,7 +328,7 @@ pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
             // for every project that was just moved to the archive?
             // Or just git-add them when archiving automatically, that is what ascii2 would
             // have done
-            let status = project.get_git_status();
+            let status = project.git_status();
             let (color, style) = status.to_style();
 
             cells.push(
@@ -186,9 +337,9 @@ pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
                     .with_style(style.unwrap_or(Attr::Standout(false))),
             );
 
-            let validation1 = project.is_missing_for_offer();
-            let validation2 = project.is_missing_for_invoice();
-            let validation3 = project.is_ready_for_archive();
+            let validation1 = project.missing_for_offer();
+            let validation2 = project.missing_for_invoice();
+            let validation3 = project.missing_for_archive();
 
             cells.extend_from_slice(&[
                 cell!(r->i+1),
@@ -199,19 +350,14 @@ pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
                 })
                 .style_spec(row_style),
                 // Hendrik Sollich
-                cell!(project.responsible().unwrap_or("")).style_spec(row_style),
+                cell!(project.responsible().unwrap_or_default()).style_spec(row_style),
                 // sort index
                 //cell!(project.index().unwrap_or(String::from(""))),
 
                 // R042
-                cell!(project.invoice().number_str().unwrap_or_default()).style_spec(row_style),
+                cell!(project.invoice_number().unwrap_or_default()).style_spec(row_style),
                 // Date
-                cell!(project
-                    .modified_date()
-                    .unwrap_or_else(Utc::today)
-                    .format("%d.%m.%Y")
-                    .to_string())
-                .style_spec(row_style),
+                cell!(project.date().unwrap_or_else(Utc::today).format("%d.%m.%Y").to_string()).style_spec(row_style),
                 // status "✓  ✓  ✗"
                 result_to_cell(&validation1, project.offer_file_exists()),
                 result_to_cell(&validation2, project.invoice_file_exists()),
@@ -219,8 +365,8 @@ pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
                 result_to_cell(&validation3, false),
                 //cell!(output_file_exists(project, Project::offer_file_name)),
                 //cell!(output_file_exists(project, Project::invoice_file_name)),
-                cell!(r->project.sum_sold().map(|i|currency_to_string(&i)).unwrap_or_else(|e| format!("{}", e))), //cell!(project.wages().map(|i|i.to_string()).unwrap_or(String::from("none"))),
-                                                                                                                  //cell!(project.sum_sold_and_wages().map(|i|i.to_string()).unwrap_or(String::from("none"))),
+                cell!(r->project.final_sum()), //cell!(project.wages().map(|i|i.to_string()).unwrap_or(String::from("none"))),
+                                               //cell!(project.sum_sold_and_wages().map(|i|i.to_string()).unwrap_or(String::from("none"))),
             ]);
 
             if let Some(ref details) = list_config.details {
@@ -254,7 +400,7 @@ pub fn verbose_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
 /// Triggered by `list --nothing`
 ///
 /// This prints nothing unless you tell it to with `--details`
-pub fn dynamic_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<Row> {
+pub fn dynamic_rows<P: Listable>(projects: &[P], list_config: &ListConfig<'_>) -> Vec<Row> {
     projects
         .iter()
         .map(|project| {
@@ -275,9 +421,9 @@ pub fn dynamic_rows(projects: &[Project], list_config: &ListConfig<'_>) -> Vec<R
                 );
                 if list_config.show_errors {
                     let validation = (
-                        project.is_missing_for_offer(),
-                        project.is_missing_for_invoice(),
-                        project.is_ready_for_archive(),
+                        project.missing_for_offer(),
+                        project.missing_for_invoice(),
+                        project.missing_for_archive(),
                     );
 
                     cells.extend_from_slice(&[
@@ -319,13 +465,177 @@ pub fn print_csv_year(year: i32) {
 }
 
 /// Prints Projects as CSV
-pub fn print_csv(projects: &[Project]) {
-    match crate::actions::projects_to_csv(projects) {
+pub fn print_csv<P: Listable>(projects: &[P]) {
+    let lines = projects.iter().flat_map(Listable::csv_lines).collect::<Vec<_>>();
+    match lines_to_csv(&lines) {
         Ok(csv) => println!("{}", csv),
         Err(err) => println!("{}", err),
     }
 }
 
+/// Prints a report as a table, totals last
+pub fn print_report(report: &Report) {
+    let mut table = Table::new();
+    table_with_borders(&mut table);
+    table.set_titles(Row::new(
+        [
+            String::new(),
+            lformat!("Events"),
+            lformat!("Revenue"),
+            lformat!("Wages"),
+            lformat!("Margin"),
+            lformat!("Invoice delay"),
+            lformat!("Payment delay"),
+        ]
+        .iter()
+        .map(|title| Cell::new(title))
+        .collect(),
+    ));
+    for row in report.rows.iter().chain(&report.totals) {
+        let cells = row.cells();
+        table.add_row(Row::new(
+            cells
+                .iter()
+                .enumerate()
+                .map(|(index, cell)| {
+                    if index == 0 {
+                        Cell::new(cell)
+                    } else {
+                        Cell::new_align(cell, Alignment::RIGHT)
+                    }
+                })
+                .collect(),
+        ));
+    }
+    table.printstd();
+}
+
+/// Prints the journal, undone changes in italics.
+pub fn print_history(entries: &[Entry], use_colors: bool) {
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let style = if entry.undone && use_colors { "i" } else { "" };
+            let undone = if entry.undone {
+                lformat!("undone")
+            } else {
+                String::new()
+            };
+            Row::new(vec![
+                Cell::new(&entry.id.to_string()).style_spec(style),
+                Cell::new(&entry.time.format("%d.%m.%Y %H:%M").to_string()).style_spec(style),
+                Cell::new(&entry.description).style_spec(style),
+                Cell::new(&undone).style_spec(style),
+            ])
+        })
+        .collect();
+    print_projects(rows);
+}
+
+/// Prints branch, upstream and changed files, like `git status --short --branch`.
+#[cfg(feature = "git_statuses")]
+pub fn print_git_status(status: &Status, use_colors: bool) {
+    match (&status.branch, &status.upstream) {
+        (Some(branch), Some(upstream)) => println!(
+            "{}",
+            lformat!(
+                "On branch {}, {} ahead and {} behind {}",
+                branch,
+                status.ahead,
+                status.behind,
+                upstream
+            )
+        ),
+        (Some(branch), None) => println!("{}", lformat!("On branch {}", branch)),
+        (None, _) => println!("{}", lformat!("Not on any branch")),
+    }
+    if status.changes.is_empty() {
+        println!("{}", lformat!("Nothing to commit"));
+        return;
+    }
+    let rows = status
+        .changes
+        .iter()
+        .map(|(path, git_status)| {
+            let mut cell = Cell::new(&git_status.to_string());
+            if use_colors {
+                let (color, attr) = git_status.to_style();
+                cell = cell.with_style(Attr::ForegroundColor(color));
+                if let Some(attr) = attr {
+                    cell = cell.with_style(attr);
+                }
+            }
+            Row::new(vec![cell, Cell::new(&path.display().to_string())])
+        })
+        .collect();
+    print_projects(rows);
+}
+
+/// Prints one line per commit, like `git log --oneline`.
+#[cfg(feature = "git_statuses")]
+pub fn print_commits(commits: &[Commit], use_colors: bool) {
+    let id_style = if use_colors { "Fr" } else { "" };
+    let rows = commits
+        .iter()
+        .map(|commit| {
+            Row::new(vec![
+                Cell::new(commit.short_id()).style_spec(id_style),
+                Cell::new(&commit.time.format("%d.%m.%Y %H:%M").to_string()),
+                Cell::new(&commit.author),
+                Cell::new(&commit.summary),
+            ])
+        })
+        .collect();
+    print_projects(rows);
+}
+
+/// Prints a patch, added lines green and removed lines red.
+pub fn print_diff(diff: &str, use_colors: bool) {
+    for line in diff.lines() {
+        let color = match line.chars().next() {
+            _ if !use_colors => None,
+            Some('+') => Some(32),
+            Some('-') => Some(31),
+            Some('@') => Some(36),
+            _ => None,
+        };
+        match color {
+            Some(color) => println!("\u{1b}[{}m{}\u{1b}[0m", color, line),
+            None => println!("{}", line),
+        }
+    }
+}
+
+/// Prints what `asciii search` found, every project with the lines that matched.
+pub fn print_found(found: &[Found<Project>], use_colors: bool) {
+    for found in found {
+        let project = &found.item;
+        let title = [
+            Some(Storable::short_desc(project)),
+            project.modified_date().map(|date| date.format("%d.%m.%Y").to_string()),
+            project.invoice().number_str(),
+            project.field("Dir"),
+        ];
+        let title = title.iter().flatten().cloned().collect::<Vec<_>>().join("  ");
+        if use_colors {
+            println!("\u{1b}[1m{}\u{1b}[0m", title);
+        } else {
+            println!("{}", title);
+        }
+
+        for hit in &found.hits {
+            let text = hit.highlight(|found| {
+                if use_colors {
+                    format!("\u{1b}[1;33m{}\u{1b}[0m", found)
+                } else {
+                    format!("[{}]", found)
+                }
+            });
+            println!("    {:<12} {}", hit.field, text);
+        }
+    }
+}
+
 //fn table_for_arrangement(table:&mut Table){
 //    table.set_format(FormatBuilder::new() .padding(0, 0) .build());
 //}
@@ -343,7 +653,7 @@ fn table_with_borders(table:&mut Table){
 
 pub fn show_details(project: &Project, bill_type: BillType) {
     log::trace!("print::show_details()");
-    println!("{}: {}", bill_type.to_string(), project.short_desc());
+    println!("{}: {}", bill_type.to_string(), Storable::short_desc(project));
 
     let (offer, invoice) = match project.bills() {
         Ok(tuple) => tuple,
@@ -355,7 +665,7 @@ pub fn show_details(project: &Project, bill_type: BillType) {
 
     let bill = match bill_type {
         BillType::Offer => offer,
-        BillType::Invoice => invoice,
+        BillType::Invoice | BillType::Cancellation => invoice,
     };
 
     // TODO: move to Project::product_table(&self) {
@@ -383,18 +693,24 @@ pub fn show_details(project: &Project, bill_type: BillType) {
         cell!(r->"======")
     ]);
     for (&tax, itemlist) in bill.iter() {
-        table.add_row(row!["", "", "", "", cell!(r->itemlist.gross_sum().postfix())]);
+        table.add_row(row![
+            "",
+            "",
+            "",
+            "",
+            cell!(r->currency_to_string(&itemlist.gross_sum()))
+        ]);
         if itemlist.tax_sum().value() > 0 {
             table.add_row(row![
                 "",
                 "",
                 "",
                 cell!(r->format!("+{}%",**tax*100f64)),
-                cell!(r->format!("{}", itemlist.tax_sum().postfix())) //cell!(r->itemlist.net_sum().postfix())
+                cell!(r->currency_to_string(&itemlist.tax_sum())) //cell!(r->itemlist.net_sum().postfix())
             ]);
         }
     }
-    table.add_row(row!["", "Total", "", "", bill.net_total().postfix()]);
+    table.add_row(row!["", "Total", "", "", currency_to_string(&bill.net_total())]);
     // }
 
     table.printstd();
@@ -0,0 +1,419 @@
+//! A cache of what `list` and `csv` need of every project, so they don't have to parse every file every time.
+//!
+//! The cache lives in the cache directory of the user, one file per storage.
+//! An entry is kept as long as the modification time of its project file stays the same,
+//! or else as long as the content of the file hashes the same.
+//! A change of asciii itself, of the configuration or of anything in `extras/`, like the client book
+//! or the catalogue, throws away the whole cache.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::Error;
+use chrono::prelude::*;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::{spec::*, ComputedField, Exportable, Project};
+use crate::{
+    actions::{csv_lines, currency_code, CsvLine},
+    config::{ConfigReader, DEFAULT_LOCATION},
+    storage::{repo::GitStatus, Storable, Storage, StorageDir},
+    util::currency_to_string,
+};
+
+/// Computed fields that change without the file changing, these are never cached.
+const LIVE_FIELDS: [&str; 5] = ["Age", "Dir", "Invalid", "OurBad", "TheirBad"];
+
+/// Those of `LIVE_FIELDS` that `Summary::field()` can compute without the project.
+const SUMMARY_FIELDS: [&str; 2] = ["Age", "Dir"];
+
+/// What the cache keeps of one project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub file: PathBuf,
+    pub short_desc: String,
+    pub canceled: bool,
+    pub responsible: Option<String>,
+    pub invoice_number: Option<String>,
+    /// `yyyy-mm-dd`
+    pub date: Option<String>,
+    pub year: Option<i32>,
+    pub index: Option<String>,
+    /// ISO 4217
+    pub currency: String,
+    /// formatted, or why there is none
+    pub final_sum: Result<String, String>,
+    pub payed: bool,
+    pub employees_payed: bool,
+    pub missing_for_offer: Vec<String>,
+    pub missing_for_invoice: Vec<String>,
+    pub missing_for_archive: Vec<String>,
+    pub offer_file: Option<PathBuf>,
+    pub invoice_file: Option<PathBuf>,
+    /// every [`ComputedField`] but those in `LIVE_FIELDS`
+    pub fields: BTreeMap<String, String>,
+    pub csv: Vec<CsvLine>,
+    /// not cached, set by [`Cache::summaries`]
+    #[serde(skip)]
+    pub git_status: Option<GitStatus>,
+}
+
+impl Summary {
+    pub fn of(project: &Project) -> Summary {
+        let fields = ComputedField::iter_variant_names()
+            .filter(|name| !LIVE_FIELDS.contains(name))
+            .filter_map(|name| Some((name.to_owned(), project.field(name)?)))
+            .collect();
+        Summary {
+            file: project.file(),
+            short_desc: Storable::short_desc(project),
+            canceled: project.canceled(),
+            responsible: project.responsible().ok().map(ToOwned::to_owned),
+            invoice_number: project.invoice().number_str(),
+            date: project.modified_date().map(|date| date.format("%Y-%m-%d").to_string()),
+            year: Storable::year(project),
+            index: project.index(),
+            currency: currency_code(project),
+            final_sum: project
+                .sum_sold()
+                .map(|sum| currency_to_string(&sum))
+                .map_err(|error| error.to_string()),
+            payed: project.is_payed(),
+            employees_payed: project.hours().employees_payed(),
+            missing_for_offer: project.is_missing_for_offer(),
+            missing_for_invoice: project.is_missing_for_invoice(),
+            missing_for_archive: Project::is_ready_for_archive(project),
+            offer_file: project.offer_file(),
+            invoice_file: project.invoice_file(),
+            fields,
+            csv: csv_lines(project),
+            git_status: None,
+        }
+    }
+
+    pub fn date(&self) -> Option<Date<Utc>> {
+        let date = NaiveDate::parse_from_str(self.date.as_deref()?, "%Y-%m-%d").ok()?;
+        Some(Date::from_utc(date, Utc))
+    }
+
+    /// Like [`Project::field`], but only computed fields.
+    pub fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "Age" => self
+                .date()
+                .map(|date| lformat!("{} days", Utc::today().signed_duration_since(date).num_days())),
+            "Dir" => {
+                let storage = crate::storage::get_storage_path();
+                self.file
+                    .parent()
+                    .and_then(Path::parent)
+                    .and_then(|dir| dir.strip_prefix(&storage).ok())
+                    .map(|dir| dir.display().to_string())
+            },
+            _ => self.fields.get(name).cloned(),
+        }
+    }
+
+    /// Whether `field()` knows `name`.
+    pub fn has_field(name: &str) -> bool {
+        SUMMARY_FIELDS.contains(&name)
+            || ComputedField::iter_variant_names().any(|field| field == name && !LIVE_FIELDS.contains(&field))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// nanoseconds since the epoch
+    modified: u128,
+    hash: u64,
+    summary: Summary,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    context: u64,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// The summaries of all projects of a storage that were ever listed.
+#[derive(Debug)]
+pub struct Cache {
+    path: Option<PathBuf>,
+    context: u64,
+    entries: HashMap<PathBuf, Entry>,
+    changed: bool,
+}
+
+fn hash_of<H: Hash>(value: H) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn modified(path: &Path) -> Option<u128> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// Everything besides the project file that a summary depends on.
+fn context_of(storage: &Storage<Project>) -> u64 {
+    let mut files = vec![ConfigReader::path_home(), PathBuf::from(DEFAULT_LOCATION)];
+    if let Ok(extras) = fs::read_dir(storage.extras_dir()) {
+        files.extend(extras.filter_map(Result::ok).map(|entry| entry.path()));
+    }
+    files.sort();
+    let stamps = files.iter().map(|file| (file, modified(file))).collect::<Vec<_>>();
+    let mut variables = env::vars()
+        .filter(|(key, _)| key.starts_with("ASCIII_"))
+        .collect::<Vec<_>>();
+    variables.sort();
+    hash_of((crate::VERSION_VERBOSE.to_string(), stamps, variables))
+}
+
+impl Cache {
+    /// Where the cache of `storage` is kept.
+    pub fn location(storage: &Storage<Project>) -> Option<PathBuf> {
+        let name = format!("index-{:x}.json", hash_of(storage.root_dir()));
+        dirs::cache_dir().map(|dir| dir.join("asciii").join(name))
+    }
+
+    /// Reads the cache of `storage`, starts over if it is outdated or can't be read.
+    pub fn open(storage: &Storage<Project>) -> Cache {
+        let path = Self::location(storage);
+        let context = context_of(storage);
+        let file = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|content| match serde_json::from_slice::<CacheFile>(&content) {
+                Ok(file) => Some(file),
+                Err(error) => {
+                    log::warn!("{}", lformat!("ignoring the broken project cache: {}", error));
+                    None
+                },
+            })
+            .filter(|file| file.context == context)
+            .unwrap_or_default();
+        Cache {
+            path,
+            context,
+            entries: file.entries,
+            changed: false,
+        }
+    }
+
+    /// Summaries of the projects in `directory`, only the files that changed are opened.
+    pub fn summaries(&mut self, storage: &Storage<Project>, directory: StorageDir) -> Result<Vec<Summary>, Error> {
+        let (folders, year) = match directory {
+            StorageDir::Year(year) => {
+                let mut folders = storage.list_project_folders(StorageDir::Archive(year))?;
+                folders.append(&mut storage.list_project_folders(StorageDir::Working)?);
+                (folders, Some(year))
+            },
+            directory => (storage.list_project_folders(directory)?, None),
+        };
+        let files = folders
+            .iter()
+            .filter_map(|folder| match storage.get_project_file(folder) {
+                Ok(file) => Some(file),
+                Err(error) => {
+                    log::warn!("{}", error);
+                    None
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let mut summaries = self.refresh(&files);
+        if year.is_some() {
+            summaries.retain(|summary| summary.year == year);
+        }
+        if let Some(repo) = storage.repository() {
+            for summary in &mut summaries {
+                let dir = summary.file.parent().map(Path::to_path_buf).unwrap_or_default();
+                summary.git_status = Some(repo.get_status(&dir));
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Summaries of `files`, in that order, files that can't be opened are left out.
+    pub fn refresh(&mut self, files: &[PathBuf]) -> Vec<Summary> {
+        let stale = files
+            .iter()
+            .filter(|file| {
+                let entry = self.entries.get(*file);
+                entry.is_none() || entry.map(|entry| entry.modified) != modified(file)
+            })
+            .collect::<Vec<_>>();
+        log::debug!(
+            "{} of {} projects changed since they were cached",
+            stale.len(),
+            files.len()
+        );
+
+        #[cfg(feature = "rayon")]
+        let stale = stale.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let stale = stale.iter();
+        let fresh = stale
+            .filter_map(|file| {
+                let content = fs::read_to_string(file).map_err(|error| log::warn!("{}", error)).ok()?;
+                Some((file.to_path_buf(), modified(file).unwrap_or_default(), content))
+            })
+            .map(|(file, modified, content)| {
+                let hash = hash_of(&content);
+                (file, modified, hash, content)
+            })
+            .collect::<Vec<_>>();
+
+        for (file, modified, hash, content) in fresh {
+            if let Some(entry) = self.entries.get_mut(&file).filter(|entry| entry.hash == hash) {
+                entry.modified = modified;
+                self.changed = true;
+                continue;
+            }
+            match Project::from_content(&file, &content) {
+                Ok(project) => {
+                    let summary = Summary::of(&project);
+                    self.entries.insert(
+                        file,
+                        Entry {
+                            modified,
+                            hash,
+                            summary,
+                        },
+                    );
+                    self.changed = true;
+                },
+                Err(error) => log::warn!("{}", error),
+            }
+        }
+
+        files
+            .iter()
+            .filter_map(|file| self.entries.get(file))
+            .map(|entry| entry.summary.clone())
+            .collect()
+    }
+
+    /// Writes the cache back if anything changed, forgetting projects that are gone.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        let before = self.entries.len();
+        self.entries.retain(|file, _| file.exists());
+        if !self.changed && before == self.entries.len() {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = CacheFile {
+            context: self.context,
+            entries: std::mem::take(&mut self.entries),
+        };
+        let written = fs::write(path, serde_json::to_vec(&file)?);
+        self.entries = file.entries;
+        self.changed = false;
+        Ok(written?)
+    }
+
+    /// Throws away the cache of `storage`.
+    pub fn clear(storage: &Storage<Project>) -> Result<(), Error> {
+        match Self::location(storage) {
+            Some(path) if path.exists() => Ok(fs::remove_file(path)?),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn project(name: &str, price: &str) -> String {
+        format!(
+            "event:\n  name: {}\n  dates:\n    - begin: 01.06.2020\n\nhours:\n  salary: 8.0\n\ntax: 0.19\nproducts:\n  Kaffee: {{ amount: 10, price: {} }}\n",
+            name, price
+        )
+    }
+
+    fn empty_cache() -> Cache {
+        Cache {
+            path: None,
+            context: 0,
+            entries: HashMap::new(),
+            changed: false,
+        }
+    }
+
+    #[test]
+    fn fields_that_depend_on_today() {
+        assert!(Summary::has_field("Age"));
+        assert!(Summary::has_field("Employees"));
+        assert!(!Summary::has_field("OurBad"));
+        assert!(!Summary::has_field("TheirBad"));
+        assert!(!Summary::has_field("Invalid"));
+        assert!(!Summary::has_field("Nonsense"));
+    }
+
+    #[test]
+    fn refresh_reads_new_files() {
+        let dir = TempDir::new("asciii_cache").unwrap();
+        let file = dir.path().join("Kaffee.yml");
+        fs::write(&file, project("Kaffee", "2.5")).unwrap();
+
+        let mut cache = empty_cache();
+        let summaries = cache.refresh(&[file.clone(), dir.path().join("missing.yml")]);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].short_desc, "Kaffee");
+        assert_eq!(summaries[0].date.as_deref(), Some("2020-06-01"));
+        assert!(summaries[0].final_sum.is_ok());
+        assert!(cache.changed);
+    }
+
+    #[test]
+    fn unchanged_files_are_reused() {
+        let dir = TempDir::new("asciii_cache").unwrap();
+        let file = dir.path().join("Kaffee.yml");
+        fs::write(&file, project("Kaffee", "2.5")).unwrap();
+
+        let mut cache = empty_cache();
+        cache.refresh(std::slice::from_ref(&file));
+        cache.changed = false;
+
+        // an entry whose mtime still matches is trusted as is
+        cache.entries.get_mut(&file).unwrap().summary.short_desc = "cached".into();
+        assert_eq!(cache.refresh(std::slice::from_ref(&file))[0].short_desc, "cached");
+        assert!(!cache.changed);
+
+        // a touched file with the same content only updates the mtime
+        cache.entries.get_mut(&file).unwrap().modified = 0;
+        assert_eq!(cache.refresh(std::slice::from_ref(&file))[0].short_desc, "cached");
+        assert_ne!(cache.entries[&file].modified, 0);
+    }
+
+    #[test]
+    fn changed_files_are_summarized_again() {
+        let dir = TempDir::new("asciii_cache").unwrap();
+        let file = dir.path().join("Kaffee.yml");
+        fs::write(&file, project("Kaffee", "2.5")).unwrap();
+
+        let mut cache = empty_cache();
+        let before = cache.refresh(std::slice::from_ref(&file))[0].final_sum.clone();
+
+        fs::write(&file, project("Kuchen", "3.5")).unwrap();
+        cache.entries.get_mut(&file).unwrap().modified = 0;
+        let after = cache.refresh(std::slice::from_ref(&file));
+        assert_eq!(after[0].short_desc, "Kuchen");
+        assert_ne!(after[0].final_sum, before);
+    }
+}
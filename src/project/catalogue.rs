@@ -0,0 +1,368 @@
+//! Products that are shared between projects, with their prices over time.
+//!
+//! The catalogue lives in `extras/catalogue.yml` of the storage, every product is a section under its id:
+//!
+//! ```yaml
+//! kaffee:
+//!   name: Kaffee
+//!   unit: 1l
+//!   prices:
+//!   - since: 01.01.2015
+//!     price: 2.5
+//!   - since: 01.03.2024
+//!     price: 2.8
+//! ```
+//!
+//! Projects refer to a product by its id, as a key in `products:` without a price of its own:
+//!
+//! ```yaml
+//! products:
+//!   kaffee: { amount: 4 }
+//! ```
+//!
+//! The price is the one that was valid at the [`price_date`](super::Project::price_date) of the project,
+//! so old projects keep their old prices when new ones are added.
+//! Prices are in the configured currency, unless a product says otherwise (`currency: USD`),
+//! only projects of the same currency can use it.
+
+use std::path::Path;
+
+use anyhow::Error;
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use yaml_rust::Yaml;
+
+use crate::{
+    storage::{self, backend::Backend},
+    util::{
+        currency, yaml,
+        yaml_edit::{self, Document},
+    },
+};
+
+/// File name of the catalogue inside the `extras` directory.
+pub const FILE_NAME: &str = "catalogue.yml";
+
+lazy_static! {
+    /// The catalogue of the configured storage, read once, empty if there is none.
+    ///
+    /// Long running processes like the server should hold their own [`Catalogue::load()`] and reload it on changes.
+    pub static ref CATALOGUE: Catalogue = Catalogue::load_default();
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CatalogueError {
+    #[error("There is no product {:?} in the catalogue", _0)]
+    UnknownProduct(String),
+
+    #[error("There is already a product {:?} in the catalogue", _0)]
+    AlreadyExists(String),
+
+    #[error("{:?} has no price before {}", _0, _1)]
+    NoPrice(String, String),
+
+    #[error("{:?} already has a price since {}", _0, _1)]
+    AlreadyPriced(String, String),
+
+    #[error("{:?} is priced in {}, not in {}", _0, _1, _2)]
+    OtherCurrency(String, String, String),
+}
+
+/// A price and the day from which on it is valid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub since: Date<Utc>,
+    pub price: f64,
+}
+
+/// How the price of a product changed between two days.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub id: String,
+    pub name: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+/// All shared products, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Catalogue {
+    content: String,
+    yaml: Yaml,
+}
+
+impl Default for Catalogue {
+    fn default() -> Self {
+        Catalogue {
+            content: String::new(),
+            yaml: Yaml::Null,
+        }
+    }
+}
+
+fn dmy(date: Date<Utc>) -> String {
+    date.format("%d.%m.%Y").to_string()
+}
+
+impl Catalogue {
+    pub fn from_content(content: &str) -> Result<Self, Error> {
+        Ok(Catalogue {
+            content: content.to_owned(),
+            yaml: yaml::parse(content)?,
+        })
+    }
+
+    /// Reads the catalogue from `file`, a missing file is an empty catalogue.
+    pub fn open<B: Backend + ?Sized>(backend: &B, file: &Path) -> Result<Self, Error> {
+        if backend.exists(file) {
+            Self::from_content(&backend.read_to_string(file)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// The catalogue of the configured storage.
+    pub fn open_default() -> Result<Self, Error> {
+        let storage = storage::setup::<super::Project>()?;
+        Self::open(storage.backend(), &storage.extras_dir().join(FILE_NAME))
+    }
+
+    /// Like [`open()`](Self::open), but an unreadable catalogue is logged and taken as an empty one.
+    pub fn load<B: Backend + ?Sized>(backend: &B, file: &Path) -> Self {
+        Self::open(backend, file).unwrap_or_else(|error| {
+            log::error!("cannot read the catalogue {}: {}", file.display(), error);
+            Self::default()
+        })
+    }
+
+    /// Like [`open_default()`](Self::open_default), see [`load()`](Self::load).
+    pub fn load_default() -> Self {
+        match storage::setup::<super::Project>() {
+            Ok(storage) => Self::load(storage.backend(), &storage.extras_dir().join(FILE_NAME)),
+            Err(error) => {
+                log::debug!("no storage, no catalogue: {}", error);
+                Self::default()
+            },
+        }
+    }
+
+    /// The catalogue as it would be written back.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        self.yaml
+            .as_hash()
+            .map(|hash| hash.keys().filter_map(Yaml::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.yaml[id].as_hash().is_some()
+    }
+
+    /// Name of the product, falls back to its id.
+    pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+        yaml::get_str(&self.yaml[id], "name").unwrap_or(id)
+    }
+
+    pub fn unit(&self, id: &str) -> Option<&str> {
+        yaml::get_str(&self.yaml[id], "unit")
+    }
+
+    /// Symbol of the currency of the prices, `currency: USD`, the configured one if not set.
+    pub fn currency_symbol(&self, id: &str) -> Option<char> {
+        yaml::get_str(&self.yaml[id], "currency")
+            .and_then(currency::by_code)
+            .map(|info| Some(info.symbol))
+            .unwrap_or_else(currency::default_symbol)
+    }
+
+    /// A tax rate of the product itself, if it differs from that of the project.
+    pub fn tax(&self, id: &str) -> Option<f64> {
+        yaml::get_f64(&self.yaml[id], "tax")
+    }
+
+    /// All prices of a product, oldest first.
+    pub fn prices(&self, id: &str) -> Vec<Price> {
+        let mut prices = self.yaml[id]["prices"]
+            .as_vec()
+            .map(|prices| {
+                prices
+                    .iter()
+                    .filter_map(|price| {
+                        Some(Price {
+                            since: yaml::get_str(price, "since").and_then(yaml::parse_dmy_date)?,
+                            price: yaml::get_f64(price, "price")
+                                .or_else(|| yaml::get_int(price, "price").map(|p| p as f64))?,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        prices.sort_by_key(|price| price.since);
+        prices
+    }
+
+    /// The price that was valid on `date`.
+    pub fn price_at(&self, id: &str, date: Date<Utc>) -> Result<Price, CatalogueError> {
+        if !self.contains(id) {
+            return Err(CatalogueError::UnknownProduct(id.into()));
+        }
+        self.prices(id)
+            .into_iter()
+            .rfind(|price| price.since <= date)
+            .ok_or_else(|| CatalogueError::NoPrice(id.into(), dmy(date)))
+    }
+
+    /// Every product whose price on `to` differs from the one on `from`.
+    pub fn changes(&self, from: Date<Utc>, to: Date<Utc>) -> Vec<PriceChange> {
+        self.ids()
+            .into_iter()
+            .map(|id| PriceChange {
+                id: id.into(),
+                name: self.name(id).into(),
+                before: self.price_at(id, from).ok().map(|p| p.price),
+                after: self.price_at(id, to).ok().map(|p| p.price),
+            })
+            .filter(|change| change.before != change.after)
+            .collect()
+    }
+
+    fn edit<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Document) -> Result<(), yaml_edit::EditError>,
+    {
+        let mut document = Document::new(&self.content);
+        f(&mut document)?;
+        *self = Self::from_content(&document.to_string())?;
+        Ok(())
+    }
+
+    /// Adds a product with its first price.
+    pub fn add(
+        &mut self,
+        id: &str,
+        name: &str,
+        unit: Option<&str>,
+        tax: Option<f64>,
+        price: Price,
+    ) -> Result<(), Error> {
+        if self.contains(id) {
+            anyhow::bail!(CatalogueError::AlreadyExists(id.into()));
+        }
+        self.edit(|document| {
+            document.set(&format!("{}.name", id), &yaml_edit::string(name))?;
+            if let Some(unit) = unit {
+                document.set(&format!("{}.unit", id), &yaml_edit::string(unit))?;
+            }
+            if let Some(tax) = tax {
+                document.set(&format!("{}.tax", id), &tax.to_string())?;
+            }
+            Ok(())
+        })?;
+        self.set_price(id, price)
+    }
+
+    /// Adds a new price, valid from `price.since` on.
+    pub fn set_price(&mut self, id: &str, price: Price) -> Result<(), Error> {
+        if !self.contains(id) {
+            anyhow::bail!(CatalogueError::UnknownProduct(id.into()));
+        }
+        if self.prices(id).iter().any(|p| p.since == price.since) {
+            anyhow::bail!(CatalogueError::AlreadyPriced(id.into(), dmy(price.since)));
+        }
+        self.edit(|document| {
+            document.push(
+                &format!("{}.prices", id),
+                &[
+                    ("since", dmy(price.since)),
+                    ("price", round_cents(price.price).to_string()),
+                ],
+            )
+        })
+    }
+}
+
+/// Prices are in cents, everything below is noise from calculating them.
+pub fn round_cents(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32, m: u32, y: i32) -> Date<Utc> {
+        Utc.ymd(y, m, d)
+    }
+
+    fn catalogue() -> Catalogue {
+        Catalogue::from_content(
+            "# prices are without tax\nkaffee:\n  name: Kaffee\n  unit: 1l\n  prices:\n  - since: 01.03.2024\n    price: 2.8\n  - since: 01.01.2015\n    price: 2.5\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn prices_over_time() {
+        let catalogue = catalogue();
+        assert_eq!(catalogue.price_at("kaffee", date(1, 1, 2015)).unwrap().price, 2.5);
+        assert_eq!(catalogue.price_at("kaffee", date(29, 2, 2024)).unwrap().price, 2.5);
+        assert_eq!(catalogue.price_at("kaffee", date(1, 3, 2024)).unwrap().price, 2.8);
+        assert_eq!(
+            catalogue.price_at("kaffee", date(31, 12, 2014)),
+            Err(CatalogueError::NoPrice("kaffee".into(), "31.12.2014".into()))
+        );
+        assert_eq!(
+            catalogue.price_at("tee", date(1, 1, 2020)),
+            Err(CatalogueError::UnknownProduct("tee".into()))
+        );
+    }
+
+    #[test]
+    fn add_and_bump() {
+        let mut catalogue = catalogue();
+        let since = |d, m, y| Price {
+            since: date(d, m, y),
+            price: 0.0,
+        };
+        catalogue
+            .add(
+                "tee",
+                "Tee",
+                Some("1l"),
+                None,
+                Price {
+                    price: 1.75,
+                    ..since(1, 1, 2020)
+                },
+            )
+            .unwrap();
+        catalogue
+            .set_price(
+                "tee",
+                Price {
+                    price: 1.9 * 1.1,
+                    ..since(1, 1, 2025)
+                },
+            )
+            .unwrap();
+        assert!(catalogue.set_price("tee", since(1, 1, 2025)).is_err());
+        assert!(catalogue.add("tee", "Tee", None, None, since(1, 1, 2025)).is_err());
+        assert!(catalogue.content().ends_with(
+            "\ntee:\n  name: Tee\n  unit: 1l\n  prices:\n    - since: 01.01.2020\n      price: 1.75\n    - since: 01.01.2025\n      price: 2.09\n"
+        ));
+
+        let changes = catalogue.changes(date(1, 1, 2024), date(1, 1, 2025));
+        assert_eq!(
+            changes
+                .iter()
+                .map(|c| (c.id.as_str(), c.before, c.after))
+                .collect::<Vec<_>>(),
+            vec![("kaffee", Some(2.5), Some(2.8)), ("tee", Some(1.75), Some(2.09))]
+        );
+        assert_eq!(catalogue.changes(date(1, 1, 2015), date(1, 1, 2016)), Vec::new());
+    }
+}
@@ -0,0 +1,339 @@
+//! Clients that are shared between projects.
+//!
+//! The client book lives in `extras/clients.yml` of the storage, every client is a section under its id:
+//!
+//! ```yaml
+//! graf-zahl:
+//!   title: Herr
+//!   first_name: Graf
+//!   last_name: Zahl
+//!   email: zahl@example.com
+//!   address: |
+//!     Graf Zahl
+//!     Nummernhöllenstraße 666
+//!   country: DE
+//! ```
+//!
+//! Instead of a copy of this, a project can refer to a client by id with `client: { ref: graf-zahl }`.
+//! Fields that are set in the project itself win over those in the book.
+//! Ids are made up once, from the name, and don't change when the client does.
+
+use std::path::Path;
+
+use anyhow::Error;
+use lazy_static::lazy_static;
+use yaml_rust::Yaml;
+
+use super::{
+    spec::IsClient,
+    yaml_provider::{FieldError, FieldResult, YamlProvider},
+};
+use crate::{
+    storage::{self, backend::Backend},
+    util::{
+        yaml,
+        yaml_edit::{self, Document},
+    },
+};
+
+/// File name of the client book inside the `extras` directory.
+pub const FILE_NAME: &str = "clients.yml";
+
+/// The fields a client can have.
+pub const FIELDS: [&str; 6] = ["title", "first_name", "last_name", "email", "address", "country"];
+
+lazy_static! {
+    /// The client book of the configured storage, read once, empty if there is none.
+    ///
+    /// Long running processes like the server should hold their own [`ClientBook::load()`] and reload it on changes.
+    pub static ref CLIENT_BOOK: ClientBook = ClientBook::load_default();
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ClientBookError {
+    #[error("There is no client {:?}", _0)]
+    UnknownClient(String),
+
+    #[error("A client has no field {:?}, only {}", _0, FIELDS.join(", "))]
+    UnknownField(String),
+
+    #[error("There is already a client {:?}", _0)]
+    AlreadyExists(String),
+}
+
+/// One client, as it is written in the book.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientEntry {
+    pub title: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub country: Option<String>,
+}
+
+impl ClientEntry {
+    /// Copies the client of a project, wherever its data comes from.
+    pub fn from_client<C: IsClient>(client: &C) -> Self {
+        let owned = |field: FieldResult<&str>| field.ok().map(ToOwned::to_owned);
+        ClientEntry {
+            title: owned(client.title()),
+            first_name: owned(client.first_name()),
+            last_name: owned(client.last_name()),
+            email: owned(client.email()),
+            address: owned(client.address()),
+            country: owned(client.country()),
+        }
+    }
+
+    fn from_yaml(yaml: &Yaml) -> Self {
+        let get = |key| yaml::get_string(yaml, key);
+        ClientEntry {
+            title: get("title"),
+            first_name: get("first_name"),
+            last_name: get("last_name"),
+            email: get("email"),
+            address: get("address"),
+            country: get("country"),
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, Option<&str>); 6] {
+        [
+            ("title", self.title.as_deref()),
+            ("first_name", self.first_name.as_deref()),
+            ("last_name", self.last_name.as_deref()),
+            ("email", self.email.as_deref()),
+            ("address", self.address.as_deref()),
+            ("country", self.country.as_deref()),
+        ]
+    }
+
+    /// First and last name, or whatever there is of it.
+    pub fn name(&self) -> String {
+        [self.first_name.as_deref(), self.last_name.as_deref()]
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// All known clients, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct ClientBook {
+    content: String,
+    yaml: Yaml,
+}
+
+impl Default for ClientBook {
+    fn default() -> Self {
+        ClientBook {
+            content: String::new(),
+            yaml: Yaml::Null,
+        }
+    }
+}
+
+impl YamlProvider for ClientBook {
+    fn data(&self) -> &Yaml {
+        &self.yaml
+    }
+}
+
+impl ClientBook {
+    pub fn from_content(content: &str) -> Result<Self, Error> {
+        Ok(ClientBook {
+            content: content.to_owned(),
+            yaml: yaml::parse(content)?,
+        })
+    }
+
+    /// Reads the book from `file`, a missing file is an empty book.
+    pub fn open<B: Backend + ?Sized>(backend: &B, file: &Path) -> Result<Self, Error> {
+        if backend.exists(file) {
+            Self::from_content(&backend.read_to_string(file)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// The book of the configured storage.
+    pub fn open_default() -> Result<Self, Error> {
+        let storage = storage::setup::<super::Project>()?;
+        Self::open(storage.backend(), &storage.extras_dir().join(FILE_NAME))
+    }
+
+    /// Like [`open()`](Self::open), but an unreadable client book is logged and taken as an empty one.
+    pub fn load<B: Backend + ?Sized>(backend: &B, file: &Path) -> Self {
+        Self::open(backend, file).unwrap_or_else(|error| {
+            log::error!("cannot read the client book {}: {}", file.display(), error);
+            Self::default()
+        })
+    }
+
+    /// Like [`open_default()`](Self::open_default), see [`load()`](Self::load).
+    pub fn load_default() -> Self {
+        match storage::setup::<super::Project>() {
+            Ok(storage) => Self::load(storage.backend(), &storage.extras_dir().join(FILE_NAME)),
+            Err(error) => {
+                log::debug!("no storage, no client book: {}", error);
+                Self::default()
+            },
+        }
+    }
+
+    /// The book as it would be written back.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        self.yaml
+            .as_hash()
+            .map(|hash| hash.keys().filter_map(Yaml::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.yaml[id].as_hash().is_some()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ClientEntry> {
+        self.yaml[id].as_hash().map(|_| ClientEntry::from_yaml(&self.yaml[id]))
+    }
+
+    /// A single field of a client, used to resolve `client.ref` in projects.
+    pub fn resolve(&self, id: &str, field: &str) -> FieldResult<&str> {
+        if !self.contains(id) {
+            return Err(FieldError::Invalid(
+                ClientBookError::UnknownClient(id.into()).to_string(),
+            ));
+        }
+        self.get_str(&format!("{}/{}", id, field))
+    }
+
+    /// Makes up a new id from the name of `entry`.
+    fn new_id(&self, entry: &ClientEntry) -> String {
+        let name = entry.name();
+        let base = match slug::slugify(&name) {
+            slug if slug.is_empty() => String::from("client"),
+            slug => slug,
+        };
+        let mut id = base.clone();
+        let mut counter = 1;
+        while self.contains(&id) {
+            counter += 1;
+            id = format!("{}-{}", base, counter);
+        }
+        id
+    }
+
+    fn edit<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Document) -> Result<(), yaml_edit::EditError>,
+    {
+        let mut document = Document::new(&self.content);
+        f(&mut document)?;
+        *self = Self::from_content(&document.to_string())?;
+        Ok(())
+    }
+
+    /// Adds a client, returns its new id.
+    pub fn add(&mut self, entry: &ClientEntry) -> Result<String, Error> {
+        let id = self.new_id(entry);
+        self.insert(&id, entry)?;
+        Ok(id)
+    }
+
+    /// Adds a client under a given id.
+    pub fn insert(&mut self, id: &str, entry: &ClientEntry) -> Result<(), Error> {
+        if self.contains(id) {
+            anyhow::bail!(ClientBookError::AlreadyExists(id.into()));
+        }
+        self.edit(|document| {
+            for (field, value) in entry.fields() {
+                set_field(document, id, field, value.unwrap_or_default())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Changes one field of a client, an empty value clears it.
+    pub fn set(&mut self, id: &str, field: &str, value: &str) -> Result<(), Error> {
+        if !self.contains(id) {
+            anyhow::bail!(ClientBookError::UnknownClient(id.into()));
+        }
+        if !FIELDS.contains(&field) {
+            anyhow::bail!(ClientBookError::UnknownField(field.into()));
+        }
+        self.edit(|document| {
+            let path = format!("{}.{}", id, field);
+            if document.contains(&path) {
+                document.clear(&path)?;
+            }
+            set_field(document, id, field, value)
+        })
+    }
+}
+
+fn set_field(document: &mut Document, id: &str, field: &str, value: &str) -> Result<(), yaml_edit::EditError> {
+    let path = format!("{}.{}", id, field);
+    if value.contains('\n') {
+        document.set_text(&path, value)
+    } else if value.is_empty() {
+        document.set(&path, "")
+    } else {
+        document.set(&path, &yaml_edit::string(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zahl() -> ClientEntry {
+        ClientEntry {
+            title: Some("Herr".into()),
+            first_name: Some("Graf".into()),
+            last_name: Some("Zahl".into()),
+            email: None,
+            address: Some("Graf Zahl\nNummernhöllenstraße 666\n".into()),
+            country: None,
+        }
+    }
+
+    #[test]
+    fn add_and_read_clients() {
+        let mut book = ClientBook::default();
+        assert_eq!(book.add(&zahl()).unwrap(), "graf-zahl");
+        assert_eq!(book.add(&zahl()).unwrap(), "graf-zahl-2");
+        assert_eq!(book.add(&ClientEntry::default()).unwrap(), "client");
+        assert_eq!(book.ids(), vec!["graf-zahl", "graf-zahl-2", "client"]);
+        assert_eq!(book.get("graf-zahl"), Some(zahl()));
+        assert!(book.content().starts_with(
+            "graf-zahl:\n  title: Herr\n  first_name: Graf\n  last_name: Zahl\n  email:\n  address: |\n    Graf Zahl\n"
+        ));
+        assert!(book.insert("client", &zahl()).is_err());
+    }
+
+    #[test]
+    fn edit_clients() {
+        let mut book =
+            ClientBook::from_content("# our clients\ngraf-zahl:\n  title: Herr # not Frau\n  last_name: Zahl\n")
+                .unwrap();
+        book.set("graf-zahl", "title", "Frau").unwrap();
+        book.set("graf-zahl", "address", "Gräfin Zahl\nCountilvania\n").unwrap();
+        book.set("graf-zahl", "address", "Countilvania").unwrap();
+        assert_eq!(
+            book.content(),
+            "# our clients\ngraf-zahl:\n  title: Frau # not Frau\n  last_name: Zahl\n  address: Countilvania\n"
+        );
+        assert!(book.set("graf-zahl", "age", "1000").is_err());
+        assert!(book.set("dracula", "title", "Herr").is_err());
+        assert_eq!(book.resolve("graf-zahl", "last_name"), Ok("Zahl"));
+        assert_eq!(book.resolve("graf-zahl", "email"), Err(FieldError::Missing));
+        assert!(matches!(book.resolve("dracula", "email"), Err(FieldError::Invalid(_))));
+    }
+}
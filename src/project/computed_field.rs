@@ -31,6 +31,8 @@ custom_derive! {
         Name,
         /// Amount of money owed by the customer
         Final,
+        /// What is left of `Final` after subtracting payments
+        Outstanding,
         /// Age of the Project in days
         Age,
         /// Time in weeks it took to write the invoice
@@ -83,6 +85,7 @@ impl ComputedField {
                     .unwrap_or_else(|| project.file_name()),
             ), // TODO: remove name() from `Storable`, storables only need a slug()
             ComputedField::Final => project.sum_sold().map(|c| util::currency_to_string(&c)).ok(),
+            ComputedField::Outstanding => project.outstanding().map(|c| util::currency_to_string(&c)).ok(),
             ComputedField::Age => project.age().map(|a| lformat!("{} days", a)),
 
             ComputedField::OurBad => project.our_bad().map(|a| lformat!("{} weeks", a.num_weeks().abs())),
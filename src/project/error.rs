@@ -9,6 +9,25 @@ pub enum ProjectError {
 
     #[error("Cannot determine target file name")]
     CantDetermineTargetFile,
+
+    #[error("Missing for an e-invoice: {}", _0.join(", "))]
+    MissingForEInvoice(Vec<String>),
+
+    #[error("Invalid for an e-invoice: {}", _0.join(", "))]
+    InvalidForEInvoice(Vec<String>),
+
+    #[error("This invoice has already been canceled")]
+    AlreadyCanceled,
+
+    #[error("{:?} has a total of hours, not single shifts", _0)]
+    HoursInTotal(String),
+
+    #[error("Line {}, column {}: {}", line, column, message)]
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
 }
 
 /// Result of validating part of a project.
@@ -1,6 +1,10 @@
-use bill::{Bill, ItemList, Tax};
+use bill::{Bill, Currency, ItemList, Tax};
 
-use crate::{project::Project, storage::storable::Storable, util::currency_to_string};
+use crate::{
+    project::Project,
+    storage::storable::Storable,
+    util::{currency, currency_to_string},
+};
 
 use super::{computed_field::ComputedField, spec::*};
 
@@ -124,8 +128,8 @@ fn export_employee(e: &crate::project::spec::Employee) -> Employee {
     Employee {
         name: e.name.clone(),
         time: e.time.into(),
-        salary: e.salary.postfix().to_string(),
-        wage: e.wage.postfix().to_string(),
+        salary: currency_to_string(&e.salary),
+        wage: currency_to_string(&e.wage),
     }
 }
 
@@ -134,9 +138,9 @@ impl ExportTarget<Service> for Project {
         Service {
             time: self.hours().total_time().map(ExportFloat),
             tax: self.hours().tax().ok().map(|t| t.value()).map(ExportFloat),
-            salary: self.hours().salary().ok().map(|s| s.postfix().to_string()),
-            gross_total: self.hours().gross_wages().map(|s| s.postfix().to_string()),
-            net_total: self.hours().net_wages().map(|s| s.postfix().to_string()),
+            salary: self.hours().salary().ok().map(|s| currency_to_string(&s)),
+            gross_total: self.hours().gross_wages().map(|s| currency_to_string(&s)),
+            net_total: self.hours().net_wages().map(|s| currency_to_string(&s)),
             employees: self
                 .hours()
                 .employees()
@@ -152,6 +156,7 @@ pub struct Sum {
     gross_sum: String,
     has_tax: bool,
     tax_sum: String,
+    net_sum: String,
     tax_value: ExportFloat,
 }
 
@@ -163,6 +168,10 @@ fn sums_from_bill(bill: &Bill<Product<'_>>) -> Vec<Sum> {
         .collect::<Vec<_>>()
 }
 
+fn negated(c: Currency) -> Currency {
+    c * -1i64
+}
+
 impl Sum {
     pub fn from_itemlist(tax: Tax, list: &ItemList<Product<'_>>) -> Sum {
         let gross_sum = list.gross_sum();
@@ -171,9 +180,20 @@ impl Sum {
             tax_value: ExportFloat(tax.into_inner() * 100.0),
             gross_sum: currency_to_string(&gross_sum),
             tax_sum: currency_to_string(&tax_sum),
+            net_sum: currency_to_string(&list.net_sum()),
             has_tax: (tax.into_inner() > 0f64),
         }
     }
+
+    /// Same sums, but taken back
+    pub fn negated_from_itemlist(tax: Tax, list: &ItemList<Product<'_>>) -> Sum {
+        Sum {
+            gross_sum: currency_to_string(&negated(list.gross_sum())),
+            tax_sum: currency_to_string(&negated(list.tax_sum())),
+            net_sum: currency_to_string(&negated(list.net_sum())),
+            ..Sum::from_itemlist(tax, list)
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -229,6 +249,40 @@ impl ExportTarget<Invoice> for Project {
     }
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct Cancellation {
+    date: Option<String>,
+    number: Option<String>,
+    number_long: Option<String>,
+    reason: Option<String>,
+    invoice_number: Option<String>,
+    sums: Vec<Sum>,
+    net_total: String,
+    gross_total: String,
+}
+
+impl ExportTarget<Cancellation> for Project {
+    fn export(&self) -> Cancellation {
+        let (_, invoice) = self.bills().unwrap();
+
+        Cancellation {
+            date: dmy(self.cancellation().date().ok()),
+            number: self.cancellation().number_str(),
+            number_long: self.cancellation().number_long_str(),
+            reason: self.cancellation().reason().ok(),
+            invoice_number: self.cancellation().invoice_number(),
+            sums: invoice
+                .iter()
+                .map(|(tax, list)| Sum::negated_from_itemlist(*tax, list))
+                .rev()
+                .collect(),
+            net_total: currency_to_string(&negated(invoice.net_total())),
+            gross_total: currency_to_string(&negated(invoice.gross_total())),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "serialization", derive(Serialize))]
 pub struct ExportProduct {
@@ -240,6 +294,21 @@ pub struct ExportProduct {
     tax: ExportFloat,
 }
 
+/// Everything that was invoiced, taken back
+fn negated_bill_products(bill: &Bill<Product<'_>>) -> Vec<ExportProduct> {
+    bill.as_items_with_tax()
+        .into_iter()
+        .map(|(tax, item)| ExportProduct {
+            name: item.product.name.to_string(),
+            price: currency_to_string(&item.product.price),
+            unit: item.product.unit.unwrap_or("").to_string(),
+            amount: (-item.amount).into(),
+            cost: currency_to_string(&negated(item.gross())),
+            tax: tax.value().into(),
+        })
+        .collect()
+}
+
 fn bill_products(bill: &Bill<Product<'_>>) -> Vec<ExportProduct> {
     bill.as_items_with_tax()
         .into_iter()
@@ -259,6 +328,7 @@ fn bill_products(bill: &Bill<Product<'_>>) -> Vec<ExportProduct> {
 pub struct Bills {
     pub offer: Vec<ExportProduct>,
     pub invoice: Vec<ExportProduct>,
+    pub cancellation: Vec<ExportProduct>,
 }
 
 impl ExportTarget<Bills> for Project {
@@ -268,6 +338,7 @@ impl ExportTarget<Bills> for Project {
         Bills {
             offer: bill_products(&offer),
             invoice: bill_products(&invoice),
+            cancellation: negated_bill_products(&invoice),
         }
     }
 }
@@ -275,11 +346,14 @@ impl ExportTarget<Bills> for Project {
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "serialization", derive(Serialize))]
 pub struct Complete {
+    /// ISO 4217 code of all amounts
+    currency: Option<String>,
     client: Client,
     event: Event,
     service: Service,
     offer: Offer,
     invoice: Invoice,
+    cancellation: Cancellation,
     bills: Bills,
     checks: Checks,
     errors: Errors,
@@ -289,11 +363,16 @@ pub struct Complete {
 impl ExportTarget<Complete> for Project {
     fn export(&self) -> Complete {
         Complete {
+            currency: self
+                .currency_symbol()
+                .and_then(currency::by_symbol)
+                .map(|info| info.code.to_owned()),
             client: self.export(),
             event: self.export(),
             service: self.export(),
             offer: self.export(),
             invoice: self.export(),
+            cancellation: self.export(),
             bills: self.export(),
             checks: self.export(),
             errors: self.export(),
@@ -307,6 +386,7 @@ impl ExportTarget<Complete> for Project {
 pub struct Checks {
     missing_for_offer: bool,
     missing_for_invoice: bool,
+    missing_for_cancellation: bool,
     ready_for_archive: bool,
     payed_by_customer: bool,
     payed_employees: bool,
@@ -318,6 +398,7 @@ impl ExportTarget<Checks> for Project {
         Checks {
             missing_for_offer: self.is_missing_for_offer().is_empty(),
             missing_for_invoice: self.is_missing_for_invoice().is_empty(),
+            missing_for_cancellation: self.is_missing_for_cancellation().is_empty(),
             ready_for_archive: self.is_ready_for_archive().is_empty(),
             payed_by_customer: self.is_payed(),
             payed_employees: self.hours().employees_payed(),
@@ -332,6 +413,7 @@ impl ExportTarget<Checks> for Project {
 pub struct Errors {
     missing_for_offer: Vec<String>,
     missing_for_invoice: Vec<String>,
+    missing_for_cancellation: Vec<String>,
     ready_for_archive: Vec<String>,
 }
 
@@ -340,6 +422,7 @@ impl ExportTarget<Errors> for Project {
         Errors {
             missing_for_offer: self.is_missing_for_offer(),
             missing_for_invoice: self.is_missing_for_invoice(),
+            missing_for_cancellation: self.is_missing_for_cancellation(),
             ready_for_archive: self.is_ready_for_archive(),
         }
     }
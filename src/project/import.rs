@@ -1,15 +1,48 @@
-use anyhow::Error;
+//! Typed model of a project file, deserialized with serde.
+//!
+//! This covers the current format (`meta.format: 3.x`) as well as older documents.
+//! Legacy fields are kept as they are, the accessors on [`Project`] resolve the fallbacks
+//! the same way the `YamlProvider` based implementation in `spec_yaml` does.
+//! [`Project::client()`] and [`Project::invoice()`] only provide the raw fields through
+//! [`ClientFields`] and [`InvoiceFields`], so [`IsClient`](super::spec::IsClient) and
+//! [`Invoicable`](super::spec::Invoicable) resolve older documents in one place for both.
+//!
+//! The JSON Schema in [`schema`](super::schema) is generated from these types,
+//! doc comments on fields end up as descriptions there.
+
+// the code derived by schemars for `schema_with` and `required` trips this
+#![allow(unused_qualifications)]
+
+use anyhow::{bail, Error};
+use chrono::prelude::*;
 use num_traits::Float;
 use ordered_float::OrderedFloat;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+use serde::{de, Deserializer};
 
 use std::collections::HashMap;
+use std::fmt;
+
+use super::{
+    error::ProjectError,
+    spec::{ClientFields, InvoiceFields},
+    yaml_provider::{FieldError, FieldResult},
+};
+use crate::util::{
+    naive_time_from_str,
+    yaml::{parse_dmy_date, parse_dmy_date_range},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(remote = "OrderedFloat")]
 pub struct OrderedFloatDef<T: Float>(pub T);
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
-pub struct Number(#[serde(with = "OrderedFloatDef")] OrderedFloat<f64>);
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash, Clone)]
+pub struct Number(#[serde(with = "OrderedFloatDef")] #[schemars(with = "f64")] OrderedFloat<f64>);
 
 impl From<Number> for f64 {
     fn from(num: Number) -> f64 {
@@ -18,44 +51,295 @@ impl From<Number> for f64 {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl From<&Number> for f64 {
+    fn from(num: &Number) -> f64 {
+        num.0.into_inner()
+    }
+}
+
+/// A date written as `dd.mm.yyyy`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub struct DmyDate(#[serde(serialize_with = "serialize_dmy")] pub Date<Utc>);
+
+fn serialize_dmy<S: serde::Serializer>(date: &Date<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date.format("%d.%m.%Y").to_string())
+}
+
+impl JsonSchema for DmyDate {
+    fn schema_name() -> String {
+        "DmyDate".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some("dd.mm.yyyy".into()),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(r"^\d{1,2}\.\d{1,2}\.\d{4}$".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl<'de> de::Deserialize<'de> for DmyDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DateVisitor;
+
+        // failing inside the visitor lets the error point at the value itself
+        impl<'de> de::Visitor<'de> for DateVisitor {
+            type Value = DmyDate;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a date (dd.mm.yyyy)")
+            }
+
+            fn visit_str<E: de::Error>(self, date: &str) -> Result<DmyDate, E> {
+                NaiveDate::parse_from_str(date.trim(), "%d.%m.%Y")
+                    .map(|date| DmyDate(Date::from_utc(date, Utc)))
+                    .map_err(|_| E::custom(lformat!("{:?} is not a date (dd.mm.yyyy)", date)))
+            }
+        }
+
+        deserializer.deserialize_str(DateVisitor)
+    }
+}
+
+/// Sections that used to be a plain string in older documents.
+///
+/// Unlike `#[serde(untagged)]` this keeps the location of errors inside the section.
+macro_rules! section_or_legacy_string {
+    ($spec:ident, $section:ident, $expecting:expr) => {
+        impl<'de> de::Deserialize<'de> for $spec {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct SpecVisitor;
+
+                impl<'de> de::Visitor<'de> for SpecVisitor {
+                    type Value = $spec;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<$spec, E> {
+                        Ok($spec::Legacy(value.to_owned()))
+                    }
+
+                    fn visit_map<M: de::MapAccess<'de>>(self, map: M) -> Result<$spec, M::Error> {
+                        de::Deserialize::deserialize(de::value::MapAccessDeserializer::new(map)).map($spec::$section)
+                    }
+                }
+
+                deserializer.deserialize_any(SpecVisitor)
+            }
+        }
+    };
+}
+
+section_or_legacy_string!(ClientSpec, Client, "a client section or title and name");
+section_or_legacy_string!(EventSpec, Event, "an event section or the name of the event");
+
+/// Accepts `true`/`false` as well as `"yes"`/`"no"`, just like `YamlProvider::get_bool()`.
+fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    struct FlagVisitor;
+
+    impl<'de> de::Visitor<'de> for FlagVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a boolean or yes/no")
+        }
+
+        fn visit_bool<E: de::Error>(self, value: bool) -> Result<bool, E> {
+            Ok(value)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<bool, E> {
+            Ok(value.to_lowercase() == "yes")
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+            Ok(false)
+        }
+    }
+
+    deserializer.deserialize_any(FlagVisitor)
+}
+
+fn flag_schema(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(vec![InstanceType::Boolean, InstanceType::String, InstanceType::Null].into()),
+        enum_values: Some(vec![true.into(), false.into(), "yes".into(), "no".into(), serde_json::Value::Null]),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn currency_schema(_: &mut SchemaGenerator) -> Schema {
+    let codes = crate::util::currency::CURRENCIES.iter().map(|info| info.code.into());
+    SchemaObject {
+        instance_type: Some(vec![InstanceType::String, InstanceType::Null].into()),
+        enum_values: Some(codes.chain(Some(serde_json::Value::Null)).collect()),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// `hh:mm`, quoted in the document so it stays a string
+const TIME_PATTERN: &str = r"^(\d{1,2}([:.]\d{0,2})?)?$";
+
+/// A project file of asciii, format 3.x
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Project {
-    client: Client,
-    event: Event,
-    hours: Hours,
-    products: HashMap<ProductDesc, Product>,
-    manager: Option<String>,
-    invoicer_version: Option<String>,
-    template: Option<String>,
-    created: Option<String>,
-    canceled: Option<String>,
-    meta: Option<Meta>,
-    tax: Option<Number>,
+    pub client: ClientSpec,
+    pub event: EventSpec,
+    pub offer: Option<Offer>,
+    pub invoice: Option<Invoice>,
+    pub cancellation: Option<Cancellation>,
+    pub payments: Option<Vec<Payment>>,
+    pub hours: Option<Hours>,
+    /// product name or catalogue reference, with amounts
+    pub products: Option<HashMap<ProductDesc, Product>>,
+    pub manager: Option<String>,
+    pub invoicer_version: Option<String>,
+    pub template: Option<String>,
+    pub created: Option<DmyDate>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub canceled: bool,
+    /// there since format 3.0
+    #[schemars(required)]
+    pub meta: Option<Meta>,
+    pub tax: Option<Number>,
+    pub lang: Option<String>,
+    /// ISO 4217 code of all amounts, the configured currency if not set
+    #[serde(default)]
+    #[schemars(schema_with = "currency_schema")]
+    pub currency: Option<String>,
+
+    /// not part of the schema, which only describes the current format
+    #[serde(flatten)]
+    #[schemars(skip)]
+    pub legacy: Legacy,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Top level fields of documents before `format: 2.0`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Legacy {
+    pub format: Option<String>,
+    pub signature: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    /// the event date, may also be a range like `01-03.05.2014`
+    pub date: Option<String>,
+    pub manumber: Option<String>,
+    pub rnumber: Option<i64>,
+    pub invoice_date: Option<DmyDate>,
+    pub payed_date: Option<DmyDate>,
+    pub wages_date: Option<DmyDate>,
+}
+
+/// The client is a section since `format: 2.0`, before that it was two lines: title and last name.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ClientSpec {
+    Client(Client),
+    Legacy(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Client {
-    title: String,
-    first_name: String,
-    last_name: String,
-    email: Option<String>,
-    address: Option<String>,
+    /// id of a client in extras/clients.yml, fields set here take precedence
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    /// the first word determines the salutation, e.g. Herr, Frau, Mr, Ms
+    pub title: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    /// ISO 3166-1 code like `DE`
+    pub country: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The event is a section since `format: 2.0`, before that it was only its name.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum EventSpec {
+    Event(Event),
+    Legacy(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Event {
-    name: String,
-    location: Option<String>,
-    description: Option<String>,
-    dates: Vec<ProjectDate>,
+    pub name: Option<String>,
+    pub location: Option<Location>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub dates: Vec<ProjectDate>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One place or several
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Location {
+    Place(String),
+    Places(Vec<String>),
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename = "Date")]
 pub struct ProjectDate {
-    begin: String,
-    end: Option<String>,
-    times: Vec<Time>,
+    pub begin: Option<DmyDate>,
+    pub end: Option<DmyDate>,
+    #[serde(default)]
+    pub times: Vec<Time>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Time {
+    #[schemars(regex = "TIME_PATTERN")]
+    pub begin: Option<String>,
+    #[schemars(regex = "TIME_PATTERN")]
+    pub end: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Offer {
+    pub date: Option<DmyDate>,
+    #[schemars(range(min = 1))]
+    pub appendix: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Invoice {
+    #[schemars(range(min = 1))]
+    pub number: Option<i64>,
+    pub date: Option<DmyDate>,
+    pub payed_date: Option<DmyDate>,
+    pub official: Option<String>,
+    pub buyer_reference: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Cancellation {
+    #[schemars(range(min = 1))]
+    pub number: Option<i64>,
+    pub date: Option<DmyDate>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Payment {
+    pub date: DmyDate,
+    pub amount: f64,
+    pub method: Option<String>,
+    pub note: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -81,38 +365,272 @@ impl ProductDesc {
     pub fn price(&self) -> Option<f64> {
         match *self {
             ProductDesc::Name(_) => None,
-            ProductDesc::Head { ref price, .. } => Some(price.clone().into()),
+            ProductDesc::Head { ref price, .. } => Some(price.into()),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Products are either described in their key (`*kaffee:`) or inline, next to the amount.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Product {
-    amount: f64,
-    returned: Option<f64>,
-    price: Option<f64>,
-    sold: Option<f64>,
+    pub amount: f64,
+    pub returned: Option<f64>,
+    pub sold: Option<f64>,
+    pub name: Option<String>,
+    pub price: Option<f64>,
+    pub unit: Option<String>,
+    pub tax: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Hours {
-    salary: f64,
-    caterers: HashMap<String, f64>,
+/// What an employee worked, either in total or shift by shift.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Worked {
+    Hours(Option<f64>),
+    Shifts(Vec<Shift>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Time {
-    begin: String,
-    end: Option<String>,
+impl Worked {
+    pub fn hours(&self) -> Option<f64> {
+        match self {
+            Worked::Hours(hours) => *hours,
+            Worked::Shifts(shifts) => Some(shifts.iter().filter_map(Shift::hours).sum()),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One check-in and check-out, the date defaults to the first day of the event.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Shift {
+    pub date: Option<DmyDate>,
+    #[schemars(regex = "TIME_PATTERN")]
+    pub begin: String,
+    #[schemars(regex = "TIME_PATTERN")]
+    pub end: String,
+    /// minutes
+    pub pause: Option<u32>,
+}
+
+impl Shift {
+    pub fn hours(&self) -> Option<f64> {
+        let begin = naive_time_from_str(&self.begin)?;
+        let end = naive_time_from_str(&self.end)?;
+        let mut minutes = (end - begin).num_minutes();
+        if minutes <= 0 {
+            minutes += 24 * 60;
+        }
+        Some((minutes - i64::from(self.pause.unwrap_or(0))).max(0) as f64 / 60.0)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Hours {
+    pub salary: Option<f64>,
+    pub tax: Option<f64>,
+    /// ISO 4217 code the wages are paid in, the configured currency if not set
+    #[serde(default)]
+    #[schemars(schema_with = "currency_schema")]
+    pub currency: Option<String>,
+    pub wages_date: Option<DmyDate>,
+    /// hours per person, in total or shift by shift
+    pub caterers: Option<HashMap<String, Worked>>,
+    /// older name of `caterers`
+    pub employees: Option<HashMap<String, Worked>>,
+    /// legacy total time
+    pub time: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Meta {
-    invoicer_version: String,
-    template: String,
-    format: String,
+    pub invoicer_version: Option<String>,
+    pub template: Option<String>,
+    #[schemars(regex(pattern = r"^3\.\d+\.\d+"))]
+    pub format: String,
 }
 
+impl Project {
+    pub fn name(&self) -> Option<&str> {
+        match self.event {
+            EventSpec::Event(ref event) => event.name.as_deref(),
+            EventSpec::Legacy(ref name) => Some(name),
+        }
+    }
+
+    pub fn event_date(&self) -> Option<Date<Utc>> {
+        let first_date = match self.event {
+            EventSpec::Event(ref event) => event.dates.first().and_then(|d| d.begin),
+            EventSpec::Legacy(_) => None,
+        };
+        first_date.or(self.created).map(|DmyDate(date)| date).or_else(|| {
+            self.legacy
+                .date
+                .as_deref()
+                .and_then(|date| parse_dmy_date(date).or_else(|| parse_dmy_date_range(date)))
+        })
+    }
+
+    pub fn format(&self) -> Option<&str> {
+        self.meta
+            .as_ref()
+            .map(|meta| meta.format.as_str())
+            .or(self.legacy.format.as_deref())
+    }
+
+    pub fn responsible(&self) -> Option<&str> {
+        self.manager
+            .as_deref()
+            .or_else(|| self.legacy.signature.as_deref().and_then(|s| s.lines().last()))
+    }
+
+    /// The client, falling back to the fields of older documents
+    pub fn client(&self) -> ClientView<'_> {
+        ClientView { project: self }
+    }
+
+    /// The invoice, falling back to the fields of older documents
+    pub fn invoice(&self) -> InvoiceView<'_> {
+        InvoiceView { project: self }
+    }
+
+    pub fn offer_number(&self) -> Option<String> {
+        self.offer
+            .as_ref()
+            .and_then(|offer| {
+                let DmyDate(date) = offer.date?;
+                Some(format!("{}-{}", date.format("A%Y%m%d"), offer.appendix.unwrap_or(1)))
+            })
+            .or_else(|| self.legacy.manumber.clone())
+    }
+
+    pub fn payed_date(&self) -> Option<Date<Utc>> {
+        self.invoice
+            .as_ref()
+            .and_then(|invoice| invoice.payed_date)
+            .or(self.legacy.payed_date)
+            .map(|DmyDate(date)| date)
+    }
+
+    pub fn wages_date(&self) -> Option<Date<Utc>> {
+        self.hours
+            .as_ref()
+            .and_then(|hours| hours.wages_date)
+            .or(self.legacy.wages_date)
+            .map(|DmyDate(date)| date)
+    }
+
+    /// Employees and their hours, from `hours.caterers` or `hours.employees`.
+    pub fn employees(&self) -> HashMap<&str, f64> {
+        self.hours
+            .as_ref()
+            .and_then(|hours| hours.caterers.as_ref().or(hours.employees.as_ref()))
+            .map(|employees| {
+                employees
+                    .iter()
+                    .filter_map(|(name, worked)| Some((name.as_str(), worked.hours()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn present<T>(value: Option<T>) -> FieldResult<T> {
+    value.ok_or(FieldError::Missing)
+}
+
+/// The client section of a typed [`Project`]
+pub struct ClientView<'a> {
+    project: &'a Project,
+}
+
+impl<'a> ClientFields for ClientView<'a> {
+    fn client_field(&self, field: &str) -> FieldResult<&str> {
+        let client = match self.project.client {
+            ClientSpec::Client(ref client) => client,
+            ClientSpec::Legacy(_) => return Err(FieldError::Missing),
+        };
+        present(match field {
+            "title" => client.title.as_deref(),
+            "first_name" => client.first_name.as_deref(),
+            "last_name" => client.last_name.as_deref(),
+            "email" => client.email.as_deref(),
+            "address" => client.address.as_deref(),
+            "country" => client.country.as_deref(),
+            _ => None,
+        })
+    }
+
+    fn legacy_field(&self, field: &str) -> FieldResult<&str> {
+        let legacy = &self.project.legacy;
+        present(match field {
+            "email" => legacy.email.as_deref(),
+            "address" => legacy.address.as_deref(),
+            _ => None,
+        })
+    }
+
+    fn legacy_client(&self) -> FieldResult<&str> {
+        match self.project.client {
+            ClientSpec::Legacy(ref client) => Ok(client),
+            ClientSpec::Client(_) => Err(FieldError::Missing),
+        }
+    }
+}
+
+/// The invoice section of a typed [`Project`]
+pub struct InvoiceView<'a> {
+    project: &'a Project,
+}
+
+impl<'a> InvoiceView<'a> {
+    fn section(&self) -> Option<&'a Invoice> {
+        self.project.invoice.as_ref()
+    }
+}
+
+impl<'a> InvoiceFields for InvoiceView<'a> {
+    fn invoice_number(&self) -> FieldResult<i64> {
+        present(self.section().and_then(|invoice| invoice.number))
+    }
+
+    fn invoice_date(&self) -> FieldResult<Date<Utc>> {
+        present(self.section().and_then(|invoice| invoice.date).map(|DmyDate(date)| date))
+    }
+
+    fn invoice_official(&self) -> FieldResult<String> {
+        present(self.section().and_then(|invoice| invoice.official.clone()))
+    }
+
+    fn invoice_buyer_reference(&self) -> FieldResult<String> {
+        present(self.section().and_then(|invoice| invoice.buyer_reference.clone()))
+    }
+
+    fn legacy_number(&self) -> FieldResult<i64> {
+        present(self.project.legacy.rnumber)
+    }
+
+    fn legacy_date(&self) -> FieldResult<Date<Utc>> {
+        present(self.project.legacy.invoice_date.map(|DmyDate(date)| date))
+    }
+}
+
+/// Parses a project document, errors point to the line and column that could not be read.
 pub fn from_str(content: &str) -> Result<Project, Error> {
-    Ok(serde_yaml::from_str(content)?)
+    match serde_yaml::from_str(content) {
+        Ok(project) => Ok(project),
+        Err(error) => {
+            let message = error.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(message, _)| message)
+                .to_owned();
+            match error.location() {
+                Some(location) => bail!(ProjectError::Parse {
+                    line: location.line(),
+                    column: location.column(),
+                    message
+                }),
+                None => bail!(error),
+            }
+        },
+    }
 }
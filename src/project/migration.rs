@@ -0,0 +1,308 @@
+//! Upgrades project files to the current format.
+//!
+//! The format of a document is `meta.format`, or `format` in `2.x`; documents without either are `1.x`.
+//! Each [`Migration`] lifts a document to the next version,
+//! [`migrate()`] runs all steps a document still needs, one after the other.
+//!
+//! All edits go through [`Document`], comments and everything that does not need to change stay where they are.
+
+use std::fmt::Write;
+
+use anyhow::Error;
+use chrono::prelude::*;
+use semver::Version;
+use yaml_rust::Yaml;
+
+use crate::util::{
+    yaml,
+    yaml_edit::{string, Document, EditError},
+};
+
+/// The format that `templates/default.tyml` produces.
+pub const CURRENT_FORMAT: &str = "3.0.0";
+
+/// One step in the chain of upgrades.
+pub struct Migration {
+    /// the format of the document after this step
+    pub to: &'static str,
+    pub description: &'static str,
+    apply: fn(&Yaml, &mut Document) -> Result<(), EditError>,
+}
+
+impl Migration {
+    pub fn to(&self) -> Version {
+        Version::parse(self.to).expect("migrations carry valid versions")
+    }
+}
+
+/// All steps, oldest first.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: "2.0.0",
+        description: "client, event, offer and invoice become sections",
+        apply: sections,
+    },
+    Migration {
+        to: "3.0.0",
+        description: "format and template information move to meta, employees become caterers",
+        apply: meta,
+    },
+];
+
+/// The result of migrating one document.
+#[derive(Debug)]
+pub struct Migrated {
+    pub from: Version,
+    pub to: Version,
+    pub original: String,
+    pub content: String,
+}
+
+impl Migrated {
+    /// The changed lines, prefixed with `-` and `+`, each group headed by its line number.
+    pub fn diff(&self) -> String {
+        let mut out = String::new();
+        let mut line = 0;
+        let mut in_hunk = false;
+        for change in diff::lines(&self.original, &self.content) {
+            let (prefix, text) = match change {
+                diff::Result::Both(..) => {
+                    line += 1;
+                    in_hunk = false;
+                    continue;
+                },
+                diff::Result::Left(text) => ('-', text),
+                diff::Result::Right(text) => ('+', text),
+            };
+            if !in_hunk {
+                writeln!(out, "@@ {} @@", line + 1).unwrap();
+                in_hunk = true;
+            }
+            if prefix == '-' {
+                line += 1;
+            }
+            writeln!(out, "{}{}", prefix, text).unwrap();
+        }
+        out
+    }
+}
+
+/// Format of a parsed document, `1.0.0` if it does not say.
+pub fn format_of(document: &Yaml) -> Result<Version, Error> {
+    match yaml::get_str(document, "meta/format").or_else(|| yaml::get_str(document, "format")) {
+        Some(format) => Ok(Version::parse(format)?),
+        None => Ok(Version::new(1, 0, 0)),
+    }
+}
+
+/// Runs all migrations `content` still needs.
+///
+/// Returns `None` if the document is already up to date.
+pub fn migrate(content: &str) -> Result<Option<Migrated>, Error> {
+    let mut parsed = yaml::parse(content)?;
+    let from = format_of(&parsed)?;
+    let original = content;
+    let mut content = content.to_owned();
+    let mut format = from.clone();
+
+    for migration in MIGRATIONS {
+        if format >= migration.to() {
+            continue;
+        }
+        log::debug!(
+            "migrating from {} to {}: {}",
+            format,
+            migration.to,
+            migration.description
+        );
+        let mut document = Document::new(&content);
+        (migration.apply)(&parsed, &mut document)?;
+        content = document.to_string();
+        parsed = yaml::parse(&content)?;
+        format = migration.to();
+    }
+
+    if format == from {
+        return Ok(None);
+    }
+    Ok(Some(Migrated {
+        from,
+        to: format,
+        original: original.to_owned(),
+        content,
+    }))
+}
+
+/// Moves the value of `from` to `to`, single lines keep their quoting, multi line strings become literal blocks.
+fn move_value(yaml: &Yaml, document: &mut Document, from: &str, to: &str) -> Result<(), EditError> {
+    if !document.contains(from) {
+        return Ok(());
+    }
+    match (document.get(from).map(ToOwned::to_owned), yaml::get_str(yaml, from)) {
+        (Some(raw), _) if !raw.is_empty() && !raw.starts_with(['|', '>']) => document.set(to, &raw)?,
+        (_, Some(text)) if text.contains('\n') => document.set_text(to, text)?,
+        (_, Some(text)) => document.set(to, &string(text))?,
+        _ => {},
+    }
+    document.delete(from)
+}
+
+/// Unlike [`parse_dmy_date()`](yaml::parse_dmy_date) this takes any year, old documents contain made up ones.
+fn parse_date(date: &str) -> Option<Date<Utc>> {
+    NaiveDate::parse_from_str(date.trim(), "%d.%m.%Y")
+        .ok()
+        .map(|date| Utc.from_utc_date(&date))
+}
+
+/// Splits legacy date ranges like `01-03.05.2014` or `30.04.-02.05.2014` into begin and end.
+fn parse_date_range(range: &str) -> Option<(Date<Utc>, Option<Date<Utc>>)> {
+    if let Some(date) = parse_date(range) {
+        return Some((date, None));
+    }
+    let (begin, end) = range.split_once('-')?;
+    let end = parse_date(end)?;
+    let begin = begin.trim().trim_end_matches('.');
+    let given = begin.split('.').count();
+    let missing = end
+        .format("%d.%m.%Y")
+        .to_string()
+        .split('.')
+        .skip(given)
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    let begin = parse_date(
+        &[begin.to_owned()]
+            .iter()
+            .chain(&missing)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("."),
+    )?;
+    Some((begin, Some(end)))
+}
+
+/// Reads old offer numbers like `A20141107-1`.
+fn parse_offer_number(number: &str) -> Option<(Date<Utc>, Option<i64>)> {
+    let number = number.trim().strip_prefix('A')?;
+    let (date, appendix) = match number.split_once('-') {
+        Some((date, appendix)) => (date, appendix.parse().ok()),
+        None => (number, None),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y%m%d").ok()?;
+    Some((Utc.from_utc_date(&date), appendix))
+}
+
+fn dmy(date: Date<Utc>) -> String {
+    date.format("%d.%m.%Y").to_string()
+}
+
+/// `1.x` → `2.0.0`
+fn sections(yaml: &Yaml, document: &mut Document) -> Result<(), EditError> {
+    if document.contains("version") {
+        document.delete("version")?;
+    }
+
+    if let Some(client) = yaml::get_str(yaml, "client") {
+        let mut lines = client.lines().map(str::trim).filter(|l| !l.is_empty());
+        document.clear("client")?;
+        if let Some(title) = lines.next() {
+            document.set("client.title", &string(title))?;
+        }
+        if let Some(last_name) = lines.next() {
+            document.set("client.last_name", &string(last_name))?;
+        }
+    }
+    move_value(yaml, document, "email", "client.email")?;
+    move_value(yaml, document, "address", "client.address")?;
+
+    if let Some(event) = yaml::get_str(yaml, "event") {
+        document.clear("event")?;
+        document.set("event.name", &string(event.trim()))?;
+    }
+    if let Some(date) = yaml::get_str(yaml, "date") {
+        match parse_date_range(date) {
+            Some((begin, end)) => {
+                let mut item = vec![("begin", dmy(begin))];
+                item.extend(end.map(|end| ("end", dmy(end))));
+                document.push("event.dates", &item)?;
+                document.delete("date")?;
+            },
+            None => log::warn!("{:?} is neither date nor date range, it stays where it is", date),
+        }
+    }
+
+    let manumber = yaml::get_str(yaml, "manumber");
+    match manumber.map(|number| (number, parse_offer_number(number))) {
+        Some((_, Some((date, appendix)))) => {
+            document.set("offer.date", &dmy(date))?;
+            let appendix = yaml::get_int(yaml, "anumber").or(appendix).unwrap_or(1);
+            document.set("offer.appendix", &appendix.to_string())?;
+            document.delete("manumber")?;
+            if document.contains("anumber") {
+                document.delete("anumber")?;
+            }
+        },
+        Some((number, None)) => log::warn!("can't read offer number {:?}, it stays where it is", number),
+        None => move_value(yaml, document, "anumber", "offer.appendix")?,
+    }
+
+    move_value(yaml, document, "rnumber", "invoice.number")?;
+    move_value(yaml, document, "invoice_date", "invoice.date")?;
+    move_value(yaml, document, "payed_date", "invoice.payed_date")?;
+
+    if let Some(manager) =
+        yaml::get_str(yaml, "signature").and_then(|s| s.lines().map(str::trim).rfind(|l| !l.is_empty()))
+    {
+        document.set("manager", &string(manager))?;
+        document.delete("signature")?;
+    }
+    move_value(yaml, document, "wages_date", "hours.wages_date")?;
+
+    document.set("format", "2.0.0")
+}
+
+/// `2.x` → `3.0.0`
+fn meta(yaml: &Yaml, document: &mut Document) -> Result<(), EditError> {
+    move_value(yaml, document, "invoicer_version", "meta.invoicer_version")?;
+    move_value(yaml, document, "template", "meta.template")?;
+    if document.contains("format") {
+        document.delete("format")?;
+    }
+    document.set("meta.format", CURRENT_FORMAT)?;
+
+    if document.contains("hours.employees") && !document.contains("hours.caterers") {
+        document.rename("hours.employees", "caterers")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_ranges() {
+        let date = |d, m, y| Utc.ymd(y, m, d);
+        assert_eq!(parse_date_range("01.05.2014"), Some((date(1, 5, 2014), None)));
+        assert_eq!(
+            parse_date_range("01-03.05.2014"),
+            Some((date(1, 5, 2014), Some(date(3, 5, 2014))))
+        );
+        assert_eq!(
+            parse_date_range("30.04.-02.05.2014"),
+            Some((date(30, 4, 2014), Some(date(2, 5, 2014))))
+        );
+        assert_eq!(
+            parse_date_range("30.12.2013 - 02.01.2014"),
+            Some((date(30, 12, 2013), Some(date(2, 1, 2014))))
+        );
+        assert_eq!(parse_date_range("soon"), None);
+    }
+
+    #[test]
+    fn offer_numbers() {
+        assert_eq!(parse_offer_number("A20141107-2"), Some((Utc.ymd(2014, 11, 7), Some(2))));
+        assert_eq!(parse_offer_number("A20141107"), Some((Utc.ymd(2014, 11, 7), None)));
+        assert_eq!(parse_offer_number("20141107-1"), None);
+    }
+}
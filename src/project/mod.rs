@@ -9,12 +9,12 @@ use std::{
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::{bail, Error};
 use chrono::{prelude::*, Duration};
 use maplit::hashmap;
-use tempdir::TempDir;
 use yaml_rust::Yaml;
 
 use bill::BillItem;
@@ -22,33 +22,45 @@ use icalendar::{Calendar, CalendarDateTime, Component, Todo};
 use semver::Version;
 
 use crate::{
-    storage::{list_path_content, repo::GitStatus, Storable, StorableAndTempDir, StorageError},
+    storage::{backend::FileSystem, journal::Journal, list_path_content, repo::GitStatus, Storable, StorageError},
     templater::{IsKeyword, Templater},
-    util::{get_valid_path, yaml},
+    util::{currency, get_valid_path, yaml, yaml_edit},
 };
 
+pub mod catalogue;
+pub mod client_book;
 pub mod product;
+pub mod query;
+pub mod search;
 pub mod spec;
 mod spec_yaml;
 mod yaml_provider;
 
 mod computed_field;
 pub mod error;
+pub mod migration;
+pub mod xrechnung;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(all(feature = "serialization", not(target_arch = "wasm32")))]
+pub mod cache;
 #[cfg(feature = "serialization")]
 pub mod export;
 #[cfg(feature = "deserialization")]
 pub mod import;
+#[cfg(all(feature = "serialization", feature = "deserialization"))]
+pub mod schema;
 #[cfg(feature = "serialization")]
 use self::export::*;
 
 use self::{
-    error::ProjectError,
+    catalogue::Catalogue,
+    client_book::ClientBook,
+    error::{ProjectError, ValidationResult},
     product::{Product, ProductError},
-    spec::{HasEmployees, Invoicable, IsClient, IsProject, Offerable, Redeemable, Validatable},
+    spec::{Cancelable, HasEmployees, Invoicable, IsClient, IsProject, Offerable, Payment, Redeemable, Validatable},
     yaml_provider::*,
 };
 
@@ -64,6 +76,10 @@ pub struct Project {
     git_status: Option<GitStatus>,
     file_content: String,
     yaml: Yaml,
+    /// resolves `client.ref`, the global [`CLIENT_BOOK`](client_book::CLIENT_BOOK) if `None`
+    client_book: Option<Arc<ClientBook>>,
+    /// resolves product ids, the global [`CATALOGUE`](catalogue::CATALOGUE) if `None`
+    catalogue: Option<Arc<Catalogue>>,
 }
 
 impl Project {
@@ -72,11 +88,53 @@ impl Project {
         &self.yaml
     }
 
+    /// Resolves `client.ref` in `book` instead of the client book of the storage.
+    pub fn with_client_book(mut self, book: Arc<ClientBook>) -> Self {
+        self.client_book = Some(book);
+        self
+    }
+
+    /// The client book that `client.ref` refers to.
+    pub fn client_book(&self) -> &ClientBook {
+        self.client_book.as_deref().unwrap_or(&client_book::CLIENT_BOOK)
+    }
+
+    /// Resolves product ids in `catalogue` instead of the catalogue of the storage.
+    pub fn with_catalogue(mut self, catalogue: Arc<Catalogue>) -> Self {
+        self.catalogue = Some(catalogue);
+        self
+    }
+
+    /// The catalogue that product ids refer to.
+    pub fn catalogue(&self) -> &Catalogue {
+        self.catalogue.as_deref().unwrap_or(&catalogue::CATALOGUE)
+    }
+
+    /// Symbol of the [currency](IsProject::currency) of the project, the configured one if it has none.
+    pub fn currency_symbol(&self) -> Option<char> {
+        self.currency()
+            .map(|info| Some(info.symbol))
+            .unwrap_or_else(|_| currency::default_symbol())
+    }
+
+    /// The day whose catalogue prices apply: the offer date, or else the event date, or else today.
+    pub fn price_date(&self) -> Date<Utc> {
+        self.offer()
+            .date()
+            .or_else(|_| self.event_date())
+            .unwrap_or_else(|_| Utc::today())
+    }
+
     /// Opens a project from file path;
     pub fn open<S: AsRef<OsStr> + std::fmt::Debug + ?Sized>(pathish: &S) -> Result<Project, Error> {
         log::trace!("Project::open({:?});", pathish);
         let file_path = Path::new(&pathish);
         let file_content = fs::read_to_string(file_path)?;
+        Ok(Self::load(file_path, file_content))
+    }
+
+    /// Parses and validates the content of the project file at `file_path`.
+    fn load(file_path: &Path, file_content: String) -> Project {
         let project = Project {
             file_path: file_path.to_owned(),
             git_status: None,
@@ -85,6 +143,8 @@ impl Project {
                 Yaml::Null
             }),
             file_content,
+            client_book: None,
+            catalogue: None,
         };
 
         let validation = project
@@ -93,7 +153,8 @@ impl Project {
             .and(project.invoice().validate())
             .and(project.offer().validate())
             .and(project.hours().validate())
-            .and(<dyn Redeemable>::validate(&project));
+            .and(<dyn Redeemable>::validate(&project))
+            .and(project.validate_schema());
 
         if !validation.validation_errors.is_empty() {
             let name = project.short_desc();
@@ -102,7 +163,25 @@ impl Project {
                 println!(" * {}", err);
             }
         }
-        Ok(project)
+        project
+    }
+
+    /// Checks documents of the current format against the [`schema`], older ones are left alone.
+    #[cfg(all(feature = "serialization", feature = "deserialization"))]
+    pub fn validate_schema(&self) -> ValidationResult {
+        let mut validation = ValidationResult::new();
+        if self.format().is_ok_and(|format| format.major >= 3) {
+            validation
+                .validation_errors
+                .extend(schema::validate(self.yaml()).iter().map(ToString::to_string));
+        }
+        validation
+    }
+
+    /// (feature deactivated) Checks documents against the schema
+    #[cfg(not(all(feature = "serialization", feature = "deserialization")))]
+    pub fn validate_schema(&self) -> ValidationResult {
+        ValidationResult::new()
     }
 
     /// import from yaml file
@@ -140,6 +219,26 @@ impl Project {
         bail!(error::ProjectError::FeatureDeactivated)
     }
 
+    /// export the invoice as XRechnung (UBL)
+    pub fn to_xrechnung(&self) -> Result<String, Error> {
+        xrechnung::to_ubl(self, &xrechnung::Seller::from_config())
+    }
+
+    /// export the cancellation of the invoice as XRechnung credit note (UBL)
+    pub fn cancellation_to_xrechnung(&self) -> Result<String, Error> {
+        xrechnung::credit_note_to_ubl(self, &xrechnung::Seller::from_config())
+    }
+
+    /// export the invoice as Factur-X/ZUGFeRD (CII), which is embedded into the pdf
+    pub fn to_factur_x(&self) -> Result<String, Error> {
+        xrechnung::to_cii(self, &xrechnung::Seller::from_config())
+    }
+
+    /// export the cancellation of the invoice as Factur-X/ZUGFeRD credit note (CII)
+    pub fn cancellation_to_factur_x(&self) -> Result<String, Error> {
+        xrechnung::credit_note_to_cii(self, &xrechnung::Seller::from_config())
+    }
+
     /// Used mostly for testing purposes
     pub fn from_file_content(content: &str) -> Result<Project, Error> {
         Ok(Project {
@@ -147,6 +246,8 @@ impl Project {
             git_status: None,
             yaml: yaml::parse(content).unwrap(),
             file_content: String::from(content),
+            client_book: None,
+            catalogue: None,
         })
     }
 
@@ -172,6 +273,11 @@ impl Project {
         Invoice { inner: self }
     }
 
+    /// Returns the struct `Cancellation`, which abstracts away the credit note of a canceled invoice.
+    pub fn cancellation(&self) -> Cancellation<'_> {
+        Cancellation { inner: self }
+    }
+
     /// Returns the struct `Invoice`, which abstracts away invoice specific stuff.
     pub fn hours(&self) -> Hours<'_> {
         Hours { inner: self }
@@ -201,6 +307,50 @@ impl Project {
         missing
     }
 
+    /// Valid to produce a cancellation
+    ///
+    /// Only an invoice can be canceled.
+    ///
+    /// Returns list of missing fields, empty vector if ready.
+    pub fn is_missing_for_cancellation(&self) -> Vec<String> {
+        let mut missing = self.is_missing_for_invoice();
+        missing.extend(self.cancellation().validate().missing_fields);
+        missing
+    }
+
+    /// Checks every tax rate used in the project against `defaults/tax_rates`,
+    /// and against `defaults/temporary_tax_rates` by the date of the event, or else of the invoice.
+    ///
+    /// Unknown rates are invalid values, not missing ones.
+    pub fn validate_taxes(&self) -> ValidationResult {
+        let mut validation = ValidationResult::new();
+        let date = self.event_date().or_else(|_| self.invoice().date()).ok();
+
+        if let Ok(tax) = Redeemable::tax(self) {
+            if !product::is_known_tax(tax, date) {
+                let error = lformat!("unknown tax rate {}%", tax.value() * 100.0);
+                validation
+                    .validation_errors
+                    .push(lformat!("{:?} is invalid: {}", "tax", error));
+            }
+        }
+
+        if let Ok((offer, invoice)) = self.bills() {
+            let mut reported = Vec::new();
+            for item in offer.as_items().into_iter().chain(invoice.as_items()) {
+                let product = item.product;
+                if !product::is_known_tax(product.tax, date) && !reported.contains(&product.name) {
+                    reported.push(product.name);
+                    validation
+                        .validation_errors
+                        .push(ProductError::UnknownTax(product.name.into(), product.tax.value()).to_string());
+                }
+            }
+        }
+
+        validation
+    }
+
     /// Completely done and in the past.
     ///
     /// Ready to be **h:
@@ -221,7 +371,7 @@ impl Project {
         let (offer, invoice) = self.bills()?;
         let bill = match bill_type {
             BillType::Offer => offer,
-            BillType::Invoice => invoice,
+            BillType::Invoice | BillType::Cancellation => invoice,
         };
         let mut csv_string = String::new();
         let splitter = ";";
@@ -255,33 +405,158 @@ impl Project {
     }
 
     /// Fill certain field
+    ///
+    /// Placeholders that are the value of a key are set through [`Document`](yaml_edit::Document),
+    /// any others are replaced in place.
     pub fn replace_field(&self, field: &str, value: &str) -> Result<(), Error> {
-        // fills the template
-        let filled = Templater::new(&self.file_content)
-            .fill_in_field(field, value)
-            .finalize()
-            .filled;
+        self.replace_fields(&[(field, value)])
+    }
 
-        match yaml::parse(&filled) {
-            Ok(_) => {
-                let mut file = File::create(self.file())?;
-                file.write_all(filled.as_bytes())?;
-                file.sync_all()?;
-                Ok(())
-            },
-            Err(error) => {
-                log::error!(
-                    "The resulting document is no valid yaml. SORRY!\n{}\n\n{}",
-                    filled.lines().enumerate().fold(String::new(), |mut mes, (n, l)| {
-                        //line numbers :D
-                        let _ = writeln!(&mut mes, "{:>3}. {}", n, l);
-                        mes
-                    }),
-                    error
-                );
-                bail!(error)
-            },
+    /// Fills several fields like [`Project::replace_field`], the file is only written once all of them are filled.
+    pub fn replace_fields(&self, fields: &[(&str, &str)]) -> Result<(), Error> {
+        let mut filled = self.file_content.clone();
+        for &(field, value) in fields {
+            let mut document = yaml_edit::Document::new(&filled);
+            filled = match document.placeholder_path(field) {
+                Some(path) => {
+                    document.set(&path, &yaml_edit::scalar(value))?;
+                    document.to_string()
+                },
+                None => Templater::new(&filled).fill_in_field(field, value).finalize().filled,
+            };
+        }
+        let what = fields
+            .iter()
+            .map(|(field, value)| format!("set {} to {:?}", field, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.write_content(&filled, &what)
+    }
+
+    /// Sets a value by its path, e.g. `invoice.payed_date`, comments and everything else stay as they are.
+    pub fn set_value(&self, path: &str, value: &str) -> Result<(), Error> {
+        let mut document = yaml_edit::Document::new(&self.file_content);
+        document.set(path, &yaml_edit::scalar(value))?;
+        self.write_content(&document.to_string(), &format!("set {} to {:?}", path, value))
+    }
+
+    /// Removes a value by its path, including everything nested inside it.
+    pub fn delete_value(&self, path: &str) -> Result<(), Error> {
+        let mut document = yaml_edit::Document::new(&self.file_content);
+        document.delete(path)?;
+        self.write_content(&document.to_string(), &format!("removed {}", path))
+    }
+
+    /// What upgrading the project file to the current format changes, see [`migration`].
+    ///
+    /// Returns `None` if there is nothing to do, nothing is written yet.
+    pub fn migration(&self) -> Result<Option<migration::Migrated>, Error> {
+        migration::migrate(&self.file_content)
+    }
+
+    /// Writes the result of [`Project::migration`] to the project file.
+    pub fn apply_migration(&self, migrated: &migration::Migrated) -> Result<(), Error> {
+        self.write_content(
+            &migrated.content,
+            &format!("migrated from format {} to {}", migrated.from, migrated.to),
+        )
+    }
+
+    /// Replaces the client section with a reference to the client book, see [`client_book`].
+    pub fn link_client(&self, id: &str) -> Result<(), Error> {
+        let mut document = yaml_edit::Document::new(&self.file_content);
+        if document.contains("client") {
+            document.clear("client")?;
         }
+        document.set("client.ref", &yaml_edit::string(id))?;
+        self.write_content(&document.to_string(), &format!("linked client {}", id))
+    }
+
+    /// Writes `content` to the project file, as long as it is still valid yaml.
+    ///
+    /// Inside the storage, what the file was before goes into the [`journal`](crate::storage::journal),
+    /// `what` describes the change there.
+    fn write_content(&self, content: &str, what: &str) -> Result<(), Error> {
+        if let Err(error) = yaml::parse(content) {
+            log::error!(
+                "The resulting document is no valid yaml. SORRY!\n{}\n\n{}",
+                content.lines().enumerate().fold(String::new(), |mut mes, (n, l)| {
+                    //line numbers :D
+                    let _ = writeln!(&mut mes, "{:>3}. {}", n, l);
+                    mes
+                }),
+                error
+            );
+            bail!(error)
+        }
+        let before = fs::read_to_string(self.file()).unwrap_or_else(|_| self.file_content.clone());
+
+        let mut file = File::create(self.file())?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+
+        let storage = crate::storage::get_storage_path();
+        if self.file().starts_with(&storage) {
+            let description = format!("{}: {}", Storable::short_desc(self), what);
+            if let Err(error) =
+                Journal::new(&storage, &FileSystem).record_edit(&self.file(), &before, content, &description)
+            {
+                log::warn!("could not record {:?} in the journal: {}", description, error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends an entry to `payments`, leaves the rest of the file untouched
+    pub fn add_payment(&self, payment: &Payment) -> Result<(), Error> {
+        self.write_content(
+            &append_payment(&self.file_content, payment)?,
+            &format!("payment of {}", crate::util::currency_to_string(&payment.amount)),
+        )
+    }
+
+    /// Adds a shift of `name` to `hours.caterers`, without a `date` it is on the first day of the event
+    pub fn add_shift(
+        &self,
+        name: &str,
+        date: Option<Date<Utc>>,
+        begin: NaiveTime,
+        end: NaiveTime,
+        pause: i64,
+    ) -> Result<(), Error> {
+        if !matches!(
+            self.yaml()["hours"]["caterers"][name],
+            Yaml::BadValue | Yaml::Null | Yaml::Array(_)
+        ) {
+            bail!(ProjectError::HoursInTotal(name.into()));
+        }
+        let content = append_shift(&self.file_content, name, date, begin, end, pause)?;
+        self.write_content(&content, &format!("shift of {} from {} to {}", name, begin, end))
+    }
+
+    /// Sets the invoice number, and the invoice `date` unless the project already has one.
+    pub fn set_invoice_number(&self, number: i64, date: Date<Utc>) -> Result<(), Error> {
+        let mut document = yaml_edit::Document::new(&self.file_content);
+        document.set("invoice.number", &number.to_string())?;
+        // the number belongs to the series of the invoice date, without one it would count for the event
+        if self.invoice().date().is_err() {
+            document.set("invoice.date", &date.format("%d.%m.%Y").to_string())?;
+        }
+        self.write_content(&document.to_string(), &format!("invoiced with number {}", number))
+    }
+
+    /// Adds a `cancellation` section, there can only be one
+    pub fn add_cancellation(&self, number: i64, date: Date<Utc>, reason: Option<&str>) -> Result<(), Error> {
+        let mut document = yaml_edit::Document::new(&self.file_content);
+        if document.contains("cancellation") {
+            bail!(ProjectError::AlreadyCanceled);
+        }
+        document.set("cancellation.number", &number.to_string())?;
+        document.set("cancellation.date", &date.format("%d.%m.%Y").to_string())?;
+        if let Some(reason) = reason {
+            document.set("cancellation.reason", &yaml_edit::string(reason))?;
+        }
+        self.write_content(&document.to_string(), &format!("canceled with credit note {}", number))
     }
 
     /// Time between event and creation of invoice
@@ -420,7 +695,7 @@ impl Project {
     }
 
     fn item_from_desc_and_value<'y>(
-        &self,
+        &'y self,
         desc: &'y Yaml,
         values: &'y Yaml,
     ) -> Result<(BillItem<Product<'y>>, BillItem<Product<'y>>), Error> {
@@ -429,7 +704,14 @@ impl Project {
                 .and_then(|y| y.as_f64().or_else(|| y.as_i64().map(|y| y as f64)))
         };
 
-        let product = Product::from_desc_and_value(desc, values, self.tax().ok())?;
+        let product = Product::from_desc_and_value(
+            desc,
+            values,
+            self.tax().ok(),
+            self.catalogue(),
+            self.price_date(),
+            self.currency_symbol(),
+        )?;
 
         let offered = get_f64(values, "amount").ok_or_else(|| ProductError::MissingAmount(product.name.to_owned()))?;
 
@@ -464,6 +746,8 @@ impl Project {
 pub enum BillType {
     Offer,
     Invoice,
+    /// Credit note that cancels the invoice (Storno)
+    Cancellation,
 }
 
 impl ToString for BillType {
@@ -471,6 +755,7 @@ impl ToString for BillType {
         match *self {
             BillType::Offer => "Offer",
             BillType::Invoice => "Invoice",
+            BillType::Cancellation => "Cancellation",
         }
         .to_owned()
     }
@@ -487,10 +772,22 @@ pub trait Exportable {
     /// Filename of the invoice output file. **Careful!** uses today's date.
     fn invoice_file_name(&self, extension: &str) -> Option<String>;
 
+    /// Filename of the cancellation output file.
+    fn cancellation_file_name(&self, extension: &str) -> Option<String>;
+
+    fn output_file_name(&self, bill_type: BillType, extension: &str) -> Option<String> {
+        match bill_type {
+            BillType::Offer => self.offer_file_name(extension),
+            BillType::Invoice => self.invoice_file_name(extension),
+            BillType::Cancellation => self.cancellation_file_name(extension),
+        }
+    }
+
     fn output_file_exists(&self, bill_type: BillType) -> bool {
         match bill_type {
             BillType::Offer => self.offer_file_exists(),
             BillType::Invoice => self.invoice_file_exists(),
+            BillType::Cancellation => self.cancellation_file().is_some_and(|f| f.exists()),
         }
     }
 
@@ -498,6 +795,7 @@ pub trait Exportable {
         match bill_type {
             BillType::Offer => self.offer_file(),
             BillType::Invoice => self.invoice_file(),
+            BillType::Cancellation => self.cancellation_file(),
         }
     }
 
@@ -519,6 +817,15 @@ pub trait Exportable {
         }
     }
 
+    fn cancellation_file(&self) -> Option<PathBuf> {
+        let output_folder = get_valid_path(crate::CONFIG.get_str("output_path"));
+        let convert_ext = crate::CONFIG.get_str("document_export/output_extension");
+        match (output_folder, self.cancellation_file_name(convert_ext)) {
+            (Some(folder), Some(name)) => folder.join(name).into(),
+            _ => None,
+        }
+    }
+
     fn offer_file_exists(&self) -> bool {
         self.offer_file().map_or(false, |f| f.exists())
     }
@@ -539,6 +846,7 @@ pub trait Exportable {
         match bill_type {
             BillType::Offer => self.full_offer_file_path(ext),
             BillType::Invoice => self.full_invoice_file_path(ext),
+            BillType::Cancellation => self.full_cancellation_file_path(ext),
         }
     }
 
@@ -558,13 +866,20 @@ pub trait Exportable {
         }
     }
 
-    fn write_to_file(&self, content: &str, bill_type: BillType, ext: &str) -> Result<PathBuf, Error> {
-        match bill_type {
-            BillType::Offer => self.write_to_offer_file(content, ext),
-            BillType::Invoice => self.write_to_invoice_file(content, ext),
+    fn full_cancellation_file_path(&self, ext: &str) -> Result<PathBuf, Error> {
+        if let Some(target) = self.cancellation_file_name(ext) {
+            Ok(self.export_dir().join(target))
+        } else {
+            bail!(ProjectError::CantDetermineTargetFile)
         }
     }
 
+    fn write_to_file(&self, content: &str, bill_type: BillType, ext: &str) -> Result<PathBuf, Error> {
+        let full_path = self.full_file_path(bill_type, ext)?;
+        Self::write_to_path(content, &full_path)?;
+        Ok(full_path)
+    }
+
     fn write_to_offer_file(&self, content: &str, ext: &str) -> Result<PathBuf, Error> {
         let full_path = self.full_offer_file_path(ext)?;
         Self::write_to_path(content, &full_path)?;
@@ -596,6 +911,14 @@ impl Exportable for Project {
         let date = self.invoice().date().ok()?.format("%Y-%m-%d").to_string();
         Some(format!("{} {} {}.{}", num, name, date, extension))
     }
+
+    fn cancellation_file_name(&self, extension: &str) -> Option<String> {
+        let num = self.cancellation().number_str()?;
+        let invoice = self.invoice().number_str()?;
+        let name = slug::slugify(self.name().ok()?);
+        let date = self.cancellation().date().ok()?.format("%Y-%m-%d").to_string();
+        Some(format!("{} {} {} {}.{}", num, invoice, name, date, extension))
+    }
 }
 
 impl Storable for Project {
@@ -605,11 +928,10 @@ impl Storable for Project {
 
     fn from_template(
         project_name: &str,
-        template: &Path,
+        template_name: &str,
+        template: &str,
         fill: &HashMap<&str, String>,
-    ) -> Result<StorableAndTempDir<Self>, Error> {
-        let template_name = template.file_stem().unwrap().to_str().unwrap();
-
+    ) -> Result<String, Error> {
         let event_date = (Utc::today() + Duration::days(14)).format("%d.%m.%Y").to_string();
         let created_date = Utc::today().format("%d.%m.%Y").to_string();
 
@@ -628,7 +950,7 @@ impl Storable for Project {
         };
 
         // fills the template
-        let file_content = Templater::from_file(template)?
+        let file_content = Templater::new(template)
             .fill_in_data(fill)
             .fix()
             .fill_in_data(&default_fill)
@@ -637,45 +959,20 @@ impl Storable for Project {
 
         log::debug!("remaining template fields: {:#?}", file_content.list_keywords());
 
-        // generates a temp file
-        let temp_dir = TempDir::new(project_name).unwrap();
-        let temp_file = temp_dir
-            .path()
-            .join(slug::slugify(project_name) + "." + &Self::file_extension());
-
-        // write into a file
-        let mut file = File::create(&temp_file)?;
-        file.write_all(file_content.as_bytes())?;
-        file.sync_all()?;
-
-        let yaml = match yaml::parse(&file_content) {
-            Ok(y) => y,
-            Err(error) => {
-                log::error!(
-                    "The created document is no valid yaml. SORRY!\n{}\n\n{}",
-                    file_content.lines().enumerate().fold(String::new(), |mut mes, (n, l)| {
-                        // line numbers :D
-                        let _ = writeln!(&mut mes, "{:>3}. {}", n, l);
-                        mes
-                    }),
-                    error
-                );
-                bail!(error)
-            },
-        };
-
-        // project now lives in the temp_file
-        let project = Project {
-            file_path: temp_file,
-            git_status: None,
-            file_content,
-            yaml,
-        };
+        if let Err(error) = yaml::parse(&file_content) {
+            log::error!(
+                "The created document is no valid yaml. SORRY!\n{}\n\n{}",
+                file_content.lines().enumerate().fold(String::new(), |mut mes, (n, l)| {
+                    // line numbers :D
+                    let _ = writeln!(&mut mes, "{:>3}. {}", n, l);
+                    mes
+                }),
+                error
+            );
+            bail!(error)
+        }
 
-        Ok(StorableAndTempDir {
-            storable: project,
-            temp_dir,
-        })
+        Ok(file_content)
     }
 
     fn prefix(&self) -> Option<String> {
@@ -738,6 +1035,10 @@ impl Storable for Project {
         Project::open(file_path)
     }
 
+    fn from_content(file_path: &Path, content: &str) -> Result<Project, Error> {
+        Ok(Project::load(file_path, content.into()))
+    }
+
     /// Checks against a certain key-val pair.
     fn matches_filter(&self, key: &str, val: &str) -> bool {
         self.field(key)
@@ -775,6 +1076,11 @@ pub struct Invoice<'a> {
     inner: &'a Project,
 }
 
+/// This is returned by [`Product::cancellation()`](struct.Project.html#method.cancellation).
+pub struct Cancellation<'a> {
+    inner: &'a Project,
+}
+
 /// This is returned by [`Product::hours()`](struct.Project.html#method.hours).
 pub struct Hours<'a> {
     inner: &'a Project,
@@ -801,6 +1107,47 @@ impl<'a> From<&'a Project> for Debug {
     }
 }
 
+/// Inserts `payment` behind the last entry of the top level `payments` list, or starts that list.
+fn append_payment(content: &str, payment: &Payment) -> Result<String, yaml_edit::EditError> {
+    let mut entry = vec![
+        ("date", payment.date.format("%d.%m.%Y").to_string()),
+        ("amount", payment.amount.as_float().to_string()),
+    ];
+    if let Some(ref method) = payment.method {
+        entry.push(("method", yaml_edit::string(method)));
+    }
+    if let Some(ref note) = payment.note {
+        entry.push(("note", yaml_edit::string(note)));
+    }
+
+    let mut document = yaml_edit::Document::new(content);
+    document.push("payments", &entry)?;
+    Ok(document.to_string())
+}
+
+fn append_shift(
+    content: &str,
+    name: &str,
+    date: Option<Date<Utc>>,
+    begin: NaiveTime,
+    end: NaiveTime,
+    pause: i64,
+) -> Result<String, yaml_edit::EditError> {
+    let mut entry = Vec::new();
+    if let Some(date) = date {
+        entry.push(("date", date.format("%d.%m.%Y").to_string()));
+    }
+    entry.push(("begin", yaml_edit::quoted(&begin.format("%H:%M").to_string())));
+    entry.push(("end", yaml_edit::quoted(&end.format("%H:%M").to_string())));
+    if pause > 0 {
+        entry.push(("pause", pause.to_string()));
+    }
+
+    let mut document = yaml_edit::Document::new(content);
+    document.push_at(&["hours", "caterers", name], &entry)?;
+    Ok(document.to_string())
+}
+
 impl fmt::Debug for Project {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         //write!(f, "{:?}", self.debug())
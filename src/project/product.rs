@@ -4,9 +4,11 @@
 #![allow(missing_docs)]
 
 use bill::{BillProduct, Currency, Tax};
+use chrono::prelude::*;
 use thiserror::Error;
 
-use crate::util::{to_currency, yaml};
+use super::catalogue::{Catalogue, CatalogueError};
+use crate::util::{currency, to_currency_in, yaml};
 
 //#[derive(Debug)] // manually implemented
 /// Stores properties of a product.
@@ -40,6 +42,78 @@ pub enum ProductError {
 
     #[error("Cannot Parse Service")]
     InvalidServerSection,
+
+    #[error("unknown tax rate {}% for {:?}", _1 * 100.0, _0)]
+    UnknownTax(String, f64),
+
+    #[error(transparent)]
+    Catalogue(#[from] CatalogueError),
+}
+
+/// A tax rate that was only valid for a while, see `defaults/temporary_tax_rates`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemporaryTax {
+    pub tax: Tax,
+    pub since: Date<Utc>,
+    pub until: Date<Utc>,
+}
+
+fn taxes_in(rates: Option<&yaml::Yaml>) -> Vec<Tax> {
+    rates
+        .and_then(yaml::Yaml::as_vec)
+        .map(|rates| {
+            rates
+                .iter()
+                .filter_map(|r| r.as_f64().or_else(|| r.as_i64().map(|i| i as f64)))
+                .map(Tax::new)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+lazy_static::lazy_static! {
+    static ref KNOWN_TAX_RATES: Vec<Tax> = taxes_in(crate::CONFIG.get("defaults/tax_rates"));
+
+    static ref TEMPORARY_TAX_RATES: Vec<TemporaryTax> = crate::CONFIG
+        .get("defaults/temporary_tax_rates")
+        .and_then(yaml::Yaml::as_vec)
+        .map(|periods| {
+            periods
+                .iter()
+                .filter_map(|period| {
+                    let since = yaml::get_str(period, "since").and_then(yaml::parse_dmy_date)?;
+                    let until = yaml::get_str(period, "until").and_then(yaml::parse_dmy_date)?;
+                    Some(
+                        taxes_in(yaml::get(period, "rates"))
+                            .into_iter()
+                            .map(move |tax| TemporaryTax { tax, since, until }),
+                    )
+                })
+                .flatten()
+                .collect()
+        })
+        .unwrap_or_default();
+}
+
+/// Tax rates listed in `defaults/tax_rates`
+pub fn known_tax_rates() -> &'static [Tax] {
+    &KNOWN_TAX_RATES
+}
+
+/// Tax rates listed in `defaults/temporary_tax_rates`
+pub fn temporary_tax_rates() -> &'static [TemporaryTax] {
+    &TEMPORARY_TAX_RATES
+}
+
+/// Checks `tax` against `defaults/tax_rates`, and `defaults/temporary_tax_rates` that were valid on `date`
+pub fn is_known_tax(tax: Tax, date: Option<Date<Utc>>) -> bool {
+    let same = |known: Tax| (known.value() - tax.value()).abs() < 1e-9;
+    known_tax_rates().iter().any(|known| same(*known))
+        || date.is_some_and(|date| {
+            temporary_tax_rates()
+                .iter()
+                .any(|temporary| same(temporary.tax) && temporary.since <= date && date <= temporary.until)
+        })
 }
 
 impl<'a> Product<'a> {
@@ -47,6 +121,7 @@ impl<'a> Product<'a> {
         name: &'y str,
         values: &'y yaml::Yaml,
         local_tax: Option<Tax>,
+        currency: Option<char>,
     ) -> Result<Product<'y>, ProductError> {
         let default_tax = crate::CONFIG
             .get_f64("defaults/tax")
@@ -58,7 +133,7 @@ impl<'a> Product<'a> {
 
         let unit = yaml::get_str(values, "unit");
         let price = yaml::get_f64(values, "price")
-            .map(to_currency)
+            .map(|price| to_currency_in(price, currency))
             .ok_or_else(|| ProductError::InvalidPrice(name.to_string()))?;
 
         Ok(Product { name, unit, tax, price })
@@ -68,6 +143,7 @@ impl<'a> Product<'a> {
         desc: &'y yaml::Yaml,
         values: &'y yaml::Yaml,
         local_tax: Option<Tax>,
+        currency: Option<char>,
     ) -> Result<Product<'y>, ProductError> {
         let default_tax = crate::CONFIG
             .get_f64("defaults/tax")
@@ -81,20 +157,67 @@ impl<'a> Product<'a> {
         let name = yaml::get_str(desc, "name").unwrap_or("unnamed");
         let price = yaml::get_f64(desc, "price")
             .ok_or_else(|| ProductError::InvalidPrice(name.to_string()))
-            .map(to_currency)?;
+            .map(|price| to_currency_in(price, currency))?;
         let unit = yaml::get_str(desc, "unit");
 
         Ok(Product { name, unit, tax, price })
     }
 
+    fn from_catalogue<'y>(
+        id: &'y str,
+        values: &'y yaml::Yaml,
+        local_tax: Option<Tax>,
+        catalogue: &'y Catalogue,
+        date: Date<Utc>,
+        currency: Option<char>,
+    ) -> Result<Product<'y>, ProductError> {
+        let default_tax = crate::CONFIG
+            .get_f64("defaults/tax")
+            .map(Tax::new)
+            .expect("Faulty config: field defaults/tax does not contain a value");
+
+        let values_tax = yaml::get_f64(values, "tax").map(Tax::new);
+        let catalogue_tax = catalogue.tax(id).map(Tax::new);
+        let tax = values_tax.or(catalogue_tax).or(local_tax).unwrap_or(default_tax);
+
+        if catalogue.currency_symbol(id) != currency {
+            let code = |symbol: Option<char>| {
+                symbol.and_then(currency::by_symbol).map_or_else(
+                    || symbol.map(String::from).unwrap_or_default(),
+                    |info| info.code.to_owned(),
+                )
+            };
+            return Err(
+                CatalogueError::OtherCurrency(id.into(), code(catalogue.currency_symbol(id)), code(currency)).into(),
+            );
+        }
+        let price = to_currency_in(catalogue.price_at(id, date)?.price, currency);
+
+        Ok(Product {
+            name: catalogue.name(id),
+            unit: catalogue.unit(id),
+            tax,
+            price,
+        })
+    }
+
+    /// Products are either described in their key (`*kaffee:` or `Kaffee:` with a price next to the amount)
+    /// or refer to the `catalogue` by id, with the price that was valid on `date`.
+    /// All prices are in `currency`, the symbol of the currency of the project.
     pub fn from_desc_and_value<'y>(
         desc: &'y yaml::Yaml,
         values: &'y yaml::Yaml,
         local_tax: Option<Tax>,
+        catalogue: &'y Catalogue,
+        date: Date<Utc>,
+        currency: Option<char>,
     ) -> Result<Product<'y>, ProductError> {
         match *desc {
-            yaml::Yaml::String(ref name) => Self::from_old_format(name, values, local_tax),
-            yaml::Yaml::Hash(_) => Self::from_new_format(desc, values, local_tax),
+            yaml::Yaml::String(ref id) if yaml::get(values, "price").is_none() && catalogue.contains(id) => {
+                Self::from_catalogue(id, values, local_tax, catalogue, date, currency)
+            },
+            yaml::Yaml::String(ref name) => Self::from_old_format(name, values, local_tax, currency),
+            yaml::Yaml::Hash(_) => Self::from_new_format(desc, values, local_tax, currency),
             _ => Err(ProductError::UnknownFormat),
         }
     }
@@ -0,0 +1,651 @@
+//! A small query language over the fields of projects, behind `asciii list --where` and the web api.
+//!
+//! ```text
+//! Final > 500 and Year = 2024 and not payed
+//! Date in 01.01.2024..31.03.2024 or (Name ~ kaffee and Manager = "Hendrik Sollich")
+//! client/last_name = Zahl order by Final desc, Date
+//! ```
+//!
+//! Left of a comparison is a field: a [`ComputedField`] like `Final` or `Date`, one of the flags
+//! `payed`, `canceled`, `invoiced` and `archivable`, or any path in the document like `client/last_name`.
+//! Right of it is a number, a date (`dd.mm.yyyy` or `yyyy-mm-dd`), `true`, `false`, a quoted string or a single word.
+//! `~` means contains, ranges (`in a..b`) include both ends.
+//! A field on its own is true if it is set, not `false` and not `0`.
+//! Nothing compares to a field that is not set, not even `!=`.
+//!
+//! Field names and keywords are case insensitive, so are comparisons of text.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use chrono::prelude::*;
+use thiserror::Error;
+
+use super::{spec::*, ComputedField, Project};
+use crate::{storage::Storable, util::yaml::parse_dmy_date};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("Unexpected {:?} at {} in query, expected {}", found, position, expected)]
+    Unexpected {
+        found: String,
+        position: usize,
+        expected: String,
+    },
+
+    #[error("Query ended early, expected {}", _0)]
+    UnexpectedEnd(String),
+
+    #[error("Unterminated string at {} in query", _0)]
+    UnterminatedString(usize),
+}
+
+/// What a field of a project or a literal in a query can be.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(f64),
+    Date(Date<Utc>),
+    Text(String),
+}
+
+impl Value {
+    /// Reads a literal, everything that is neither a number, a date nor a boolean is text.
+    fn literal(word: &str) -> Value {
+        if let Ok(number) = word.parse::<f64>() {
+            Value::Number(number)
+        } else if let Some(date) = parse_date(word) {
+            Value::Date(date)
+        } else if word.eq_ignore_ascii_case("true") || word.eq_ignore_ascii_case("false") {
+            Value::Bool(word.eq_ignore_ascii_case("true"))
+        } else {
+            Value::Text(word.to_owned())
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Date(_) => true,
+            Value::Text(text) => !text.is_empty() && !text.eq_ignore_ascii_case("false"),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Date(date) => date.format("%d.%m.%Y").to_string(),
+            Value::Text(text) => text.to_lowercase(),
+        }
+    }
+
+    /// Converts `self` into the kind of `like`, if it can.
+    fn coerce(&self, like: &Value) -> Option<Value> {
+        match (self, like) {
+            (Value::Text(text), Value::Number(_)) => parse_number(text).map(Value::Number),
+            (Value::Text(text), Value::Date(_)) => parse_date(text).map(Value::Date),
+            (Value::Text(text), Value::Bool(_)) => match Value::literal(text) {
+                Value::Bool(b) => Some(Value::Bool(b)),
+                _ => None,
+            },
+            (_, Value::Text(_)) => Some(Value::Text(self.to_text())),
+            (Value::Number(_), Value::Number(_))
+            | (Value::Date(_), Value::Date(_))
+            | (Value::Bool(_), Value::Bool(_)) => Some(self.clone()),
+            _ => None,
+        }
+    }
+
+    /// Compares two values of the same kind, `None` if they are of different kinds.
+    fn partial_cmp_same(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().partial_cmp(&b.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+fn parse_date(word: &str) -> Option<Date<Utc>> {
+    parse_dmy_date(word).or_else(|| {
+        NaiveDate::parse_from_str(word, "%Y-%m-%d")
+            .ok()
+            .map(|date| Date::from_utc(date, Utc))
+    })
+}
+
+/// Reads `500`, but also amounts as they are printed, like `1.234,50€` or `$12.10`.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if let Ok(number) = text.parse() {
+        return Some(number);
+    }
+    let digits = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '-' | ',' | '.'))
+        .collect::<String>();
+    if digits.contains(',') {
+        digits.replace('.', "").replace(',', ".").parse().ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `~`
+    Contains,
+}
+
+impl Op {
+    fn applies(self, field: &Value, literal: &Value) -> bool {
+        if self == Op::Contains {
+            return field.to_text().contains(&literal.to_text());
+        }
+        let Some(ordering) = field.coerce(literal).and_then(|field| field.partial_cmp_same(literal)) else {
+            return false;
+        };
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains => unreachable!(),
+        }
+    }
+}
+
+/// A parsed condition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: String,
+        op: Op,
+        value: Value,
+    },
+    Range {
+        field: String,
+        from: Value,
+        to: Value,
+    },
+    /// a field on its own
+    Set(String),
+}
+
+impl Expr {
+    pub fn matches(&self, project: &Project) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(project) && b.matches(project),
+            Expr::Or(a, b) => a.matches(project) || b.matches(project),
+            Expr::Not(expr) => !expr.matches(project),
+            Expr::Compare { field, op, value } => {
+                field_value(project, field).is_some_and(|field| op.applies(&field, value))
+            },
+            Expr::Range { field, from, to } => field_value(project, field)
+                .is_some_and(|field| Op::Ge.applies(&field, from) && Op::Le.applies(&field, to)),
+            Expr::Set(field) => field_value(project, field).is_some_and(|field| field.is_truthy()),
+        }
+    }
+}
+
+/// One field to sort by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// A condition and the order of the results, both optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub filter: Option<Expr>,
+    pub order: Vec<Order>,
+}
+
+impl Query {
+    /// Appends fields to sort by, as in `order by`: `Final desc, Date`.
+    pub fn order_by(mut self, fields: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser::new(fields)?;
+        self.order.extend(parser.orders()?);
+        parser.end()?;
+        Ok(self)
+    }
+
+    pub fn matches(&self, project: &Project) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter.matches(project))
+    }
+
+    /// Sorts stably, projects that don't have a field go last.
+    pub fn sort(&self, projects: &mut [Project]) {
+        self.sort_by_project(projects, |project| project);
+    }
+
+    /// Sorts anything that has a project in it, like `(ident, project)` pairs.
+    pub fn sort_by_project<T, F>(&self, items: &mut [T], project: F)
+    where
+        F: Fn(&T) -> &Project,
+    {
+        if self.order.is_empty() {
+            return;
+        }
+        items.sort_by(|a, b| {
+            let (a, b) = (project(a), project(b));
+            self.order
+                .iter()
+                .map(
+                    |order| match (field_value(a, &order.field), field_value(b, &order.field)) {
+                        (Some(a), Some(b)) => {
+                            let ordering = a.partial_cmp_same(&b).unwrap_or_else(|| a.to_text().cmp(&b.to_text()));
+                            if order.descending {
+                                ordering.reverse()
+                            } else {
+                                ordering
+                            }
+                        },
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    },
+                )
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s)?;
+        let filter = if parser.peek().is_none() || parser.peek_keyword("order") {
+            None
+        } else {
+            Some(parser.or()?)
+        };
+        let order = if parser.eat_keyword("order") {
+            parser.expect_keyword("by")?;
+            parser.orders()?
+        } else {
+            Vec::new()
+        };
+        parser.end()?;
+        Ok(Query { filter, order })
+    }
+}
+
+/// The value of `field` in `project`, typed where the type is known.
+pub fn field_value(project: &Project, field: &str) -> Option<Value> {
+    let money = |amount: bill::Currency| Value::Number(amount.as_float());
+    match field.to_lowercase().as_str() {
+        "payed" | "paid" => Some(Value::Bool(project.is_payed())),
+        "canceled" => Some(Value::Bool(project.canceled())),
+        "invoiced" => Some(Value::Bool(project.invoice().number().is_ok())),
+        "archivable" => Some(Value::Bool(Project::is_ready_for_archive(project).is_empty())),
+        "final" => project.sum_sold().ok().map(money),
+        "outstanding" => project.outstanding().ok().map(money),
+        "wages" => project.hours().gross_wages().map(money),
+        "age" => project.age().map(|days| Value::Number(days as f64)),
+        "ourbad" => project.our_bad().map(|bad| Value::Number(bad.num_weeks().abs() as f64)),
+        "theirbad" => project
+            .their_bad()
+            .map(|bad| Value::Number(bad.num_weeks().abs() as f64)),
+        "year" => Storable::year(project).map(|year| Value::Number(f64::from(year))),
+        "date" => project.modified_date().map(Value::Date),
+        "manager" => project
+            .responsible()
+            .ok()
+            .map(|manager| Value::Text(manager.to_owned())),
+        "index" => project.index().map(Value::Text),
+        _ => {
+            let name = ComputedField::iter_variant_names()
+                .find(|name| name.eq_ignore_ascii_case(field))
+                .unwrap_or(field);
+            project.field(name).map(Value::Text)
+        },
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Op(Op),
+    Open,
+    Close,
+    Comma,
+    Range,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Op(op) => write!(f, "{:?}", op),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::Range => write!(f, ".."),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let next = chars.get(i + 1).copied();
+        let token = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            },
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '~' => Token::Op(Op::Contains),
+            '.' if next == Some('.') => {
+                i += 1;
+                Token::Range
+            },
+            '=' => {
+                if next == Some('=') {
+                    i += 1;
+                }
+                Token::Op(Op::Eq)
+            },
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Op(Op::Ne)
+            },
+            '<' | '>' => {
+                let op = match (chars[i], next == Some('=')) {
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    _ => Op::Ge,
+                };
+                if next == Some('=') {
+                    i += 1;
+                }
+                Token::Op(op)
+            },
+            quote @ ('"' | '\'') => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|c| *c == quote)
+                    .ok_or(QueryError::UnterminatedString(start))?;
+                let text = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 1;
+                Token::Text(text)
+            },
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let mut end = i + 1;
+                while end < chars.len() {
+                    let c = chars[end];
+                    let range_follows = c == '.' && chars.get(end + 1) == Some(&'.');
+                    if range_follows || !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | '.' | ':')) {
+                        break;
+                    }
+                    end += 1;
+                }
+                let word = chars[i..end].iter().collect();
+                i = end - 1;
+                Token::Word(word)
+            },
+            c => {
+                return Err(QueryError::Unexpected {
+                    found: c.to_string(),
+                    position: start,
+                    expected: lformat!("a field, a value or an operator"),
+                })
+            },
+        };
+        i += 1;
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+const KEYWORDS: [&str; 8] = ["and", "or", "not", "in", "order", "by", "asc", "desc"];
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(query: &str) -> Result<Self, QueryError> {
+        Ok(Parser {
+            tokens: tokenize(query)?,
+            position: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn unexpected(&self, expected: String) -> QueryError {
+        match self.tokens.get(self.position) {
+            Some((position, token)) => QueryError::Unexpected {
+                found: token.to_string(),
+                position: *position,
+                expected,
+            },
+            None => QueryError::UnexpectedEnd(expected),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(format!("{:?}", keyword)))
+        }
+    }
+
+    fn end(&self) -> Result<(), QueryError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected(lformat!("\"and\", \"or\" or the end"))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.eat_keyword("not") {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let expr = self.or()?;
+            if self.peek() != Some(&Token::Close) {
+                return Err(self.unexpected("\")\"".into()));
+            }
+            self.position += 1;
+            return Ok(expr);
+        }
+
+        let field = self.field()?;
+        if let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            self.position += 1;
+            let value = self.value()?;
+            Ok(Expr::Compare { field, op, value })
+        } else if self.eat_keyword("in") {
+            let from = self.value()?;
+            if self.peek() != Some(&Token::Range) {
+                return Err(self.unexpected("\"..\"".into()));
+            }
+            self.position += 1;
+            let to = self.value()?;
+            Ok(Expr::Range { field, from, to })
+        } else {
+            Ok(Expr::Set(field))
+        }
+    }
+
+    fn field(&mut self) -> Result<String, QueryError> {
+        match self.peek() {
+            Some(Token::Word(word)) if !KEYWORDS.iter().any(|keyword| word.eq_ignore_ascii_case(keyword)) => {
+                let field = word.clone();
+                self.position += 1;
+                Ok(field)
+            },
+            _ => Err(self.unexpected(lformat!("a field"))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, QueryError> {
+        let value = match self.peek() {
+            Some(Token::Word(word)) => Value::literal(word),
+            Some(Token::Text(text)) => Value::Text(text.clone()),
+            _ => return Err(self.unexpected(lformat!("a value"))),
+        };
+        self.position += 1;
+        Ok(value)
+    }
+
+    fn orders(&mut self) -> Result<Vec<Order>, QueryError> {
+        let mut orders = Vec::new();
+        loop {
+            let field = self.field()?;
+            let descending = self.eat_keyword("desc");
+            if !descending {
+                self.eat_keyword("asc");
+            }
+            orders.push(Order { field, descending });
+            if self.peek() != Some(&Token::Comma) {
+                return Ok(orders);
+            }
+            self.position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(content: &str) -> Project {
+        Project::from_file_content(content).unwrap()
+    }
+
+    fn projects() -> Vec<Project> {
+        vec![
+            project(
+                "event: { name: Kaffee, dates: [ { begin: 20.03.2024 } ] }\nclient: { last_name: Zahl }\ninvoice: { number: 1, date: 21.03.2024 }\nhours: { salary: 10 }\ntax: 0.19\nproducts: { Kaffee: { amount: 300, price: 2.5 } }\n",
+            ),
+            project(
+                "event: { name: Tee, dates: [ { begin: 03.01.2023 } ] }\nclient: { last_name: Meier }\nhours: { salary: 10 }\ntax: 0.19\nproducts: { Tee: { amount: 10, price: 2 } }\n",
+            ),
+            project(
+                "event: { name: Kuchen, dates: [ { begin: 04.04.2024 } ] }\nclient: { last_name: Zahlmann }\nhours: { salary: 10 }\ntax: 0.19\nproducts: { Kuchen: { amount: 100, price: 8 } }\n",
+            ),
+        ]
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        let query = query.parse::<Query>().unwrap();
+        let mut projects = projects();
+        projects.retain(|project| query.matches(project));
+        query.sort(&mut projects);
+        projects.iter().map(|project| project.field("Name").unwrap()).collect()
+    }
+
+    #[test]
+    fn comparisons_and_logic() {
+        assert_eq!(names("Final > 500 and Year = 2024 and not invoiced"), vec!["Kuchen"]);
+        assert_eq!(
+            names("final>500 AND (invoiced or client/last_name = meier)"),
+            vec!["Kaffee"]
+        );
+        assert_eq!(names("client/last_name ~ zahl"), vec!["Kaffee", "Kuchen"]);
+        assert_eq!(names("Name != Tee and Name != 'Kuchen'"), vec!["Kaffee"]);
+        assert_eq!(names("invoice/number"), vec!["Kaffee"]);
+        assert_eq!(names("offer/number = 1"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn date_ranges() {
+        assert_eq!(names("Date in 01.01.2024..31.03.2024"), vec!["Kaffee"]);
+        assert_eq!(names("Date >= 2024-01-01 order by Date desc"), vec!["Kuchen", "Kaffee"]);
+        assert_eq!(names("invoice/date < 2024-03-22"), vec!["Kaffee"]);
+    }
+
+    #[test]
+    fn ordering() {
+        assert_eq!(names("order by Final desc"), vec!["Kuchen", "Kaffee", "Tee"]);
+        assert_eq!(names("order by invoice/number, Name"), vec!["Kaffee", "Kuchen", "Tee"]);
+        let query = Query::default().order_by("Year desc, Name").unwrap();
+        let mut projects = projects();
+        query.sort(&mut projects);
+        assert_eq!(projects[2].field("Name").as_deref(), Some("Tee"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            "Final >".parse::<Query>(),
+            Err(QueryError::UnexpectedEnd(lformat!("a value")))
+        );
+        assert!(matches!(
+            "Final > 5 Year".parse::<Query>(),
+            Err(QueryError::Unexpected { position: 10, .. })
+        ));
+        assert_eq!("Name = 'Tee".parse::<Query>(), Err(QueryError::UnterminatedString(7)));
+        assert!("(payed".parse::<Query>().is_err());
+        assert!("Date in 2024".parse::<Query>().is_err());
+        assert!("".parse::<Query>().unwrap().filter.is_none());
+    }
+}
@@ -0,0 +1,242 @@
+//! JSON Schema of the project file format.
+//!
+//! [`schema()`] describes documents of `meta.format: 3.x`, as produced by `templates/default.tyml`.
+//! It is generated from the typed model in [`import`](super::import), so whatever that can read the schema accepts.
+//! Editors with a yaml language server pick it up through a modeline:
+//!
+//! ```yaml
+//! # yaml-language-server: $schema=asciii-project.schema.json
+//! ```
+//!
+//! [`validate()`] checks a parsed document against it.
+//! It only knows the keywords the generated schema actually uses:
+//! `type`, `properties`, `additionalProperties`, `required`, `items`, `anyOf`, `pattern`, `enum`, `minimum` and local `$ref`s.
+
+use std::{collections::HashMap, fmt};
+
+use regex::Regex;
+use schemars::gen::SchemaSettings;
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use super::import;
+
+lazy_static::lazy_static! {
+    static ref SCHEMA: Value = build();
+    static ref PATTERNS: HashMap<String, Regex> = {
+        let mut patterns = HashMap::new();
+        compile_patterns(&SCHEMA, &mut patterns);
+        patterns
+    };
+}
+
+/// The schema of the current project format.
+pub fn schema() -> &'static Value {
+    &SCHEMA
+}
+
+fn build() -> Value {
+    let mut root = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<import::Project>();
+    root.schema.metadata().title = Some("asciii project".into());
+    serde_json::to_value(root).expect("the schema is valid json")
+}
+
+fn compile_patterns(schema: &Value, patterns: &mut HashMap<String, Regex>) {
+    match schema {
+        Value::Object(object) => {
+            if let Some(pattern) = object.get("pattern").and_then(Value::as_str) {
+                let regex = Regex::new(pattern).expect("the schema only contains valid patterns");
+                patterns.insert(pattern.to_owned(), regex);
+            }
+            object.values().for_each(|child| compile_patterns(child, patterns));
+        },
+        Value::Array(items) => items.iter().for_each(|child| compile_patterns(child, patterns)),
+        _ => {},
+    }
+}
+
+/// A place where the document does not match the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// like `invoice.number` or `event.dates.0.begin`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{:?} {}", self.path, self.message)
+        }
+    }
+}
+
+/// Checks `document` against [`schema()`].
+pub fn validate(document: &Yaml) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check(schema(), schema(), document, "", &mut violations);
+    violations
+}
+
+fn type_name(value: &Yaml) -> &'static str {
+    match value {
+        Yaml::Real(_) => "number",
+        Yaml::Integer(_) => "integer",
+        Yaml::String(_) => "string",
+        Yaml::Boolean(_) => "boolean",
+        Yaml::Array(_) => "array",
+        Yaml::Hash(_) => "object",
+        Yaml::Alias(_) | Yaml::BadValue => "invalid",
+        Yaml::Null => "null",
+    }
+}
+
+fn has_type(value: &Yaml, expected: &str) -> bool {
+    let found = type_name(value);
+    found == expected || (expected == "number" && found == "integer")
+}
+
+fn as_f64(value: &Yaml) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_i64().map(|i| i as f64))
+}
+
+fn equals(value: &Yaml, expected: &Value) -> bool {
+    match (value, expected) {
+        (Yaml::String(s), Value::String(e)) => s == e,
+        (Yaml::Boolean(b), Value::Bool(e)) => b == e,
+        (Yaml::Null, Value::Null) => true,
+        (_, Value::Number(e)) => as_f64(value) == e.as_f64(),
+        _ => false,
+    }
+}
+
+/// Keys are usually strings, catalogue references are whole products, those go by their name.
+fn key_name(key: &Yaml) -> String {
+    match key {
+        Yaml::String(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Real(r) => r.clone(),
+        Yaml::Hash(_) => format!("*{}", crate::util::yaml::get_str(key, "name").unwrap_or("?")),
+        other => type_name(other).into(),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix("#/")
+            .map(|pointer| format!("/{}", pointer))
+            .and_then(|pointer| root.pointer(&pointer))
+            .map_or(schema, |target| resolve(root, target)),
+        None => schema,
+    }
+}
+
+/// The types a schema allows, including those of its alternatives
+fn types<'a>(root: &'a Value, schema: &'a Value) -> Vec<&'a str> {
+    let schema = resolve(root, schema);
+    match (schema.get("type"), schema.get("anyOf").and_then(Value::as_array)) {
+        (Some(Value::String(t)), _) => vec![t.as_str()],
+        (Some(Value::Array(ts)), _) => ts.iter().filter_map(Value::as_str).collect(),
+        (None, Some(alternatives)) => alternatives.iter().flat_map(|a| types(root, a)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether the value has one of the types the schema allows, schemas without a type allow anything.
+fn accepts(root: &Value, schema: &Value, value: &Yaml) -> bool {
+    let types = types(root, schema);
+    types.is_empty() || types.iter().any(|t| has_type(value, t))
+}
+
+fn check(root: &Value, schema: &Value, value: &Yaml, path: &str, out: &mut Vec<Violation>) {
+    let schema = resolve(root, schema);
+    let mut violation = |message: String| {
+        out.push(Violation {
+            path: path.into(),
+            message,
+        })
+    };
+
+    if !accepts(root, schema, value) {
+        let types = types(root, schema);
+        violation(lformat!("should be {}, not {}", types.join(" or "), type_name(value)));
+        return;
+    }
+
+    // only the violations of the alternative that fits the type are of interest
+    if let Some(alternatives) = schema.get("anyOf").and_then(Value::as_array) {
+        let mut fitting = None;
+        for alternative in alternatives.iter().filter(|a| accepts(root, a, value)) {
+            let mut found = Vec::new();
+            check(root, alternative, value, path, &mut found);
+            if found.is_empty() {
+                fitting = None;
+                break;
+            }
+            fitting.get_or_insert(found);
+        }
+        out.extend(fitting.into_iter().flatten());
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.iter().any(|e| equals(value, e)) {
+            violation(lformat!("should be one of {}", Value::from(allowed.clone())));
+        }
+    }
+
+    if let (Some(pattern), Some(text)) = (schema.get("pattern").and_then(Value::as_str), value.as_str()) {
+        if !PATTERNS.get(pattern).is_some_and(|regex| regex.is_match(text)) {
+            violation(lformat!("{:?} does not match {:?}", text, pattern));
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (schema.get("minimum").and_then(Value::as_f64), as_f64(value)) {
+        if number < minimum {
+            violation(lformat!("should be at least {}", minimum));
+        }
+    }
+
+    if let Yaml::Hash(hash) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !hash.contains_key(&Yaml::String(key.into())) {
+                    out.push(Violation {
+                        path: join(path, key),
+                        message: lformat!("is required"),
+                    });
+                }
+            }
+        }
+        for (key, child) in hash {
+            let name = key_name(key);
+            let child_schema = key
+                .as_str()
+                .and_then(|key| properties.and_then(|properties| properties.get(key)))
+                .or_else(|| schema.get("additionalProperties").filter(|s| s.is_object()));
+            if let Some(child_schema) = child_schema {
+                check(root, child_schema, child, &join(path, &name), out);
+            }
+        }
+    }
+
+    if let (Yaml::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            check(root, item_schema, item, &join(path, &index.to_string()), out);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Full-text search over what projects are about, behind `asciii search` and `/api/search`.
+//!
+//! A project is found if every term is in one of its texts: the name, the invoice number, the client,
+//! the manager, the location, the address, the products or the description of the event.
+//! Case does not matter and a term can be a whole phrase like `"Max Mustermann"`.
+//!
+//! Results are ranked by where the terms were found, a hit in the name counts more than one in the description,
+//! whole words count twice. Ties go to the more recent event.
+
+use std::cmp::Reverse;
+
+use bill::BillItem;
+
+use super::{spec::*, Project};
+use crate::{storage::Storable, util::yaml};
+
+/// The texts of a project that are searched and how much a hit in them counts.
+const FIELDS: [(&str, u32); 8] = [
+    ("name", 10),
+    ("invoice", 8),
+    ("client", 6),
+    ("manager", 4),
+    ("location", 4),
+    ("products", 3),
+    ("address", 2),
+    ("description", 2),
+];
+
+/// One line of a project that contains at least one of the terms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct Hit {
+    /// one of `name`, `invoice`, `client`, `manager`, `location`, `products`, `address` or `description`
+    pub field: &'static str,
+    pub text: String,
+    /// byte ranges of the terms in `text`, sorted and not overlapping
+    pub matches: Vec<(usize, usize)>,
+}
+
+impl Hit {
+    /// `text` with every match passed through `mark`.
+    pub fn highlight<F: Fn(&str) -> String>(&self, mark: F) -> String {
+        let mut highlighted = String::new();
+        let mut last = 0;
+        for &(start, end) in &self.matches {
+            highlighted += &self.text[last..start];
+            highlighted += &mark(&self.text[start..end]);
+            last = end;
+        }
+        highlighted + &self.text[last..]
+    }
+}
+
+/// Something that was found, with the lines the terms were found in.
+#[derive(Debug)]
+pub struct Found<T> {
+    pub item: T,
+    pub score: u32,
+    pub hits: Vec<Hit>,
+}
+
+/// Splits `input` into terms at whitespace, quoted phrases stay together.
+pub fn terms(input: &str) -> Vec<String> {
+    input
+        .split('"')
+        .enumerate()
+        .flat_map(|(index, part)| {
+            if index % 2 == 1 {
+                vec![part.trim().to_owned()]
+            } else {
+                part.split_whitespace().map(ToOwned::to_owned).collect()
+            }
+        })
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// The searched texts of `project`, line by line.
+fn texts(project: &Project) -> Vec<(&'static str, String)> {
+    let client = project.client();
+    let mut texts = vec![
+        ("name", project.name().ok().map(ToOwned::to_owned)),
+        ("invoice", project.invoice().number_str()),
+        ("invoice", project.invoice().official().ok()),
+        ("client", client.full_name()),
+        ("client", client.email().ok().map(ToOwned::to_owned)),
+        ("manager", project.responsible().ok().map(ToOwned::to_owned)),
+        ("address", client.address().ok().map(ToOwned::to_owned)),
+        (
+            "description",
+            yaml::get_str(project.yaml(), "event/description").map(ToOwned::to_owned),
+        ),
+    ];
+
+    match yaml::get(project.yaml(), "event/location") {
+        Some(yaml::Yaml::Array(locations)) => texts.extend(
+            locations
+                .iter()
+                .map(|location| ("location", location.as_str().map(ToOwned::to_owned))),
+        ),
+        Some(location) => texts.push(("location", location.as_str().map(ToOwned::to_owned))),
+        None => {},
+    }
+
+    if let Ok((offer, invoice)) = project.bills() {
+        let mut products = offer
+            .as_items()
+            .into_iter()
+            .chain(invoice.as_items())
+            .map(|item: &BillItem<_>| item.product.name.to_owned())
+            .collect::<Vec<_>>();
+        products.sort();
+        products.dedup();
+        texts.push(("products", Some(products.join(", "))));
+    }
+
+    texts
+        .into_iter()
+        .filter_map(|(field, text)| Some((field, text?)))
+        .flat_map(|(field, text)| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| (field, line.to_owned()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Byte ranges of `term` in `text`, ignoring case.
+fn find_all(text: &str, term: &str) -> Vec<(usize, usize)> {
+    // lowercasing may change the length of a character, so every byte of `lower` remembers its character in `text`
+    let mut lower = String::new();
+    let mut origin = Vec::new();
+    for (start, character) in text.char_indices() {
+        let end = start + character.len_utf8();
+        for lower_character in character.to_lowercase() {
+            lower.push(lower_character);
+            origin.resize(lower.len(), (start, end));
+        }
+    }
+
+    let term = term.to_lowercase();
+    if term.is_empty() {
+        return Vec::new();
+    }
+    lower
+        .match_indices(&term)
+        .map(|(position, found)| (origin[position].0, origin[position + found.len() - 1].1))
+        .collect()
+}
+
+fn is_whole_word(text: &str, (start, end): (usize, usize)) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+/// Merges overlapping ranges.
+fn merge(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Score and hits of `project`, `None` unless every term is in it.
+pub fn search_project<S: AsRef<str>>(project: &Project, terms: &[S]) -> Option<(u32, Vec<Hit>)> {
+    let mut hits = texts(project)
+        .into_iter()
+        .map(|(field, text)| Hit {
+            field,
+            text,
+            matches: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut score = 0;
+    for term in terms {
+        let mut found = false;
+        for hit in &mut hits {
+            let matches = find_all(&hit.text, term.as_ref());
+            if matches.is_empty() {
+                continue;
+            }
+            found = true;
+            let weight = FIELDS
+                .iter()
+                .find(|(field, _)| *field == hit.field)
+                .map_or(1, |(_, weight)| *weight);
+            let whole_word = matches.iter().any(|&range| is_whole_word(&hit.text, range));
+            score += if whole_word { 2 * weight } else { weight };
+            hit.matches.extend(matches);
+        }
+        if !found {
+            return None;
+        }
+    }
+
+    hits.retain(|hit| !hit.matches.is_empty());
+    for hit in &mut hits {
+        hit.matches = merge(std::mem::take(&mut hit.matches));
+    }
+    Some((score, hits))
+}
+
+/// The items whose project contains all `terms`, best first.
+pub fn search<T, I, F, S>(items: I, project_of: F, terms: &[S]) -> Vec<Found<T>>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> &Project,
+    S: AsRef<str>,
+{
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut found = items
+        .into_iter()
+        .filter_map(|item| {
+            let (score, hits) = search_project(project_of(&item), terms)?;
+            Some(Found { item, score, hits })
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_cached_key(|found| (Reverse(found.score), Reverse(project_of(&found.item).modified_date())));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, client: &str, location: &str, description: &str) -> Project {
+        Project::from_file_content(&format!(
+            "client:\n  first_name: {}\n  last_name: Mustermann\n  address: |\n    Hauptstraße 1\n    01069 Dresden\n\n\
+             event:\n  name: {}\n  location: {}\n  dates:\n    - begin: 01.06.2020\n  description: |\n    {}\n\n\
+             hours:\n  salary: 8.0\n\ntax: 0.19\nproducts:\n  Kaffee: {{ amount: 10, price: 2.5 }}\n",
+            client, name, location, description
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn terms_keep_phrases() {
+        assert_eq!(
+            terms(r#"kaffee "Max Mustermann"  dresden"#),
+            vec!["kaffee", "Max Mustermann", "dresden"]
+        );
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let sommerfest = project("Sommerfest", "Max", "Mensa", "Kuchen für alle");
+        assert!(search_project(&sommerfest, &["sommerfest", "dresden"]).is_some());
+        assert!(search_project(&sommerfest, &["kaffee", "kuchen"]).is_some());
+        assert!(search_project(&sommerfest, &["sommerfest", "berlin"]).is_none());
+    }
+
+    #[test]
+    fn hits_are_highlighted() {
+        let sommerfest = project("Sommerfest", "Max", "Mensa", "Kuchen für alle");
+        let (_, hits) = search_project(&sommerfest, &["STRAßE"]).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field, "address");
+        assert_eq!(hits[0].highlight(|found| format!("[{}]", found)), "Haupt[straße] 1");
+
+        let (_, hits) = search_project(&sommerfest, &["max must"]).unwrap();
+        assert_eq!(hits[0].field, "client");
+        assert_eq!(hits[0].highlight(|found| format!("[{}]", found)), "[Max Must]ermann");
+    }
+
+    #[test]
+    fn names_rank_first() {
+        let projects = vec![
+            project("Lesung", "Max", "Mensa", "Sommerfest im Anschluss"),
+            project("Sommerfest", "Max", "Mensa", "Kuchen für alle"),
+            project("Sommerfestival", "Max", "Mensa", "Kuchen für alle"),
+            project("Winterfest", "Max", "Mensa", "Kuchen für alle"),
+        ];
+        let found = search(projects, |project| project, &["sommerfest"]);
+        let names = found.iter().map(|found| found.item.name().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Sommerfest", "Sommerfestival", "Lesung"]);
+    }
+}
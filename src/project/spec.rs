@@ -9,14 +9,18 @@ use std::fmt;
 
 use anyhow::Error;
 use bill::{Bill, Currency, Tax};
-use chrono::{Date, NaiveTime, Utc};
+use chrono::{Date, Datelike, Duration, NaiveDateTime, NaiveTime, Utc};
 use icalendar::Calendar;
 use semver::Version;
 use yaml_rust::Yaml;
 
-use crate::storage::Storable;
+use crate::{storage::Storable, util::currency};
 
-use super::{error::ValidationResult, product::Product, yaml_provider::FieldResult};
+use super::{
+    error::ValidationResult,
+    product::Product,
+    yaml_provider::{error::FieldResultExt, FieldError, FieldResult},
+};
 
 /// Every other trait in this module ought to be `Validatable`
 ///
@@ -57,6 +61,9 @@ pub trait IsProject {
 
     /// Long description of the project
     fn long_desc(&self) -> String;
+
+    /// Currency of every amount in the project, given by its code, e.g. `currency: USD`
+    fn currency(&self) -> FieldResult<&'static currency::Info>;
 }
 
 /// Extended functionality for projects
@@ -97,11 +104,20 @@ pub trait IsClient {
     ///Returns the content of `/client/address`
     fn address(&self) -> FieldResult<&str>;
 
+    ///Returns the content of `/client/country`, a code after ISO 3166-1 like `DE`
+    fn country(&self) -> FieldResult<&str>;
+
     ///Returns the content of `/client/title`
     fn title(&self) -> FieldResult<&str>;
 
     ///Returns the first word of `client/title`
-    fn salute(&self) -> FieldResult<&str>;
+    fn salute(&self) -> FieldResult<&str> {
+        self.title().and_then(|s| {
+            s.split_whitespace()
+                .next()
+                .ok_or_else(|| FieldError::invalid("title has no salute"))
+        })
+    }
 
     ///Returns the content of `/client/first_name`
     fn first_name(&self) -> FieldResult<&str>;
@@ -110,10 +126,84 @@ pub trait IsClient {
     fn last_name(&self) -> FieldResult<&str>;
 
     /// Combines `first_name` and `last_name`.
-    fn full_name(&self) -> Option<String>;
+    fn full_name(&self) -> Option<String> {
+        let first = self.first_name().ok();
+        let last = self.last_name().ok();
+        first
+            .and(last)
+            .and(Some(format!("{} {}", first.unwrap_or(""), last.unwrap_or(""))))
+    }
 
     /// Produces a standard salutation field.
-    fn addressing(&self) -> Option<String>;
+    fn addressing(&self) -> Option<String> {
+        if let Some(salute) = self.salute().ok().and_then(|salute| salute.split_whitespace().next())
+        // only the first word
+        {
+            let last_name = self.last_name().ok();
+
+            let lang = crate::CONFIG.get_str("defaults/lang");
+
+            let gender_path = "gender_matches/".to_owned() + &salute.to_lowercase();
+            let gender = crate::CONFIG.get_str_or(&gender_path)?;
+
+            let addr_path = "lang_addressing/".to_owned() + &lang.to_lowercase() + "/" + gender;
+            let addr = crate::CONFIG.get_str_or(&addr_path)?;
+
+            last_name.and(Some(format!("{} {} {}", addr, salute, last_name.unwrap_or(""))))
+        } else {
+            None
+        }
+    }
+}
+
+/// Where a client is read from, [`IsClient`] is implemented on top of it,
+/// so the yaml based and the typed project resolve older documents the same way.
+pub trait ClientFields {
+    /// A field of the `client` section, like `email`
+    fn client_field(&self, field: &str) -> FieldResult<&str>;
+
+    /// A field that documents before `format: 2.0` kept at the top level, like `email` or `address`
+    fn legacy_field(&self, field: &str) -> FieldResult<&str>;
+
+    /// Before `format: 2.0` the client was two lines: title and last name
+    fn legacy_client(&self) -> FieldResult<&str>;
+}
+
+fn legacy_client_line<C: ClientFields + ?Sized>(client: &C, n: usize) -> FieldResult<&str> {
+    client.legacy_client().and_then(|c| {
+        c.lines()
+            .nth(n)
+            .ok_or_else(|| FieldError::invalid("invalid client name"))
+    })
+}
+
+impl<C: ClientFields> IsClient for C {
+    fn email(&self) -> FieldResult<&str> {
+        self.client_field("email").if_missing_try(|| self.legacy_field("email"))
+    }
+
+    fn address(&self) -> FieldResult<&str> {
+        self.client_field("address")
+            .if_missing_try(|| self.legacy_field("address"))
+    }
+
+    fn country(&self) -> FieldResult<&str> {
+        self.client_field("country")
+    }
+
+    fn title(&self) -> FieldResult<&str> {
+        self.client_field("title")
+            .if_missing_try(|| legacy_client_line(self, 0))
+    }
+
+    fn first_name(&self) -> FieldResult<&str> {
+        self.client_field("first_name")
+    }
+
+    fn last_name(&self) -> FieldResult<&str> {
+        self.client_field("last_name")
+            .if_missing_try(|| legacy_client_line(self, 1))
+    }
 }
 
 /// Stage 2: requirements for an invoice
@@ -125,13 +215,93 @@ pub trait Invoicable {
     fn date(&self) -> FieldResult<Date<Utc>>;
 
     /// invoice number as a string
-    fn number_str(&self) -> Option<String>;
+    fn number_str(&self) -> Option<String> {
+        self.number().ok().map(|n| format!("R{:03}", n))
+    }
 
     /// invoice number as a long string
-    fn number_long_str(&self) -> Option<String>;
+    fn number_long_str(&self) -> Option<String> {
+        let year = self.date().ok()?.year();
+        // TODO: Length or format should be a setting
+        self.number().ok().map(|n| format!("R{}-{:03}", year, n))
+    }
 
     /// An official identifier
     fn official(&self) -> FieldResult<String>;
+
+    /// Reference the client wants to see on e-invoices (Leitweg-ID)
+    fn buyer_reference(&self) -> FieldResult<String>;
+}
+
+/// Where an invoice is read from, [`Invoicable`] is implemented on top of it like [`IsClient`] on [`ClientFields`].
+pub trait InvoiceFields {
+    /// `invoice/number`
+    fn invoice_number(&self) -> FieldResult<i64>;
+
+    /// `invoice/date`
+    fn invoice_date(&self) -> FieldResult<Date<Utc>>;
+
+    /// `invoice/official`
+    fn invoice_official(&self) -> FieldResult<String>;
+
+    /// `invoice/buyer_reference`
+    fn invoice_buyer_reference(&self) -> FieldResult<String>;
+
+    /// `rnumber` of documents before `format: 2.0`
+    fn legacy_number(&self) -> FieldResult<i64>;
+
+    /// `invoice_date` of documents before `format: 2.0`
+    fn legacy_date(&self) -> FieldResult<Date<Utc>>;
+}
+
+impl<I: InvoiceFields> Invoicable for I {
+    fn number(&self) -> FieldResult<i64> {
+        self.invoice_number().if_missing_try(|| self.legacy_number())
+    }
+
+    fn date(&self) -> FieldResult<Date<Utc>> {
+        self.invoice_date().if_missing_try(|| self.legacy_date())
+    }
+
+    fn official(&self) -> FieldResult<String> {
+        self.invoice_official()
+    }
+
+    fn buyer_reference(&self) -> FieldResult<String> {
+        self.invoice_buyer_reference()
+    }
+}
+
+impl<I: InvoiceFields> Validatable for I {
+    fn validate(&self) -> ValidationResult {
+        let mut validation = ValidationResult::new();
+
+        validation.require_field("invoice.number", self.number());
+        validation.require_field("invoice.date", self.date());
+
+        validation
+    }
+}
+
+/// Stage 2b: an invoice that went out wrong is canceled by a credit note (Storno)
+pub trait Cancelable {
+    /// plain access to `cancellation/number`, counted separately from invoices
+    fn number(&self) -> FieldResult<i64>;
+
+    /// When was the invoice canceled
+    fn date(&self) -> FieldResult<Date<Utc>>;
+
+    /// cancellation number as a string
+    fn number_str(&self) -> Option<String>;
+
+    /// cancellation number as a long string
+    fn number_long_str(&self) -> Option<String>;
+
+    /// Why the invoice had to be canceled
+    fn reason(&self) -> FieldResult<String>;
+
+    /// Long number of the invoice this cancels
+    fn invoice_number(&self) -> Option<String>;
 }
 
 /// Represents an Employee
@@ -147,6 +317,46 @@ pub struct Employee {
 
     /// Salary times hours
     pub wage: Currency,
+
+    /// Check-ins and check-outs the hours were summed up from, empty if only the total is known
+    pub entries: Vec<TimeEntry>,
+}
+
+/// One shift of an employee, written as `{ date: 01.10.2026, begin: "10:00", end: "14:30", pause: 30 }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeEntry {
+    /// Day the shift began, defaults to the first day of the event
+    pub date: Date<Utc>,
+
+    /// Check-in
+    pub begin: NaiveTime,
+
+    /// Check-out, on the next day if it is not after `begin`
+    pub end: NaiveTime,
+
+    /// Minutes of breaks, which are not payed
+    pub pause: i64,
+}
+
+impl TimeEntry {
+    pub fn start(&self) -> NaiveDateTime {
+        self.date.naive_utc().and_time(self.begin)
+    }
+
+    pub fn finish(&self) -> NaiveDateTime {
+        let finish = self.date.naive_utc().and_time(self.end);
+        if self.end > self.begin {
+            finish
+        } else {
+            finish + Duration::days(1)
+        }
+    }
+
+    /// Time between check-in and check-out, without breaks
+    pub fn hours(&self) -> f64 {
+        let minutes = (self.finish() - self.start()).num_minutes() - self.pause;
+        minutes.max(0) as f64 / 60.0
+    }
 }
 
 /// Something that has employees
@@ -194,14 +404,56 @@ pub trait HasEmployees {
     fn wages(&self) -> Option<Currency>;
 }
 
+/// One entry in the `payments` list of a project
+#[derive(Debug)]
+pub struct Payment {
+    /// When the money arrived
+    pub date: Date<Utc>,
+
+    /// How much was payed
+    pub amount: Currency,
+
+    /// Cash, transfer, etc
+    pub method: Option<String>,
+
+    /// Anything worth remembering
+    pub note: Option<String>,
+}
+
 /// Stage 3: when an `IsProject` is redeem and can be archived
 pub trait Redeemable: IsProject {
     /// When was the project payed
     fn payed_date(&self) -> FieldResult<Date<Utc>>;
 
-    /// If was the project payed
+    /// If was the project payed, either marked with a `payed_date` or by payments covering the invoice
     fn is_payed(&self) -> bool;
 
+    /// Partial payments received so far
+    fn payments(&self) -> FieldResult<Vec<Payment>>;
+
+    /// Sum of all `payments`
+    fn payed_sum(&self) -> Currency {
+        self.payments()
+            .unwrap_or_default()
+            .into_iter()
+            .fold(Currency::default(), |acc, p| acc + p.amount)
+    }
+
+    /// What the customer still owes us, zero once a `payed_date` is set or the invoice was canceled
+    fn outstanding(&self) -> Result<Currency, Error> {
+        let sold = self.sum_sold()?;
+        if self.payed_date().is_ok() || self.invoice_canceled() {
+            return Ok(Currency { value: 0, ..sold });
+        }
+        Ok(Currency {
+            value: sold.value - self.payed_sum().value,
+            ..sold
+        })
+    }
+
+    /// The invoice was canceled by a credit note
+    fn invoice_canceled(&self) -> bool;
+
     /// Returns a bill for the offer and one for the invoice.
     fn bills(&self) -> Result<(Bill<Product<'_>>, Bill<Product<'_>>), Error>;
 
@@ -6,9 +6,10 @@ use icalendar::{Calendar, Component, Event as CalEvent, EventLike as _};
 use yaml_rust::Yaml;
 
 use super::{
-    error::ValidationResult, product::ProductError, spec::Employee, spec::*, yaml_provider::error::FieldResultExt, *,
+    error::ValidationResult, product::ProductError, spec::Employee, spec::Event, spec::*,
+    yaml_provider::error::FieldResultExt, *,
 };
-use crate::util::{self, to_currency, yaml::parse_dmy_date};
+use crate::util::{self, currency, to_currency_in, yaml::parse_dmy_date};
 
 impl YamlProvider for Project {
     fn data(&self) -> &Yaml {
@@ -64,6 +65,20 @@ impl IsProject for Project {
 
         out_string
     }
+
+    fn currency(&self) -> FieldResult<&'static currency::Info> {
+        currency_at(self, "currency")
+    }
+}
+
+/// Looks up the currency whose code is found at `path`.
+fn currency_at<P: YamlProvider + ?Sized>(provider: &P, path: &str) -> FieldResult<&'static currency::Info> {
+    provider.get_str(path).and_then(|code| {
+        currency::by_code(code).ok_or_else(|| {
+            let known = currency::CURRENCIES.iter().map(|info| info.code).collect::<Vec<_>>();
+            FieldError::invalid(&lformat!("unknown currency {:?}, known are {}", code, known.join(", ")))
+        })
+    })
 }
 
 impl HasEvents for Project {
@@ -172,14 +187,14 @@ impl HasEvents for Project {
     }
 }
 
-/// Returns a product from Service
-fn service_to_product<'a, T: HasEmployees>(s: &T) -> Result<Product<'a>, Error> {
+/// Returns a product from Service, billed in `symbol` no matter which currency the wages are paid in
+fn service_to_product<'a, T: HasEmployees>(s: &T, symbol: Option<char>) -> Result<Product<'a>, Error> {
     if let Ok(salary) = s.salary() {
         Ok(Product {
             name: "Service",
             unit: Some("h"),
             tax: s.tax().ok().unwrap_or_else(|| Tax::new(0.0)),
-            price: salary,
+            price: Currency { symbol, ..salary },
         })
     } else {
         bail!(ProductError::InvalidServerSection)
@@ -195,6 +210,31 @@ impl Redeemable for Project {
 
     fn is_payed(&self) -> bool {
         self.payed_date().ok().is_some()
+            || (self.payments().is_ok_and(|p| !p.is_empty()) && self.outstanding().is_ok_and(|o| o.value <= 0))
+    }
+
+    fn invoice_canceled(&self) -> bool {
+        self.cancellation().number().is_ok()
+    }
+
+    fn payments(&self) -> FieldResult<Vec<Payment>> {
+        YamlProvider::field(self, "payments", "not a list", Yaml::as_vec)?
+            .iter()
+            .map(|entry| {
+                let date = yaml::get_str(entry, "date")
+                    .and_then(parse_dmy_date)
+                    .ok_or_else(|| FieldError::invalid(&lformat!("payment without valid date ({:?})", entry)))?;
+                let amount = yaml::get_f64(entry, "amount")
+                    .map(|amount| to_currency_in(amount, self.currency_symbol()))
+                    .ok_or_else(|| FieldError::invalid(&lformat!("payment without amount ({:?})", entry)))?;
+                Ok(Payment {
+                    date,
+                    amount,
+                    method: yaml::get_string(entry, "method"),
+                    note: yaml::get_string(entry, "note"),
+                })
+            })
+            .collect()
     }
 
     fn tax(&self) -> FieldResult<Tax> {
@@ -205,7 +245,7 @@ impl Redeemable for Project {
         let mut offer: Bill<Product<'_>> = Bill::new();
         let mut invoice: Bill<Product<'_>> = Bill::new();
 
-        let service = service_to_product(&self.hours())?;
+        let service = service_to_product(&self.hours(), self.currency_symbol())?;
         //  .("cannot create product from employees, salary or tax missing");
 
         if let Some(total) = self.hours().total_time() {
@@ -217,8 +257,7 @@ impl Redeemable for Project {
 
         let raw_products = self.get_hash("products").ok().ok_or(ProductError::UnknownFormat)?;
 
-        // let document_tax =  // TODO: activate this once the tax no longer 19%
-
+        // every product brings its own tax, falling back to the document's `tax` and `defaults/tax`
         for (desc, values) in raw_products {
             let (offer_item, invoice_item) = self.item_from_desc_and_value(desc, values)?;
             if offer_item.amount.is_normal() {
@@ -241,8 +280,10 @@ impl Validatable for Project {
         validation.require_field("date", self.event_date());
         validation.require_field("manager", self.responsible());
         validation.require_field("format", self.format());
+        validation.validate_field("payments", self.payments());
+        validation.validate_field("currency", self.currency());
 
-        validation
+        validation.and(self.validate_taxes())
     }
 }
 
@@ -268,81 +309,21 @@ impl<'a> YamlProvider for Client<'a> {
     }
 }
 
-impl<'a> IsClient for Client<'a> {
-    fn email(&self) -> FieldResult<&str> {
-        self.get_str("client/email").if_missing_try(|| self.get_str("email"))
-    }
-
-    fn address(&self) -> FieldResult<&str> {
-        self.get_str("client.address")
-            // old spec
-            .if_missing_try(|| self.get_str("address"))
-    }
-
-    fn title(&self) -> FieldResult<&str> {
-        self.get_str("client/title")
-            // old spec
-            .if_missing_try(|| {
-                self.get_str("client").and_then(|c| {
-                    c.lines()
-                        .next()
-                        .ok_or_else(|| FieldError::invalid("invalid client name"))
-                })
-            })
-    }
-
-    fn salute(&self) -> FieldResult<&str> {
-        self.title().and_then(|s| {
-            s.split_whitespace()
-                .next()
-                .ok_or_else(|| FieldError::invalid("title has no salute"))
+impl<'a> ClientFields for Client<'a> {
+    /// A field of the client in the project, or in the client book if the project has a `client.ref`.
+    fn client_field(&self, field: &str) -> FieldResult<&str> {
+        self.get_str(&format!("client/{}", field)).if_missing_try(|| {
+            let id = self.get_str("client/ref")?;
+            self.inner.client_book().resolve(id, field)
         })
     }
 
-    fn first_name(&self) -> FieldResult<&str> {
-        self.get_str("client.first_name")
-        // old spec
-        // .or_else(|_|  yaml::get_str(&yaml, "client").and_then(|c|c.lines().next()))
-    }
-
-    fn last_name(&self) -> FieldResult<&str> {
-        self.get_str("client.last_name")
-            // old spec
-            .if_missing_try(|| {
-                self.get_str("client").and_then(|c| {
-                    c.lines()
-                        .nth(1)
-                        .ok_or_else(|| FieldError::invalid("invalid client name"))
-                })
-            })
-    }
-
-    fn full_name(&self) -> Option<String> {
-        let first = self.first_name().ok();
-        let last = self.last_name().ok();
-        first
-            .and(last)
-            .and(Some(format!("{} {}", first.unwrap_or(""), last.unwrap_or(""))))
+    fn legacy_field(&self, field: &str) -> FieldResult<&str> {
+        self.get_str(field)
     }
 
-    fn addressing(&self) -> Option<String> {
-        if let Some(salute) = self.salute().ok().and_then(|salute| salute.split_whitespace().next())
-        // only the first word
-        {
-            let last_name = self.last_name().ok();
-
-            let lang = crate::CONFIG.get_str("defaults/lang");
-
-            let gender_path = "gender_matches/".to_owned() + &salute.to_lowercase();
-            let gender = crate::CONFIG.get_str_or(&gender_path)?;
-
-            let addr_path = "lang_addressing/".to_owned() + &lang.to_lowercase() + "/" + gender;
-            let addr = crate::CONFIG.get_str_or(&addr_path)?;
-
-            last_name.and(Some(format!("{} {} {}", addr, salute, last_name.unwrap_or(""))))
-        } else {
-            None
-        }
+    fn legacy_client(&self) -> FieldResult<&str> {
+        self.get_str("client")
     }
 }
 
@@ -404,38 +385,71 @@ impl<'a> YamlProvider for Invoice<'a> {
     }
 }
 
-impl<'a> Invoicable for Invoice<'a> {
-    fn number(&self) -> FieldResult<i64> {
+impl<'a> InvoiceFields for Invoice<'a> {
+    fn invoice_number(&self) -> FieldResult<i64> {
         self.get_int("invoice.number")
-            .if_missing_try(|| self.get_int("rnumber"))
     }
 
-    fn date(&self) -> FieldResult<Date<Utc>> {
+    fn invoice_date(&self) -> FieldResult<Date<Utc>> {
         self.get_dmy("invoice.date")
-            .if_missing_try(|| self.get_dmy("invoice_date"))
+    }
+
+    fn invoice_official(&self) -> FieldResult<String> {
+        self.get_str("invoice.official").map(ToOwned::to_owned)
+    }
+
+    fn invoice_buyer_reference(&self) -> FieldResult<String> {
+        self.get_str("invoice.buyer_reference").map(ToOwned::to_owned)
+    }
+
+    fn legacy_number(&self) -> FieldResult<i64> {
+        self.get_int("rnumber")
+    }
+
+    fn legacy_date(&self) -> FieldResult<Date<Utc>> {
+        self.get_dmy("invoice_date")
+    }
+}
+
+impl<'a> YamlProvider for Cancellation<'a> {
+    fn data(&self) -> &Yaml {
+        self.inner.data()
+    }
+}
+
+impl<'a> Cancelable for Cancellation<'a> {
+    fn number(&self) -> FieldResult<i64> {
+        self.get_int("cancellation.number")
+    }
+
+    fn date(&self) -> FieldResult<Date<Utc>> {
+        self.get_dmy("cancellation.date")
     }
 
     fn number_str(&self) -> Option<String> {
-        self.number().ok().map(|n| format!("R{:03}", n))
+        self.number().ok().map(|n| format!("S{:03}", n))
     }
 
     fn number_long_str(&self) -> Option<String> {
         let year = self.date().ok()?.year();
-        // TODO: Length or format should be a setting
-        self.number().ok().map(|n| format!("R{}-{:03}", year, n))
+        self.number().ok().map(|n| format!("S{}-{:03}", year, n))
     }
 
-    fn official(&self) -> FieldResult<String> {
-        self.get_str("invoice.official").map(ToOwned::to_owned)
+    fn reason(&self) -> FieldResult<String> {
+        self.get_str("cancellation.reason").map(ToOwned::to_owned)
+    }
+
+    fn invoice_number(&self) -> Option<String> {
+        self.inner.invoice().number_long_str()
     }
 }
 
-impl<'a> Validatable for Invoice<'a> {
+impl<'a> Validatable for Cancellation<'a> {
     fn validate(&self) -> ValidationResult {
         let mut validation = ValidationResult::new();
 
-        validation.require_field("invoice.number", self.number());
-        validation.require_field("invoice.date", self.date());
+        validation.require_field("cancellation.number", self.number());
+        validation.require_field("cancellation.date", self.date());
 
         validation
     }
@@ -454,8 +468,14 @@ impl<'a> HasEmployees for Hours<'a> {
             .or_else(|_| self.get_dmy("wages_date"))
     }
 
+    /// Wages are paid in the configured currency, unless `hours.currency` says otherwise.
     fn salary(&self) -> FieldResult<Currency> {
-        self.get_f64("hours.salary").map(to_currency)
+        let symbol = match currency_at(self, "hours.currency") {
+            Ok(info) => Some(info.symbol),
+            Err(FieldError::Missing) => currency::default_symbol(),
+            Err(error) => return Err(error),
+        };
+        self.get_f64("hours.salary").map(|salary| to_currency_in(salary, symbol))
     }
 
     fn tax(&self) -> FieldResult<Tax> {
@@ -491,7 +511,14 @@ impl<'a> HasEmployees for Hours<'a> {
         self.employees().ok().map(|e| {
             e.iter()
                 .filter(|e| e.time as u32 > 0)
-                .map(|e| format!("{}: ({}h {})", e.name, e.time, (e.salary * e.time).postfix()))
+                .map(|e| {
+                    format!(
+                        "{}: ({}h {})",
+                        e.name,
+                        e.time,
+                        util::currency_to_string(&(e.salary * e.time))
+                    )
+                })
                 .collect::<Vec<String>>()
                 .join(", ")
         })
@@ -504,9 +531,20 @@ impl<'a> HasEmployees for Hours<'a> {
 
         employees?
             .iter()
-            .map(|(c, h)| (c.as_str().unwrap_or("").into(), make_float(h)))
-            .filter(|&(_, h)| h > 0f64)
-            .map(|(name, time)| {
+            .map(|(c, h)| {
+                let name: String = c.as_str().unwrap_or("").into();
+                match h.as_vec() {
+                    Some(list) => {
+                        let entries = time_entries(&name, list, self.inner.event_date().ok())?;
+                        let time = entries.iter().map(TimeEntry::hours).sum();
+                        Ok((name, time, entries))
+                    },
+                    None => Ok((name, make_float(h), Vec::new())),
+                }
+            })
+            .filter(|employee| employee.as_ref().map_or(true, |&(_, time, _)| time > 0f64))
+            .map(|employee| {
+                let (name, time, entries) = employee?;
                 let wage = self.salary()? * time;
                 let salary = self.salary()?;
                 FieldResult::Ok(Employee {
@@ -514,6 +552,7 @@ impl<'a> HasEmployees for Hours<'a> {
                     salary,
                     time,
                     wage,
+                    entries,
                 })
             })
             .collect::<FieldResult<Vec<Employee>>>()
@@ -537,11 +576,110 @@ fn make_float(h: &Yaml) -> f64 {
     h.as_f64().or_else(|| h.as_i64().map(|f| f as f64)).unwrap_or(0f64)
 }
 
+// helper for HasEmployees::employees(), reads the shifts of `name` if there is a list instead of a total
+fn time_entries(name: &str, list: &[Yaml], first_day: Option<Date<Utc>>) -> FieldResult<Vec<TimeEntry>> {
+    list.iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let invalid =
+                |what: &str| FieldError::Invalid(lformat!("shift {} of {} has no valid {}", index + 1, name, what));
+            let time = |key: &str| entry[key].as_str().and_then(util::naive_time_from_str);
+            let date = match entry["date"].as_str() {
+                Some(date) => parse_dmy_date(date),
+                None => first_day,
+            };
+            let pause = match entry["pause"] {
+                Yaml::BadValue | Yaml::Null => 0,
+                Yaml::Integer(minutes) if minutes >= 0 => minutes,
+                _ => return Err(invalid("pause")),
+            };
+            Ok(TimeEntry {
+                date: date.ok_or_else(|| invalid("date"))?,
+                begin: time("begin").ok_or_else(|| invalid("begin"))?,
+                end: time("end").ok_or_else(|| invalid("end"))?,
+                pause,
+            })
+        })
+        .collect()
+}
+
+/// Overlapping shifts of one person, and shifts on other days or at other times than the event.
+fn shift_conflicts(employees: &[Employee], events: &[Event]) -> Vec<String> {
+    let dmy = |date: Date<Utc>| date.format("%d.%m.%Y").to_string();
+    let mut conflicts = Vec::new();
+
+    // every day of every event with each of its times, times ending before they begin end the next day
+    let mut windows = Vec::new();
+    for event in events {
+        let mut day = event.begin;
+        while day <= event.end.unwrap_or(event.begin) {
+            for time in &event.times {
+                let begin = day.naive_utc().and_time(time.start);
+                let mut end = day.naive_utc().and_time(time.end);
+                if end <= begin {
+                    end += Duration::days(1);
+                }
+                windows.push((begin, end));
+            }
+            day = day.succ();
+        }
+    }
+
+    for employee in employees {
+        let mut entries = employee.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.start());
+        for pair in entries.windows(2) {
+            if pair[1].start() < pair[0].finish() {
+                conflicts.push(lformat!("shifts of {} overlap on {}", employee.name, dmy(pair[1].date)));
+            }
+        }
+
+        if events.is_empty() {
+            continue;
+        }
+        for entry in entries {
+            let event = events
+                .iter()
+                .find(|event| event.begin <= entry.date && entry.date <= event.end.unwrap_or(event.begin));
+            let Some(event) = event else {
+                conflicts.push(lformat!(
+                    "{} worked on {}, which is not a day of the event",
+                    employee.name,
+                    dmy(entry.date)
+                ));
+                continue;
+            };
+            if !event.times.is_empty()
+                && !windows
+                    .iter()
+                    .any(|(begin, end)| *begin <= entry.start() && entry.finish() <= *end)
+            {
+                conflicts.push(lformat!(
+                    "shift of {} on {} from {} to {} is outside of the event times",
+                    employee.name,
+                    dmy(entry.date),
+                    entry.begin.format("%H:%M"),
+                    entry.end.format("%H:%M")
+                ));
+            }
+        }
+    }
+    conflicts
+}
+
 impl<'a> Validatable for Hours<'a> {
     fn validate(&self) -> ValidationResult {
         let mut validation = ValidationResult::new();
 
         validation.validate_field("hours.caterers", self.employees());
+        if let Ok(ref employees) = self.employees() {
+            let events = self.inner.events().unwrap_or_default();
+            for conflict in shift_conflicts(employees, &events) {
+                validation
+                    .validation_errors
+                    .push(lformat!("{:?} is invalid: {}", "hours.caterers", conflict));
+            }
+        }
 
         // return directly if no employees need to be paid
         if self.employees().unwrap_or_default().is_empty() {
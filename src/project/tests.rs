@@ -169,6 +169,862 @@ pub mod invoice {
     }
 }
 
+pub mod xrechnung {
+    use super::*;
+    use crate::project::xrechnung::{self, Seller};
+
+    static DOC: &str = r#"
+      client:
+        title:      Herr
+        first_name: Graf
+        last_name:  Zahl
+        email: this.man@example.com
+        address: |
+          Graf Zahl
+          Nummernhöllenstraße 666
+          01234 Countilvania
+        country: DE
+
+      event:
+        name: Party & Hard
+        dates:
+        - begin: 03.11.2014
+
+      manager: Hendrik Sollich
+
+      invoice:
+        number: 41
+        date: 06.12.2014
+        buyer_reference: 04011000-12345-67
+
+      hours:
+        salary: 8.0
+        tax: 0.19
+
+      tax: 0.19
+      products:
+        Kaffee: { amount: 5, price: 2.5, unit: l }
+      "#;
+
+    fn seller() -> Seller {
+        Seller {
+            name: Some("ascii".into()),
+            street: Some("Nöthnitzer Str. 46".into()),
+            postal_code: Some("01187".into()),
+            city: Some("Dresden".into()),
+            country: Some("DE".into()),
+            vat_id: Some("DE123456789".into()),
+            email: Some("ascii@example.com".into()),
+            phone: Some("0351 1234".into()),
+            iban: Some("DE02120300000000202051".into()),
+            tax_exemption_reason: None,
+            payment_days: 14,
+        }
+    }
+
+    #[test]
+    fn missing_seller_fields() {
+        let missing = xrechnung::validate(&parse_project(DOC), &Seller::default()).missing_fields;
+        assert!(missing.contains(&String::from("seller/vat_id")));
+        assert!(!missing.contains(&String::from("invoice.buyer_reference")));
+    }
+
+    #[test]
+    fn missing_buyer_reference() {
+        let doc = DOC.replace("buyer_reference", "reference");
+        let missing = xrechnung::validate(&parse_project(&doc), &seller()).missing_fields;
+        assert_eq!(missing, vec!["invoice.buyer_reference"]);
+        assert!(xrechnung::to_ubl(&parse_project(&doc), &seller()).is_err());
+    }
+
+    #[test]
+    fn buyer_country() {
+        let doc = DOC.replace("country: DE", "country: Deutschland");
+        let validation = xrechnung::validate(&parse_project(&doc), &seller());
+        assert_eq!(validation.validation_errors.len(), 1);
+        assert!(xrechnung::to_ubl(&parse_project(&doc), &seller()).is_err());
+
+        let doc = DOC.replace("country: DE", "");
+        let missing = xrechnung::validate(&parse_project(&doc), &seller()).missing_fields;
+        assert_eq!(missing, vec!["client.country"]);
+    }
+
+    #[test]
+    fn tax_exempt_items() {
+        let doc = DOC.replace("tax: 0.19", "tax: 0.0");
+        let missing = xrechnung::validate(&parse_project(&doc), &seller()).missing_fields;
+        assert_eq!(missing, vec!["seller/tax_exemption_reason"]);
+
+        let seller = Seller {
+            tax_exemption_reason: Some("Kleinunternehmer gemäß § 19 UStG".into()),
+            ..seller()
+        };
+        let xml = xrechnung::to_ubl(&parse_project(&doc), &seller).unwrap();
+        assert!(xml.contains("<cbc:ID>E</cbc:ID>"));
+        assert!(xml.contains("<cbc:TaxExemptionReason>Kleinunternehmer gemäß § 19 UStG</cbc:TaxExemptionReason>"));
+    }
+
+    #[test]
+    fn ubl_invoice() {
+        let xml = xrechnung::to_ubl(&parse_project(DOC), &seller()).unwrap();
+        assert!(xml.contains("<cbc:ID>R2014-041</cbc:ID>"));
+        assert!(xml.contains("<cbc:IssueDate>2014-12-06</cbc:IssueDate>"));
+        assert!(xml.contains("<cbc:DueDate>2014-12-20</cbc:DueDate>"));
+        assert!(xml.contains("<cbc:Note>Party &amp; Hard</cbc:Note>"));
+        assert!(xml.contains("<cbc:BuyerReference>04011000-12345-67</cbc:BuyerReference>"));
+        assert!(xml.contains("<cbc:PostalZone>01234</cbc:PostalZone>"));
+        assert!(!xml.contains("TaxExemptionReason"));
+        assert!(xml.contains(r#"<cbc:InvoicedQuantity unitCode="LTR">5</cbc:InvoicedQuantity>"#));
+        assert!(xml.contains(r#"<cbc:TaxAmount currencyID="EUR">2.38</cbc:TaxAmount>"#));
+        assert!(xml.contains(r#"<cbc:PayableAmount currencyID="EUR">14.88</cbc:PayableAmount>"#));
+        assert!(xml.contains("<cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>"));
+        assert!(!xml.contains("BillingReference"));
+    }
+
+    #[test]
+    fn ubl_credit_note() {
+        let missing = xrechnung::credit_note_to_ubl(&parse_project(DOC), &seller()).unwrap_err();
+        assert!(missing.to_string().contains("cancellation.number"), "{}", missing);
+
+        let doc = format!(
+            "{}\n      cancellation:\n        number: 3\n        date: 10.12.2014\n        reason: wrong address\n",
+            DOC
+        );
+        let xml = xrechnung::credit_note_to_ubl(&parse_project(&doc), &seller()).unwrap();
+        assert!(xml.contains(r#"<ubl:CreditNote xmlns:ubl="urn:oasis:names:specification:ubl:schema:xsd:CreditNote-2""#));
+        assert!(xml.trim_end().ends_with("</ubl:CreditNote>"));
+        assert!(xml.contains("<cbc:CreditNoteTypeCode>381</cbc:CreditNoteTypeCode>"));
+        assert!(!xml.contains("Invoice>") && !xml.contains("InvoiceTypeCode"));
+        assert!(xml.contains(r#"<cbc:CreditedQuantity unitCode="LTR">5</cbc:CreditedQuantity>"#));
+        assert!(xml.contains("<cac:CreditNoteLine>"));
+        assert!(xml.contains("<cbc:ID>S2014-003</cbc:ID>"));
+        assert!(xml.contains("<cbc:IssueDate>2014-12-10</cbc:IssueDate>"));
+        assert!(!xml.contains("DueDate"));
+        assert!(xml.contains("<cbc:Note>wrong address</cbc:Note>"));
+        assert!(xml.contains(
+            "<cac:BillingReference>\n    <cac:InvoiceDocumentReference>\n      <cbc:ID>R2014-041</cbc:ID>\n      <cbc:IssueDate>2014-12-06</cbc:IssueDate>"
+        ));
+        assert!(xml.contains(r#"<cbc:PayableAmount currencyID="EUR">14.88</cbc:PayableAmount>"#));
+
+        let xml = xrechnung::to_ubl(&parse_project(&doc), &seller()).unwrap();
+        assert!(xml.contains("<cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>"));
+        assert!(xml.contains(r#"<ubl:Invoice xmlns:ubl="urn:oasis:names:specification:ubl:schema:xsd:Invoice-2""#));
+        assert!(xml.contains("<cac:InvoiceLine>"));
+    }
+
+    #[test]
+    fn cii_invoice() {
+        let xml = xrechnung::to_cii(&parse_project(DOC), &seller()).unwrap();
+        assert!(xml.contains("<rsm:CrossIndustryInvoice"));
+        assert!(xml.contains("<ram:ID>urn:cen.eu:en16931:2017</ram:ID>"));
+        assert!(xml.contains("<ram:ID>R2014-041</ram:ID>"));
+        assert!(xml.contains("<ram:TypeCode>380</ram:TypeCode>"));
+        assert!(xml.contains(r#"<udt:DateTimeString format="102">20141206</udt:DateTimeString>"#));
+        assert!(xml.contains(r#"<ram:BilledQuantity unitCode="LTR">5</ram:BilledQuantity>"#));
+        assert!(xml.contains("<ram:BuyerReference>04011000-12345-67</ram:BuyerReference>"));
+        assert!(xml.contains(r#"<ram:TaxTotalAmount currencyID="EUR">2.38</ram:TaxTotalAmount>"#));
+        assert!(xml.contains("<ram:DuePayableAmount>14.88</ram:DuePayableAmount>"));
+        assert!(!xml.contains("InvoiceReferencedDocument"));
+    }
+
+    #[test]
+    fn cii_credit_note() {
+        let doc = format!(
+            "{}\n      cancellation:\n        number: 3\n        date: 10.12.2014\n",
+            DOC
+        );
+        let xml = xrechnung::credit_note_to_cii(&parse_project(&doc), &seller()).unwrap();
+        assert!(xml.contains("<ram:ID>S2014-003</ram:ID>"));
+        assert!(xml.contains("<ram:TypeCode>381</ram:TypeCode>"));
+        assert!(xml.contains("<ram:InvoiceReferencedDocument>"));
+        assert!(xml.contains("<ram:IssuerAssignedID>R2014-041</ram:IssuerAssignedID>"));
+        assert!(!xml.contains("SpecifiedTradePaymentTerms"));
+    }
+}
+
+pub mod payments {
+    use super::*;
+    use crate::{project::append_payment, util::to_currency};
+    use chrono::prelude::*;
+
+    static DOC: &str = r#"
+invoice:
+  number: 41
+  date: 06.12.2014
+
+hours:
+  salary: 8.0
+
+tax: 0.19
+products:
+  Kaffee: { amount: 10, price: 2.5 } # 25 + 4.75
+
+payments:
+  - date: 10.12.2014
+    amount: 10
+    method: cash
+
+# keep me
+meta:
+  format: 2.0.0
+"#;
+
+    fn payment(amount: f64) -> Payment {
+        Payment {
+            date: Utc.ymd(2014, 12, 24),
+            amount: to_currency(amount),
+            method: Some("transfer".into()),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn outstanding_subtracts_payments() {
+        let project = parse_project(DOC);
+        assert_eq!(project.payed_sum().value, 10_00);
+        assert_eq!(project.outstanding().unwrap().value, 19_75);
+        assert!(!project.is_payed());
+    }
+
+    #[test]
+    fn payments_cover_invoice() {
+        let appended = append_payment(DOC, &payment(19.75)).unwrap();
+        let project = parse_project(&appended);
+        assert_eq!(project.payments().unwrap().len(), 2);
+        assert_eq!(project.outstanding().unwrap().value, 0);
+        assert!(project.is_payed());
+    }
+
+    #[test]
+    fn append_keeps_the_rest() {
+        let appended = append_payment(DOC, &payment(5.0)).unwrap();
+        let expected = DOC.replace(
+            "    method: cash\n",
+            "    method: cash\n  - date: 24.12.2014\n    amount: 5\n    method: transfer\n",
+        );
+        assert_eq!(appended, expected);
+    }
+
+    #[test]
+    fn append_starts_list() {
+        let doc = "tax: 0.19\npayments: []\n";
+        let appended = append_payment(doc, &payment(5.0)).unwrap();
+        assert_eq!(
+            appended,
+            "tax: 0.19\npayments:\n  - date: 24.12.2014\n    amount: 5\n    method: transfer\n"
+        );
+
+        let appended = append_payment("tax: 0.19\n", &payment(5.0)).unwrap();
+        assert_eq!(parse_project(&appended).payments().unwrap().len(), 1);
+
+        assert!(append_payment("payments: [{date: 01.01.2020, amount: 1}]", &payment(5.0)).is_err());
+    }
+
+    #[test]
+    fn invalid_payments() {
+        let project = parse_project("payments:\n  - amount: 10\n");
+        assert!(project.payments().is_err());
+        assert_eq!(project.validate().validation_errors.len(), 1);
+    }
+}
+
+pub mod cancellation {
+    use super::*;
+    use crate::project::{BillType, Exportable};
+
+    static DOC: &str = r#"
+event:
+  name: Party & Hard
+
+invoice:
+  number: 41
+  date: 06.12.2014
+
+cancellation:
+  number: 3
+  date: 10.12.2014
+  reason: wrong address
+
+hours:
+  salary: 8.0
+
+tax: 0.19
+products:
+  Kaffee: { amount: 10, price: 2.5 }
+"#;
+
+    #[test]
+    fn numbering() {
+        let project = parse_project(DOC);
+        assert_eq!(project.cancellation().number_str().unwrap(), "S003");
+        assert_eq!(project.cancellation().number_long_str().unwrap(), "S2014-003");
+        assert_eq!(project.cancellation().invoice_number().unwrap(), "R2014-041");
+        assert_eq!(
+            project.output_file_name(BillType::Cancellation, "pdf").unwrap(),
+            "S003 R041 party-hard 2014-12-10.pdf"
+        );
+    }
+
+    #[test]
+    fn nothing_outstanding() {
+        let project = parse_project(DOC);
+        assert!(project.invoice_canceled());
+        assert_eq!(project.outstanding().unwrap().value, 0);
+    }
+
+    #[test]
+    fn csv_nets_against_invoice() {
+        let csv = crate::actions::projects_to_csv(&[parse_project(DOC)]).unwrap();
+        let amounts = csv
+            .lines()
+            .skip(1)
+            .map(|row| row.split(';').nth(7).unwrap().parse::<i64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(amounts, vec![29_75, -29_75]);
+    }
+
+    #[test]
+    fn missing_for_cancellation() {
+        let project = parse_project(&DOC.replace("  number: 3\n", ""));
+        assert!(!project.invoice_canceled());
+        assert!(project
+            .is_missing_for_cancellation()
+            .contains(&String::from("cancellation.number")));
+    }
+}
+
+#[cfg(feature = "deserialization")]
+pub mod import {
+    use super::*;
+    use crate::project::{error::ProjectError, import, yaml_provider::FieldError};
+
+    const FIXTURES: [&str; 6] = [
+        "./tests/test_projects/current.yml",
+        "./tests/test_projects/inline.yml",
+        "./tests/test_projects/tax.yml",
+        "./tests/test_projects/tax_inline.yml",
+        "./tests/test_projects/canceled.yml",
+        "./tests/old.yml",
+    ];
+
+    #[test]
+    fn typed_model_agrees_with_yaml_provider() {
+        for path in &FIXTURES {
+            let project = Project::open_file(Path::new(path)).unwrap();
+            let typed = project.parse_yaml().unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+            assert_eq!(typed.name(), project.name().ok(), "{}", path);
+            // the yaml provider rejects years before 1900
+            if let Ok(date) = project.event_date() {
+                assert_eq!(typed.event_date(), Some(date), "{}", path);
+            }
+            assert_eq!(typed.responsible(), project.responsible().ok(), "{}", path);
+            assert_eq!(typed.client().title().ok(), project.client().title().ok(), "{}", path);
+            assert_eq!(typed.client().last_name().ok(), project.client().last_name().ok(), "{}", path);
+            assert_eq!(typed.client().email().ok(), project.client().email().ok(), "{}", path);
+            assert_eq!(typed.offer_number(), project.offer().number().ok(), "{}", path);
+            assert_eq!(typed.invoice().number().ok(), project.invoice().number().ok(), "{}", path);
+            assert_eq!(typed.invoice().date().ok(), project.invoice().date().ok(), "{}", path);
+            assert_eq!(typed.invoice().number_long_str(), project.invoice().number_long_str(), "{}", path);
+            assert_eq!(typed.invoice().missing_fields(), project.invoice().missing_fields(), "{}", path);
+            assert_eq!(typed.client().address().ok(), project.client().address().ok(), "{}", path);
+            assert_eq!(typed.client().full_name(), project.client().full_name(), "{}", path);
+            assert_eq!(typed.client().addressing(), project.client().addressing(), "{}", path);
+            assert_eq!(typed.payed_date(), project.payed_date().ok(), "{}", path);
+            assert_eq!(typed.canceled, project.canceled(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn legacy_fields() {
+        let typed = import::from_str(&std::fs::read_to_string("./tests/old.yml").unwrap()).unwrap();
+        assert_eq!(typed.name(), Some("Party Hard"));
+        assert_eq!(typed.client().title().ok(), Some("Herr"));
+        assert_eq!(typed.client().last_name().ok(), Some("Zahl"));
+        assert_eq!(typed.invoice().number().ok(), Some(41));
+        assert_eq!(typed.invoice().number_long_str().as_deref(), Some("R2014-041"));
+        assert_eq!(typed.client().salute().ok(), Some("Herr"));
+        assert_eq!(typed.client().first_name(), Err(FieldError::Missing));
+        assert_eq!(typed.offer_number().as_deref(), Some("A20141107-1"));
+        assert_eq!(typed.responsible(), Some("Hendrik Sollich"));
+        assert_eq!(typed.employees().get("Hendrik"), Some(&3.0));
+    }
+
+    #[test]
+    fn errors_carry_line_and_column() {
+        let doc = "client:\n  title: Herr\nevent:\n  name: Foo\ninvoice:\n  number: 3\n  date: 32.13.2014\n";
+        let error = import::from_str(doc).unwrap_err();
+        match error.downcast_ref::<ProjectError>() {
+            Some(ProjectError::Parse { line, column, message }) => {
+                assert_eq!((*line, *column), (7, 9));
+                assert!(message.contains("32.13.2014"), "{}", message);
+            },
+            _ => panic!("unexpected error {:?}", error),
+        }
+    }
+}
+
+pub mod migration {
+    use super::*;
+    use crate::{
+        project::migration::{self, CURRENT_FORMAT},
+        util::yaml,
+    };
+
+    #[test]
+    fn legacy_document_keeps_its_values() {
+        let content = std::fs::read_to_string("./tests/old.yml").unwrap();
+        let migrated = migration::migrate(&content).unwrap().unwrap();
+        assert_eq!(migrated.from.to_string(), "1.0.0");
+        assert_eq!(migrated.to.to_string(), CURRENT_FORMAT);
+
+        let old = parse_project(&content);
+        let new = parse_project(&migrated.content);
+        let new_yaml = new.yaml();
+        for path in [
+            "client",
+            "email",
+            "address",
+            "event",
+            "date",
+            "manumber",
+            "rnumber",
+            "signature",
+        ] {
+            assert!(
+                yaml::get(new_yaml, path).is_none_or(|v| v.as_hash().is_some()),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(yaml::get_str(new_yaml, "event/dates/0/begin"), Some("01.05.1512"));
+
+        assert_eq!(old.name().ok(), new.name().ok());
+        assert_eq!(old.responsible().ok(), new.responsible().ok());
+        assert_eq!(old.offer().number().ok(), new.offer().number().ok());
+        assert_eq!(old.invoice().number_str(), new.invoice().number_str());
+        assert_eq!(old.invoice().date().ok(), new.invoice().date().ok());
+        assert_eq!(old.payed_date().ok(), new.payed_date().ok());
+        assert_eq!(old.client().title().ok(), new.client().title().ok());
+        assert_eq!(old.client().last_name().ok(), new.client().last_name().ok());
+        assert_eq!(old.client().email().ok(), new.client().email().ok());
+        assert_eq!(old.client().address().ok(), new.client().address().ok());
+        assert_eq!(old.hours().employees_string(), new.hours().employees_string());
+        assert_eq!(new.format().unwrap().to_string(), CURRENT_FORMAT);
+
+        assert!(migration::migrate(&migrated.content).unwrap().is_none());
+    }
+
+    #[test]
+    fn format_moves_to_meta() {
+        let doc = "--- # header\nformat: 2.4.0\ninvoicer_version: 2.4.4\nevent:\n  name: Foo\nhours:\n  employees:\n    Maxime: 2 # Stunden\n...\n";
+        let migrated = migration::migrate(doc).unwrap().unwrap();
+        assert_eq!(
+            migrated.content,
+            "--- # header\nevent:\n  name: Foo\nhours:\n  caterers:\n    Maxime: 2 # Stunden\n\n\
+             meta:\n  invoicer_version: 2.4.4\n  format: 3.0.0\n...\n"
+        );
+        assert_eq!(
+            migrated.diff(),
+            "@@ 2 @@\n-format: 2.4.0\n-invoicer_version: 2.4.4\n@@ 7 @@\n-  employees:\n+  caterers:\n\
+             @@ 9 @@\n+\n+meta:\n+  invoicer_version: 2.4.4\n+  format: 3.0.0\n"
+        );
+    }
+
+    #[test]
+    fn current_documents_stay_untouched() {
+        let project = Project::open_file(Path::new("./templates/default.tyml")).unwrap();
+        assert_eq!(project.format().unwrap().to_string(), CURRENT_FORMAT);
+        assert!(migration::migrate("meta:\n  format: 3.0.0\n").unwrap().is_none());
+    }
+}
+
+#[cfg(all(feature = "serialization", feature = "deserialization"))]
+pub mod schema {
+    use super::*;
+    use crate::project::{migration, schema};
+
+    #[test]
+    fn migrated_fixtures_match_the_schema() {
+        for path in [
+            "./tests/test_projects/current.yml",
+            "./tests/test_projects/inline.yml",
+            "./tests/test_projects/tax.yml",
+            "./tests/test_projects/tax_inline.yml",
+            "./tests/test_projects/canceled.yml",
+            "./tests/old.yml",
+        ] {
+            let content = std::fs::read_to_string(path).unwrap();
+            let migrated = migration::migrate(&content).unwrap().unwrap();
+            let project = parse_project(&migrated.content);
+            assert_eq!(schema::validate(project.yaml()), Vec::new(), "{}", path);
+            assert!(project.validate_schema().is_ok(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn violations_carry_paths() {
+        let project = parse_project(
+            "client:\n  title: Herr\n\
+             event:\n  dates:\n  - begin: 1.1.20\n    times:\n    - begin: \"19:00\"\n\
+             offer:\n  appendix: eins\n\
+             products:\n  Kaffee: { amount: viele }\n\
+             hours:\n  caterers:\n    Maxime: 2\n    Lena: zwei\n\
+             payments:\n  - date: 10.12.2014\n\
+             canceled: vielleicht\n\
+             meta:\n  format: 3.0.0\n",
+        );
+        let paths = schema::validate(project.yaml())
+            .into_iter()
+            .map(|violation| violation.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "event.dates.0.begin",
+                "offer.appendix",
+                "products.Kaffee.amount",
+                "hours.caterers.Lena",
+                "payments.0.amount",
+                "canceled",
+            ]
+        );
+        assert_eq!(project.validate_schema().validation_errors.len(), 6);
+
+        // of the alternatives (date or nothing) only the date is reported
+        let violation = &schema::validate(project.yaml())[0];
+        assert!(violation.message.contains("does not match"), "{}", violation);
+    }
+
+    #[test]
+    fn follows_the_typed_model() {
+        let doc = "event:\n  name: Party\n  location: [APB, Mensa]\nmeta:\n  format: 3.0.0\n";
+        assert!(crate::project::import::from_str(doc).is_err());
+        let paths = schema::validate(parse_project(doc).yaml())
+            .into_iter()
+            .map(|violation| violation.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["client"]);
+
+        let doc = format!("client: {{ title: Herr }}\n{}", doc);
+        assert!(crate::project::import::from_str(&doc).is_ok());
+        assert_eq!(schema::validate(parse_project(&doc).yaml()), Vec::new());
+    }
+
+    #[test]
+    fn only_current_documents_are_checked() {
+        let project = parse_project("format: 2.4.0\ninvoice:\n  number: eins\n");
+        assert!(project.validate_schema().is_ok());
+        let project = parse_project("invoice:\n  number: 1\n");
+        assert!(project.validate_schema().is_ok());
+        let project = parse_project("meta: {}\n");
+        assert!(project.validate_schema().is_ok());
+    }
+
+    #[test]
+    fn references_resolve() {
+        let schema = schema::schema().to_string();
+        for reference in schema.split("\"$ref\":\"#/").skip(1) {
+            let pointer = reference.split('"').next().unwrap();
+            assert!(
+                schema::schema().pointer(&format!("/{}", pointer)).is_some(),
+                "{}",
+                pointer
+            );
+        }
+    }
+}
+
+pub mod client_book {
+    use super::*;
+    use crate::project::{client_book::ClientBook, yaml_provider::FieldError};
+    use std::sync::Arc;
+
+    fn book() -> Arc<ClientBook> {
+        Arc::new(
+            ClientBook::from_content(
+                "graf-zahl:\n  title: Herr\n  first_name: Graf\n  last_name: Zahl\n  email: zahl@example.com\n  address: |\n    Graf Zahl\n    Nummernhöllenstraße 666\n",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn references_are_resolved() {
+        let project = parse_project("client:\n  ref: graf-zahl\n").with_client_book(book());
+        let client = project.client();
+        assert_eq!(client.title(), Ok("Herr"));
+        assert_eq!(client.last_name(), Ok("Zahl"));
+        assert_eq!(client.address(), Ok("Graf Zahl\nNummernhöllenstraße 666\n"));
+        assert_eq!(client.full_name(), Some("Graf Zahl".into()));
+        assert!(client.missing_fields().is_empty());
+    }
+
+    #[test]
+    fn local_fields_win() {
+        let project = parse_project("client:\n  ref: graf-zahl\n  email: graf@example.com\n").with_client_book(book());
+        assert_eq!(project.client().email(), Ok("graf@example.com"));
+        assert_eq!(project.client().first_name(), Ok("Graf"));
+    }
+
+    #[test]
+    fn unknown_references_are_invalid() {
+        let project = parse_project("client:\n  ref: dracula\n").with_client_book(book());
+        assert!(matches!(project.client().last_name(), Err(FieldError::Invalid(_))));
+    }
+}
+
+pub mod catalogue {
+    use super::*;
+    use crate::{
+        project::catalogue::Catalogue,
+        util::{to_currency, yaml::parse_dmy_date},
+    };
+    use bill::BillProduct;
+    use std::sync::Arc;
+
+    fn catalogue() -> Arc<Catalogue> {
+        Arc::new(
+            Catalogue::from_content(
+                "kaffee:\n  name: Kaffee\n  unit: 1l\n  prices:\n  - since: 01.01.2015\n    price: 2.5\n  - since: 01.03.2024\n    price: 2.8\n\
+                 kuchen:\n  name: Kuchen\n  tax: 0.07\n  prices:\n  - since: 01.01.2015\n    price: 1.0\n",
+            )
+            .unwrap(),
+        )
+    }
+
+    fn offered_on(offer_date: &str) -> Project {
+        parse_project(&format!(
+            "offer:\n  date: {}\nhours:\n  salary: 8.0\ntax: 0.19\nproducts:\n  kaffee: {{ amount: 10 }}\n  kuchen: {{ amount: 4, sold: 2 }}\n  Tee: {{ amount: 2, price: 1.5 }}\n",
+            offer_date
+        ))
+        .with_catalogue(catalogue())
+    }
+
+    #[test]
+    fn prices_of_the_offer_date() {
+        let project = offered_on("10.10.2023");
+        assert_eq!(project.price_date(), parse_dmy_date("10.10.2023").unwrap());
+        let (offer, invoice) = project.bills().unwrap();
+        let kaffee = offer
+            .as_items()
+            .into_iter()
+            .find(|i| i.product.name == "Kaffee")
+            .unwrap();
+        assert_eq!(kaffee.product.price(), to_currency(2.5));
+        assert_eq!(kaffee.product.unit, Some("1l"));
+        assert_eq!(
+            project.sum_sold().unwrap(),
+            to_currency(25.0 * 1.19 + 2.0 * 1.07 + 3.0 * 1.19)
+        );
+        assert_eq!(invoice.as_items().len(), 3);
+
+        let later = offered_on("01.03.2024");
+        assert_eq!(
+            later.sum_sold().unwrap(),
+            to_currency(28.0 * 1.19 + 2.0 * 1.07 + 3.0 * 1.19)
+        );
+    }
+
+    #[test]
+    fn catalogue_taxes() {
+        let project = offered_on("10.10.2023");
+        let (offer, _) = project.bills().unwrap();
+        let kuchen = offer
+            .as_items()
+            .into_iter()
+            .find(|i| i.product.name == "Kuchen")
+            .unwrap();
+        assert_eq!(kuchen.product.tax.value(), 0.07);
+    }
+
+    #[test]
+    fn no_price_yet() {
+        assert!(offered_on("10.10.2014").bills().is_err());
+    }
+}
+
+pub mod currency {
+    use super::*;
+    use crate::{
+        project::catalogue::Catalogue,
+        util::{currency, currency_to_string},
+    };
+    use std::sync::Arc;
+
+    const DOC: &str = "event:\n  name: Party\n  dates:\n  - begin: 01.10.2024\n\
+                       invoice:\n  number: 7\n  date: 10.10.2024\n\
+                       hours:\n  salary: 10.0\n  caterers:\n    Ada: 2\n\
+                       tax: 0.19\n\
+                       currency: USD\n\
+                       products:\n  Coffee: { amount: 10, price: 2.5 }\n\
+                       payments:\n  - date: 20.10.2024\n    amount: 20.0\n";
+
+    #[test]
+    fn amounts_are_in_the_currency_of_the_project() {
+        let project = parse_project(DOC);
+        assert_eq!(project.currency().map(|info| info.code), Ok("USD"));
+        let sold = project.sum_sold().unwrap();
+        assert_eq!(sold.symbol, Some('$'));
+        assert_eq!(currency_to_string(&sold), "$49.75");
+        assert_eq!(currency_to_string(&project.outstanding().unwrap()), "$29.75");
+    }
+
+    #[test]
+    fn wages_are_in_the_configured_currency() {
+        let project = parse_project(DOC);
+        assert_eq!(project.hours().salary().unwrap().symbol, currency::default_symbol());
+        assert_eq!(project.hours().wages().unwrap().symbol, currency::default_symbol());
+        // the service is billed like everything else
+        let (_, invoice) = project.bills().unwrap();
+        assert_eq!(invoice.gross_total().symbol, Some('$'));
+
+        let project = parse_project(&DOC.replace("  salary: 10.0\n", "  salary: 10.0\n  currency: usd\n"));
+        assert_eq!(project.hours().salary().unwrap().symbol, Some('$'));
+        assert_eq!(currency_to_string(&project.hours().wages().unwrap()), "$20.00");
+
+        let project = parse_project(&DOC.replace("  salary: 10.0\n", "  salary: 10.0\n  currency: DOGE\n"));
+        assert!(project.hours().salary().is_err());
+        let errors = project.hours().validate().validation_errors;
+        assert!(errors.iter().any(|e| e.contains("unknown currency \"DOGE\"")), "{:?}", errors);
+    }
+
+    #[test]
+    fn unknown_currencies_are_invalid() {
+        let project = parse_project(&DOC.replace("USD", "DOGE"));
+        let errors = project.validate().validation_errors;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("unknown currency \"DOGE\""), "{:?}", errors);
+    }
+
+    #[test]
+    fn catalogue_prices_keep_their_currency() {
+        let catalogue =
+            Catalogue::from_content("tea:\n  name: Tea\n  prices:\n  - since: 01.01.2020\n    price: 1.5\n").unwrap();
+        let project = parse_project(&DOC.replace("Coffee: { amount: 10, price: 2.5 }", "tea: { amount: 2 }"))
+            .with_catalogue(Arc::new(catalogue));
+        let error = project.bills().unwrap_err().to_string();
+        assert!(error.contains("is priced in EUR, not in USD"), "{}", error);
+    }
+
+    #[test]
+    fn csv_names_the_currency() {
+        let csv = crate::actions::projects_to_csv(&[parse_project(DOC)]).unwrap();
+        let row = csv.lines().nth(1).unwrap().split(';').collect::<Vec<_>>();
+        assert_eq!(&row[7..9], &["4975", "USD"]);
+    }
+}
+
+pub mod time_tracking {
+    use super::*;
+    use crate::project::{append_shift, import};
+    use chrono::prelude::*;
+
+    static DOC: &str = r#"
+event:
+  name: Schicht
+  dates:
+    - begin: 20.03.2024
+      end: 21.03.2024
+      times:
+        - begin: "18:00"
+          end: "02:00"
+
+hours:
+  salary: 10
+  caterers:
+    Anna:
+      - begin: "18:00"
+        end: "22:30"
+        pause: 30
+    Bob:
+      - date: 20.03.2024
+        begin: "22:00"
+        end: "01:30"
+    Carl: 3
+
+tax: 0.19
+"#;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms(hour, minute, 0)
+    }
+
+    fn time_of(project: &Project, name: &str) -> f64 {
+        let employees = project.hours().employees().unwrap();
+        employees.iter().find(|e| e.name == name).unwrap().time
+    }
+
+    #[test]
+    fn hours_from_shifts() {
+        let project = parse_project(DOC);
+        assert_eq!(time_of(&project, "Anna"), 4.0);
+        assert_eq!(time_of(&project, "Bob"), 3.5);
+        assert_eq!(time_of(&project, "Carl"), 3.0);
+        assert_eq!(project.hours().total_time(), Some(10.5));
+        assert_eq!(project.hours().validate().validation_errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn overlapping_shifts() {
+        let doc = append_shift(DOC, "Bob", Some(Utc.ymd(2024, 3, 21)), time(1, 0), time(2, 0), 0).unwrap();
+        let errors = parse_project(&doc).hours().validate().validation_errors;
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].contains("overlap"), "{:?}", errors);
+    }
+
+    #[test]
+    fn shifts_outside_the_event() {
+        let doc = append_shift(DOC, "Anna", Some(Utc.ymd(2024, 3, 22)), time(18, 0), time(20, 0), 0).unwrap();
+        let doc = append_shift(&doc, "Dora", None, time(12, 0), time(19, 0), 0).unwrap();
+        let errors = parse_project(&doc).hours().validate().validation_errors;
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].contains("not a day of the event"), "{:?}", errors);
+        assert!(errors[1].contains("outside of the event times"), "{:?}", errors);
+    }
+
+    #[test]
+    fn append_to_shifts() {
+        let doc = append_shift(DOC, "Anna", None, time(23, 0), time(23, 45), 15).unwrap();
+        let expected = DOC.replace(
+            "        pause: 30\n",
+            "        pause: 30\n      - begin: \"23:00\"\n        end: \"23:45\"\n        pause: 15\n",
+        );
+        assert_eq!(doc, expected);
+        assert_eq!(time_of(&parse_project(&doc), "Anna"), 4.5);
+
+        let doc = append_shift(DOC, "Dora", None, time(18, 0), time(19, 0), 0).unwrap();
+        assert_eq!(time_of(&parse_project(&doc), "Dora"), 1.0);
+    }
+
+    #[test]
+    fn names_with_dots_and_slashes() {
+        let doc = append_shift(DOC, "A. Meier", None, time(18, 0), time(19, 0), 0).unwrap();
+        let doc = append_shift(&doc, "A. Meier", None, time(20, 0), time(21, 30), 0).unwrap();
+        let doc = append_shift(&doc, "Meier/Schulz", None, time(18, 0), time(20, 0), 0).unwrap();
+        assert!(doc.contains("    Carl: 3\n    A. Meier:\n      - begin: \"18:00\"\n"), "{}", doc);
+        let project = parse_project(&doc);
+        assert_eq!(time_of(&project, "A. Meier"), 2.5);
+        assert_eq!(time_of(&project, "Meier/Schulz"), 2.0);
+        assert!(project.yaml()["hours"]["caterers"]["A"].is_badvalue());
+    }
+
+    #[test]
+    fn invalid_shifts() {
+        let project = parse_project("hours:\n  salary: 10\n  caterers:\n    Anna:\n      - begin: \"18:00\"\n");
+        assert!(project.hours().employees().is_err());
+    }
+
+    #[test]
+    fn typed_model_reads_shifts() {
+        let typed = import::from_str(&format!("client: {{ title: Frau, last_name: Zahl }}\n{}", DOC)).unwrap();
+        let employees = typed.employees();
+        assert_eq!(employees.get("Anna"), Some(&4.0));
+        assert_eq!(employees.get("Bob"), Some(&3.5));
+        assert_eq!(employees.get("Carl"), Some(&3.0));
+    }
+}
+
 /*
 
 mod product {
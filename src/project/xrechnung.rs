@@ -0,0 +1,838 @@
+//! Structured e-invoices according to XRechnung (EN 16931) in the UBL syntax,
+//! and in the CII syntax that ZUGFeRD/Factur-X embeds into the pdf.
+//!
+//! Everything about the seller (that's us) comes from the `seller` section of the config,
+//! everything about the buyer and the invoice itself comes from the project.
+//!
+//! The cancellation (Storno) of an invoice becomes a UBL `CreditNote` (type code 381) that refers to the invoice.
+//!
+//! The XRechnung is written next to the pdf,
+//! the CII document is attached to the rendered pdf by [`document_export`](crate::document_export).
+
+use std::fmt::Write;
+
+use anyhow::{bail, Error};
+use bill::{Bill, Currency, Tax};
+use chrono::{Date, Duration, Utc};
+use yaml_rust::Yaml;
+
+use super::{
+    error::{ProjectError, ValidationResult},
+    spec::{Cancelable, Invoicable, IsClient, IsProject, Redeemable, Validatable},
+    yaml_provider::{FieldError, FieldResult},
+    product::Product,
+    Project,
+};
+use crate::util::currency;
+
+const CUSTOMIZATION_ID: &str = "urn:cen.eu:en16931:2017#compliant#urn:xeinkauf.de:kosit:xrechnung_3.0";
+const PROFILE_ID: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+/// The EN 16931 profile of Factur-X, called COMFORT in ZUGFeRD
+const FACTUR_X_GUIDELINE_ID: &str = "urn:cen.eu:en16931:2017";
+
+/// Our own company, as configured in `seller/*`
+#[derive(Debug, Default)]
+pub struct Seller {
+    pub name: Option<String>,
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub vat_id: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub iban: Option<String>,
+    /// why items without VAT are exempt (BT-120), e.g. `Kleinunternehmer gemäß § 19 UStG`
+    pub tax_exemption_reason: Option<String>,
+    pub payment_days: i64,
+}
+
+impl Seller {
+    pub fn from_config() -> Self {
+        let get = |key: &str| {
+            crate::CONFIG
+                .get_str_or(&format!("seller/{}", key))
+                .filter(|value| !value.trim().is_empty())
+                .map(ToOwned::to_owned)
+        };
+        Seller {
+            name: get("name"),
+            street: get("street"),
+            postal_code: get("postal_code"),
+            city: get("city"),
+            country: get("country"),
+            vat_id: get("vat_id"),
+            email: get("email"),
+            phone: get("phone"),
+            iban: get("iban"),
+            tax_exemption_reason: get("tax_exemption_reason"),
+            payment_days: crate::CONFIG
+                .get("seller/payment_days")
+                .and_then(Yaml::as_i64)
+                .unwrap_or(14),
+        }
+    }
+}
+
+impl Validatable for Seller {
+    fn validate(&self) -> ValidationResult {
+        let mut validation = ValidationResult::new();
+        validation.require_option("seller/name", self.name.as_ref());
+        validation.require_option("seller/street", self.street.as_ref());
+        validation.require_option("seller/postal_code", self.postal_code.as_ref());
+        validation.require_option("seller/city", self.city.as_ref());
+        validation.require_option("seller/country", self.country.as_ref());
+        validation.require_option("seller/vat_id", self.vat_id.as_ref());
+        validation.require_option("seller/email", self.email.as_ref());
+        validation.require_option("seller/phone", self.phone.as_ref());
+        validation.require_option("seller/iban", self.iban.as_ref());
+        validation
+    }
+}
+
+/// The client address split into street lines, postal code and city.
+///
+/// The last line is expected to look like `01234 City`.
+#[derive(Debug, PartialEq, Eq)]
+struct PostalAddress<'a> {
+    lines: Vec<&'a str>,
+    postal_code: &'a str,
+    city: &'a str,
+}
+
+impl<'a> PostalAddress<'a> {
+    fn parse(address: &'a str) -> Option<Self> {
+        let mut lines = address
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>();
+        let (postal_code, city) = lines.pop()?.split_once(char::is_whitespace)?;
+        if !postal_code.chars().any(|c| c.is_ascii_digit())
+            || !postal_code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return None;
+        }
+        Some(PostalAddress {
+            lines,
+            postal_code,
+            city: city.trim(),
+        })
+    }
+}
+
+/// A country code after ISO 3166-1 alpha-2
+fn country_code(country: &str) -> FieldResult<&str> {
+    if country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(country)
+    } else {
+        Err(FieldError::invalid("expected a country code like DE"))
+    }
+}
+
+/// Whether any item of the invoice is sold without VAT.
+fn has_exempt_items(project: &Project) -> bool {
+    project
+        .bills()
+        .map(|(_, bill)| bill.iter().any(|(tax, _)| tax.value() == 0.0))
+        .unwrap_or(false)
+}
+
+/// Everything an e-invoice needs that an ordinary invoice does not.
+pub fn validate(project: &Project, seller: &Seller) -> ValidationResult {
+    let mut validation = ValidationResult::new();
+    validation.require_field("invoice.buyer_reference", project.invoice().buyer_reference());
+    validation.require_field("client.email", project.client().email());
+    validation.require_field("client.country", project.client().country().and_then(country_code));
+    if let Some(ref country) = seller.country {
+        validation.validate_field("seller/country", country_code(country));
+    }
+    if has_exempt_items(project) {
+        validation.require_option("seller/tax_exemption_reason", seller.tax_exemption_reason.as_ref());
+    }
+    validation.require_option("client.first_name/last_name", project.client().full_name());
+    validation.require_option(
+        "client.address (ending in \"<postal code> <city>\")",
+        project.client().address().ok().and_then(PostalAddress::parse),
+    );
+    validation.require_field("manager", project.responsible());
+
+    project
+        .invoice()
+        .validate()
+        .and(project.validate_taxes())
+        .and(seller.validate())
+        .and(validation)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn amount(money: Currency) -> String {
+    format!("{:.2}", money.as_float())
+}
+
+fn quantity(amount: f64) -> String {
+    let rounded = format!("{:.4}", amount);
+    rounded.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+fn percent(tax: Tax) -> String {
+    quantity(tax.value() * 100.0)
+}
+
+/// Unit code after UN/ECE Recommendation 20
+fn unit_code(unit: Option<&str>) -> &'static str {
+    match unit.map(str::to_lowercase).as_deref() {
+        Some("h" | "std" | "stunde" | "stunden") => "HUR",
+        Some("l" | "liter") => "LTR",
+        Some("kg") => "KGM",
+        Some("g") => "GRM",
+        Some("stk" | "stück" | "pcs") => "H87",
+        _ => "C62",
+    }
+}
+
+/// Standard rate or exempt from VAT, which needs a reason in the VAT breakdown
+fn tax_category(tax: Tax) -> &'static str {
+    if tax.value() > 0.0 {
+        "S"
+    } else {
+        "E"
+    }
+}
+
+fn write_tax_category(xml: &mut String, element: &str, tax: Tax, exemption_reason: Option<&str>) -> std::fmt::Result {
+    writeln!(xml, "      <cac:{}>", element)?;
+    writeln!(xml, "        <cbc:ID>{}</cbc:ID>", tax_category(tax))?;
+    writeln!(xml, "        <cbc:Percent>{}</cbc:Percent>", percent(tax))?;
+    if let (Some(reason), "E") = (exemption_reason, tax_category(tax)) {
+        writeln!(
+            xml,
+            "        <cbc:TaxExemptionReason>{}</cbc:TaxExemptionReason>",
+            escape(reason)
+        )?;
+    }
+    writeln!(xml, "        <cac:TaxScheme><cbc:ID>VAT</cbc:ID></cac:TaxScheme>")?;
+    writeln!(xml, "      </cac:{}>", element)
+}
+
+/// `value`, or the error `validate()` would have reported for it.
+fn required<T>(name: &str, value: Option<T>) -> Result<T, Error> {
+    value.ok_or_else(|| ProjectError::MissingForEInvoice(vec![name.to_owned()]).into())
+}
+
+fn write_address(xml: &mut String, lines: &[&str], postal_code: &str, city: &str, country: &str) -> std::fmt::Result {
+    writeln!(xml, "      <cac:PostalAddress>")?;
+    if let Some(street) = lines.last() {
+        writeln!(xml, "        <cbc:StreetName>{}</cbc:StreetName>", escape(street))?;
+    }
+    writeln!(xml, "        <cbc:CityName>{}</cbc:CityName>", escape(city))?;
+    writeln!(xml, "        <cbc:PostalZone>{}</cbc:PostalZone>", escape(postal_code))?;
+    writeln!(
+        xml,
+        "        <cac:Country><cbc:IdentificationCode>{}</cbc:IdentificationCode></cac:Country>",
+        escape(country)
+    )?;
+    writeln!(xml, "      </cac:PostalAddress>")
+}
+
+/// Produces an XRechnung in UBL syntax from the invoice of `project`.
+///
+/// Fails with a list of missing fields if `validate()` is not happy.
+pub fn to_ubl(project: &Project, seller: &Seller) -> Result<String, Error> {
+    write_ubl(&EInvoice::collect(project, seller, false)?)
+}
+
+/// Produces an XRechnung credit note in UBL syntax from the cancellation of the invoice of `project`.
+///
+/// Fails with a list of missing fields if `validate()` or the cancellation is not happy.
+pub fn credit_note_to_ubl(project: &Project, seller: &Seller) -> Result<String, Error> {
+    write_ubl(&EInvoice::collect(project, seller, true)?)
+}
+
+/// Produces the invoice of `project` in CII syntax, as embedded into the pdf for ZUGFeRD/Factur-X.
+///
+/// Fails with a list of missing fields if `validate()` is not happy.
+pub fn to_cii(project: &Project, seller: &Seller) -> Result<String, Error> {
+    write_cii(&EInvoice::collect(project, seller, false)?)
+}
+
+/// Produces the cancellation of the invoice of `project` in CII syntax, as embedded into the pdf for ZUGFeRD/Factur-X.
+///
+/// Fails with a list of missing fields if `validate()` or the cancellation is not happy.
+pub fn credit_note_to_cii(project: &Project, seller: &Seller) -> Result<String, Error> {
+    write_cii(&EInvoice::collect(project, seller, true)?)
+}
+
+/// Everything either syntax needs, checked and collected from the project and the seller.
+struct EInvoice<'a> {
+    seller: &'a Seller,
+    credit_note: bool,
+    /// number and date of the invoice or of the cancellation
+    number: String,
+    date: Date<Utc>,
+    invoice_number: String,
+    invoice_date: Date<Utc>,
+    name: Option<String>,
+    reason: Option<String>,
+    buyer_reference: String,
+    buyer_name: String,
+    buyer_email: String,
+    buyer_country: String,
+    buyer_address: String,
+    manager: String,
+    bill: Bill<Product<'a>>,
+    currency: &'static str,
+}
+
+impl<'a> EInvoice<'a> {
+    fn collect(project: &'a Project, seller: &'a Seller, credit_note: bool) -> Result<Self, Error> {
+        let mut validation = validate(project, seller);
+        if credit_note {
+            validation = validation.and(project.cancellation().validate());
+        }
+        if !validation.validation_errors.is_empty() {
+            bail!(ProjectError::InvalidForEInvoice(validation.validation_errors));
+        }
+        if !validation.missing_fields.is_empty() {
+            bail!(ProjectError::MissingForEInvoice(validation.missing_fields));
+        }
+
+        // all of these are checked by `validate()` already
+        let invoice = project.invoice();
+        let client = project.client();
+        let cancellation = project.cancellation();
+        let invoice_number = required("invoice.number", invoice.number_long_str())?;
+        let invoice_date = required("invoice.date", invoice.date().ok())?;
+        let (number, date) = if credit_note {
+            (
+                required("cancellation.number", cancellation.number_long_str())?,
+                required("cancellation.date", cancellation.date().ok())?,
+            )
+        } else {
+            (invoice_number.clone(), invoice_date)
+        };
+        let buyer_address = required("client.address", client.address().ok())?;
+        required("client.address", PostalAddress::parse(buyer_address))?;
+
+        let (_, bill) = project.bills()?;
+        Ok(EInvoice {
+            seller,
+            credit_note,
+            number,
+            date,
+            invoice_number,
+            invoice_date,
+            name: project.name().ok().map(ToOwned::to_owned),
+            reason: cancellation.reason().ok().filter(|_| credit_note),
+            buyer_reference: required("invoice.buyer_reference", invoice.buyer_reference().ok())?,
+            buyer_name: required("client.first_name/last_name", client.full_name())?,
+            buyer_email: required("client.email", client.email().ok())?.to_owned(),
+            buyer_country: required("client.country", client.country().ok())?.to_owned(),
+            buyer_address: buyer_address.to_owned(),
+            manager: required("manager", project.responsible().ok())?.to_owned(),
+            bill,
+            currency: project
+                .currency_symbol()
+                .and_then(currency::by_symbol)
+                .map_or("EUR", |info| info.code),
+        })
+    }
+
+    fn buyer_address(&self) -> PostalAddress<'_> {
+        PostalAddress::parse(&self.buyer_address).expect("checked by collect()")
+    }
+
+    fn due_date(&self) -> Date<Utc> {
+        self.date + Duration::days(self.seller.payment_days)
+    }
+
+    fn seller_field(&self, name: &str, value: &Option<String>) -> Result<String, Error> {
+        required(name, value.as_deref().map(escape))
+    }
+}
+
+fn write_ubl(doc: &EInvoice<'_>) -> Result<String, Error> {
+    let seller = doc.seller;
+    let currency = doc.currency;
+    let buyer_address = doc.buyer_address();
+    let seller_email = doc.seller_field("seller/email", &seller.email)?;
+
+    // credit notes have a document type of their own in UBL
+    let (root, line, quantity_element) = if doc.credit_note {
+        ("CreditNote", "CreditNoteLine", "CreditedQuantity")
+    } else {
+        ("Invoice", "InvoiceLine", "InvoicedQuantity")
+    };
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        xml,
+        r#"<ubl:{0} xmlns:ubl="urn:oasis:names:specification:ubl:schema:xsd:{0}-2" xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2" xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">"#,
+        root
+    )?;
+    writeln!(xml, "  <cbc:CustomizationID>{}</cbc:CustomizationID>", CUSTOMIZATION_ID)?;
+    writeln!(xml, "  <cbc:ProfileID>{}</cbc:ProfileID>", PROFILE_ID)?;
+    writeln!(xml, "  <cbc:ID>{}</cbc:ID>", escape(&doc.number))?;
+    writeln!(xml, "  <cbc:IssueDate>{}</cbc:IssueDate>", doc.date.format("%Y-%m-%d"))?;
+    if doc.credit_note {
+        writeln!(xml, "  <cbc:CreditNoteTypeCode>381</cbc:CreditNoteTypeCode>")?;
+    } else {
+        writeln!(xml, "  <cbc:DueDate>{}</cbc:DueDate>", doc.due_date().format("%Y-%m-%d"))?;
+        writeln!(xml, "  <cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>")?;
+    }
+    for note in doc.name.iter().chain(&doc.reason) {
+        writeln!(xml, "  <cbc:Note>{}</cbc:Note>", escape(note))?;
+    }
+    writeln!(
+        xml,
+        "  <cbc:DocumentCurrencyCode>{}</cbc:DocumentCurrencyCode>",
+        currency
+    )?;
+    writeln!(
+        xml,
+        "  <cbc:BuyerReference>{}</cbc:BuyerReference>",
+        escape(&doc.buyer_reference)
+    )?;
+    if doc.credit_note {
+        writeln!(xml, "  <cac:BillingReference>")?;
+        writeln!(xml, "    <cac:InvoiceDocumentReference>")?;
+        writeln!(xml, "      <cbc:ID>{}</cbc:ID>", escape(&doc.invoice_number))?;
+        writeln!(
+            xml,
+            "      <cbc:IssueDate>{}</cbc:IssueDate>",
+            doc.invoice_date.format("%Y-%m-%d")
+        )?;
+        writeln!(xml, "    </cac:InvoiceDocumentReference>")?;
+        writeln!(xml, "  </cac:BillingReference>")?;
+    }
+
+    writeln!(xml, "  <cac:AccountingSupplierParty>")?;
+    writeln!(xml, "    <cac:Party>")?;
+    writeln!(
+        xml,
+        r#"      <cbc:EndpointID schemeID="EM">{}</cbc:EndpointID>"#,
+        seller_email
+    )?;
+    write_address(
+        &mut xml,
+        &[required("seller/street", seller.street.as_deref())?],
+        required("seller/postal_code", seller.postal_code.as_deref())?,
+        required("seller/city", seller.city.as_deref())?,
+        required("seller/country", seller.country.as_deref())?,
+    )?;
+    writeln!(xml, "      <cac:PartyTaxScheme>")?;
+    writeln!(
+        xml,
+        "        <cbc:CompanyID>{}</cbc:CompanyID>",
+        doc.seller_field("seller/vat_id", &seller.vat_id)?
+    )?;
+    writeln!(xml, "        <cac:TaxScheme><cbc:ID>VAT</cbc:ID></cac:TaxScheme>")?;
+    writeln!(xml, "      </cac:PartyTaxScheme>")?;
+    writeln!(xml, "      <cac:PartyLegalEntity>")?;
+    writeln!(
+        xml,
+        "        <cbc:RegistrationName>{}</cbc:RegistrationName>",
+        doc.seller_field("seller/name", &seller.name)?
+    )?;
+    writeln!(xml, "      </cac:PartyLegalEntity>")?;
+    writeln!(xml, "      <cac:Contact>")?;
+    writeln!(xml, "        <cbc:Name>{}</cbc:Name>", escape(&doc.manager))?;
+    writeln!(
+        xml,
+        "        <cbc:Telephone>{}</cbc:Telephone>",
+        doc.seller_field("seller/phone", &seller.phone)?
+    )?;
+    writeln!(xml, "        <cbc:ElectronicMail>{}</cbc:ElectronicMail>", seller_email)?;
+    writeln!(xml, "      </cac:Contact>")?;
+    writeln!(xml, "    </cac:Party>")?;
+    writeln!(xml, "  </cac:AccountingSupplierParty>")?;
+
+    writeln!(xml, "  <cac:AccountingCustomerParty>")?;
+    writeln!(xml, "    <cac:Party>")?;
+    writeln!(
+        xml,
+        r#"      <cbc:EndpointID schemeID="EM">{}</cbc:EndpointID>"#,
+        escape(&doc.buyer_email)
+    )?;
+    write_address(
+        &mut xml,
+        &buyer_address.lines,
+        buyer_address.postal_code,
+        buyer_address.city,
+        &doc.buyer_country,
+    )?;
+    writeln!(xml, "      <cac:PartyLegalEntity>")?;
+    writeln!(
+        xml,
+        "        <cbc:RegistrationName>{}</cbc:RegistrationName>",
+        escape(&doc.buyer_name)
+    )?;
+    writeln!(xml, "      </cac:PartyLegalEntity>")?;
+    writeln!(xml, "    </cac:Party>")?;
+    writeln!(xml, "  </cac:AccountingCustomerParty>")?;
+
+    writeln!(xml, "  <cac:PaymentMeans>")?;
+    writeln!(xml, "    <cbc:PaymentMeansCode>58</cbc:PaymentMeansCode>")?;
+    writeln!(
+        xml,
+        "    <cac:PayeeFinancialAccount><cbc:ID>{}</cbc:ID></cac:PayeeFinancialAccount>",
+        doc.seller_field("seller/iban", &seller.iban)?
+    )?;
+    writeln!(xml, "  </cac:PaymentMeans>")?;
+
+    writeln!(xml, "  <cac:TaxTotal>")?;
+    writeln!(
+        xml,
+        r#"    <cbc:TaxAmount currencyID="{}">{}</cbc:TaxAmount>"#,
+        currency,
+        amount(doc.bill.tax_total())
+    )?;
+    for (tax, items) in doc.bill.iter() {
+        writeln!(xml, "    <cac:TaxSubtotal>")?;
+        writeln!(
+            xml,
+            r#"      <cbc:TaxableAmount currencyID="{}">{}</cbc:TaxableAmount>"#,
+            currency,
+            amount(items.gross_sum())
+        )?;
+        writeln!(
+            xml,
+            r#"      <cbc:TaxAmount currencyID="{}">{}</cbc:TaxAmount>"#,
+            currency,
+            amount(items.tax_sum())
+        )?;
+        write_tax_category(&mut xml, "TaxCategory", *tax, seller.tax_exemption_reason.as_deref())?;
+        writeln!(xml, "    </cac:TaxSubtotal>")?;
+    }
+    writeln!(xml, "  </cac:TaxTotal>")?;
+
+    writeln!(xml, "  <cac:LegalMonetaryTotal>")?;
+    for (element, total) in [
+        ("LineExtensionAmount", doc.bill.gross_total()),
+        ("TaxExclusiveAmount", doc.bill.gross_total()),
+        ("TaxInclusiveAmount", doc.bill.net_total()),
+        ("PayableAmount", doc.bill.net_total()),
+    ] {
+        writeln!(
+            xml,
+            r#"    <cbc:{0} currencyID="{1}">{2}</cbc:{0}>"#,
+            element,
+            currency,
+            amount(total)
+        )?;
+    }
+    writeln!(xml, "  </cac:LegalMonetaryTotal>")?;
+
+    for (index, (tax, item)) in doc.bill.as_items_with_tax().into_iter().enumerate() {
+        writeln!(xml, "  <cac:{}>", line)?;
+        writeln!(xml, "    <cbc:ID>{}</cbc:ID>", index + 1)?;
+        writeln!(
+            xml,
+            r#"    <cbc:{0} unitCode="{1}">{2}</cbc:{0}>"#,
+            quantity_element,
+            unit_code(item.product.unit),
+            quantity(item.amount)
+        )?;
+        writeln!(
+            xml,
+            r#"    <cbc:LineExtensionAmount currencyID="{}">{}</cbc:LineExtensionAmount>"#,
+            currency,
+            amount(item.gross())
+        )?;
+        writeln!(xml, "    <cac:Item>")?;
+        writeln!(xml, "      <cbc:Name>{}</cbc:Name>", escape(item.product.name))?;
+        write_tax_category(&mut xml, "ClassifiedTaxCategory", tax, None)?;
+        writeln!(xml, "    </cac:Item>")?;
+        writeln!(
+            xml,
+            r#"    <cac:Price><cbc:PriceAmount currencyID="{}">{}</cbc:PriceAmount></cac:Price>"#,
+            currency,
+            amount(item.product.price)
+        )?;
+        writeln!(xml, "  </cac:{}>", line)?;
+    }
+
+    writeln!(xml, "</ubl:{}>", root)?;
+    Ok(xml)
+}
+
+/// `udt:DateTimeString` and friends, format 102 is `yyyymmdd`
+fn cii_date(element: &str, date: Date<Utc>) -> String {
+    format!(r#"<{0} format="102">{1}</{0}>"#, element, date.format("%Y%m%d"))
+}
+
+fn write_cii_address(xml: &mut String, lines: &[&str], postal_code: &str, city: &str, country: &str) -> std::fmt::Result {
+    writeln!(xml, "        <ram:PostalTradeAddress>")?;
+    writeln!(xml, "          <ram:PostcodeCode>{}</ram:PostcodeCode>", escape(postal_code))?;
+    for (element, line) in ["LineOne", "LineTwo", "LineThree"].iter().zip(lines) {
+        writeln!(xml, "          <ram:{0}>{1}</ram:{0}>", element, escape(line))?;
+    }
+    writeln!(xml, "          <ram:CityName>{}</ram:CityName>", escape(city))?;
+    writeln!(xml, "          <ram:CountryID>{}</ram:CountryID>", escape(country))?;
+    writeln!(xml, "        </ram:PostalTradeAddress>")
+}
+
+fn write_cii(doc: &EInvoice<'_>) -> Result<String, Error> {
+    let seller = doc.seller;
+    let currency = doc.currency;
+    let buyer_address = doc.buyer_address();
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        xml,
+        r#"<rsm:CrossIndustryInvoice xmlns:rsm="urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100" xmlns:ram="urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100" xmlns:qdt="urn:un:unece:uncefact:data:standard:QualifiedDataType:100" xmlns:udt="urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100">"#
+    )?;
+    writeln!(xml, "  <rsm:ExchangedDocumentContext>")?;
+    writeln!(
+        xml,
+        "    <ram:GuidelineSpecifiedDocumentContextParameter><ram:ID>{}</ram:ID></ram:GuidelineSpecifiedDocumentContextParameter>",
+        FACTUR_X_GUIDELINE_ID
+    )?;
+    writeln!(xml, "  </rsm:ExchangedDocumentContext>")?;
+
+    writeln!(xml, "  <rsm:ExchangedDocument>")?;
+    writeln!(xml, "    <ram:ID>{}</ram:ID>", escape(&doc.number))?;
+    writeln!(xml, "    <ram:TypeCode>{}</ram:TypeCode>", if doc.credit_note { 381 } else { 380 })?;
+    writeln!(
+        xml,
+        "    <ram:IssueDateTime>{}</ram:IssueDateTime>",
+        cii_date("udt:DateTimeString", doc.date)
+    )?;
+    for note in doc.name.iter().chain(&doc.reason) {
+        writeln!(
+            xml,
+            "    <ram:IncludedNote><ram:Content>{}</ram:Content></ram:IncludedNote>",
+            escape(note)
+        )?;
+    }
+    writeln!(xml, "  </rsm:ExchangedDocument>")?;
+
+    writeln!(xml, "  <rsm:SupplyChainTradeTransaction>")?;
+    for (index, (tax, item)) in doc.bill.as_items_with_tax().into_iter().enumerate() {
+        writeln!(xml, "    <ram:IncludedSupplyChainTradeLineItem>")?;
+        writeln!(
+            xml,
+            "      <ram:AssociatedDocumentLineDocument><ram:LineID>{}</ram:LineID></ram:AssociatedDocumentLineDocument>",
+            index + 1
+        )?;
+        writeln!(
+            xml,
+            "      <ram:SpecifiedTradeProduct><ram:Name>{}</ram:Name></ram:SpecifiedTradeProduct>",
+            escape(item.product.name)
+        )?;
+        writeln!(xml, "      <ram:SpecifiedLineTradeAgreement>")?;
+        writeln!(
+            xml,
+            "        <ram:NetPriceProductTradePrice><ram:ChargeAmount>{}</ram:ChargeAmount></ram:NetPriceProductTradePrice>",
+            amount(item.product.price)
+        )?;
+        writeln!(xml, "      </ram:SpecifiedLineTradeAgreement>")?;
+        writeln!(
+            xml,
+            r#"      <ram:SpecifiedLineTradeDelivery><ram:BilledQuantity unitCode="{}">{}</ram:BilledQuantity></ram:SpecifiedLineTradeDelivery>"#,
+            unit_code(item.product.unit),
+            quantity(item.amount)
+        )?;
+        writeln!(xml, "      <ram:SpecifiedLineTradeSettlement>")?;
+        writeln!(xml, "        <ram:ApplicableTradeTax>")?;
+        writeln!(xml, "          <ram:TypeCode>VAT</ram:TypeCode>")?;
+        writeln!(xml, "          <ram:CategoryCode>{}</ram:CategoryCode>", tax_category(tax))?;
+        writeln!(
+            xml,
+            "          <ram:RateApplicablePercent>{}</ram:RateApplicablePercent>",
+            percent(tax)
+        )?;
+        writeln!(xml, "        </ram:ApplicableTradeTax>")?;
+        writeln!(
+            xml,
+            "        <ram:SpecifiedTradeSettlementLineMonetarySummation><ram:LineTotalAmount>{}</ram:LineTotalAmount></ram:SpecifiedTradeSettlementLineMonetarySummation>",
+            amount(item.gross())
+        )?;
+        writeln!(xml, "      </ram:SpecifiedLineTradeSettlement>")?;
+        writeln!(xml, "    </ram:IncludedSupplyChainTradeLineItem>")?;
+    }
+
+    writeln!(xml, "    <ram:ApplicableHeaderTradeAgreement>")?;
+    writeln!(
+        xml,
+        "      <ram:BuyerReference>{}</ram:BuyerReference>",
+        escape(&doc.buyer_reference)
+    )?;
+    let seller_email = doc.seller_field("seller/email", &seller.email)?;
+    writeln!(xml, "      <ram:SellerTradeParty>")?;
+    writeln!(
+        xml,
+        "        <ram:Name>{}</ram:Name>",
+        doc.seller_field("seller/name", &seller.name)?
+    )?;
+    writeln!(xml, "        <ram:DefinedTradeContact>")?;
+    writeln!(xml, "          <ram:PersonName>{}</ram:PersonName>", escape(&doc.manager))?;
+    writeln!(
+        xml,
+        "          <ram:TelephoneUniversalCommunication><ram:CompleteNumber>{}</ram:CompleteNumber></ram:TelephoneUniversalCommunication>",
+        doc.seller_field("seller/phone", &seller.phone)?
+    )?;
+    writeln!(
+        xml,
+        "          <ram:EmailURIUniversalCommunication><ram:URIID>{}</ram:URIID></ram:EmailURIUniversalCommunication>",
+        seller_email
+    )?;
+    writeln!(xml, "        </ram:DefinedTradeContact>")?;
+    write_cii_address(
+        &mut xml,
+        &[required("seller/street", seller.street.as_deref())?],
+        required("seller/postal_code", seller.postal_code.as_deref())?,
+        required("seller/city", seller.city.as_deref())?,
+        required("seller/country", seller.country.as_deref())?,
+    )?;
+    writeln!(
+        xml,
+        r#"        <ram:URIUniversalCommunication><ram:URIID schemeID="EM">{}</ram:URIID></ram:URIUniversalCommunication>"#,
+        seller_email
+    )?;
+    writeln!(
+        xml,
+        r#"        <ram:SpecifiedTaxRegistration><ram:ID schemeID="VA">{}</ram:ID></ram:SpecifiedTaxRegistration>"#,
+        doc.seller_field("seller/vat_id", &seller.vat_id)?
+    )?;
+    writeln!(xml, "      </ram:SellerTradeParty>")?;
+    writeln!(xml, "      <ram:BuyerTradeParty>")?;
+    writeln!(xml, "        <ram:Name>{}</ram:Name>", escape(&doc.buyer_name))?;
+    write_cii_address(
+        &mut xml,
+        &buyer_address.lines,
+        buyer_address.postal_code,
+        buyer_address.city,
+        &doc.buyer_country,
+    )?;
+    writeln!(
+        xml,
+        r#"        <ram:URIUniversalCommunication><ram:URIID schemeID="EM">{}</ram:URIID></ram:URIUniversalCommunication>"#,
+        escape(&doc.buyer_email)
+    )?;
+    writeln!(xml, "      </ram:BuyerTradeParty>")?;
+    writeln!(xml, "    </ram:ApplicableHeaderTradeAgreement>")?;
+    writeln!(xml, "    <ram:ApplicableHeaderTradeDelivery/>")?;
+
+    writeln!(xml, "    <ram:ApplicableHeaderTradeSettlement>")?;
+    writeln!(xml, "      <ram:InvoiceCurrencyCode>{}</ram:InvoiceCurrencyCode>", currency)?;
+    writeln!(xml, "      <ram:SpecifiedTradeSettlementPaymentMeans>")?;
+    writeln!(xml, "        <ram:TypeCode>58</ram:TypeCode>")?;
+    writeln!(
+        xml,
+        "        <ram:PayeePartyCreditorFinancialAccount><ram:IBANID>{}</ram:IBANID></ram:PayeePartyCreditorFinancialAccount>",
+        doc.seller_field("seller/iban", &seller.iban)?
+    )?;
+    writeln!(xml, "      </ram:SpecifiedTradeSettlementPaymentMeans>")?;
+    for (tax, items) in doc.bill.iter() {
+        writeln!(xml, "      <ram:ApplicableTradeTax>")?;
+        writeln!(
+            xml,
+            "        <ram:CalculatedAmount>{}</ram:CalculatedAmount>",
+            amount(items.tax_sum())
+        )?;
+        writeln!(xml, "        <ram:TypeCode>VAT</ram:TypeCode>")?;
+        if let (Some(reason), "E") = (seller.tax_exemption_reason.as_deref(), tax_category(*tax)) {
+            writeln!(xml, "        <ram:ExemptionReason>{}</ram:ExemptionReason>", escape(reason))?;
+        }
+        writeln!(xml, "        <ram:BasisAmount>{}</ram:BasisAmount>", amount(items.gross_sum()))?;
+        writeln!(xml, "        <ram:CategoryCode>{}</ram:CategoryCode>", tax_category(*tax))?;
+        writeln!(
+            xml,
+            "        <ram:RateApplicablePercent>{}</ram:RateApplicablePercent>",
+            percent(*tax)
+        )?;
+        writeln!(xml, "      </ram:ApplicableTradeTax>")?;
+    }
+    if !doc.credit_note {
+        writeln!(
+            xml,
+            "      <ram:SpecifiedTradePaymentTerms><ram:DueDateDateTime>{}</ram:DueDateDateTime></ram:SpecifiedTradePaymentTerms>",
+            cii_date("udt:DateTimeString", doc.due_date())
+        )?;
+    }
+    writeln!(xml, "      <ram:SpecifiedTradeSettlementHeaderMonetarySummation>")?;
+    writeln!(
+        xml,
+        "        <ram:LineTotalAmount>{}</ram:LineTotalAmount>",
+        amount(doc.bill.gross_total())
+    )?;
+    writeln!(
+        xml,
+        "        <ram:TaxBasisTotalAmount>{}</ram:TaxBasisTotalAmount>",
+        amount(doc.bill.gross_total())
+    )?;
+    writeln!(
+        xml,
+        r#"        <ram:TaxTotalAmount currencyID="{}">{}</ram:TaxTotalAmount>"#,
+        currency,
+        amount(doc.bill.tax_total())
+    )?;
+    writeln!(
+        xml,
+        "        <ram:GrandTotalAmount>{}</ram:GrandTotalAmount>",
+        amount(doc.bill.net_total())
+    )?;
+    writeln!(
+        xml,
+        "        <ram:DuePayableAmount>{}</ram:DuePayableAmount>",
+        amount(doc.bill.net_total())
+    )?;
+    writeln!(xml, "      </ram:SpecifiedTradeSettlementHeaderMonetarySummation>")?;
+    if doc.credit_note {
+        writeln!(xml, "      <ram:InvoiceReferencedDocument>")?;
+        writeln!(
+            xml,
+            "        <ram:IssuerAssignedID>{}</ram:IssuerAssignedID>",
+            escape(&doc.invoice_number)
+        )?;
+        writeln!(
+            xml,
+            "        <ram:FormattedIssueDateTime>{}</ram:FormattedIssueDateTime>",
+            cii_date("qdt:DateTimeString", doc.invoice_date)
+        )?;
+        writeln!(xml, "      </ram:InvoiceReferencedDocument>")?;
+    }
+    writeln!(xml, "    </ram:ApplicableHeaderTradeSettlement>")?;
+    writeln!(xml, "  </rsm:SupplyChainTradeTransaction>")?;
+    writeln!(xml, "</rsm:CrossIndustryInvoice>")?;
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_postal_address() {
+        assert_eq!(
+            PostalAddress::parse("Graf Zahl\nNummernhöllenstraße 666\n01234 Countilvania\n"),
+            Some(PostalAddress {
+                lines: vec!["Graf Zahl", "Nummernhöllenstraße 666"],
+                postal_code: "01234",
+                city: "Countilvania",
+            })
+        );
+        assert_eq!(PostalAddress::parse("Graf Zahl"), None);
+        assert_eq!(PostalAddress::parse("Graf Zahl\nCountilvania"), None);
+        assert_eq!(PostalAddress::parse("Graf Zahl\nNummernhöllenstraße 666"), None);
+    }
+
+    #[test]
+    fn format_quantities() {
+        assert_eq!(quantity(4.0), "4");
+        assert_eq!(quantity(4.4), "4.4");
+        assert_eq!(quantity(0.125), "0.125");
+        assert_eq!(percent(Tax::new(0.19)), "19");
+        assert_eq!(percent(Tax::new(0.07)), "7");
+    }
+}
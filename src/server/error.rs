@@ -0,0 +1,19 @@
+//! Errors that may occur while serving projects
+
+#[derive(thiserror::Error, Debug)]
+pub enum ServerError {
+    #[error("No project found for {:?}", _0)]
+    ProjectNotFound(String),
+
+    #[error("{:?} can not be set in {:?}, it is not an empty field", _1, _0)]
+    FieldNotSettable(String, String),
+
+    #[error("{:?} is not in the working directory", _0)]
+    NotInWorkingDir(String),
+
+    #[error("{:?} is not archived", _0)]
+    NotArchived(String),
+
+    #[error("{:?} has no year to be archived into", _0)]
+    NoYear(String),
+}
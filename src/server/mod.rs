@@ -1,17 +1,39 @@
 #![cfg(feature = "server")]
 #![allow(clippy::new_without_default)]
 
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Error};
 use itertools::Itertools;
 use linked_hash_map::LinkedHashMap;
 
 use crate::{
-    project::Project,
+    project::{
+        catalogue::{self, Catalogue},
+        client_book::{self, ClientBook},
+        Project,
+    },
     storage::{self, ProjectList, Storable, Storage, StorageDir},
 };
 
+pub mod error;
+pub mod watcher;
+#[cfg(test)]
+mod tests;
+use self::{error::ServerError, watcher::ProjectChange};
+
 pub struct ProjectLoader {
     pub storage: Storage<Project>,
     pub state: State,
+    /// resolves `client.ref` of every loaded project, reloaded when it changes
+    client_book: Arc<ClientBook>,
+    /// resolves product ids of every loaded project, reloaded when it changes
+    catalogue: Arc<Catalogue>,
 }
 
 pub struct State {
@@ -21,21 +43,42 @@ pub struct State {
     pub years: Vec<i32>,
 }
 
-fn reinitialize(storage: &Storage<Project>) -> State {
-    let all = storage.open_projects(StorageDir::All).unwrap();
+/// Identifier under which a project is found in `State::mapped`
+pub fn mapped_ident(project: &Project) -> String {
+    format!("{}-{}", Storable::year(project).unwrap(), Storable::ident(project))
+}
+
+/// The client book and the catalogue of `storage`, empty if they can't be read.
+fn load_extras(storage: &Storage<Project>) -> (Arc<ClientBook>, Arc<Catalogue>) {
+    let extras = storage.extras_dir();
+    (
+        Arc::new(ClientBook::load(storage.backend(), &extras.join(client_book::FILE_NAME))),
+        Arc::new(Catalogue::load(storage.backend(), &extras.join(catalogue::FILE_NAME))),
+    )
+}
+
+fn reinitialize(storage: &Storage<Project>, client_book: &Arc<ClientBook>, catalogue: &Arc<Catalogue>) -> State {
+    let attach = |project: Project| {
+        project
+            .with_client_book(Arc::clone(client_book))
+            .with_catalogue(Arc::clone(catalogue))
+    };
+    let all = storage
+        .open_projects(StorageDir::All)
+        .unwrap()
+        .into_iter()
+        .map(attach)
+        .collect::<ProjectList<Project>>();
 
     let working = storage
         .open_projects(StorageDir::Working)
         .unwrap()
         .into_iter()
+        .map(attach)
         .map(|p| (Storable::ident(&p), p))
         .collect();
 
-    let mapped = all
-        .iter()
-        .cloned()
-        .map(|p| (format!("{}-{}", Storable::year(&p).unwrap(), Storable::ident(&p)), p))
-        .collect();
+    let mapped = all.iter().cloned().map(|p| (mapped_ident(&p), p)).collect();
 
     let years = all.iter().filter_map(Project::year).unique().collect::<Vec<_>>();
 
@@ -47,16 +90,234 @@ fn reinitialize(storage: &Storage<Project>) -> State {
     }
 }
 
+/// Every project file below `dir`, nothing if it is not a directory.
+fn project_files_below(dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .flat_map(|path| {
+            if path.is_dir() {
+                project_files_below(&path)
+            } else if watcher::is_project_file(&path) {
+                vec![path]
+            } else {
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
 impl ProjectLoader {
     pub fn new() -> Self {
-        let storage = storage::setup().unwrap();
-        let state = reinitialize(&storage);
+        Self::with_storage(storage::setup().unwrap())
+    }
+
+    pub fn with_storage(storage: Storage<Project>) -> Self {
+        let (client_book, catalogue) = load_extras(&storage);
+        let state = reinitialize(&storage, &client_book, &catalogue);
 
-        Self { storage, state }
+        Self {
+            storage,
+            state,
+            client_book,
+            catalogue,
+        }
     }
 
     pub fn update(&mut self) {
         log::debug!("updating projects");
-        self.state = reinitialize(&self.storage);
+        (self.client_book, self.catalogue) = load_extras(&self.storage);
+        self.state = reinitialize(&self.storage, &self.client_book, &self.catalogue);
+    }
+
+    /// Rereads the client book or the catalogue, every project refers to the new one afterwards.
+    ///
+    /// Returns `None` if `file` is neither of them, otherwise every project, as any of them might have changed.
+    pub fn refresh_extra_file(&mut self, file: &Path) -> Option<Vec<ProjectChange>> {
+        let backend = self.storage.backend();
+        match file.file_name().and_then(|name| name.to_str()) {
+            Some(client_book::FILE_NAME) => {
+                log::debug!("reloading the client book");
+                self.client_book = Arc::new(ClientBook::load(backend, file));
+            },
+            Some(catalogue::FILE_NAME) => {
+                log::debug!("reloading the catalogue");
+                self.catalogue = Arc::new(Catalogue::load(backend, file));
+            },
+            _ => return None,
+        }
+
+        let (client_book, catalogue) = (&self.client_book, &self.catalogue);
+        let attach = |project: &mut Project| {
+            *project = project
+                .clone()
+                .with_client_book(Arc::clone(client_book))
+                .with_catalogue(Arc::clone(catalogue));
+        };
+        self.state.all.iter_mut().for_each(attach);
+        self.state.working.iter_mut().for_each(|(_, project)| attach(project));
+        self.state.mapped.iter_mut().for_each(|(_, project)| attach(project));
+        Some(self.state.mapped.keys().cloned().map(ProjectChange::Updated).collect())
+    }
+
+    /// Reloads only the project stored in `file`, instead of everything like `update()` does.
+    ///
+    /// Returns `None` if `file` did not belong to a known project and still doesn't.
+    pub fn refresh_file(&mut self, file: &Path) -> Option<ProjectChange> {
+        let previous = self.forget_file(file);
+
+        let project = if file.exists() {
+            Project::open(file)
+                .map_err(|error| log::warn!("cannot reload {}: {}", file.display(), error))
+                .ok()
+                .filter(|p| Storable::year(p).is_some())
+                .map(|p| {
+                    p.with_client_book(Arc::clone(&self.client_book))
+                        .with_catalogue(Arc::clone(&self.catalogue))
+                })
+        } else {
+            None
+        };
+
+        match (project, previous) {
+            (Some(project), _) => {
+                let ident = mapped_ident(&project);
+                log::debug!("reloaded {:?}", ident);
+                if file.starts_with(self.storage.working_dir()) {
+                    self.state.working.insert(Storable::ident(&project), project.clone());
+                }
+                self.state.mapped.insert(ident.clone(), project.clone());
+                self.state.all.push(project);
+                self.state.years = self.state.all.iter().filter_map(Project::year).unique().collect();
+                Some(ProjectChange::Updated(ident))
+            },
+            (None, Some(ident)) => {
+                log::debug!("removed {:?}", ident);
+                Some(ProjectChange::Removed(ident))
+            },
+            (None, None) => None,
+        }
+    }
+
+    /// Reloads the project file `path`, or every project below the directory `path` and those that were there before.
+    pub fn refresh_path(&mut self, path: &Path) -> Vec<ProjectChange> {
+        if path.starts_with(self.storage.extras_dir()) {
+            return self.refresh_extra_file(path).unwrap_or_default();
+        }
+        if watcher::is_project_file(path) {
+            return self.refresh_file(path).into_iter().collect();
+        }
+        let mut files = self
+            .state
+            .all
+            .iter()
+            .map(Storable::file)
+            .filter(|file| file.starts_with(path))
+            .collect::<Vec<_>>();
+        files.extend(project_files_below(path));
+        files.sort();
+        files.dedup();
+        files.iter().filter_map(|file| self.refresh_file(file)).collect()
+    }
+
+    /// Removes every trace of the project in `file` from the state, returns its old mapped identifier.
+    fn forget_file(&mut self, file: &Path) -> Option<String> {
+        fn key_of(map: &LinkedHashMap<String, Project>, file: &Path) -> Option<String> {
+            map.iter().find(|(_, p)| p.file() == file).map(|(key, _)| key.clone())
+        }
+
+        if let Some(key) = key_of(&self.state.working, file) {
+            self.state.working.remove(&key);
+        }
+        self.state.all.retain(|p| p.file() != file);
+        let ident = key_of(&self.state.mapped, file)?;
+        self.state.mapped.remove(&ident);
+        Some(ident)
+    }
+
+    fn get_mapped(&self, ident: &str) -> Result<&Project, Error> {
+        self.state
+            .mapped
+            .get(ident)
+            .ok_or_else(|| ServerError::ProjectNotFound(ident.into()).into())
+    }
+
+    /// Looks up a project after the state has been refreshed.
+    fn reload(&mut self, file: &Path) -> Result<Project, Error> {
+        self.update();
+        self.state
+            .all
+            .iter()
+            .find(|p| p.file() == file)
+            .cloned()
+            .ok_or_else(|| ServerError::ProjectNotFound(file.display().to_string()).into())
+    }
+
+    /// Creates a new project in the working directory from the template `template_name`.
+    pub fn create_project(
+        &mut self,
+        project_name: &str,
+        template_name: &str,
+        fill_data: &HashMap<&str, String>,
+    ) -> Result<Project, Error> {
+        log::info!("creating {:?} from template {:?}", project_name, template_name);
+        let project = self.storage.create_project(project_name, template_name, fill_data)?;
+        self.reload(&project.file())
+    }
+
+    /// Fills empty template fields like `Project::replace_fields()` does, either all of them or none.
+    pub fn set_fields(&mut self, ident: &str, fields: &LinkedHashMap<String, String>) -> Result<Project, Error> {
+        let file = self.get_mapped(ident)?.file();
+        // what is on disk, the state may be behind the watcher
+        let project = Project::open(&file)?;
+        let empty_fields = project.empty_fields();
+        if let Some(field) = fields.keys().find(|field| !empty_fields.contains(field)) {
+            bail!(ServerError::FieldNotSettable(ident.into(), field.clone()));
+        }
+
+        log::info!("setting {:?} in {:?}", fields.keys().collect::<Vec<_>>(), ident);
+        let fields = fields
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        let written = project.replace_fields(&fields);
+        // even a failed write may have changed the file, the state has to follow it
+        let reloaded = self.reload(&file);
+        written?;
+        reloaded
+    }
+
+    /// Moves a project from the working directory into the archive.
+    ///
+    /// The year defaults to the year of the project.
+    pub fn archive_project(&mut self, ident: &str, year: Option<i32>) -> Result<Project, Error> {
+        let project = self.get_mapped(ident)?.clone();
+        if !project.dir().starts_with(self.storage.working_dir()) {
+            bail!(ServerError::NotInWorkingDir(ident.into()));
+        }
+        let year = year
+            .or_else(|| Storable::year(&project))
+            .ok_or_else(|| ServerError::NoYear(ident.into()))?;
+
+        let moved_files = self.storage.archive_project(&project, year)?;
+        let target_dir = moved_files.last().cloned().expect("archive_project returns its target");
+        let target_file = target_dir.join(project.file_name());
+        self.reload(&target_file)
+    }
+
+    /// Moves an archived project back into the working directory.
+    pub fn unarchive_project(&mut self, ident: &str) -> Result<Project, Error> {
+        let project = self.get_mapped(ident)?.clone();
+        if !project.dir().starts_with(self.storage.archive_dir()) {
+            bail!(ServerError::NotArchived(ident.into()));
+        }
+
+        let target_dir = self.storage.unarchive_project(&project)?;
+        let target_file = target_dir.join(project.file_name());
+        self.reload(&target_file)
     }
 }
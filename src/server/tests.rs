@@ -0,0 +1,203 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use linked_hash_map::LinkedHashMap;
+use serde_json::Value;
+use tempdir::TempDir;
+
+use super::*;
+use crate::project::export::{Complete, ExportTarget};
+
+fn setup() -> (TempDir, ProjectLoader) {
+    let dir = TempDir::new_in(Path::new("."), "server_test").unwrap();
+    let storage = Storage::try_new(dir.path().join("storage"), "working", "archive", "templates").unwrap();
+    storage.create_dirs().unwrap();
+    fs::copy("./templates/default.tyml", storage.templates_dir().join("default.tyml")).unwrap();
+    (dir, ProjectLoader::with_storage(storage))
+}
+
+fn exported(project: &Project) -> Value {
+    let exported: Complete = project.export();
+    serde_json::to_value(exported).unwrap()
+}
+
+fn create(loader: &mut ProjectLoader, name: &str) -> Project {
+    let fill = HashMap::from([("DESCRIPTION", String::from("lots of coffee"))]);
+    loader.create_project(name, "default", &fill).unwrap()
+}
+
+#[test]
+fn create_project() {
+    let (_dir, mut loader) = setup();
+    let project = create(&mut loader, "Party Hard");
+
+    let json = exported(&project);
+    assert_eq!(json["event"]["name"], "Party Hard");
+    assert_eq!(json["invoice"]["number"], Value::Null);
+
+    let ident = mapped_ident(&project);
+    assert!(ident.ends_with("-party-hard"), "{}", ident);
+    assert_eq!(loader.state.working.keys().collect::<Vec<_>>(), vec!["party-hard"]);
+    assert_eq!(loader.state.mapped.keys().collect::<Vec<_>>(), vec![&ident]);
+    assert_eq!(loader.state.years, vec![Storable::year(&project).unwrap()]);
+
+    assert!(loader.create_project("Party Hard", "default", &HashMap::new()).is_err());
+    assert!(loader.create_project("Other Party", "missing", &HashMap::new()).is_err());
+}
+
+#[test]
+fn set_fields() {
+    let (_dir, mut loader) = setup();
+    let ident = mapped_ident(&create(&mut loader, "Party Hard"));
+
+    let mut fields = LinkedHashMap::new();
+    fields.insert("INVOICE-NUMBER".to_owned(), "42".to_owned());
+    fields.insert("INVOICE-DATE".to_owned(), "01.02.2024".to_owned());
+    let project = loader.set_fields(&ident, &fields).unwrap();
+
+    let json = exported(&project);
+    assert_eq!(json["invoice"]["number"], "R042");
+    assert_eq!(json["invoice"]["date"], "01.02.2024");
+    assert_eq!(
+        exported(&loader.state.working["party-hard"])["invoice"]["number"],
+        "R042"
+    );
+    assert_eq!(exported(&loader.state.mapped[&ident])["invoice"]["number"], "R042");
+
+    // filled fields can't be set again
+    let error = loader.set_fields(&ident, &fields).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ServerError>(),
+        Some(ServerError::FieldNotSettable(..))
+    ));
+    assert!(loader.set_fields("1999-nothing", &fields).is_err());
+}
+
+#[test]
+fn set_fields_all_or_nothing() {
+    let (_dir, mut loader) = setup();
+    let project = create(&mut loader, "Party Hard");
+    let ident = mapped_ident(&project);
+    let content = fs::read_to_string(project.file()).unwrap();
+
+    let mut fields = LinkedHashMap::new();
+    fields.insert("INVOICE-NUMBER".to_owned(), "42".to_owned());
+    fields.insert("DESCRIPTION".to_owned(), "not a placeholder anymore".to_owned());
+    let error = loader.set_fields(&ident, &fields).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ServerError>(),
+        Some(ServerError::FieldNotSettable(_, field)) if field == "DESCRIPTION"
+    ));
+
+    assert_eq!(fs::read_to_string(project.file()).unwrap(), content);
+    assert_eq!(exported(&loader.state.mapped[&ident])["invoice"]["number"], Value::Null);
+    assert!(loader.state.mapped[&ident].empty_fields().contains(&"INVOICE-NUMBER".to_owned()));
+}
+
+#[test]
+fn archive_and_unarchive() {
+    let (_dir, mut loader) = setup();
+    let ident = mapped_ident(&create(&mut loader, "Party Hard"));
+
+    let archived = loader.archive_project(&ident, Some(2020)).unwrap();
+    assert!(archived.dir().starts_with(loader.storage.archive_dir().join("2020")));
+    assert_eq!(exported(&archived)["event"]["name"], "Party Hard");
+    assert!(loader.state.working.is_empty());
+    let archived_ident = mapped_ident(&archived);
+    assert_eq!(loader.state.mapped.keys().collect::<Vec<_>>(), vec![&archived_ident]);
+
+    let error = loader.archive_project(&archived_ident, None).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ServerError>(),
+        Some(ServerError::NotInWorkingDir(..))
+    ));
+
+    let unarchived = loader.unarchive_project(&archived_ident).unwrap();
+    assert!(unarchived.dir().starts_with(loader.storage.working_dir()));
+    assert_eq!(exported(&unarchived)["event"]["name"], "Party Hard");
+    assert_eq!(loader.state.working.keys().collect::<Vec<_>>(), vec!["party-hard"]);
+    assert_eq!(loader.state.mapped.keys().collect::<Vec<_>>(), vec![&ident]);
+
+    let error = loader.unarchive_project(&ident).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ServerError>(),
+        Some(ServerError::NotArchived(..))
+    ));
+}
+
+#[test]
+fn refresh_changed_files() {
+    let (_dir, mut loader) = setup();
+    let project = create(&mut loader, "Party Hard");
+    let ident = mapped_ident(&project);
+    let file = project.file();
+
+    // modified
+    let content = fs::read_to_string(&file).unwrap().replace("name: Party Hard", "name: Party Harder");
+    fs::write(&file, content).unwrap();
+    assert_eq!(loader.refresh_file(&file), Some(ProjectChange::Updated(ident.clone())));
+    assert_eq!(exported(&loader.state.mapped[&ident])["event"]["name"], "Party Harder");
+    assert_eq!(exported(&loader.state.working["party-hard"])["event"]["name"], "Party Harder");
+    assert_eq!(loader.state.all.len(), 1);
+
+    // not a project
+    let notes = loader.storage.working_dir().join("party-hard").join("notes.txt");
+    fs::write(&notes, "bring cups").unwrap();
+    assert_eq!(loader.refresh_file(&notes), None);
+    let no_project = loader.storage.working_dir().join("party-hard").join("todo.yml");
+    fs::write(&no_project, "cups: 200\n").unwrap();
+    assert_eq!(loader.refresh_file(&no_project), None);
+    assert_eq!(loader.state.mapped.keys().collect::<Vec<_>>(), vec![&ident]);
+    fs::remove_file(&no_project).unwrap();
+
+    // moved into the archive
+    let archive = loader.storage.archive_dir().join("2020");
+    fs::create_dir(&archive).unwrap();
+    fs::rename(project.dir(), archive.join("party-hard")).unwrap();
+    let archived_file = archive.join("party-hard").join(project.file_name());
+    assert_eq!(loader.refresh_file(&file), Some(ProjectChange::Removed(ident.clone())));
+    let archived_ident = match loader.refresh_file(&archived_file) {
+        Some(ProjectChange::Updated(archived_ident)) => archived_ident,
+        change => panic!("unexpected {:?}", change),
+    };
+    assert!(loader.state.working.is_empty());
+    assert_eq!(loader.state.mapped.keys().collect::<Vec<_>>(), vec![&archived_ident]);
+    assert_eq!(loader.state.mapped[&archived_ident].file(), archived_file);
+    assert_eq!(loader.state.all.len(), 1);
+
+    // deleted, found through its directory
+    fs::remove_dir_all(archive.join("party-hard")).unwrap();
+    assert_eq!(
+        loader.refresh_path(&archive.join("party-hard")),
+        vec![ProjectChange::Removed(archived_ident)]
+    );
+    assert!(loader.state.mapped.is_empty());
+    assert!(loader.state.all.is_empty());
+    assert_eq!(loader.refresh_file(&archived_file), None);
+}
+
+#[test]
+fn reload_client_book() {
+    let (_dir, mut loader) = setup();
+    let project = create(&mut loader, "Party Hard");
+    let ident = mapped_ident(&project);
+    project.link_client("graf-zahl").unwrap();
+    loader.refresh_file(&project.file()).unwrap();
+    assert_eq!(exported(&loader.state.mapped[&ident])["client"]["last_name"], Value::Null);
+
+    let extras = loader.storage.extras_dir().to_owned();
+    fs::create_dir_all(&extras).unwrap();
+    let book = extras.join(client_book::FILE_NAME);
+    fs::write(&book, "graf-zahl:\n  title: Herr\n  last_name: Zahl\n").unwrap();
+    assert_eq!(loader.refresh_path(&book), vec![ProjectChange::Updated(ident.clone())]);
+    assert_eq!(exported(&loader.state.mapped[&ident])["client"]["last_name"], "Zahl");
+    assert_eq!(exported(&loader.state.working["party-hard"])["client"]["last_name"], "Zahl");
+
+    // a broken book is logged and empty, it doesn't keep the old one around
+    fs::write(&book, "graf-zahl:\n  title: Herr\n last_name: [Zahl\n").unwrap();
+    assert_eq!(loader.refresh_path(&book).len(), 1);
+    assert_eq!(exported(&loader.state.mapped[&ident])["client"]["last_name"], Value::Null);
+
+    let unrelated = extras.join("notes.yml");
+    fs::write(&unrelated, "cups: 200\n").unwrap();
+    assert_eq!(loader.refresh_path(&unrelated), vec![]);
+}
@@ -0,0 +1,72 @@
+//! Watches the working and archive directories for changed project files, and `extras` for the client book and catalogue.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use anyhow::Error;
+use notify::{event::EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+
+use crate::{project::Project, storage::Storage};
+
+/// Sent to clients whenever a project file changed on disk.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "change", content = "project", rename_all = "lowercase")]
+pub enum ProjectChange {
+    /// Project was created or modified, carries its mapped identifier
+    Updated(String),
+    /// Project file disappeared, carries its last known mapped identifier
+    Removed(String),
+}
+
+pub fn is_project_file(path: &Path) -> bool {
+    let project_file_extension = crate::CONFIG.get_to_string("extensions.project_file");
+    path.extension().and_then(OsStr::to_str) == Some(project_file_extension.as_str())
+}
+
+/// Project files and directories, archiving or deleting a project only renames its directory.
+///
+/// A path that is gone can't be told apart anymore, so it is passed on as well.
+fn is_relevant(path: &Path) -> bool {
+    is_project_file(path) || path.is_dir() || !path.exists()
+}
+
+/// Sends every project file and directory below the working and archive directory that is created, modified or removed,
+/// as well as the files in `extras`.
+///
+/// Watching stops once the returned watcher is dropped.
+pub fn watch(storage: &Storage<Project>, changed_files: Sender<PathBuf>) -> Result<RecommendedWatcher, Error> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+        Ok(event) => {
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                for path in event.paths.into_iter().filter(|p| is_relevant(p)) {
+                    log::trace!("{:?} changed", path);
+                    if changed_files.send(path).is_err() {
+                        log::debug!("nobody listens for changes anymore");
+                    }
+                }
+            }
+        },
+        Err(error) => log::error!("watching projects failed: {}", error),
+    })?;
+
+    for dir in [storage.working_dir(), storage.archive_dir()] {
+        log::debug!("watching {}", dir.display());
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+    }
+
+    // client book and catalogue, there might be no extras at all
+    let extras = storage.extras_dir();
+    if extras.is_dir() {
+        log::debug!("watching {}", extras.display());
+        watcher.watch(extras, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(watcher)
+}
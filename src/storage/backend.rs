@@ -0,0 +1,231 @@
+//! Where `Storage` actually keeps its directories and files.
+//!
+//! [`FileSystem`] is what you normally use, [`Memory`] keeps everything in a map,
+//! which is handy for tests and wherever there is no file system (e.g. `wasm32`).
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// The file operations `Storage` relies on.
+///
+/// Paths are always absolute and in the layout described in the [module documentation](super).
+pub trait Backend: Send + Sync + fmt::Debug {
+    fn exists(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Basically `ls`, leaves out dot files.
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Fails if `dir` exists already or its parent does not.
+    fn create_dir(&self, dir: &Path) -> io::Result<()>;
+
+    fn read_to_string(&self, file: &Path) -> io::Result<String>;
+
+    /// Creates or overwrites `file`.
+    fn write(&self, file: &Path, content: &str) -> io::Result<()>;
+
+    /// Moves a file or a directory with all its content.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, dir: &Path) -> io::Result<()>;
+}
+
+pub(super) fn is_dot_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(std::ffi::OsStr::to_str)
+        .and_then(|s| s.chars().next())
+        .map(|c| c == '.')
+        .unwrap_or(false)
+}
+
+/// The local file system, this is the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileSystem;
+
+impl Backend for FileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        if !dir.exists() {
+            log::error!("Path does not exist: {}", dir.display());
+        }
+
+        Ok(fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| !is_dot_file(path))
+            .collect())
+    }
+
+    fn create_dir(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir(dir)
+    }
+
+    fn read_to_string(&self, file: &Path) -> io::Result<String> {
+        fs::read_to_string(file)
+    }
+
+    fn write(&self, file: &Path, content: &str) -> io::Result<()> {
+        fs::write(file, content)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_dir_all(&self, dir: &Path) -> io::Result<()> {
+        fs::remove_dir_all(dir)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Dir,
+    File(String),
+}
+
+/// Keeps all directories and files in memory, nothing is ever written to disk.
+#[derive(Debug, Default)]
+pub struct Memory {
+    entries: RwLock<BTreeMap<PathBuf, Entry>>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates `dir` and all of its parents, like `mkdir -p`.
+    pub fn create_dir_all(&self, dir: &Path) {
+        let mut entries = self.entries.write().unwrap();
+        for ancestor in dir.ancestors() {
+            entries.entry(ancestor.to_owned()).or_insert(Entry::Dir);
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<Entry> {
+        self.entries.read().unwrap().get(path).cloned()
+    }
+
+    fn parent_is_dir(&self, path: &Path) -> bool {
+        path.parent().is_some_and(|parent| self.is_dir(parent))
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(ErrorKind::NotFound, format!("{} does not exist", path.display()))
+}
+
+impl Backend for Memory {
+    fn exists(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.get(path), Some(Entry::Dir))
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(dir) {
+            return Err(not_found(dir));
+        }
+        Ok(self
+            .entries
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|path| path.parent() == Some(dir))
+            .filter(|path| !is_dot_file(path))
+            .cloned()
+            .collect())
+    }
+
+    fn create_dir(&self, dir: &Path) -> io::Result<()> {
+        if self.exists(dir) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", dir.display()),
+            ));
+        }
+        if let Some(parent) = dir.parent().filter(|parent| !self.is_dir(parent)) {
+            return Err(not_found(parent));
+        }
+        self.entries.write().unwrap().insert(dir.to_owned(), Entry::Dir);
+        Ok(())
+    }
+
+    fn read_to_string(&self, file: &Path) -> io::Result<String> {
+        match self.get(file) {
+            Some(Entry::File(content)) => Ok(content),
+            Some(Entry::Dir) => Err(io::Error::other(format!("{} is a directory", file.display()))),
+            None => Err(not_found(file)),
+        }
+    }
+
+    fn write(&self, file: &Path, content: &str) -> io::Result<()> {
+        if self.is_dir(file) {
+            return Err(io::Error::other(format!("{} is a directory", file.display())));
+        }
+        if !self.parent_is_dir(file) {
+            return Err(not_found(file.parent().unwrap_or(file)));
+        }
+        self.entries
+            .write()
+            .unwrap()
+            .insert(file.to_owned(), Entry::File(content.into()));
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        if !self.exists(from) {
+            return Err(not_found(from));
+        }
+        if self.exists(to) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", to.display()),
+            ));
+        }
+        if !self.parent_is_dir(to) {
+            return Err(not_found(to.parent().unwrap_or(to)));
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        let moved = entries
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect::<Vec<_>>();
+        for old in moved {
+            let entry = entries.remove(&old).unwrap();
+            let rest = old.strip_prefix(from).unwrap();
+            let new = if rest.as_os_str().is_empty() {
+                to.to_owned()
+            } else {
+                to.join(rest)
+            };
+            entries.insert(new, entry);
+        }
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, dir: &Path) -> io::Result<()> {
+        if !self.is_dir(dir) {
+            return Err(not_found(dir));
+        }
+        self.entries.write().unwrap().retain(|path, _| !path.starts_with(dir));
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+//! A record of everything asciii changed in a storage, so it can be undone.
+//!
+//! The journal lives in the storage root, next to the trash:
+//!
+//! ```bash
+//! # root dir
+//! ├── .journal
+//! │   ├── log          # one change per line, oldest first
+//! │   └── 12.yml       # a project file as it was before change 12
+//! ├── .trash
+//! │   └── 13_Project1  # deleted in change 13
+//! ...
+//! ```
+//!
+//! Both directories get a `.gitignore` so they never end up in the repository.
+//!
+//! Every line of the `log` is `id`, time, kind, paths relative to the root and a description, separated by tabs.
+//! Undoing a change appends an `undone` line instead of removing it, so the `log` only ever grows.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Error};
+use chrono::prelude::*;
+use thiserror::Error;
+
+use super::backend::Backend;
+
+const JOURNAL_DIR: &str = ".journal";
+const TRASH_DIR: &str = ".trash";
+const LOG_FILE: &str = "log";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum JournalError {
+    #[error("Line {} of the journal is broken: {:?}", _0, _1)]
+    BrokenLine(usize, String),
+
+    #[error("There is nothing left to undo.")]
+    NothingToUndo,
+
+    #[error("{:?} was changed since, not undoing {:?}", _0, _1)]
+    ChangedSince(PathBuf, String),
+
+    #[error("{:?} is in the way, not undoing {:?}", _0, _1)]
+    InTheWay(PathBuf, String),
+}
+
+/// Something asciii did to a storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A project directory was created.
+    Created { dir: PathBuf },
+
+    /// A project directory was moved, into the archive or out of it.
+    Moved { from: PathBuf, to: PathBuf },
+
+    /// A project directory was moved into the trash.
+    Deleted { dir: PathBuf, trash: PathBuf },
+
+    /// A project file was written, `before` keeps what it was before, `after` is the hash of what was written.
+    Edited { file: PathBuf, before: PathBuf, after: u64 },
+}
+
+impl Change {
+    /// Every path the change touched.
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            Change::Created { dir } => vec![dir],
+            Change::Moved { from, to } => vec![from, to],
+            Change::Deleted { dir, .. } => vec![dir],
+            Change::Edited { file, .. } => vec![file],
+        }
+    }
+}
+
+/// One line of the journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub id: usize,
+    pub time: DateTime<FixedOffset>,
+    pub change: Change,
+    pub description: String,
+    pub undone: bool,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.description)
+    }
+}
+
+/// FNV-1a, unlike `DefaultHasher` it stays the same between versions of Rust.
+fn hash_of(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Tabs and line breaks would break the line.
+fn one_line(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Whether `path` is inside the journal or the trash, of any storage.
+pub fn is_internal(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == JOURNAL_DIR || component.as_os_str() == TRASH_DIR)
+}
+
+/// The journal of the storage in `root`, all access goes through `backend`.
+#[derive(Debug)]
+pub struct Journal<'a, B: Backend> {
+    root: PathBuf,
+    backend: &'a B,
+}
+
+impl<'a, B: Backend> Journal<'a, B> {
+    pub fn new(root: &Path, backend: &'a B) -> Self {
+        Journal {
+            root: root.to_owned(),
+            backend,
+        }
+    }
+
+    pub fn journal_dir(&self) -> PathBuf {
+        self.root.join(JOURNAL_DIR)
+    }
+
+    pub fn trash_dir(&self) -> PathBuf {
+        self.root.join(TRASH_DIR)
+    }
+
+    fn log_file(&self) -> PathBuf {
+        self.journal_dir().join(LOG_FILE)
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Creates `dir` if necessary, git ignores everything inside it.
+    fn ensure_dir(&self, dir: &Path) -> Result<(), Error> {
+        if !self.backend.is_dir(dir) {
+            self.backend.create_dir(dir)?;
+        }
+        let gitignore = dir.join(".gitignore");
+        if !self.backend.exists(&gitignore) {
+            self.backend.write(&gitignore, "*\n")?;
+        }
+        Ok(())
+    }
+
+    fn read_log(&self) -> Result<String, Error> {
+        if self.backend.exists(&self.log_file()) {
+            Ok(self.backend.read_to_string(&self.log_file())?)
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    fn append(&self, line: &str) -> Result<(), Error> {
+        self.ensure_dir(&self.journal_dir())?;
+        let mut log = self.read_log()?;
+        log += line;
+        log.push('\n');
+        Ok(self.backend.write(&self.log_file(), &log)?)
+    }
+
+    fn next_id(&self) -> Result<usize, Error> {
+        Ok(self.entries()?.last().map_or(1, |entry| entry.id + 1))
+    }
+
+    /// Every change ever recorded, oldest first.
+    pub fn entries(&self) -> Result<Vec<Entry>, Error> {
+        let log = self.read_log()?;
+        let mut entries: Vec<Entry> = Vec::new();
+        for (number, line) in log.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let broken = || JournalError::BrokenLine(number + 1, line.to_owned());
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let (id, time, kind) = match fields[..] {
+                [id, time, kind, ..] => (
+                    id.parse::<usize>().map_err(|_| broken())?,
+                    DateTime::parse_from_rfc3339(time).map_err(|_| broken())?,
+                    kind,
+                ),
+                _ => bail!(broken()),
+            };
+            let path = |index: usize| -> Result<PathBuf, JournalError> {
+                fields.get(index).map(|path| self.root.join(path)).ok_or_else(broken)
+            };
+            let change = match kind {
+                "undone" => {
+                    if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                        entry.undone = true;
+                    }
+                    continue;
+                },
+                "created" => Change::Created { dir: path(3)? },
+                "moved" => Change::Moved {
+                    from: path(3)?,
+                    to: path(4)?,
+                },
+                "deleted" => Change::Deleted {
+                    dir: path(3)?,
+                    trash: path(4)?,
+                },
+                "edited" => Change::Edited {
+                    file: path(3)?,
+                    before: path(4)?,
+                    after: fields
+                        .get(5)
+                        .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+                        .ok_or_else(broken)?,
+                },
+                _ => bail!(broken()),
+            };
+            entries.push(Entry {
+                id,
+                time,
+                change,
+                description: fields.last().map(ToString::to_string).unwrap_or_default(),
+                undone: false,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn write_entry(&self, id: usize, change: Change, description: &str) -> Result<Entry, Error> {
+        let time = Local::now().fixed_offset();
+        let paths = match change {
+            Change::Created { ref dir } => format!("created\t{}", self.relative(dir).display()),
+            Change::Moved { ref from, ref to } => format!(
+                "moved\t{}\t{}",
+                self.relative(from).display(),
+                self.relative(to).display()
+            ),
+            Change::Deleted { ref dir, ref trash } => format!(
+                "deleted\t{}\t{}",
+                self.relative(dir).display(),
+                self.relative(trash).display()
+            ),
+            Change::Edited {
+                ref file,
+                ref before,
+                after,
+            } => format!(
+                "edited\t{}\t{}\t{:x}",
+                self.relative(file).display(),
+                self.relative(before).display(),
+                after
+            ),
+        };
+        let description = one_line(description);
+        self.append(&format!("{}\t{}\t{}\t{}", id, time.to_rfc3339(), paths, description))?;
+        Ok(Entry {
+            id,
+            time,
+            change,
+            description,
+            undone: false,
+        })
+    }
+
+    /// Records a change that already happened.
+    pub fn record(&self, change: Change, description: &str) -> Result<Entry, Error> {
+        self.write_entry(self.next_id()?, change, description)
+    }
+
+    /// Records that `file` was overwritten, keeping a copy of what it was `before`.
+    pub fn record_edit(&self, file: &Path, before: &str, after: &str, description: &str) -> Result<Entry, Error> {
+        let id = self.next_id()?;
+        self.ensure_dir(&self.journal_dir())?;
+        let copy = self.journal_dir().join(format!("{}.yml", id));
+        self.backend.write(&copy, before)?;
+        let change = Change::Edited {
+            file: file.to_owned(),
+            before: copy,
+            after: hash_of(after),
+        };
+        self.write_entry(id, change, description)
+    }
+
+    /// Moves `dir` into the trash instead of deleting it.
+    pub fn trash(&self, dir: &Path, description: &str) -> Result<Entry, Error> {
+        let id = self.next_id()?;
+        self.ensure_dir(&self.trash_dir())?;
+        let name = dir.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let trash = self.trash_dir().join(format!("{}_{}", id, name));
+        self.backend.rename(dir, &trash)?;
+        let change = Change::Deleted {
+            dir: dir.to_owned(),
+            trash,
+        };
+        self.write_entry(id, change, description)
+    }
+
+    /// Reverses the last `count` changes that were not undone yet, newest first.
+    ///
+    /// Stops at the first change that can't be undone, those before it stay undone.
+    pub fn undo(&self, count: usize) -> Result<Vec<Entry>, Error> {
+        let pending = self
+            .entries()?
+            .into_iter()
+            .rev()
+            .filter(|entry| !entry.undone)
+            .take(count)
+            .collect::<Vec<_>>();
+        ensure!(!pending.is_empty(), JournalError::NothingToUndo);
+
+        let mut undone = Vec::new();
+        for mut entry in pending {
+            self.revert(&entry)?;
+            self.append(&format!(
+                "{}\t{}\tundone",
+                entry.id,
+                Local::now().fixed_offset().to_rfc3339()
+            ))?;
+            entry.undone = true;
+            undone.push(entry);
+        }
+        Ok(undone)
+    }
+
+    fn revert(&self, entry: &Entry) -> Result<(), Error> {
+        log::debug!("undoing {:?}", entry);
+        let in_the_way = |path: &Path| JournalError::InTheWay(path.to_owned(), entry.description.clone());
+        match entry.change {
+            Change::Created { ref dir } => {
+                self.ensure_dir(&self.trash_dir())?;
+                let name = dir.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+                let trash = self.trash_dir().join(format!("{}_{}", entry.id, name));
+                ensure!(!self.backend.exists(&trash), in_the_way(&trash));
+                self.backend.rename(dir, &trash)?;
+            },
+            Change::Moved { ref from, ref to } => {
+                ensure!(!self.backend.exists(from), in_the_way(from));
+                self.backend.rename(to, from)?;
+            },
+            Change::Deleted { ref dir, ref trash } => {
+                ensure!(!self.backend.exists(dir), in_the_way(dir));
+                self.backend.rename(trash, dir)?;
+            },
+            Change::Edited {
+                ref file,
+                ref before,
+                after,
+            } => {
+                let current = self.backend.read_to_string(file)?;
+                ensure!(
+                    hash_of(&current) == after,
+                    JournalError::ChangedSince(file.to_owned(), entry.description.clone())
+                );
+                let before = self.backend.read_to_string(before)?;
+                self.backend.write(file, &before)?;
+            },
+        }
+        Ok(())
+    }
+}
@@ -18,6 +18,9 @@
 //! ...
 //! ```
 //!
+//! Where these directories actually live is up to the [`Backend`](backend::Backend),
+//! usually that is the [`FileSystem`](backend::FileSystem).
+//!
 
 #[cfg(target_arch = "wasm32")]
 use crate::util::dirs::home_dir;
@@ -26,6 +29,8 @@ use dirs::home_dir;
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
+use self::backend::{Backend, FileSystem};
+use self::journal::{Change, Entry, Journal};
 use self::repo::Repository;
 use linked_hash_map::LinkedHashMap;
 
@@ -35,7 +40,7 @@ use std::{
     collections::HashMap,
     env::{self, current_dir},
     ffi::OsStr,
-    fmt, fs,
+    fmt,
     marker::PhantomData,
     ops::DerefMut,
     path::{Path, PathBuf},
@@ -49,9 +54,11 @@ mod realworld;
 #[cfg(test)]
 mod tests;
 
+pub mod backend;
 mod project_list;
 pub use self::project_list::{ProjectList, Projects, ProjectsByYear};
 pub mod error;
+pub mod journal;
 pub mod repo;
 pub use self::error::StorageError;
 pub mod storable;
@@ -67,7 +74,9 @@ pub use self::storable::*;
 /// * listing templates
 /// * archiving and unarchiving projects
 /// * git interaction
-pub struct Storage<L: Storable> {
+///
+/// All file access goes through the `Backend` `B`.
+pub struct Storage<L: Storable, B: Backend = FileSystem> {
     /// Root of the entire Structure.
     root: PathBuf,
 
@@ -86,6 +95,8 @@ pub struct Storage<L: Storable> {
     project_type: PhantomData<L>,
 
     repository: Option<Repository>,
+
+    backend: B,
 }
 
 /// Used to identify what directory you are talking about.
@@ -139,14 +150,6 @@ impl Default for StorageSelection {
     }
 }
 
-fn is_dot_file(path: &Path) -> bool {
-    path.file_name()
-        .and_then(std::ffi::OsStr::to_str)
-        .and_then(|s| s.chars().next())
-        .map(|c| c == '.')
-        .unwrap_or(false)
-}
-
 #[cfg_attr(feature = "serialization", derive(Serialize))]
 #[derive(Debug)]
 pub struct Paths {
@@ -158,15 +161,7 @@ pub struct Paths {
 
 /// Basically `ls`, returns a list of paths.
 pub fn list_path_content(path: &Path) -> Result<Vec<PathBuf>, Error> {
-    if !path.exists() {
-        log::error!("Path does not exist: {}", path.display());
-    }
-
-    Ok(fs::read_dir(path)?
-        .filter_map(Result::ok)
-        .filter(|entry| !is_dot_file(&entry.path()))
-        .map(|entry| entry.path())
-        .collect::<Vec<PathBuf>>())
+    Ok(FileSystem.list(path)?)
 }
 
 fn replace_home_tilde(p: &Path) -> PathBuf {
@@ -237,6 +232,33 @@ fn slugify(string: &str) -> String {
 impl<L: Storable> Storage<L> {
     /// Inits storage, does not check existence, yet. TODO
     pub fn try_new<P: AsRef<Path>>(root: P, working: &str, archive: &str, template: &str) -> Result<Self, Error> {
+        Self::try_new_with_backend(root, working, archive, template, FileSystem)
+    }
+
+    /// Inits storage with git capabilities.
+    pub fn try_new_with_git<P: AsRef<Path>>(
+        root: P,
+        working: &str,
+        archive: &str,
+        template: &str,
+    ) -> Result<Self, Error> {
+        log::trace!("initializing storage, with git");
+        Ok(Storage {
+            repository: Some(Repository::try_new(root.as_ref())?),
+            ..Self::try_new(root, working, archive, template)?
+        })
+    }
+}
+
+impl<L: Storable, B: Backend> Storage<L, B> {
+    /// Inits storage on top of any `Backend`, does not check existence either.
+    pub fn try_new_with_backend<P: AsRef<Path>>(
+        root: P,
+        working: &str,
+        archive: &str,
+        template: &str,
+        backend: B,
+    ) -> Result<Self, Error> {
         log::trace!("initializing storage, root: {}", root.as_ref().display());
         let root = root.as_ref();
         if root.is_absolute() {
@@ -248,24 +270,47 @@ impl<L: Storable> Storage<L> {
                 extras: root.join("extras"),
                 project_type: PhantomData,
                 repository: None,
+                backend,
             })
         } else {
             bail!(StorageError::StoragePathNotAbsolute)
         }
     }
 
-    /// Inits storage with git capabilities.
-    pub fn try_new_with_git<P: AsRef<Path>>(
-        root: P,
-        working: &str,
-        archive: &str,
-        template: &str,
-    ) -> Result<Self, Error> {
-        log::trace!("initializing storage, with git");
-        Ok(Storage {
-            repository: Some(Repository::try_new(root.as_ref())?),
-            ..Self::try_new(root, working, archive, template)?
-        })
+    /// Getter for Storage::backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The record of every change to this storage, see [`journal`].
+    pub fn journal(&self) -> Journal<'_, B> {
+        Journal::new(self.root_dir(), &self.backend)
+    }
+
+    /// Records a change that already happened, the change stands even if that fails.
+    fn record(&self, change: Change, description: &str) {
+        if let Err(error) = self.journal().record(change, description) {
+            log::warn!("could not record {:?} in the journal: {}", description, error);
+        }
+    }
+
+    /// Changes to this storage, oldest first.
+    pub fn history(&self) -> Result<Vec<Entry>, Error> {
+        self.journal().entries()
+    }
+
+    /// Reverses the last `count` changes, see [`Journal::undo`].
+    pub fn undo(&self, count: usize) -> Result<Vec<Entry>, Error> {
+        let undone = self.journal().undo(count)?;
+        if let Some(repo) = self.repository() {
+            let paths = undone
+                .iter()
+                .flat_map(|entry| entry.change.paths())
+                .map(Path::to_path_buf)
+                .collect::<Vec<_>>();
+            repo.add(&paths)?;
+        }
+        Ok(undone)
     }
 
     /// Checks whether the folder structure is as it's supposed to be.
@@ -275,11 +320,12 @@ impl<L: Storable> Storage<L> {
         let a = self.archive_dir();
         let t = self.templates_dir();
 
-        if r.exists() && w.exists() && a.exists() && t.exists() {
+        let exists = |dir: &Path| self.backend.exists(dir);
+        if exists(r) && exists(w) && exists(a) && exists(t) {
             Ok(())
         } else {
             for f in &[r, w, a, t] {
-                if !f.exists() {
+                if !exists(f) {
                     log::warn!("{} does not exist", f.display())
                 }
             }
@@ -349,17 +395,15 @@ impl<L: Storable> Storage<L> {
         log::trace!("creating storage directories");
         ensure!(self.root_dir().is_absolute(), StorageError::StoragePathNotAbsolute);
 
-        if !self.root_dir().exists() {
-            fs::create_dir(self.root_dir())?;
-        }
-        if !self.working_dir().exists() {
-            fs::create_dir(self.working_dir())?;
-        }
-        if !self.archive_dir().exists() {
-            fs::create_dir(self.archive_dir())?;
-        }
-        if !self.templates_dir().exists() {
-            fs::create_dir(self.templates_dir())?;
+        for dir in &[
+            self.root_dir(),
+            self.working_dir(),
+            self.archive_dir(),
+            self.templates_dir(),
+        ] {
+            if !self.backend.exists(dir) {
+                self.backend.create_dir(dir)?;
+            }
         }
 
         Ok(())
@@ -375,11 +419,11 @@ impl<L: Storable> Storage<L> {
     ///</pre>
     pub fn create_archive(&self, year: Year) -> Result<PathBuf, Error> {
         log::trace!("creating archive directory: {}", year);
-        assert!(self.archive_dir().exists());
+        assert!(self.backend.exists(self.archive_dir()));
         let archive = &self.archive_dir().join(year.to_string());
 
-        if self.archive_dir().exists() && !archive.exists() {
-            fs::create_dir(archive)?;
+        if !self.backend.exists(archive) {
+            self.backend.create_dir(archive)?;
         }
         Ok(archive.to_owned())
     }
@@ -387,7 +431,26 @@ impl<L: Storable> Storage<L> {
     /// Produces a list of files in the `extras_dir()`
     pub fn list_extra_files(&self) -> Result<Vec<PathBuf>, Error> {
         log::trace!("listing extra files");
-        list_path_content(self.extras_dir())
+        Ok(self.backend.list(self.extras_dir())?)
+    }
+
+    /// Writes `content` to `name` in `extras`, what it was before goes into the journal.
+    pub fn write_extra_file(&self, name: &str, content: &str, what: &str) -> Result<PathBuf, Error> {
+        if !self.backend.exists(self.extras_dir()) {
+            self.backend.create_dir(self.extras_dir())?;
+        }
+        let path = self.extras_dir().join(name);
+        let before = if self.backend.exists(&path) {
+            self.backend.read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        self.backend.write(&path, content)?;
+        let description = format!("{}: {}", name, what);
+        if let Err(error) = self.journal().record_edit(&path, &before, content, &description) {
+            log::warn!("could not record {:?} in the journal: {}", description, error);
+        }
+        Ok(path)
     }
 
     /// Returns the Path to the extra file by the given name, maybe.
@@ -403,7 +466,9 @@ impl<L: Storable> Storage<L> {
         // TODO: this is the only reference to `CONFIG`, lets get rid of it
         let template_file_extension = crate::CONFIG.get_str("extensions/project_template");
         log::trace!("listing template files (.{})", template_file_extension);
-        let template_files = list_path_content(self.templates_dir())?
+        let template_files = self
+            .backend
+            .list(self.templates_dir())?
             .into_iter()
             .filter(|p| p.extension().unwrap_or_else(|| OsStr::new("")) == OsStr::new(template_file_extension))
             .collect::<Vec<PathBuf>>();
@@ -439,7 +504,7 @@ impl<L: Storable> Storage<L> {
     /// an invoice number etc.
     pub fn list_archives(&self) -> Result<Vec<PathBuf>, Error> {
         log::trace!("listing archives files");
-        list_path_content(self.archive_dir())
+        Ok(self.backend.list(self.archive_dir())?)
     }
 
     /// Produces a list of years for which there is an archive.
@@ -469,13 +534,13 @@ impl<L: Storable> Storage<L> {
             name = project_name,
             tmpl = template_name
         );
-        if !self.working_dir().exists() {
+        if !self.backend.exists(self.working_dir()) {
             log::error!("working directory does not exist");
             bail!(StorageError::NoWorkingDir)
         };
         let slugged_name = slugify(project_name);
         let project_dir = self.working_dir().join(&slugged_name);
-        if project_dir.exists() {
+        if self.backend.exists(&project_dir) {
             log::error!("project directory already exists");
             bail!(StorageError::ProjectDirExists);
         }
@@ -485,18 +550,21 @@ impl<L: Storable> Storage<L> {
         let target_file = project_dir.join(slugged_name + "." + &L::file_extension());
 
         let template_path = self.get_template_file(template_name)?;
+        let template = self.backend.read_to_string(&template_path)?;
 
         log::trace!("creating project using concrete Project implementation of from_template");
-        let mut project = L::from_template(project_name, &template_path, fill_data)?;
-
-        // TODO: Hand of creation entirely to Storable implementation
-        //      Storage it self should only concern itself with Project folders!
-        fs::create_dir(&project_dir)?;
-        fs::copy(project.file(), &target_file)?;
-        log::trace!("copied project file successfully");
-        project.set_file(&target_file);
+        let content = L::from_template(project_name, template_name, &template, fill_data)?;
+
+        self.backend.create_dir(&project_dir)?;
+        self.backend.write(&target_file, &content)?;
+        log::trace!("wrote project file successfully");
+        let project = L::from_content(&target_file, &content)?;
+        self.record(
+            Change::Created { dir: project_dir },
+            &format!("created {}", project_name),
+        );
 
-        Ok(project.storable)
+        Ok(project)
     }
 
     /// Moves a project folder from `/working` dir to `/archive/$year`.
@@ -518,7 +586,7 @@ impl<L: Storable> Storage<L> {
         let target = archive.join(name_in_archive);
         log::trace!(" moving file into {:?}", target);
 
-        fs::rename(project_folder, &target)?;
+        self.backend.rename(&project_folder, &target)?;
 
         Ok(target)
     }
@@ -548,14 +616,21 @@ impl<L: Storable> Storage<L> {
         let project_folder = project.dir();
         let target = archive.join(name_in_archive);
 
-        fs::rename(project_folder, &target)?;
+        self.backend.rename(&project_folder, &target)?;
         log::info!("successfully archived {:?} to {:?}", project.short_desc(), target);
+        self.record(
+            Change::Moved {
+                from: project_folder,
+                to: target.clone(),
+            },
+            &format!("archived {} into {}", project.short_desc(), year),
+        );
 
         moved_files.push(project.dir());
         moved_files.push(target);
 
         if let Some(repo) = self.repository() {
-            repo.add(&moved_files);
+            repo.add(&moved_files)?;
         }
 
         Ok(moved_files)
@@ -597,7 +672,7 @@ impl<L: Storable> Storage<L> {
         }
 
         if let Some(repo) = self.repository() {
-            repo.add(&moved_files);
+            repo.add(&moved_files)?;
         }
 
         Ok(moved_files)
@@ -608,12 +683,14 @@ impl<L: Storable> Storage<L> {
         F: Fn() -> bool,
     {
         log::debug!("deleting {}", project.dir().display());
-        project.delete_project_dir_if(confirmed)?;
+        if confirmed() {
+            log::debug!("moving {} to the trash", project.dir().display());
+            self.journal()
+                .trash(&project.dir(), &format!("deleted {}", project.short_desc()))?;
+        }
         if let Some(ref repo) = self.repository {
-            if !repo.add(&[project.dir()]).success() {
-                log::debug!("adding {} to git", project.dir().display());
-                bail!(StorageError::GitProcessFailed);
-            }
+            log::debug!("adding {} to git", project.dir().display());
+            repo.add(&[project.dir()])?;
         }
         Ok(())
     }
@@ -633,7 +710,7 @@ impl<L: Storable> Storage<L> {
         }
 
         if let Some(repo) = self.repository() {
-            repo.add(&moved_files);
+            repo.add(&moved_files)?;
         }
 
         Ok(moved_files)
@@ -662,12 +739,19 @@ impl<L: Storable> Storage<L> {
             .map_or(false, |s| s.parse::<i32>().is_ok());
 
         let name = self.get_project_name(archived_dir)?;
-        let target = self.working_dir().join(name);
-        ensure!(!target.exists(), StorageError::ProjectFileExists);
+        let target = self.working_dir().join(&name);
+        ensure!(!self.backend.exists(&target), StorageError::ProjectFileExists);
         log::info!("unarchiving project from {:?} to {:?}", archived_dir, target);
 
         if child_of_archive && !archive_itself && parent_is_num {
-            fs::rename(archived_dir, &target)?;
+            self.backend.rename(archived_dir, &target)?;
+            self.record(
+                Change::Moved {
+                    from: archived_dir.to_owned(),
+                    to: target.clone(),
+                },
+                &format!("unarchived {}", name),
+            );
         } else {
             log::error!("moving out of archive failed");
             bail!(StorageError::InvalidDirStructure);
@@ -732,7 +816,7 @@ impl<L: Storable> Storage<L> {
             StorageDir::Archive(year) => self.get_project_dir_from_archive(name, year),
             _ => bail!(StorageError::BadChoice),
         } {
-            if path.exists() {
+            if self.backend.exists(&path) {
                 return Ok(path);
             }
         }
@@ -744,11 +828,7 @@ impl<L: Storable> Storage<L> {
     /// This is the first file with the `super::PROJECT_FILE_EXTENSION` in the folder
     pub fn get_project_file(&self, directory: &Path) -> Result<PathBuf, Error> {
         log::trace!("getting project file from {:?}", directory);
-        list_path_content(directory)?
-            .iter()
-            .find(|f| f.extension().unwrap_or_else(|| OsStr::new("")) == L::file_extension().as_str())
-            .map(ToOwned::to_owned)
-            .ok_or_else(|| StorageError::ProjectDoesNotExist.into())
+        Self::find_project_file(&self.backend, directory)
     }
 
     fn get_project_name(&self, directory: &Path) -> Result<String, Error> {
@@ -775,18 +855,18 @@ impl<L: Storable> Storage<L> {
     pub fn list_project_folders(&self, directory: StorageDir) -> Result<Vec<PathBuf>, Error> {
         log::trace!("listing project folders in {:?}-directory", directory);
         match directory {
-            StorageDir::Working => list_path_content(self.working_dir()),
+            StorageDir::Working => Ok(self.backend.list(self.working_dir())?),
             StorageDir::Archive(year) => {
                 let path = self.archive_dir().join(year.to_string());
-                let list = list_path_content(&path).unwrap_or_else(|_| Vec::new());
+                let list = self.backend.list(&path).unwrap_or_else(|_| Vec::new());
                 Ok(list)
             },
             StorageDir::All => {
                 let mut all: Vec<PathBuf> = Vec::new();
                 for year in self.list_years()? {
-                    all.append(&mut list_path_content(&self.archive_dir().join(year.to_string()))?);
+                    all.append(&mut self.backend.list(&self.archive_dir().join(year.to_string()))?);
                 }
-                all.append(&mut list_path_content(self.working_dir())?);
+                all.append(&mut self.backend.list(self.working_dir())?);
                 Ok(all)
             },
             _ => bail!(StorageError::BadChoice),
@@ -854,9 +934,10 @@ impl<L: Storable> Storage<L> {
     #[cfg(feature = "rayon")]
     fn open_paths(&self, paths: &[PathBuf]) -> ProjectList<L> {
         log::trace!("open_paths({:?})", paths);
+        let backend = &self.backend;
         let mut projects = paths
             .par_iter()
-            .filter_map(|path| Self::open_project(path).ok())
+            .filter_map(|path| Self::open_project(backend, path).ok())
             .collect::<Vec<L>>();
 
         if cfg!(feature = "git_statuses") {
@@ -880,7 +961,7 @@ impl<L: Storable> Storage<L> {
         log::trace!("open_paths({:?})", paths);
         let mut projects = paths
             .iter()
-            .filter_map(|path| Self::open_project(path).ok())
+            .filter_map(|path| Self::open_project(&self.backend, path).ok())
             .collect::<Vec<L>>();
 
         if cfg!(feature = "git_statuses") {
@@ -937,13 +1018,25 @@ impl<L: Storable> Storage<L> {
         })
     }
 
-    fn open_project(path: &Path) -> Result<L, Error> {
-        let meta = path.metadata().unwrap();
-        let project = if meta.is_dir() {
-            L::open_folder(path)
+    fn find_project_file(backend: &B, directory: &Path) -> Result<PathBuf, Error> {
+        backend
+            .list(directory)?
+            .iter()
+            .find(|f| f.extension().unwrap_or_else(|| OsStr::new("")) == L::file_extension().as_str())
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| StorageError::ProjectDoesNotExist.into())
+    }
+
+    fn open_project(backend: &B, path: &Path) -> Result<L, Error> {
+        let project = if backend.is_dir(path) {
+            Self::find_project_file(backend, path).map_err(|_| StorageError::NoProjectFile(path.to_owned()).into())
         } else {
-            L::open_file(path)
-        };
+            Ok(path.to_owned())
+        }
+        .and_then(|file| {
+            let content = backend.read_to_string(&file)?;
+            L::from_content(&file, &content)
+        });
         if let Err(ref err) = project {
             log::warn!("{}", err);
         }
@@ -951,7 +1044,7 @@ impl<L: Storable> Storage<L> {
     }
 }
 
-impl<P: Storable> fmt::Debug for Storage<P> {
+impl<P: Storable, B: Backend> fmt::Debug for Storage<P, B> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let storage = self.root_dir();
         let working = self.working_dir();
@@ -44,6 +44,15 @@ impl<L: Storable> ProjectList<L> {
     }
 }
 
+impl<P: Storable> Projects<P> {
+    /// Working directory first, then the archive year by year
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.working
+            .iter()
+            .chain(self.archive.values().flat_map(|list| list.iter()))
+    }
+}
+
 impl<L: Storable> IntoIterator for ProjectList<L> {
     type Item = L;
     type IntoIter = ::std::vec::IntoIter<L>;
@@ -1,10 +1,18 @@
+//! Git integration of the storage.
+//!
+//! With the `git_statuses` feature everything goes through `git2`, no `git` binary is needed.
+//! Without it only `add` and `commit_paths` are available, they shell out to `git`.
+
 #![allow(dead_code, unused_variables)]
-#[cfg(feature = "git_statuses")]
-use std::collections::HashMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
-use std::process::{Command, ExitStatus};
+#[cfg(not(feature = "git_statuses"))]
+use std::process::Command;
+#[cfg(feature = "git_statuses")]
+use std::{cell::RefCell, collections::HashMap, fs};
 
+#[cfg(feature = "git_statuses")]
+use chrono::prelude::*;
 #[cfg(not(feature = "git_statuses"))]
 use std::error::Error;
 
@@ -12,7 +20,8 @@ use prettytable::color::Color;
 use prettytable::{color, Attr};
 
 /// More Rustacious way of representing a git status
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
 pub enum GitStatus {
     IndexNew,
     IndexModified,
@@ -31,6 +40,18 @@ pub enum GitStatus {
 }
 
 impl GitStatus {
+    /// Whether the change is in the index, ready to be committed.
+    pub fn is_staged(&self) -> bool {
+        matches!(
+            self,
+            GitStatus::IndexNew
+                | GitStatus::IndexModified
+                | GitStatus::IndexDeleted
+                | GitStatus::IndexRenamed
+                | GitStatus::IndexTypechange
+        )
+    }
+
     pub fn to_format(&self) -> Attr {
         //Bold,
         //Dim,
@@ -104,6 +125,126 @@ impl From<git2::Status> for GitStatus{
     }
 }
 
+/// The remote `pull` and `push` talk to.
+pub const REMOTE: &str = "origin";
+
+#[derive(thiserror::Error, Debug)]
+pub enum RepoError {
+    #[cfg(feature = "git_statuses")]
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+
+    #[error("Calling `git {}` failed.", _0)]
+    ProcessFailed(String),
+
+    #[error("There is nothing to commit.")]
+    NothingToCommit,
+
+    #[error("There are no local changes to stash.")]
+    NothingToStash,
+
+    #[error("No branch is checked out.")]
+    NoBranch,
+
+    #[error("There is no remote called {:?}.", _0)]
+    NoRemote(String),
+
+    #[error("{:?} is not inside of the repository.", _0)]
+    OutsideOfRepository(PathBuf),
+
+    #[error("Pulling {} ran into conflicts in {:?}, resolve them and commit.", _0, _1)]
+    Conflicts(String, Vec<PathBuf>),
+
+    #[error("Rebasing onto {} ran into conflicts in {:?}, nothing was changed.", _0, _1)]
+    RebaseConflicts(String, Vec<PathBuf>),
+
+    #[error("{} was rejected by the remote: {}", _0, _1)]
+    Rejected(String, String),
+}
+
+/// Branch, upstream and changes of the working tree, like `git status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct Status {
+    /// `None` while no branch is checked out
+    pub branch: Option<String>,
+    /// e.g. `origin/master`, `None` if there is nothing to compare to
+    pub upstream: Option<String>,
+    /// commits on `branch` that are not on `upstream`
+    pub ahead: usize,
+    /// commits on `upstream` that are not on `branch`
+    pub behind: usize,
+    /// paths relative to the storage root
+    pub changes: Vec<(PathBuf, GitStatus)>,
+}
+
+impl Status {
+    /// Changed paths that are in the index.
+    pub fn staged(&self) -> Vec<PathBuf> {
+        self.changes
+            .iter()
+            .filter(|(_, status)| status.is_staged())
+            .map(|(path, _)| path.to_owned())
+            .collect()
+    }
+}
+
+/// One commit, like a line of `git log --oneline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct Commit {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    #[cfg(feature = "git_statuses")]
+    pub time: DateTime<FixedOffset>,
+}
+
+impl Commit {
+    /// The first seven characters of the id.
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+}
+
+/// What `pull` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialization",
+    derive(Serialize),
+    serde(tag = "result", content = "commits", rename_all = "kebab-case")
+)]
+pub enum Pulled {
+    UpToDate,
+    /// the branch only moved forward, by this many commits
+    FastForward(usize),
+    /// the changes of both sides were joined in a merge commit
+    Merged,
+    /// this many local commits were put on top of the remote ones
+    Rebased(usize),
+}
+
+/// A commit message for changes of `paths`, naming the projects they belong to.
+///
+/// `paths` are files, relative to the storage root or absolute, e.g. `working/Kaffee/Kaffee.yml` is named `Kaffee`.
+pub fn message_for(paths: &[PathBuf]) -> String {
+    let mut names: Vec<String> = Vec::new();
+    for path in paths {
+        let dir = path.parent().filter(|dir| dir.file_name().is_some()).unwrap_or(path);
+        if let Some(name) = dir.file_name().map(|name| name.to_string_lossy().into_owned()) {
+            if !name.starts_with('.') && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    match names.len() {
+        0 => format!("Update {} files", paths.len()),
+        1..=5 => format!("Update {}", names.join(", ")),
+        more => format!("Update {} and {} more", names[..4].join(", "), more - 4),
+    }
+}
+
 /// Convenience Wrapper for `git2::Repository`
 #[cfg(feature = "git_statuses")]
 pub struct Repository {
@@ -121,8 +262,42 @@ pub struct Repository {
     pub workdir: PathBuf,
 }
 
+#[cfg(feature = "git_statuses")]
+fn path_of(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(feature = "git_statuses")]
+fn time_of(time: git2::Time) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(time.offset_minutes() * 60)
+        .and_then(|offset| offset.timestamp_opt(time.seconds(), 0).single())
+        .unwrap_or_default()
+}
+
+/// Credentials from the ssh agent or the configured credential helper.
+#[cfg(feature = "git_statuses")]
+fn callbacks<'a>(config: git2::Config) -> git2::RemoteCallbacks<'a> {
+    let mut attempts = 0;
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        // libgit2 keeps asking as long as we answer
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+        if allowed.contains(git2::CredentialType::SSH_KEY) {
+            git2::Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            git2::Cred::credential_helper(&config, url, username)
+        } else {
+            git2::Cred::default()
+        }
+    });
+    callbacks
+}
+
+#[cfg(feature = "git_statuses")]
 impl Repository {
-    #[cfg(feature = "git_statuses")]
     pub fn try_new(path: &Path) -> Result<Self, git2::Error> {
         let repo = git2::Repository::open(path)?;
         let statuses = Self::cache_statuses(&repo)?;
@@ -133,14 +308,6 @@ impl Repository {
         })
     }
 
-    #[cfg(not(feature = "git_statuses"))]
-    pub fn try_new(path: &Path) -> Result<Self, GitError> {
-        Ok(Repository {
-            workdir: path.to_owned(),
-        })
-    }
-
-    #[cfg(feature = "git_statuses")]
     fn cache_statuses(repo: &git2::Repository) -> Result<HashMap<PathBuf, GitStatus>, git2::Error> {
         let repo_path = repo.path().parent().unwrap().to_owned();
 
@@ -170,97 +337,528 @@ impl Repository {
     }
 
     /// Returns the status to a given path
-    #[cfg(feature = "git_statuses")]
     pub fn get_status(&self, path: &Path) -> GitStatus {
         self.statuses.get(path).unwrap_or(&GitStatus::Unknown).to_owned()
     }
 
-    /// INERT: Returns the status to a given path
-    #[cfg(not(feature = "git_statuses"))]
-    pub fn get_status(&self, path: &Path) -> GitStatus {
-        GitStatus::Unknown
+    /// `path` relative to the working directory, which is how git wants it.
+    fn relative(&self, path: &Path) -> Result<PathBuf, RepoError> {
+        let relative = if path.is_relative() {
+            path.to_owned()
+        } else {
+            path.strip_prefix(&self.workdir)
+                .map_err(|_| RepoError::OutsideOfRepository(path.to_owned()))?
+                .to_owned()
+        };
+        if relative.as_os_str().is_empty() {
+            Ok(PathBuf::from("*"))
+        } else {
+            Ok(relative)
+        }
     }
 
-    fn execute_git(&self, command: &str, args: &[&str], paths: &[PathBuf]) -> ExitStatus {
-        let gitdir = self.workdir.join(".git");
-        log::debug!(
-            "{:?}",
-            Command::new("git")
-                .args(["--work-tree", self.workdir.to_str().unwrap()])
-                .args(["--git-dir", gitdir.to_str().unwrap()])
-                .arg(command)
-                .args(args)
-                .args(paths)
-        );
-
-        Command::new("git")
-            .args(["--work-tree", self.workdir.to_str().unwrap()])
-            .args(["--git-dir", gitdir.to_str().unwrap()])
-            .arg(command)
-            .args(args)
-            .args(paths)
-            .status()
-            .unwrap_or_else(|e| panic!("failed to execute process: {}", e))
+    fn relative_all(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>, RepoError> {
+        paths.iter().map(|path| self.relative(path)).collect()
+    }
+
+    fn is_below(path: &Path, prefixes: &[PathBuf]) -> bool {
+        prefixes
+            .iter()
+            .any(|prefix| prefix.as_os_str() == "*" || path.starts_with(prefix))
+    }
+
+    fn signature(&self) -> Result<git2::Signature<'static>, RepoError> {
+        match self.repo.signature() {
+            Ok(signature) => Ok(signature),
+            Err(_) => {
+                // no `user.name` in the git config, fall back to ours
+                let name = crate::CONFIG.get_str("user/name");
+                Ok(git2::Signature::now(name, "")?)
+            },
+        }
+    }
+
+    fn head_commit(&self) -> Option<git2::Commit<'_>> {
+        self.repo.head().ok().and_then(|head| head.peel_to_commit().ok())
     }
 
-    pub fn add(&self, paths: &[PathBuf]) -> ExitStatus {
+    /// The name of the branch that is checked out.
+    pub fn branch(&self) -> Result<String, RepoError> {
+        let head = self.repo.find_reference("HEAD")?;
+        head.symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(ToOwned::to_owned)
+            .ok_or(RepoError::NoBranch)
+    }
+
+    /// Stages `paths`, files that are gone are removed from the index.
+    pub fn add(&self, paths: &[PathBuf]) -> Result<(), RepoError> {
         log::info!("adding to git: {:?}", paths);
-        self.execute_git("add", &[], paths)
+        let specs = self.relative_all(paths)?;
+        let mut index = self.repo.index()?;
+        index.add_all(&specs, git2::IndexAddOption::DEFAULT, None)?;
+        index.update_all(&specs, None)?;
+        Ok(index.write()?)
     }
 
-    pub fn add_all(&self) -> ExitStatus {
+    /// Adds every change, except those to the journal and the trash.
+    pub fn add_all(&self) -> Result<(), RepoError> {
         log::info!("adding all to git");
-        self.execute_git("add", &["--all"], &[])
+        let mut index = self.repo.index()?;
+        let mut skip_internal = |path: &Path, _: &[u8]| i32::from(super::journal::is_internal(path));
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, Some(&mut skip_internal))?;
+        index.update_all(["*"], Some(&mut skip_internal))?;
+        Ok(index.write()?)
     }
 
-    pub fn commit(&self) -> ExitStatus {
-        // TODO: override git editor with asciii editor
-        self.execute_git("commit", &[], &[])
+    /// Commits `tree` on top of `HEAD`, and of the merged commits while a merge is in progress.
+    fn commit_tree(&self, tree: git2::Oid, message: &str) -> Result<Commit, RepoError> {
+        let tree = self.repo.find_tree(tree)?;
+        let mut parents = self.head_commit().into_iter().collect::<Vec<_>>();
+        let merging = self.repo.state() == git2::RepositoryState::Merge;
+        if merging {
+            // `mergehead_foreach` wants a mutable repository, the file is simple enough
+            let merge_heads = fs::read_to_string(self.repo.path().join("MERGE_HEAD")).unwrap_or_default();
+            for id in merge_heads
+                .lines()
+                .filter_map(|line| git2::Oid::from_str(line.trim()).ok())
+            {
+                parents.push(self.repo.find_commit(id)?);
+            }
+        } else if parents.first().is_some_and(|head| head.tree_id() == tree.id()) {
+            return Err(RepoError::NothingToCommit);
+        }
+
+        let signature = self.signature()?;
+        let parents = parents.iter().collect::<Vec<_>>();
+        let id = self
+            .repo
+            .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+        if merging {
+            self.repo.cleanup_state()?;
+        }
+        log::info!("committed {:?} as {}", message, id);
+        self.find_commit(id)
     }
 
-    pub fn status(&self) -> ExitStatus {
-        self.execute_git("status", &[], &[])
+    fn find_commit(&self, id: git2::Oid) -> Result<Commit, RepoError> {
+        let commit = self.repo.find_commit(id)?;
+        let author = commit.author().name().unwrap_or_default().to_owned();
+        Ok(Commit {
+            id: id.to_string(),
+            summary: commit.summary().unwrap_or_default().to_owned(),
+            author,
+            time: time_of(commit.time()),
+        })
     }
 
-    pub fn checkout(&self, paths: &[PathBuf]) -> ExitStatus {
-        self.execute_git("checkout", &[], paths)
+    /// Commits what is in the index.
+    pub fn commit(&self, message: &str) -> Result<Commit, RepoError> {
+        let mut index = self.repo.index()?;
+        ensure_no_conflicts(&index, "HEAD")?;
+        let tree = index.write_tree()?;
+        self.commit_tree(tree, message)
     }
 
-    pub fn clean(&self, paths: &[PathBuf]) -> ExitStatus {
-        self.execute_git("clean", &["-d", "--force"], paths)
+    /// Commits only `paths`, whatever else is in the index stays there.
+    pub fn commit_paths(&self, paths: &[PathBuf], message: &str) -> Result<Commit, RepoError> {
+        self.add(paths)?;
+        let prefixes = self.relative_all(paths)?;
+        let index = self.repo.index()?;
+
+        // the tree of `HEAD` with everything below `paths` taken from the index
+        let mut partial = git2::Index::new()?;
+        if let Some(head) = self.head_commit() {
+            partial.read_tree(&head.tree()?)?;
+        }
+        let replaced = partial
+            .iter()
+            .map(|entry| path_of(&entry.path))
+            .filter(|path| Self::is_below(path, &prefixes))
+            .collect::<Vec<_>>();
+        for path in replaced {
+            partial.remove_path(&path)?;
+        }
+        for entry in index
+            .iter()
+            .filter(|entry| Self::is_below(&path_of(&entry.path), &prefixes))
+        {
+            partial.add(&entry)?;
+        }
+
+        let tree = partial.write_tree_to(&self.repo)?;
+        self.commit_tree(tree, message)
+    }
+
+    /// Branch, upstream and every changed path.
+    pub fn status(&self) -> Result<Status, RepoError> {
+        let branch = self.branch().ok();
+        let (upstream, ahead, behind) = match self.ahead_behind()? {
+            Some((upstream, ahead, behind)) => (Some(upstream), ahead, behind),
+            None => (None, 0, 0),
+        };
+
+        let statuses = self.repo.statuses(Some(
+            git2::StatusOptions::new()
+                .include_ignored(false)
+                .include_untracked(true)
+                .recurse_untracked_dirs(true),
+        ))?;
+        let changes = statuses
+            .iter()
+            .map(|entry| (path_of(entry.path_bytes()), entry.status().into()))
+            .collect();
+
+        Ok(Status {
+            branch,
+            upstream,
+            ahead,
+            behind,
+            changes,
+        })
+    }
+
+    /// The upstream of the current branch, falls back to the branch of the same name on `origin`.
+    fn upstream(&self, branch: &str) -> Option<(String, git2::Oid)> {
+        let configured = self
+            .repo
+            .find_branch(branch, git2::BranchType::Local)
+            .and_then(|local| local.upstream())
+            .ok()
+            .map(git2::Branch::into_reference);
+        let reference = match configured {
+            Some(reference) => reference,
+            None => self
+                .repo
+                .find_reference(&format!("refs/remotes/{}/{}", REMOTE, branch))
+                .ok()?,
+        };
+        let name = reference.shorthand()?.to_owned();
+        Some((name, reference.target()?))
+    }
+
+    /// How many commits the current branch is ahead and behind of its upstream, as far as the last fetch knows.
+    ///
+    /// `None` if there is no branch or nothing to compare to.
+    pub fn ahead_behind(&self) -> Result<Option<(String, usize, usize)>, RepoError> {
+        let (branch, head) = match (self.branch(), self.head_commit()) {
+            (Ok(branch), Some(head)) => (branch, head.id()),
+            _ => return Ok(None),
+        };
+        match self.upstream(&branch) {
+            Some((upstream, id)) => {
+                let (ahead, behind) = self.repo.graph_ahead_behind(head, id)?;
+                Ok(Some((upstream, ahead, behind)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Throws away every change to `paths` since the last commit.
+    pub fn checkout(&self, paths: &[PathBuf]) -> Result<(), RepoError> {
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force();
+        for path in self.relative_all(paths)? {
+            checkout.path(path);
+        }
+        Ok(self.repo.checkout_head(Some(&mut checkout))?)
+    }
+
+    /// Deletes untracked files below `paths`, and directories that end up empty.
+    pub fn clean(&self, paths: &[PathBuf]) -> Result<(), RepoError> {
+        let prefixes = self.relative_all(paths)?;
+        let statuses = self.repo.statuses(Some(
+            git2::StatusOptions::new()
+                .include_ignored(false)
+                .include_untracked(true)
+                .recurse_untracked_dirs(true),
+        ))?;
+        for entry in statuses.iter().filter(|entry| entry.status().is_wt_new()) {
+            let relative = path_of(entry.path_bytes());
+            if !Self::is_below(&relative, &prefixes) {
+                continue;
+            }
+            let path = self.workdir.join(&relative);
+            log::info!("removing {}", path.display());
+            fs::remove_file(&path).map_err(|error| git2::Error::from_str(&error.to_string()))?;
+            // `remove_dir` refuses to remove anything that is not empty
+            for dir in path.ancestors().skip(1).take_while(|dir| *dir != self.workdir) {
+                if fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts all local changes aside, see [`Repository::stash_pop`].
+    pub fn stash(&self) -> Result<(), RepoError> {
+        // stashing needs a mutable repository
+        let mut repo = git2::Repository::open(&self.workdir)?;
+        let signature = self.signature()?;
+        match repo.stash_save(&signature, "asciii stash", None) {
+            Err(error) if error.code() == git2::ErrorCode::NotFound => Err(RepoError::NothingToStash),
+            result => result.map(|_| ()).map_err(Into::into),
+        }
+    }
+
+    /// Brings back the changes from the last `stash`.
+    pub fn stash_pop(&self) -> Result<(), RepoError> {
+        let mut repo = git2::Repository::open(&self.workdir)?;
+        Ok(repo.stash_pop(0, None)?)
+    }
+
+    /// The patch of unstaged changes below `paths`, or of the staged ones.
+    pub fn diff(&self, paths: &[PathBuf], staged: bool) -> Result<String, RepoError> {
+        let mut options = git2::DiffOptions::new();
+        for path in self.relative_all(paths)? {
+            options.pathspec(path);
+        }
+        let diff = if staged {
+            let head = self.head_commit().map(|head| head.tree()).transpose()?;
+            self.repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?
+        } else {
+            self.repo.diff_index_to_workdir(None, Some(&mut options))?
+        };
+
+        let mut patch = String::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch += &String::from_utf8_lossy(line.content());
+            true
+        })?;
+        Ok(patch)
+    }
+
+    /// Commits that changed anything below `paths`, newest first.
+    pub fn log(&self, paths: &[PathBuf]) -> Result<Vec<Commit>, RepoError> {
+        if self.head_commit().is_none() {
+            return Ok(Vec::new());
+        }
+        let prefixes = self.relative_all(paths)?;
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(git2::Sort::TIME)?;
+        walk.push_head()?;
+
+        let mut commits = Vec::new();
+        for id in walk {
+            let id = id?;
+            if !prefixes.is_empty() {
+                let commit = self.repo.find_commit(id)?;
+                let parent = commit.parents().next().map(|parent| parent.tree()).transpose()?;
+                let mut options = git2::DiffOptions::new();
+                for prefix in &prefixes {
+                    options.pathspec(prefix);
+                }
+                let diff = self
+                    .repo
+                    .diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+                if diff.deltas().len() == 0 {
+                    continue;
+                }
+            }
+            commits.push(self.find_commit(id)?);
+        }
+        Ok(commits)
+    }
+
+    /// Remotes with their fetch and push urls, like `git remote -v`.
+    pub fn remotes(&self) -> Result<Vec<(String, String, String)>, RepoError> {
+        let mut remotes = Vec::new();
+        for name in self.repo.remotes()?.iter().flatten() {
+            let remote = self.repo.find_remote(name)?;
+            let url = remote.url().unwrap_or_default().to_owned();
+            let push_url = remote.pushurl().map_or_else(|| url.clone(), ToOwned::to_owned);
+            remotes.push((name.to_owned(), url, push_url));
+        }
+        Ok(remotes)
+    }
+
+    fn find_remote(&self) -> Result<git2::Remote<'_>, RepoError> {
+        self.repo
+            .find_remote(REMOTE)
+            .map_err(|_| RepoError::NoRemote(REMOTE.into()))
+    }
+
+    /// Fetches `branch` from the remote.
+    fn fetch(&self, branch: &str) -> Result<git2::AnnotatedCommit<'_>, RepoError> {
+        let mut remote = self.find_remote()?;
+        let mut options = git2::FetchOptions::new();
+        options.remote_callbacks(callbacks(self.repo.config()?));
+        remote.fetch(&[branch], Some(&mut options), None)?;
+        let fetched = self.repo.find_reference("FETCH_HEAD")?;
+        Ok(self.repo.reference_to_annotated_commit(&fetched)?)
+    }
+
+    /// Fetches the current branch and merges it, or rebases the local commits onto it.
+    ///
+    /// A merge that runs into conflicts is left for the user to resolve and commit,
+    /// a rebase that runs into conflicts is aborted.
+    pub fn pull(&self, rebase: bool) -> Result<Pulled, RepoError> {
+        let branch = self.branch()?;
+        let theirs = self.fetch(&branch)?;
+        let upstream = format!("{}/{}", REMOTE, branch);
+        let (analysis, _) = self.repo.merge_analysis(&[&theirs])?;
+
+        if analysis.is_up_to_date() {
+            Ok(Pulled::UpToDate)
+        } else if analysis.is_fast_forward() || analysis.is_unborn() {
+            let behind = match self.head_commit() {
+                Some(head) => self.repo.graph_ahead_behind(head.id(), theirs.id())?.1,
+                None => self.log_len(theirs.id())?,
+            };
+            let reference = format!("refs/heads/{}", branch);
+            let message = format!("pull: fast-forward to {}", upstream);
+            // local changes that would be overwritten make the checkout fail before anything moved
+            let target = self.repo.find_object(theirs.id(), None)?;
+            self.repo
+                .checkout_tree(&target, Some(git2::build::CheckoutBuilder::new().safe()))?;
+            self.repo.reference(&reference, theirs.id(), true, &message)?;
+            self.repo.set_head(&reference)?;
+            Ok(Pulled::FastForward(behind))
+        } else if rebase {
+            self.rebase(&theirs, &upstream)
+        } else {
+            self.merge(&theirs, &upstream)
+        }
     }
 
-    /// TODO: not yet functional
-    pub fn stash(&self) -> ExitStatus {
-        self.execute_git("stash", &[], &[])
+    fn log_len(&self, id: git2::Oid) -> Result<usize, RepoError> {
+        let mut walk = self.repo.revwalk()?;
+        walk.push(id)?;
+        Ok(walk.count())
     }
 
-    pub fn stash_pop(&self) -> ExitStatus {
-        self.execute_git("stash", &["pop"], &[])
+    fn merge(&self, theirs: &git2::AnnotatedCommit<'_>, upstream: &str) -> Result<Pulled, RepoError> {
+        self.repo.merge(&[theirs], None, None)?;
+        let mut index = self.repo.index()?;
+        ensure_no_conflicts(&index, upstream)?;
+
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let signature = self.signature()?;
+        let ours = self.head_commit().ok_or(RepoError::NoBranch)?;
+        let theirs = self.repo.find_commit(theirs.id())?;
+        self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("Merge {}", upstream),
+            &tree,
+            &[&ours, &theirs],
+        )?;
+        self.repo.cleanup_state()?;
+        Ok(Pulled::Merged)
+    }
+
+    fn rebase(&self, theirs: &git2::AnnotatedCommit<'_>, upstream: &str) -> Result<Pulled, RepoError> {
+        let signature = self.signature()?;
+        let mut rebase = self.repo.rebase(None, Some(theirs), None, None)?;
+        let mut rebased = 0;
+        while let Some(operation) = rebase.next() {
+            operation?;
+            let index = self.repo.index()?;
+            if let Err(error) = ensure_no_conflicts(&index, upstream) {
+                rebase.abort()?;
+                return Err(match error {
+                    RepoError::Conflicts(upstream, paths) => RepoError::RebaseConflicts(upstream, paths),
+                    error => error,
+                });
+            }
+            match rebase.commit(None, &signature, None) {
+                Ok(_) => rebased += 1,
+                // the change is already upstream
+                Err(error) if error.code() == git2::ErrorCode::Applied => {},
+                Err(error) => return Err(error.into()),
+            }
+        }
+        rebase.finish(Some(&signature))?;
+        Ok(Pulled::Rebased(rebased))
+    }
+
+    /// Pushes the current branch to the branch of the same name on the remote.
+    pub fn push(&self) -> Result<(), RepoError> {
+        let branch = self.branch()?;
+        let mut remote = self.find_remote()?;
+        let rejected = RefCell::new(None);
+
+        let mut callbacks = callbacks(self.repo.config()?);
+        callbacks.push_update_reference(|reference, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some((reference.to_owned(), status.to_owned()));
+            }
+            Ok(())
+        });
+        let mut options = git2::PushOptions::new();
+        options.remote_callbacks(callbacks);
+        remote.push(&[format!("refs/heads/{0}:refs/heads/{0}", branch)], Some(&mut options))?;
+        drop(options);
+
+        match rejected.into_inner() {
+            Some((reference, status)) => Err(RepoError::Rejected(reference, status)),
+            None => Ok(()),
+        }
     }
+}
 
-    pub fn push(&self) -> ExitStatus {
-        self.execute_git("push", &["origin", "master"], &[])
+/// Fails with the conflicting paths if `index` has any.
+#[cfg(feature = "git_statuses")]
+fn ensure_no_conflicts(index: &git2::Index, upstream: &str) -> Result<(), RepoError> {
+    if !index.has_conflicts() {
+        return Ok(());
+    }
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(path_of(&entry.path));
+        }
     }
+    Err(RepoError::Conflicts(upstream.to_owned(), paths))
+}
 
-    pub fn diff(&self, paths: &[PathBuf], flags: &[&str]) -> ExitStatus {
-        self.execute_git("diff", flags, paths)
+#[cfg(not(feature = "git_statuses"))]
+impl Repository {
+    pub fn try_new(path: &Path) -> Result<Self, GitError> {
+        Ok(Repository {
+            workdir: path.to_owned(),
+        })
     }
 
-    pub fn pull(&self) -> ExitStatus {
-        self.execute_git("pull", &["origin", "master"], &[])
+    /// INERT: Returns the status to a given path
+    pub fn get_status(&self, path: &Path) -> GitStatus {
+        GitStatus::Unknown
     }
 
-    pub fn pull_rebase(&self) -> ExitStatus {
-        self.execute_git("pull", &["origin", "master", "--rebase"], &[])
+    fn execute_git(&self, command: &str, args: &[&str], paths: &[PathBuf]) -> Result<(), RepoError> {
+        let gitdir = self.workdir.join(".git");
+        let mut git = Command::new("git");
+        git.args(["--work-tree", self.workdir.to_str().unwrap()])
+            .args(["--git-dir", gitdir.to_str().unwrap()])
+            .arg(command)
+            .args(args)
+            .args(paths);
+        log::debug!("{:?}", git);
+
+        match git.status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(_) => Err(RepoError::ProcessFailed(command.into())),
+            Err(error) => {
+                log::error!("failed to execute process: {}", error);
+                Err(RepoError::ProcessFailed(command.into()))
+            },
+        }
     }
 
-    pub fn remote(&self) -> ExitStatus {
-        self.execute_git("remote", &[], &[])
+    pub fn add(&self, paths: &[PathBuf]) -> Result<(), RepoError> {
+        log::info!("adding to git: {:?}", paths);
+        self.execute_git("add", &[], paths)
     }
 
-    pub fn log(&self, paths: &[PathBuf]) -> ExitStatus {
-        self.execute_git("log", &[ "--graph", "--pretty=format:'%Cred%h%Creset -%C(bold yellow)%d%Creset %C() %s %C(reset) ( %C(yellow)%an%Creset %C(green)%cr )'", "--abbrev-commit", "--date=relative" ], paths)
+    /// Commits only `paths`, with `message` instead of asking for one.
+    pub fn commit_paths(&self, paths: &[PathBuf], message: &str) -> Result<(), RepoError> {
+        self.execute_git("commit", &["--message", message, "--"], paths)
     }
 }
 
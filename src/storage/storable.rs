@@ -2,13 +2,11 @@
 //!
 
 use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
 use anyhow::Error;
 use chrono::{Date, Datelike, Utc};
-use tempdir::TempDir;
 
 use super::repo::GitStatus;
 
@@ -28,12 +26,18 @@ pub trait Storable: Send + Sync {
     where
         Self: Sized;
 
-    /// creates in tempfile
+    /// opens a project file that was already read by the storage backend
+    fn from_content(file: &FilePath, content: &str) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// fills in `template`, the storage writes the result into the new project file
     fn from_template(
         project_name: &str,
-        template: &Path,
+        template_name: &str,
+        template: &str,
         data: &HashMap<&str, String>,
-    ) -> Result<StorableAndTempDir<Self>, Error>
+    ) -> Result<String, Error>
     where
         Self: Sized;
 
@@ -111,27 +115,3 @@ pub trait Storable: Send + Sync {
 
     fn is_ready_for_archive(&self) -> bool;
 }
-
-pub struct StorableAndTempDir<S: Storable> {
-    pub storable: S,
-    pub temp_dir: TempDir,
-}
-
-impl<S: Storable> Deref for StorableAndTempDir<S> {
-    type Target = S;
-    fn deref(&self) -> &S {
-        &self.storable
-    }
-}
-
-impl<S: Storable> DerefMut for StorableAndTempDir<S> {
-    fn deref_mut(&mut self) -> &mut S {
-        &mut self.storable
-    }
-}
-
-// impl<S: Storable> Into<S> for StorableAndTempDir<S> {
-//     fn into(self) -> S {
-//         self.storable
-//     }
-// }
@@ -21,27 +21,16 @@ pub struct TestProject {
 impl Storable for TestProject {
     // creates in tempfile
     fn from_template(
-        project_name: &str,
-        template: &Path,
+        _project_name: &str,
+        _template_name: &str,
+        template: &str,
         _fill: &HashMap<&str, String>,
-    ) -> Result<StorableAndTempDir<Self>, Error>
+    ) -> Result<String, Error>
     where
         Self: Sized,
     {
-        // generates a temp file
-        let temp_dir = TempDir::new_in("./target/debug/build/", project_name).unwrap();
-        let temp_file = temp_dir.path().join(project_name);
-
         // just copy over template
-        fs::copy(template, &temp_file)?;
-
-        // project now lives in the temp_file
-        let project = TestProject { file_path: temp_file };
-
-        Ok(StorableAndTempDir {
-            storable: project,
-            temp_dir,
-        })
+        Ok(template.to_owned())
     }
 
     fn short_desc(&self) -> String {
@@ -72,6 +61,10 @@ impl Storable for TestProject {
             file_path: PathBuf::from(path),
         })
     }
+
+    fn from_content(path: &Path, _content: &str) -> Result<Self, Error> {
+        Self::open_file(path)
+    }
     fn matches_filter(&self, _key: &str, _val: &str) -> bool {
         false
     }
@@ -292,3 +285,304 @@ fn unarchive_project_dir(){
         }
     }
 }
+
+fn setup_in_memory() -> Storage<TestProject, backend::Memory> {
+    let storage =
+        Storage::try_new_with_backend("/storage", "working", "archive", "templates", backend::Memory::new()).unwrap();
+    storage.backend().create_dir_all(Path::new("/storage"));
+    storage.create_dirs().unwrap();
+    let template = fs::read_to_string("./templates/default.tyml").unwrap();
+    storage
+        .backend()
+        .write(Path::new("/storage/templates/template1.tyml"), &template)
+        .unwrap();
+    storage
+}
+
+#[test]
+#[rustfmt::skip]
+fn in_memory_create_and_archive_project(){
+    let storage = setup_in_memory();
+    assert!(storage.health_check().is_ok());
+    assert!(!Path::new("/storage").exists());
+
+    let project = storage.create_project("foobar", "template1", &hashmap!{}).unwrap();
+    assert_eq!(project.file(), Path::new("/storage/working/foobar/foobar.PROJECT"));
+    assert!(storage.backend().exists(&project.file()));
+    assert_eq!(storage.backend().read_to_string(&project.file()).unwrap(), fs::read_to_string("./templates/default.tyml").unwrap());
+    assert!(storage.create_project("foobar", "template1", &hashmap!{}).is_err());
+    assert_eq!(storage.open_projects(StorageDir::Working).unwrap().len(), 1);
+
+    storage.archive_project(&project, 2015).unwrap();
+    assert!(!storage.backend().exists(&project.file()));
+    assert!(storage.get_project_dir("foobar", StorageDir::Working).is_err());
+    assert_eq!(storage.list_years().unwrap(), vec![2015]);
+
+    let archived = storage.get_project_file(Path::new("/storage/archive/2015/ZZ99_foobar")).unwrap();
+    assert_eq!(storage.backend().read_to_string(&archived).unwrap(),
+               fs::read_to_string("./templates/default.tyml").unwrap());
+
+    let unarchived = storage.unarchive_project_dir(archived.parent().unwrap()).unwrap();
+    assert_eq!(unarchived, Path::new("/storage/working/foobar"));
+    assert!(storage.list_project_folders(StorageDir::Archive(2015)).unwrap().is_empty());
+}
+
+#[test]
+fn in_memory_delete_project() {
+    let storage = setup_in_memory();
+    let project = storage.create_project("foobar", "template1", &hashmap! {}).unwrap();
+
+    storage.delete_project_if(&project, || false).unwrap();
+    assert!(storage.backend().exists(&project.dir()));
+
+    storage.delete_project_if(&project, || true).unwrap();
+    assert!(!storage.backend().exists(&project.dir()));
+    assert!(storage.list_project_folders(StorageDir::Working).unwrap().is_empty());
+}
+
+#[test]
+fn in_memory_undo_moves_and_deletions() {
+    let storage = setup_in_memory();
+    let working = Path::new("/storage/working/foobar");
+    let archived = Path::new("/storage/archive/2015/ZZ99_foobar");
+
+    let project = storage.create_project("foobar", "template1", &hashmap! {}).unwrap();
+    storage.archive_project(&project, 2015).unwrap();
+    let archived_project = TestProject {
+        file_path: archived.join("foobar.PROJECT"),
+    };
+    storage.delete_project_if(&archived_project, || true).unwrap();
+    assert!(!storage.backend().exists(archived));
+
+    let history = storage.history().unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| entry.description.as_str())
+            .collect::<Vec<_>>(),
+        vec!["created foobar", "archived foobar into 2015", "deleted foobar"]
+    );
+
+    // out of the trash
+    let undone = storage.undo(1).unwrap();
+    assert_eq!(undone[0].id, 3);
+    assert!(storage.backend().exists(&archived.join("foobar.PROJECT")));
+
+    // out of the archive and gone again
+    assert_eq!(storage.undo(2).unwrap().len(), 2);
+    assert!(!storage.backend().exists(archived));
+    assert!(!storage.backend().exists(working));
+    assert!(storage.history().unwrap().iter().all(|entry| entry.undone));
+
+    let nothing = storage.undo(1).unwrap_err();
+    assert_eq!(
+        nothing.downcast_ref::<journal::JournalError>(),
+        Some(&journal::JournalError::NothingToUndo)
+    );
+}
+
+#[test]
+fn in_memory_undo_edits() {
+    let storage = setup_in_memory();
+    let project = storage.create_project("foobar", "template1", &hashmap! {}).unwrap();
+    let file = project.file();
+    let journal = storage.journal();
+
+    let before = storage.backend().read_to_string(&file).unwrap();
+    storage.backend().write(&file, "edited: 1\n").unwrap();
+    journal.record_edit(&file, &before, "edited: 1\n", "first").unwrap();
+    storage.backend().write(&file, "edited: 2\n").unwrap();
+    journal
+        .record_edit(&file, "edited: 1\n", "edited: 2\n", "second")
+        .unwrap();
+
+    storage.undo(1).unwrap();
+    assert_eq!(storage.backend().read_to_string(&file).unwrap(), "edited: 1\n");
+
+    // edited by hand in the meantime
+    storage.backend().write(&file, "edited: 3\n").unwrap();
+    let changed = storage.undo(1).unwrap_err();
+    assert!(matches!(
+        changed.downcast_ref::<journal::JournalError>(),
+        Some(journal::JournalError::ChangedSince(..))
+    ));
+
+    storage.backend().write(&file, "edited: 1\n").unwrap();
+    storage.undo(1).unwrap();
+    assert_eq!(storage.backend().read_to_string(&file).unwrap(), before);
+}
+
+#[test]
+fn in_memory_undo_extra_files() {
+    let storage = setup_in_memory();
+    let file = storage
+        .write_extra_file("clients.yml", "graf-zahl:\n", "added graf-zahl")
+        .unwrap();
+    storage
+        .write_extra_file("clients.yml", "graf-zahl:\ndracula:\n", "added dracula")
+        .unwrap();
+    assert_eq!(storage.history().unwrap()[1].description, "clients.yml: added dracula");
+
+    storage.undo(1).unwrap();
+    assert_eq!(storage.backend().read_to_string(&file).unwrap(), "graf-zahl:\n");
+    storage.undo(1).unwrap();
+    assert_eq!(storage.backend().read_to_string(&file).unwrap(), "");
+
+    // neither the journal nor the trash belong into git
+    let journal = storage.journal();
+    assert_eq!(
+        storage
+            .backend()
+            .read_to_string(&journal.journal_dir().join(".gitignore"))
+            .unwrap(),
+        "*\n"
+    );
+}
+
+#[cfg(feature = "git_statuses")]
+mod git {
+    use super::*;
+    use crate::storage::repo::{message_for, GitStatus, Pulled, RepoError, Repository};
+
+    fn configured(dir: &Path) -> Repository {
+        let mut config = git2::Repository::open(dir).unwrap().config().unwrap();
+        config.set_str("user.name", "Tester").unwrap();
+        config.set_str("user.email", "tester@example.com").unwrap();
+        Repository::try_new(dir).unwrap()
+    }
+
+    fn init(dir: &Path) -> Repository {
+        git2::Repository::init(dir).unwrap();
+        configured(dir)
+    }
+
+    fn clone(remote: &Path, dir: &Path) -> Repository {
+        git2::Repository::clone(remote.to_str().unwrap(), dir).unwrap();
+        configured(dir)
+    }
+
+    fn write(dir: &Path, path: &str, content: &str) -> PathBuf {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn commit_all(repo: &Repository, message: &str) {
+        repo.add_all().unwrap();
+        repo.commit(message).unwrap();
+    }
+
+    #[test]
+    fn commit_paths_leaves_the_rest_staged() {
+        let dir = TempDir::new_in("./target/debug/build/", "git").unwrap();
+        let repo = init(dir.path());
+        let kaffee = write(dir.path(), "working/Kaffee/Kaffee.yml", "kaffee");
+        let kuchen = write(dir.path(), "working/Kuchen/Kuchen.yml", "kuchen");
+        repo.add(std::slice::from_ref(&kuchen)).unwrap();
+
+        let message = message_for(std::slice::from_ref(&kaffee));
+        let commit = repo
+            .commit_paths(&[dir.path().join("working/Kaffee")], &message)
+            .unwrap();
+        assert_eq!(commit.summary, "Update Kaffee");
+        assert_eq!(commit.author, "Tester");
+
+        let status = repo.status().unwrap();
+        assert_eq!(status.staged(), vec![PathBuf::from("working/Kuchen/Kuchen.yml")]);
+        assert_eq!(repo.log(std::slice::from_ref(&kaffee)).unwrap().len(), 1);
+        assert!(repo.log(&[kuchen]).unwrap().is_empty());
+        assert!(matches!(
+            repo.commit_paths(&[kaffee], "again"),
+            Err(RepoError::NothingToCommit)
+        ));
+    }
+
+    #[test]
+    fn add_all_leaves_out_journal_and_trash() {
+        let dir = TempDir::new_in("./target/debug/build/", "git").unwrap();
+        let repo = init(dir.path());
+        write(dir.path(), "working/Kaffee/Kaffee.yml", "kaffee\n");
+        write(dir.path(), ".journal/log", "1\n");
+        write(dir.path(), ".trash/2_Kuchen/Kuchen.yml", "kuchen\n");
+
+        repo.add_all().unwrap();
+        assert_eq!(
+            repo.status().unwrap().staged(),
+            vec![PathBuf::from("working/Kaffee/Kaffee.yml")]
+        );
+    }
+
+    #[test]
+    fn add_stages_deletions() {
+        let dir = TempDir::new_in("./target/debug/build/", "git").unwrap();
+        let repo = init(dir.path());
+        write(dir.path(), "working/Kaffee/Kaffee.yml", "kaffee\n");
+        commit_all(&repo, "init");
+
+        let project = dir.path().join("working/Kaffee");
+        fs::remove_dir_all(&project).unwrap();
+        repo.add(&[project]).unwrap();
+
+        let status = repo.status().unwrap();
+        assert_eq!(
+            status.changes,
+            vec![(PathBuf::from("working/Kaffee/Kaffee.yml"), GitStatus::IndexDeleted)]
+        );
+        assert!(repo.diff(&[], true).unwrap().contains("-kaffee"));
+        assert!(repo.diff(&[], false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pull_fast_forwards_rebases_and_reports_conflicts() {
+        let root = TempDir::new_in("./target/debug/build/", "git").unwrap();
+        let remote = root.path().join("remote.git");
+        git2::Repository::init_bare(&remote).unwrap();
+
+        let ours = init(&root.path().join("ours"));
+        ours.repo.remote(repo::REMOTE, remote.to_str().unwrap()).unwrap();
+        write(&ours.workdir, "working/Kaffee/Kaffee.yml", "kaffee\n");
+        commit_all(&ours, "init");
+        ours.push().unwrap();
+
+        let theirs = clone(&remote, &root.path().join("theirs"));
+        write(&theirs.workdir, "working/Kaffee/Kaffee.yml", "more kaffee\n");
+        commit_all(&theirs, "more kaffee");
+        assert_eq!(
+            theirs.ahead_behind().unwrap().map(|(_, ahead, behind)| (ahead, behind)),
+            Some((1, 0))
+        );
+        theirs.push().unwrap();
+
+        assert_eq!(ours.pull(false).unwrap(), Pulled::FastForward(1));
+        assert_eq!(
+            fs::read_to_string(ours.workdir.join("working/Kaffee/Kaffee.yml")).unwrap(),
+            "more kaffee\n"
+        );
+        assert_eq!(ours.pull(false).unwrap(), Pulled::UpToDate);
+
+        // different files
+        write(&ours.workdir, "working/Kuchen/Kuchen.yml", "kuchen\n");
+        commit_all(&ours, "kuchen");
+        write(&theirs.workdir, "working/Tee/Tee.yml", "tee\n");
+        commit_all(&theirs, "tee");
+        theirs.push().unwrap();
+        assert_eq!(ours.pull(true).unwrap(), Pulled::Rebased(1));
+        assert!(ours.workdir.join("working/Tee/Tee.yml").exists());
+        ours.push().unwrap();
+
+        // the same file
+        theirs.pull(false).unwrap();
+        write(&ours.workdir, "working/Kaffee/Kaffee.yml", "our kaffee\n");
+        commit_all(&ours, "ours");
+        ours.push().unwrap();
+        write(&theirs.workdir, "working/Kaffee/Kaffee.yml", "their kaffee\n");
+        commit_all(&theirs, "theirs");
+        match theirs.pull(false) {
+            Err(RepoError::Conflicts(_, paths)) => {
+                assert_eq!(paths, vec![PathBuf::from("working/Kaffee/Kaffee.yml")])
+            },
+            other => panic!("expected conflicts, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Currencies other than the configured one.
+//!
+//! [`Currency`] only knows a symbol, adding two amounts of different symbols panics.
+//! This maps symbols to ISO 4217 codes and their usual notation,
+//! and keeps sums of several currencies apart in [`Sums`].
+
+use std::{collections::BTreeMap, fmt};
+
+use bill::Currency;
+
+/// A currency asciii knows how to write.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Info {
+    /// ISO 4217, e.g. `EUR`
+    pub code: &'static str,
+    /// the symbol amounts carry, must be unique
+    pub symbol: char,
+    /// what is printed next to the amount
+    pub sign: &'static str,
+    /// `$12.10` instead of `12,10€`
+    pub prefix: bool,
+}
+
+/// All currencies that projects can be in.
+pub static CURRENCIES: &[Info] = &[
+    Info {
+        code: "EUR",
+        symbol: '€',
+        sign: "€",
+        prefix: false,
+    },
+    Info {
+        code: "USD",
+        symbol: '$',
+        sign: "$",
+        prefix: true,
+    },
+    Info {
+        code: "GBP",
+        symbol: '£',
+        sign: "£",
+        prefix: true,
+    },
+    Info {
+        code: "CHF",
+        symbol: '₣',
+        sign: "CHF ",
+        prefix: true,
+    },
+];
+
+/// Looks up a currency by its code.
+pub fn by_code(code: &str) -> Option<&'static Info> {
+    CURRENCIES
+        .iter()
+        .find(|info| info.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Looks up a currency by its symbol.
+pub fn by_symbol(symbol: char) -> Option<&'static Info> {
+    CURRENCIES.iter().find(|info| info.symbol == symbol)
+}
+
+/// The configured `currency`, either a symbol or a code.
+pub fn default_symbol() -> Option<char> {
+    let configured = crate::CONFIG.get_str("currency");
+    by_code(configured)
+        .map(|info| info.symbol)
+        .or_else(|| configured.chars().next())
+}
+
+/// Code of the currency of `amount`, `None` for unknown symbols.
+pub fn code_of(amount: &Currency) -> Option<&'static str> {
+    amount.symbol.and_then(by_symbol).map(|info| info.code)
+}
+
+/// Writes `amount` the way its currency is usually written.
+pub fn format(amount: &Currency) -> String {
+    match amount.symbol.and_then(by_symbol) {
+        Some(info) if info.prefix => {
+            let sign = if amount.value < 0 { "-" } else { "" };
+            let absolute = Currency {
+                symbol: None,
+                value: amount.value.abs(),
+            };
+            format!("{}{}{}", sign, info.sign, absolute.prefix())
+        },
+        _ => amount.postfix().to_string(),
+    }
+}
+
+/// Amounts in several currencies, each summed up on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sums(BTreeMap<Option<char>, Currency>);
+
+impl Sums {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, amount: Currency) {
+        let sum = self.0.entry(amount.symbol).or_insert(Currency { value: 0, ..amount });
+        sum.value += amount.value;
+    }
+
+    /// The sum in one currency, zero if there is none.
+    pub fn get(&self, symbol: Option<char>) -> Currency {
+        self.0.get(&symbol).copied().unwrap_or(Currency { symbol, value: 0 })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// One sum per currency, ordered by symbol.
+    pub fn iter(&self) -> impl Iterator<Item = &Currency> {
+        self.0.values()
+    }
+}
+
+impl FromIterator<Currency> for Sums {
+    fn from_iter<I: IntoIterator<Item = Currency>>(iter: I) -> Self {
+        let mut sums = Sums::new();
+        for amount in iter {
+            sums.add(amount);
+        }
+        sums
+    }
+}
+
+impl fmt::Display for Sums {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "{}", super::currency_to_string(&super::to_currency(0.0)));
+        }
+        let sums = self.iter().map(format).collect::<Vec<_>>();
+        write!(f, "{}", sums.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(symbol: char, value: i64) -> Currency {
+        Currency {
+            symbol: Some(symbol),
+            value,
+        }
+    }
+
+    #[test]
+    fn notation() {
+        assert_eq!(format(&amount('€', 1210)), "12,10€");
+        assert_eq!(format(&amount('$', 1210)), "$12.10");
+        assert_eq!(format(&amount('$', -1210)), "-$12.10");
+        assert_eq!(format(&amount('₣', 100_099)), "CHF 1000.99");
+        assert_eq!(by_code("usd").map(|info| info.symbol), Some('$'));
+        assert_eq!(code_of(&amount('£', 1)), Some("GBP"));
+    }
+
+    #[test]
+    fn sums_stay_apart() {
+        let sums = vec![amount('€', 1000), amount('$', 500), amount('€', 210)]
+            .into_iter()
+            .collect::<Sums>();
+        assert_eq!(sums.get(Some('€')), amount('€', 1210));
+        assert_eq!(sums.get(Some('$')), amount('$', 500));
+        assert_eq!(sums.get(Some('£')), amount('£', 0));
+        assert_eq!(sums.to_string(), "$5.00 + 12,10€");
+    }
+}
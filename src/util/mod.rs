@@ -13,8 +13,10 @@ use chrono::NaiveTime;
 use self::dirs::home_dir;
 use anyhow::{Context, Error};
 
+pub mod currency;
 pub mod dirs;
 pub mod yaml;
+pub mod yaml_edit;
 
 /// Sets up logging initially.
 ///
@@ -171,15 +173,20 @@ pub fn exit(status: ExitStatus) -> ! {
 
 /// One place to decide how to display currency
 pub fn currency_to_string(currency: &Currency) -> String {
-    currency.postfix().to_string()
+    currency::format(currency)
 }
 
 /// Creates a currency from an `f64`
 ///
 /// This is functionality which was explicitly left out of the `Claude` crate.
 pub fn to_currency(f: f64) -> Currency {
+    to_currency_in(f, currency::default_symbol())
+}
+
+/// Creates a currency from an `f64`, in the currency of `symbol` rather than the configured one.
+pub fn to_currency_in(f: f64, symbol: Option<char>) -> Currency {
     Currency {
-        symbol: crate::CONFIG.get_char("currency"),
+        symbol,
         value: (f * 1000.0) as i64,
     } / 10
 }
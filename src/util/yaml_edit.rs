@@ -0,0 +1,723 @@
+//! Format preserving edits of yaml documents.
+//!
+//! `yaml-rust` can only emit what it parsed, which drops comments, anchors and the order of keys.
+//! Project files are written by hand and reviewed in diffs, so edits go through [`Document`],
+//! which only touches the lines that actually change.
+//!
+//! Paths look like the ones of [`yaml::get()`](super::yaml::get): `invoice.payed_date` or `hours/caterers`.
+//! Only block style mappings and sequences can be edited,
+//! flow style values (`{ name: Kaffee }`, `[]`) are treated like any other scalar.
+//!
+//! ```
+//! use asciii::util::yaml_edit::Document;
+//!
+//! let mut doc = Document::new("invoice:\n  number: 41 # keep me\n");
+//! doc.set("invoice.number", "42").unwrap();
+//! doc.set("invoice.payed_date", "08.12.2014").unwrap();
+//! assert_eq!(doc.to_string(), "invoice:\n  number: 42 # keep me\n  payed_date: 08.12.2014\n");
+//! ```
+
+use std::fmt::{self, Write};
+
+use yaml_rust::Yaml;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum EditError {
+    #[error("{:?} does not exist", _0)]
+    NotFound(String),
+
+    #[error("{:?} has a value, it can't hold any keys", _0)]
+    NotAMapping(String),
+
+    #[error("{:?} has nested keys, it can't be set to a value", _0)]
+    NotAScalar(String),
+
+    #[error("{:?} is not a block style list", _0)]
+    NotABlockList(String),
+
+    #[error("Empty path")]
+    EmptyPath,
+}
+
+/// A yaml document as it was written, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+/// A key and the lines of its nested block.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    line: usize,
+    indent: usize,
+    /// where the value starts, right behind the colon
+    value_start: usize,
+    /// one behind the last line that belongs to the entry
+    end: usize,
+}
+
+/// Quotes `value` if yaml would not read it back as the same text.
+///
+/// Numbers, `true`, `false`, `null` and `~` stay bare on purpose,
+/// values like `invoice.number: 42` are meant to keep their type, see [`string()`] otherwise.
+pub fn scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value != value.trim()
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.chars().any(char::is_control)
+        || value.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`',
+        ]);
+    if needs_quotes {
+        quoted(value)
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Like [`scalar()`], but numbers and keywords are quoted too, so `value` is always read back as a string.
+pub fn string(value: &str) -> String {
+    if matches!(Yaml::from_str(value), Yaml::String(_)) {
+        scalar(value)
+    } else {
+        quoted(value)
+    }
+}
+
+/// Writes `value` as a double quoted yaml scalar.
+pub fn quoted(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\0' => quoted.push_str("\\0"),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\u{1b}' => quoted.push_str("\\e"),
+            '\u{85}' => quoted.push_str("\\N"),
+            '\u{a0}' => quoted.push_str("\\_"),
+            '\u{2028}' => quoted.push_str("\\L"),
+            '\u{2029}' => quoted.push_str("\\P"),
+            c if c.is_control() || c == '\u{feff}' => {
+                let _ = match c as u32 {
+                    code @ 0..=0xff => write!(quoted, "\\x{:02X}", code),
+                    code @ 0x100..=0xffff => write!(quoted, "\\u{:04X}", code),
+                    code => write!(quoted, "\\U{:08X}", code),
+                };
+            },
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split(['.', '/']).filter(|s| !s.is_empty()).collect()
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Neither blank nor a comment.
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+fn is_sequence_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed == "-" || trimmed.starts_with("- ")
+}
+
+/// Reads `key:` from a line, returns the key and the byte offset behind the colon.
+fn parse_key(line: &str) -> Option<(String, usize)> {
+    let indent = indent_of(line);
+    let rest = &line[indent..];
+    if !is_content(rest) || is_sequence_item(rest) {
+        return None;
+    }
+
+    if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let close = rest[1..].find(quote)? + 1;
+        let after = rest[close + 1..].trim_start_matches(' ');
+        let colon = indent + rest.len() - after.len();
+        return after.starts_with(':').then(|| (rest[1..close].to_owned(), colon + 1));
+    }
+
+    let bytes = rest.as_bytes();
+    (0..bytes.len())
+        .find(|&i| bytes[i] == b':' && bytes.get(i + 1).is_none_or(|b| *b == b' '))
+        .map(|i| (rest[..i].trim_end().to_owned(), indent + i + 1))
+}
+
+/// Splits what follows the colon into value and comment, both trimmed.
+fn split_comment(rest: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '#') if previous == ' ' || previous == '\t' => {
+                return (rest[..i].trim(), Some(rest[i..].trim_end()));
+            },
+            _ => {},
+        }
+        previous = c;
+    }
+    (rest.trim(), None)
+}
+
+/// Comments like `##INVOICE-NUMBER##` are placeholders of the template, they go once the value is set.
+fn is_placeholder(comment: &str) -> bool {
+    comment.len() > 4 && comment.starts_with("##") && comment.trim_end().ends_with("##")
+}
+
+fn is_empty_value(value: &str) -> bool {
+    matches!(value, "" | "~" | "null")
+}
+
+impl Document {
+    pub fn new(content: &str) -> Self {
+        Document {
+            lines: content.lines().map(ToOwned::to_owned).collect(),
+            trailing_newline: content.ends_with('\n') || content.is_empty(),
+        }
+    }
+
+    /// Lines between `---` and `...`
+    fn body(&self) -> (usize, usize) {
+        let start = self
+            .lines
+            .iter()
+            .position(|l| is_content(l))
+            .filter(|&i| self.lines[i].starts_with("---"))
+            .map_or(0, |i| i + 1);
+        let end = self.lines[start..]
+            .iter()
+            .position(|l| l.trim_end() == "...")
+            .map_or(self.lines.len(), |i| start + i);
+        (start, end)
+    }
+
+    /// Returns the last line of the block that starts in `line` with `indent`, plus one.
+    fn block_end(&self, line: usize, indent: usize, limit: usize) -> usize {
+        let mut end = line + 1;
+        for (i, l) in self.lines.iter().enumerate().take(limit).skip(line + 1) {
+            if !is_content(l) {
+                continue;
+            }
+            let l_indent = indent_of(l);
+            if l_indent > indent || (l_indent == indent && is_sequence_item(l)) {
+                end = i + 1;
+            } else {
+                break;
+            }
+        }
+        end
+    }
+
+    /// Indentation of the keys inside `start..end`.
+    fn child_indent(&self, start: usize, end: usize) -> Option<usize> {
+        self.lines[start..end]
+            .iter()
+            .find(|l| is_content(l))
+            .map(|l| indent_of(l))
+    }
+
+    fn find_in(&self, start: usize, end: usize, key: &str) -> Option<Entry> {
+        let indent = self.child_indent(start, end)?;
+        (start..end)
+            .filter(|&i| indent_of(&self.lines[i]) == indent)
+            .find_map(|i| {
+                let (found, value_start) = parse_key(&self.lines[i])?;
+                (found == key).then(|| Entry {
+                    line: i,
+                    indent,
+                    value_start,
+                    end: self.block_end(i, indent, end),
+                })
+            })
+    }
+
+    fn find(&self, segments: &[&str]) -> Option<Entry> {
+        let (mut start, mut end) = self.body();
+        let mut entry = None;
+        for segment in segments {
+            let found = self.find_in(start, end, segment)?;
+            start = found.line + 1;
+            end = found.end;
+            entry = Some(found);
+        }
+        entry
+    }
+
+    fn value_of(&self, entry: &Entry) -> (&str, Option<&str>) {
+        split_comment(&self.lines[entry.line][entry.value_start..])
+    }
+
+    fn has_children(entry: &Entry) -> bool {
+        entry.end > entry.line + 1
+    }
+
+    /// Last line that is not blank or a comment, comments at the end of a block stay where they are.
+    fn last_content(&self, start: usize, end: usize) -> Option<usize> {
+        (start..end).rev().find(|&i| is_content(&self.lines[i]))
+    }
+
+    /// Replaces the value in `entry.line`, keeps alignment and comments.
+    fn replace_value(&mut self, entry: &Entry, value: &str) {
+        let line = &self.lines[entry.line];
+        let rest = &line[entry.value_start..];
+        let spacing = &rest[..rest.len() - rest.trim_start().len()];
+        let spacing = if spacing.is_empty() || rest.trim().is_empty() {
+            " "
+        } else {
+            spacing
+        };
+        let comment = split_comment(rest).1.filter(|c| !is_placeholder(c));
+
+        let mut new_line = format!("{}{}{}", &line[..entry.value_start], spacing, value);
+        if value.is_empty() {
+            new_line.truncate(entry.value_start);
+        }
+        if let Some(comment) = comment {
+            new_line.push(' ');
+            new_line.push_str(comment);
+        }
+        self.lines[entry.line] = new_line;
+    }
+
+    /// Makes sure all `segments` exist as nested keys, creates what is missing.
+    ///
+    /// Returns the entry of the last segment, `section` tells whether it is going to have children.
+    fn ensure(&mut self, segments: &[&str], section: bool) -> Result<Entry, EditError> {
+        if segments.is_empty() {
+            return Err(EditError::EmptyPath);
+        }
+        let (mut start, mut end) = self.body();
+        let mut parent: Option<Entry> = None;
+
+        for (depth, segment) in segments.iter().enumerate() {
+            if let Some(found) = self.find_in(start, end, segment) {
+                start = found.line + 1;
+                end = found.end;
+                parent = Some(found);
+                continue;
+            }
+
+            if let Some(parent) = parent {
+                let (value, _) = self.value_of(&parent);
+                let holds_list = self.lines[start..end]
+                    .iter()
+                    .find(|l| is_content(l))
+                    .is_some_and(|l| is_sequence_item(l));
+                if !is_empty_value(value) || holds_list {
+                    return Err(EditError::NotAMapping(segments[..depth].join(".")));
+                }
+                self.replace_value(&parent, "");
+            }
+
+            let indent = self
+                .child_indent(start, end)
+                .unwrap_or_else(|| parent.map_or(0, |p| p.indent + 2));
+            let at = self.last_content(start, end).map_or(start, |l| l + 1);
+
+            let mut new_lines = Vec::new();
+            // new sections get some room
+            let new_section = section || segments.len() - depth > 1;
+            if indent == 0 && new_section && at > 0 && is_content(&self.lines[at - 1]) {
+                new_lines.push(String::new());
+            }
+            for (i, segment) in segments[depth..].iter().enumerate() {
+                let key = if parse_key(&format!("{}:", segment)).is_some_and(|(k, _)| k == *segment) {
+                    segment.to_string()
+                } else {
+                    quoted(segment)
+                };
+                new_lines.push(format!("{}{}:", " ".repeat(indent + 2 * i), key));
+            }
+            let line = at + new_lines.len() - 1;
+            self.lines.splice(at..at, new_lines);
+
+            return Ok(Entry {
+                line,
+                indent: indent + 2 * (segments.len() - depth - 1),
+                value_start: self.lines[line].len(),
+                end: line + 1,
+            });
+        }
+
+        Ok(parent.expect("segments are not empty"))
+    }
+
+    /// The value of a key, without comment, `None` if it does not exist or has nested keys.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        let entry = self.find(&split_path(path))?;
+        if Self::has_children(&entry) {
+            return None;
+        }
+        Some(self.value_of(&entry).0)
+    }
+
+    /// Whether the key exists, no matter its value.
+    pub fn contains(&self, path: &str) -> bool {
+        self.find(&split_path(path)).is_some()
+    }
+
+    /// Sets a key to `value`, which is inserted verbatim, see [`scalar()`].
+    ///
+    /// Missing keys along the path are created at the end of their parent.
+    pub fn set(&mut self, path: &str, value: &str) -> Result<(), EditError> {
+        let segments = split_path(path);
+        let entry = self.ensure(&segments, false)?;
+        if Self::has_children(&entry) {
+            return Err(EditError::NotAScalar(path.into()));
+        }
+        self.replace_value(&entry, value);
+        Ok(())
+    }
+
+    /// Sets a key to a multi line text, written as a literal block (`key: |`).
+    pub fn set_text(&mut self, path: &str, text: &str) -> Result<(), EditError> {
+        let segments = split_path(path);
+        let entry = self.ensure(&segments, false)?;
+        if Self::has_children(&entry) {
+            return Err(EditError::NotAScalar(path.into()));
+        }
+        let indicator = if text.ends_with('\n') { "|" } else { "|-" };
+        self.replace_value(&entry, indicator);
+
+        let indent = " ".repeat(entry.indent + 2);
+        let new_lines = text.trim_end_matches('\n').lines().map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        });
+        self.lines.splice(entry.line + 1..entry.line + 1, new_lines);
+        Ok(())
+    }
+
+    /// Renames the last segment of `path` to `key`, the value stays untouched.
+    pub fn rename(&mut self, path: &str, key: &str) -> Result<(), EditError> {
+        let entry = self
+            .find(&split_path(path))
+            .ok_or_else(|| EditError::NotFound(path.into()))?;
+        let line = &self.lines[entry.line];
+        self.lines[entry.line] = format!("{}{}:{}", &line[..entry.indent], key, &line[entry.value_start..]);
+        Ok(())
+    }
+
+    /// Removes the value of a key and everything nested inside it, the key itself stays.
+    pub fn clear(&mut self, path: &str) -> Result<(), EditError> {
+        let entry = self
+            .find(&split_path(path))
+            .ok_or_else(|| EditError::NotFound(path.into()))?;
+        let children = self
+            .last_content(entry.line + 1, entry.end)
+            .map_or(entry.line + 1, |l| l + 1);
+        self.lines.drain(entry.line + 1..children);
+        self.replace_value(&entry, "");
+        Ok(())
+    }
+
+    /// Removes a key together with everything nested inside it.
+    pub fn delete(&mut self, path: &str) -> Result<(), EditError> {
+        let entry = self
+            .find(&split_path(path))
+            .ok_or_else(|| EditError::NotFound(path.into()))?;
+        // the block ends with its last content line, comments behind it belong to whatever follows
+        self.lines.drain(entry.line..entry.end);
+        Ok(())
+    }
+
+    /// Appends an item with the given keys to a block style list, starts the list if necessary.
+    ///
+    /// Values are inserted verbatim, see [`scalar()`].
+    pub fn push(&mut self, path: &str, item: &[(&str, String)]) -> Result<(), EditError> {
+        self.push_at(&split_path(path), item)
+    }
+
+    /// Like [`push()`](Self::push), but takes the keys of the path one by one,
+    /// for keys that contain `.` or `/` themselves.
+    pub fn push_at(&mut self, segments: &[&str], item: &[(&str, String)]) -> Result<(), EditError> {
+        let path = segments.join(".");
+        let entry = self.ensure(segments, true)?;
+
+        if Self::has_children(&entry) {
+            let first_child = self.lines[entry.line + 1..entry.end].iter().find(|l| is_content(l));
+            if !first_child.is_some_and(|l| is_sequence_item(l)) {
+                return Err(EditError::NotABlockList(path));
+            }
+        } else {
+            let (value, _) = self.value_of(&entry);
+            if !is_empty_value(value) && value != "[]" {
+                return Err(EditError::NotABlockList(path));
+            }
+            self.replace_value(&entry, "");
+        }
+
+        let indent = (entry.line + 1..entry.end)
+            .map(|i| &self.lines[i])
+            .find(|l| is_sequence_item(l))
+            .map_or(entry.indent + 2, |l| indent_of(l));
+        let at = self
+            .last_content(entry.line, entry.end)
+            .map_or(entry.line + 1, |l| l + 1);
+
+        let new_lines = item.iter().enumerate().map(|(i, (key, value))| {
+            let bullet = if i == 0 { "- " } else { "  " };
+            format!("{}{}{}: {}", " ".repeat(indent), bullet, key, value)
+        });
+        self.lines.splice(at..at, new_lines);
+        Ok(())
+    }
+
+    /// Path of the key in `line`, if there is one.
+    fn path_at(&self, line: usize) -> Option<Vec<String>> {
+        let (key, _) = parse_key(&self.lines[line])?;
+        let mut path = vec![key];
+        let mut indent = indent_of(&self.lines[line]);
+        for l in self.lines[..line].iter().rev() {
+            if indent == 0 {
+                break;
+            }
+            if !is_content(l) || indent_of(l) >= indent {
+                continue;
+            }
+            // keys inside of lists can't be addressed
+            let (key, _) = parse_key(l)?;
+            indent = indent_of(l);
+            path.push(key);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Finds the key that still carries the template placeholder `##FIELD##` as its value.
+    pub fn placeholder_path(&self, field: &str) -> Option<String> {
+        let placeholder = format!("##{}##", field);
+        let (start, end) = self.body();
+        (start..end)
+            .filter(|&i| {
+                parse_key(&self.lines[i]).is_some_and(|(_, value_start)| {
+                    let (value, comment) = split_comment(&self.lines[i][value_start..]);
+                    let value = value.trim_matches(|c| c == '"' || c == '\'');
+                    value == placeholder || (value.is_empty() && comment.is_some_and(|c| c.trim() == placeholder))
+                })
+            })
+            .find_map(|i| self.path_at(i))
+            .map(|path| path.join("."))
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lines.join("\n"))?;
+        if self.trailing_newline && !self.lines.is_empty() {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DOC: &str = r#"--- # asciii document
+client:
+  title:      Herr # Frau, Professor, Professorin
+  last_name:  Zahl
+
+event:
+  name: Party Hard
+  dates:
+  - begin: 03.11.2014
+    times:
+    -  begin: "19:00"
+
+invoice:
+  number: ##INVOICE-NUMBER##
+  payed_date: # not yet
+
+cataloge:
+  product: &kaffee       { name: Kaffee          , price: 2.5  , unit: 1l  }
+  product: &tee          { name: Tee             , price: 1.75 , unit: 1l  }
+
+products:
+  *kaffee:
+    amount: 5
+
+hours:
+  caterers:
+    "Maxime" : 2
+
+# the end
+tax: 19
+...
+"#;
+
+    #[test]
+    fn untouched_round_trip() {
+        assert_eq!(Document::new(DOC).to_string(), DOC);
+        assert_eq!(Document::new("a: 1").to_string(), "a: 1");
+    }
+
+    #[test]
+    fn get_values() {
+        let doc = Document::new(DOC);
+        assert_eq!(doc.get("client.title"), Some("Herr"));
+        assert_eq!(doc.get("client/last_name"), Some("Zahl"));
+        assert_eq!(doc.get("invoice.number"), Some(""));
+        assert_eq!(doc.get("products.*kaffee.amount"), Some("5"));
+        assert_eq!(doc.get("hours.caterers.Maxime"), Some("2"));
+        assert_eq!(doc.get("tax"), Some("19"));
+        assert_eq!(doc.get("event.dates"), None);
+        assert_eq!(doc.get("event.location"), None);
+    }
+
+    #[test]
+    fn set_keeps_comments_and_alignment() {
+        let mut doc = Document::new(DOC);
+        doc.set("client.title", "Frau").unwrap();
+        doc.set("invoice.number", "42").unwrap();
+        doc.set("invoice.payed_date", "08.12.2014").unwrap();
+        let expected = DOC
+            .replace("title:      Herr #", "title:      Frau #")
+            .replace("number: ##INVOICE-NUMBER##", "number: 42")
+            .replace("payed_date: # not yet", "payed_date: 08.12.2014 # not yet");
+        assert_eq!(doc.to_string(), expected);
+    }
+
+    #[test]
+    fn insert_nested_keys() {
+        let mut doc = Document::new(DOC);
+        doc.set("products.*tee.amount", "3").unwrap();
+        doc.set("hours.caterers.Sebastian", "2.5").unwrap();
+        doc.set("hours.salary", "8.5").unwrap();
+        doc.set("cancellation.number", "1").unwrap();
+        doc.set("cancellation.reason", &scalar("wrong: address")).unwrap();
+        let expected = DOC
+            .replace("    amount: 5\n", "    amount: 5\n  *tee:\n    amount: 3\n")
+            .replace(
+                "\"Maxime\" : 2\n",
+                "\"Maxime\" : 2\n    Sebastian: 2.5\n  salary: 8.5\n",
+            )
+            .replace(
+                "tax: 19\n",
+                "tax: 19\n\ncancellation:\n  number: 1\n  reason: \"wrong: address\"\n",
+            );
+        assert_eq!(doc.to_string(), expected);
+        assert!(crate::util::yaml::parse(&doc.to_string()).is_ok());
+    }
+
+    #[test]
+    fn set_fails_on_sections() {
+        let mut doc = Document::new(DOC);
+        assert_eq!(doc.set("client", "foo"), Err(EditError::NotAScalar("client".into())));
+        assert_eq!(doc.set("tax.rate", "foo"), Err(EditError::NotAMapping("tax".into())));
+        assert_eq!(doc.set("", "foo"), Err(EditError::EmptyPath));
+    }
+
+    #[test]
+    fn delete_keys() {
+        let mut doc = Document::new(DOC);
+        doc.delete("products.*kaffee").unwrap();
+        doc.delete("hours").unwrap();
+        doc.delete("client.title").unwrap();
+        assert_eq!(doc.delete("hours"), Err(EditError::NotFound("hours".into())));
+        let expected = DOC
+            .replace("  title:      Herr # Frau, Professor, Professorin\n", "")
+            .replace("  *kaffee:\n    amount: 5\n", "")
+            .replace("hours:\n  caterers:\n    \"Maxime\" : 2\n", "");
+        assert_eq!(doc.to_string(), expected);
+    }
+
+    #[test]
+    fn push_to_lists() {
+        let mut doc = Document::new("payments: []\ntax: 19\n");
+        doc.push("payments", &[("date", "01.01.2020".into()), ("amount", "5".into())])
+            .unwrap();
+        doc.push("payments", &[("date", "02.01.2020".into())]).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "payments:\n  - date: 01.01.2020\n    amount: 5\n  - date: 02.01.2020\ntax: 19\n"
+        );
+
+        let mut doc = Document::new("payments: [{date: 01.01.2020}]\n");
+        assert!(doc.push("payments", &[("date", "02.01.2020".into())]).is_err());
+        assert!(Document::new(DOC).push("client", &[("a", "b".into())]).is_err());
+    }
+
+    #[test]
+    fn restructure_keys() {
+        let mut doc =
+            Document::new("client: |\n  Herr\n  Zahl\n\nevent: Party # hard\nhours:\n  employees:\n    Maxime: 2\n");
+        doc.clear("client").unwrap();
+        doc.set("client.title", "Herr").unwrap();
+        doc.set_text("client.address", "Graf Zahl\n\nCountilvania\n").unwrap();
+        doc.clear("event").unwrap();
+        doc.set("event.name", "Party").unwrap();
+        doc.set_text("event.description", "no newline").unwrap();
+        doc.rename("hours.employees", "caterers").unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "client:\n  title: Herr\n  address: |\n    Graf Zahl\n\n    Countilvania\n\n\
+             event: # hard\n  name: Party\n  description: |-\n    no newline\n\
+             hours:\n  caterers:\n    Maxime: 2\n"
+        );
+        let yaml = crate::util::yaml::parse(&doc.to_string()).unwrap();
+        assert_eq!(
+            crate::util::yaml::get_str(&yaml, "client/address"),
+            Some("Graf Zahl\n\nCountilvania\n")
+        );
+        assert_eq!(
+            crate::util::yaml::get_str(&yaml, "event/description"),
+            Some("no newline")
+        );
+    }
+
+    #[test]
+    fn find_placeholders() {
+        let doc = Document::new(DOC);
+        assert_eq!(
+            doc.placeholder_path("INVOICE-NUMBER").as_deref(),
+            Some("invoice.number")
+        );
+        assert_eq!(doc.placeholder_path("PAYED-DATE"), None);
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(scalar("Kaffee"), "Kaffee");
+        assert_eq!(scalar("08.12.2014"), "08.12.2014");
+        assert_eq!(scalar("wrong: address"), "\"wrong: address\"");
+        assert_eq!(scalar("*kaffee"), "\"*kaffee\"");
+        assert_eq!(scalar(""), "\"\"");
+        assert_eq!(scalar("42"), "42");
+        assert_eq!(scalar("true"), "true");
+        assert_eq!(string("Kaffee"), "Kaffee");
+        assert_eq!(string("01069"), "\"01069\"");
+        assert_eq!(string("true"), "\"true\"");
+        assert_eq!(string("~"), "\"~\"");
+        assert_eq!(string("1.5"), "\"1.5\"");
+        assert_eq!(quoted("say \"hi\"\\"), r#""say \"hi\"\\""#);
+        assert_eq!(quoted("a\u{7}b\u{9f}c\u{2028}"), r#""a\x07b\x9Fc\L""#);
+
+        for text in ["true", "null", "~", "12", "0x1f", ".inf", "tab\tand\u{7}bell", "Grüße \"ü\"", "a\\b"] {
+            let yaml = crate::util::yaml::parse(&format!("value: {}\n", string(text))).unwrap();
+            assert_eq!(crate::util::yaml::get_str(&yaml, "value"), Some(text));
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use asciii::{
-    document_export::fill_template,
+    document_export::{fill_template, fill_template_for, renderer::Typst},
     project::{
         export::{Complete, ExportTarget},
         BillType, Project,
@@ -41,3 +41,30 @@ fn inline_invoice() {
     let expected = include_str!("./test_projects/expected_exports/inline_invoice");
     assert_eq!(exported, expected)
 }
+
+#[test]
+fn canceled_cancellation() {
+    let exported = export("./tests/test_projects/canceled.yml", BillType::Cancellation);
+    let expected = include_str!("./test_projects/expected_exports/canceled_cancellation");
+    assert_eq!(exported, expected)
+}
+
+fn export_typst(path: &str, bill_type: BillType) -> String {
+    let p = Project::open(path).unwrap();
+    let exported: Complete = p.export();
+    std::env::set_var("ASCIII_PATH", "");
+    fill_template_for(
+        &Typst::from_config(),
+        &exported,
+        bill_type,
+        "./templates/export.typ.hbs",
+    )
+    .unwrap()
+}
+
+#[test]
+fn current_invoice_typst() {
+    let exported = export_typst("./tests/test_projects/current.yml", BillType::Invoice);
+    let expected = include_str!("./test_projects/expected_exports/current_invoice_typst");
+    assert_eq!(exported, expected)
+}
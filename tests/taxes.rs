@@ -1,4 +1,10 @@
-use asciii::project::{spec::HasEmployees, Project};
+use asciii::{
+    actions::projects_to_csv,
+    project::{
+        spec::{HasEmployees, Redeemable, Validatable},
+        Project,
+    },
+};
 
 use pretty_assertions::assert_eq;
 
@@ -27,3 +33,80 @@ tax: 0.19"#;
     let project_untaxed = Project::from_file_content(hours_untaxed).unwrap();
     assert_eq!(project_taxed.hours().net_wages(), project_untaxed.hours().net_wages());
 }
+
+static MIXED_TAXES: &str = r#"
+event: { name: Frühstück }
+hours: { salary: 8.0 }
+tax: 0.19
+products:
+  Brötchen: { amount: 10, price: 1.0, tax: 0.07 }
+  Kaffee: { amount: 4, price: 2.5, tax: 0.19 }
+  Wasser: { amount: 5, price: 2.0 }
+"#;
+
+#[test]
+fn products_keep_their_own_tax() {
+    let project = Project::from_file_content(MIXED_TAXES).unwrap();
+    let (_, invoice) = project.bills().unwrap();
+
+    assert_eq!(invoice.len(), 2);
+    assert_eq!(invoice.gross_total().value(), 10_00 + 10_00 + 10_00);
+    assert_eq!(invoice.tax_total().value(), 70 + 3_80);
+    assert!(project.validate_taxes().validation_errors.is_empty());
+}
+
+#[test]
+fn unknown_tax_rates_are_invalid() {
+    let project = Project::from_file_content(
+        r#"
+hours: { salary: 8.0 }
+tax: 0.19
+products:
+  Kaffee: { amount: 4, price: 2.5, tax: 0.16 }
+"#,
+    )
+    .unwrap();
+
+    let validation = project.validate();
+    assert_eq!(validation.validation_errors.len(), 1);
+    assert!(validation.validation_errors[0].contains("Kaffee"));
+}
+
+#[test]
+fn csv_has_a_column_per_rate() {
+    let project = Project::from_file_content(MIXED_TAXES).unwrap();
+    let csv = projects_to_csv(&[project]).unwrap();
+    let mut lines = csv.lines();
+
+    let header = lines.next().unwrap().split(';').collect::<Vec<_>>();
+    let row = lines.next().unwrap().split(';').collect::<Vec<_>>();
+    let column = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+
+    assert_eq!(column("Amount 7%"), "1000");
+    assert_eq!(column("Tax 7%"), "70");
+    assert_eq!(column("Amount 19%"), "2000");
+    assert_eq!(column("Tax 19%"), "380");
+    assert_eq!(column("Amount 0%"), "0");
+}
+
+static CORONA_TAXES: &str = r#"
+event: { name: Frühstück, dates: [ { begin: 03.08.2020 } ] }
+hours: { salary: 8.0 }
+tax: 0.16
+products:
+  Brötchen: { amount: 10, price: 1.0, tax: 0.05 }
+  Kaffee: { amount: 4, price: 2.5 }
+"#;
+
+#[test]
+fn temporary_tax_rates_are_valid_for_a_while() {
+    let project = Project::from_file_content(CORONA_TAXES).unwrap();
+    assert!(project.validate_taxes().validation_errors.is_empty());
+    let (_, invoice) = project.bills().unwrap();
+    assert_eq!(invoice.tax_total().value(), 50 + 1_60);
+
+    let project = Project::from_file_content(&CORONA_TAXES.replace("03.08.2020", "03.08.2021")).unwrap();
+    let errors = project.validate_taxes().validation_errors;
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors[0].contains("unknown tax rate 16%"), "{:?}", errors);
+}
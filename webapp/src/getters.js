@@ -1,28 +1,52 @@
+const API = 'http://localhost:8000/api';
+const KEY_STORAGE = 'asciii-api-key';
+
 const sortProjects = (
     [,{ extras: { sort_index: a } }],
     [,{ extras: { sort_index: b } }]
 ) => a > b;
 
-const getJson = response => response.json();
+// the key is asked for once and remembered, see `asciii meta gen-key`
+const apiKey = () => {
+    let key = localStorage.getItem(KEY_STORAGE);
+    if (!key) {
+        key = window.prompt('asciii api key') || '';
+        localStorage.setItem(KEY_STORAGE, key);
+    }
+    return key;
+};
+
+const getJson = response => {
+    if (response.status === 401) {
+        // ask again next time
+        localStorage.removeItem(KEY_STORAGE);
+    }
+    return response.json();
+};
+
+const get = path =>
+    fetch(`${API}/${path}`, { headers: { Authorization: `Bearer ${apiKey()}` } })
+        .then(getJson);
 
 const normalizeProjects = raw_projects =>
     Object.entries(raw_projects)
         .sort(sortProjects);
 
 export const getProjectsByYear = year =>
-    fetch(`http://localhost:8000/api/full_projects/year/${year}`)
-        .then(getJson)
+    get(`full_projects/year/${year}`)
         .then(normalizeProjects);
 
 export const getProjectsWorkingDir = () =>
-    fetch(`http://localhost:8000/api/full_projects/workingdir`)
-        .then(getJson)
+    get(`full_projects/workingdir`)
         .then(normalizeProjects);
 
 export const getYears = () =>
-    fetch(`http://localhost:8000/api/projects/year`)
-        .then(getJson);
+    get(`projects/year`);
 
 export const getVersion = () =>
-    fetch(`http://localhost:8000/api/version`)
-        .then(getJson);
+    get(`version`);
+
+// `EventSource` can't send headers, `/api/events` takes the key as `?key=` instead
+export const subscribeChanges = onChange =>
+    new EventSource(`${API}/events?key=${encodeURIComponent(apiKey())}`)
+        .onmessage = event => onChange(JSON.parse(event.data));
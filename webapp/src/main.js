@@ -1,5 +1,5 @@
 import App from './App.html';
-import { getYears, getProjectsWorkingDir, getVersion } from './getters';
+import { getYears, getProjectsWorkingDir, getVersion, subscribeChanges } from './getters';
 
 const app = new App({
 	target: document.body,
@@ -25,4 +25,7 @@ getProjectsWorkingDir()
 getVersion()
 	.then(versionInfo=> app.set({ versionInfo }));
 
+// reload whatever is shown when somebody edits a project
+subscribeChanges(() => app.selectYear(app.get().selectedYear || 'working'));
+
 export default app;
\ No newline at end of file